@@ -0,0 +1,177 @@
+mod alloc;
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::process;
+use std::time::{Duration, Instant};
+
+use chip8::{Chip8, Instruction, State};
+
+#[global_allocator]
+static ALLOCATOR: alloc::CountingAllocator = alloc::CountingAllocator;
+
+const DEFAULT_CYCLES: u64 = 10_000_000;
+// The timed pass reads the clock around every instruction, so it only does a slice of the run
+const TIMED_FRACTION: u64 = 10;
+
+const USAGE: &str = "Usage: chip8-bench <rom> [instructions]
+Runs the ROM headless for the given number of instructions (default 10 million) with no keys pressed,
+then reports the speed, what was allocated, the speed with the decode cache and how long each kind of instruction took";
+
+// Headless benchmark for measuring the interpreter before and after performance work
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let (path, cycles) = match args.as_slice() {
+        [_, path] => (path, DEFAULT_CYCLES),
+        [_, path, cycles] => match cycles.replace('_', "").parse() {
+            Ok(cycles) => (path, cycles),
+            Err(_) => usage(),
+        },
+        _ => usage(),
+    };
+    let rom = match fs::read(path) {
+        Ok(rom) => rom,
+        Err(err) => {
+            eprintln!("Couldn't read {}: {}", path, err);
+            process::exit(1);
+        },
+    };
+
+    let mut bench = Bench::new(&rom);
+    let (allocations, bytes) = alloc::allocated();
+    let start = Instant::now();
+    bench.run(cycles, |chip8| chip8.clock());
+    let elapsed = start.elapsed();
+    let (end_allocations, end_bytes) = alloc::allocated();
+
+    println!(
+        "{} instructions in {:.3}s, {:.2} million instructions a second",
+        cycles,
+        elapsed.as_secs_f64(),
+        cycles as f64 / elapsed.as_secs_f64() / 1e6
+    );
+    println!("{} allocations, {} bytes, while running", end_allocations - allocations, end_bytes - bytes);
+    if bench.restarts > 0 {
+        println!("The ROM halted and was restarted {} times", bench.restarts);
+    }
+
+    let mut bench = Bench::new(&rom);
+    bench.chip8.enable_decode_cache(true);
+    let start = Instant::now();
+    bench.run(cycles, |chip8| chip8.clock());
+    let elapsed = start.elapsed();
+    println!("With the decode cache {:.2} million instructions a second", cycles as f64 / elapsed.as_secs_f64() / 1e6);
+
+    // Time each instruction, attributing it to the opcode it ran, then lump opcodes together by instruction
+    let timed = (cycles / TIMED_FRACTION).max(1);
+    let overhead = timer_overhead();
+    let mut opcodes: HashMap<u16, (u64, Duration)> = HashMap::new();
+    let mut bench = Bench::new(&rom);
+    bench.run(timed, |chip8| {
+        let pc = chip8.pc() as usize;
+        let opcode = match chip8.ram().get(pc..pc + 2) {
+            Some(bytes) => u16::from_be_bytes([bytes[0], bytes[1]]),
+            None => 0,
+        };
+        let start = Instant::now();
+        let result = chip8.clock();
+        let elapsed = start.elapsed();
+        let entry = opcodes.entry(opcode).or_default();
+        entry.0 += 1;
+        entry.1 += elapsed.saturating_sub(overhead);
+        result
+    });
+
+    let mut kinds: HashMap<String, (u64, Duration)> = HashMap::new();
+    for (opcode, (count, time)) in opcodes {
+        let entry = kinds.entry(kind(opcode)).or_default();
+        entry.0 += count;
+        entry.1 += time;
+    }
+    let total: Duration = kinds.values().map(|(_, time)| *time).sum();
+    let mut kinds: Vec<(String, (u64, Duration))> = kinds.into_iter().collect();
+    kinds.sort_by(|a, b| b.1 .1.cmp(&a.1 .1).then(a.0.cmp(&b.0)));
+
+    println!();
+    println!("Per instruction over {} timed instructions, less {}ns of timer overhead each:", timed, overhead.as_nanos());
+    println!("  {:<12} {:>10} {:>8} {:>10}", "instruction", "count", "time", "average");
+    for (kind, (count, time)) in kinds {
+        println!(
+            "  {:<12} {:>10} {:>7.1}% {:>8.1}ns",
+            kind,
+            count,
+            time.as_secs_f64() * 100.0 / total.as_secs_f64().max(f64::MIN_POSITIVE),
+            time.as_nanos() as f64 / count as f64
+        );
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+// A machine running a ROM flat out, with the timers ticked every frame's worth of instructions as in
+// run_frame(). A ROM that exits is started again so short ones can still be run for a long time
+struct Bench<'a> {
+    rom: &'a [u8],
+    chip8: Chip8,
+    restarts: u64,
+}
+
+impl<'a> Bench<'a> {
+    fn new(rom: &'a [u8]) -> Self {
+        let mut chip8 = Chip8::init();
+        if let Err(err) = chip8.load(rom) {
+            eprintln!("Couldn't load the ROM: {}", err);
+            process::exit(1);
+        }
+        Self { rom, chip8, restarts: 0 }
+    }
+
+    fn restart(&mut self) {
+        self.chip8.reset();
+        self.chip8.load(self.rom).expect("the ROM loaded before");
+        self.restarts += 1;
+    }
+
+    fn run(&mut self, cycles: u64, mut clock: impl FnMut(&mut Chip8) -> chip8::Result<State>) {
+        let ticks_per_frame = self.chip8.ticks_per_frame() as u64;
+        for cycle in 0..cycles {
+            match clock(&mut self.chip8) {
+                Ok(State::Running) => (),
+                Ok(State::Halted) => self.restart(),
+                Err(err) => {
+                    eprintln!("Emulator fault after {} instructions: {}", cycle, err);
+                    process::exit(1);
+                },
+            }
+            if (cycle + 1).is_multiple_of(ticks_per_frame) {
+                self.chip8.clock_timers();
+            }
+        }
+    }
+}
+
+// The cost of reading the clock twice, the smallest of many tries so scheduling noise doesn't inflate it
+fn timer_overhead() -> Duration {
+    (0..10_000)
+        .map(|_| {
+            let start = Instant::now();
+            start.elapsed()
+        })
+        .min()
+        .unwrap_or_default()
+}
+
+// Instruction name for an opcode, e.g. "Draw" for DXYN
+fn kind(opcode: u16) -> String {
+    match Instruction::decode(opcode) {
+        Some(instruction) => {
+            let name = format!("{:?}", instruction);
+            name.split('(').next().unwrap_or_default().to_string()
+        },
+        None => format!("{:04X}", opcode),
+    }
+}
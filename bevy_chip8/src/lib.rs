@@ -0,0 +1,147 @@
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::render::texture::ImageSampler;
+use chip8::{Chip8, Keymap, Palette, SharedChip8, State, HIRES_SCREEN_HEIGHT, HIRES_SCREEN_WIDTH};
+
+// CHIP-8 machines as entities in a Bevy game, e.g. arcade cabinets the player can walk up to and play:
+//
+//     app.add_plugins((DefaultPlugins, Chip8Plugin));
+//     ...
+//     let cabinet = Cabinet::new(chip8, &mut images);
+//     commands.spawn(SpriteBundle { texture: cabinet.screen.clone(), ..default() });
+//     commands.spawn(cabinet);
+//
+// Each update the keys are handed to cabinets with focus, every running cabinet is advanced by the update's
+// game time and their screens are redrawn where the display has changed
+pub struct Chip8Plugin;
+
+impl Plugin for Chip8Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<CabinetStopped>()
+            .add_systems(Update, (cabinet_input, run_cabinets, draw_cabinets).chain().in_set(Chip8Systems));
+    }
+}
+
+// The plugin's systems, for ordering game systems around them, e.g. moving focus before keys are read
+#[derive(SystemSet, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Chip8Systems;
+
+#[derive(Component)]
+pub struct Cabinet {
+    // Shared so other systems, or another thread, can load, reset or inspect it
+    pub machine: SharedChip8,
+    // Host keys and the keypad key each presses. Keys go by Bevy's KeyCode names less any Key or Digit in
+    // front, e.g. "Q", "1" or "ArrowUp"
+    pub keys: Keymap,
+    // Whether keys go to this cabinet, e.g. while the player stands at it. Without it all its keys are up
+    pub focused: bool,
+    // Cleared when the machine halts or faults, and set again to carry on, e.g. after a reset
+    pub running: bool,
+    pub palette: Palette,
+    // What the display is drawn into, for a sprite or material to show. Nearest filtered, so pixels stay sharp
+    pub screen: Handle<Image>,
+    // display_hash() and the palette the screen was last drawn with, if it has been
+    drawn: Option<(u64, Palette)>,
+}
+
+// Sent when a cabinet stops running, with the fault if it wasn't the ROM exiting
+#[derive(Event, Debug)]
+pub struct CabinetStopped {
+    pub cabinet: Entity,
+    pub fault: Option<String>,
+}
+
+impl Cabinet {
+    // A running cabinet with the QWERTY keymap and a new screen, not focused
+    pub fn new(chip8: Chip8, images: &mut Assets<Image>) -> Self {
+        let size = Extent3d { width: HIRES_SCREEN_WIDTH as u32, height: HIRES_SCREEN_HEIGHT as u32, ..default() };
+        let palette = Palette::default();
+        let [r, g, b] = palette.off;
+        let mut image = Image::new_fill(
+            size,
+            TextureDimension::D2,
+            &[r, g, b, 0xFF],
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::default(),
+        );
+        image.sampler = ImageSampler::nearest();
+        Self {
+            machine: SharedChip8::new(chip8),
+            keys: Keymap::qwerty(),
+            focused: false,
+            running: true,
+            palette,
+            screen: images.add(image),
+            drawn: None,
+        }
+    }
+}
+
+// The name a keymap knows host by, Bevy's with the Key in front of letters and Digit in front of digits dropped,
+// so Keymap::qwerty()'s names match
+fn key_name(host: KeyCode) -> String {
+    let name = format!("{:?}", host);
+    match name.strip_prefix("Key").or_else(|| name.strip_prefix("Digit")) {
+        Some(rest) if rest.len() == 1 => rest.to_string(),
+        _ => name,
+    }
+}
+
+fn cabinet_input(keyboard: Res<ButtonInput<KeyCode>>, cabinets: Query<&Cabinet>) {
+    let names: Vec<String> = keyboard.get_pressed().map(|host| key_name(*host)).collect();
+    for cabinet in &cabinets {
+        // Every keypad key with a host key down for it, all up without focus
+        let mut down = [false; 16];
+        if cabinet.focused {
+            for key in names.iter().filter_map(|name| cabinet.keys.key(name)) {
+                down[key] = true;
+            }
+        }
+        for (key, pressed) in down.into_iter().enumerate() {
+            cabinet.machine.keypress(key, pressed);
+        }
+    }
+}
+
+fn run_cabinets(
+    time: Res<Time>,
+    mut cabinets: Query<(Entity, &mut Cabinet)>,
+    mut stopped: EventWriter<CabinetStopped>,
+) {
+    for (entity, mut cabinet) in &mut cabinets {
+        if !cabinet.running {
+            continue;
+        }
+        let fault = match cabinet.machine.advance(time.delta()) {
+            Ok(State::Running) => continue,
+            Ok(State::Halted) => None,
+            Err(err) => Some(err.to_string()),
+        };
+        cabinet.running = false;
+        stopped.send(CabinetStopped { cabinet: entity, fault });
+    }
+}
+
+fn draw_cabinets(mut cabinets: Query<&mut Cabinet>, mut images: ResMut<Assets<Image>>, mut buffer: Local<Vec<u8>>) {
+    for mut cabinet in &mut cabinets {
+        let chip8 = cabinet.machine.lock();
+        let drawn = (chip8.display_hash(), cabinet.palette);
+        // Telling the asset it's changed uploads it again, so only when there's something new
+        if cabinet.drawn == Some(drawn) {
+            continue;
+        }
+        let Some(image) = images.get_mut(&cabinet.screen) else {
+            continue;
+        };
+        chip8.render_rgba(cabinet.palette, &mut buffer);
+        let size = Extent3d { width: chip8.width() as u32, height: chip8.height() as u32, ..default() };
+        drop(chip8);
+        // Switching between lo-res and hi-res changes the texture's size
+        if image.texture_descriptor.size != size {
+            image.resize(size);
+        }
+        image.data.copy_from_slice(&buffer);
+        cabinet.drawn = Some(drawn);
+    }
+}
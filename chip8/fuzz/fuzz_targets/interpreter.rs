@@ -0,0 +1,58 @@
+// Runs the interpreter from an arbitrary machine state under arbitrary configuration. Anything the program
+// does wrong should come back as a Chip8Error, so any panic here is a bug. Run with
+//   cargo +nightly fuzz run interpreter
+#![no_main]
+
+use arbitrary::Arbitrary;
+use chip8::{Chip8, Chip8State, PcPolicy, UnknownOpcodePolicy, Variant};
+use libfuzzer_sys::fuzz_target;
+
+// Enough for loops and subroutines to get going without each input taking long
+const MAX_CYCLES: u16 = 4096;
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    variant: Variant,
+    pc_policy: PcPolicy,
+    skip_unknown: bool,
+    start_address: u16,
+    cycles: u16,
+    // Keys to flip as the program runs, as (cycle, key)
+    presses: Vec<(u16, u8)>,
+    // Last, as it takes whatever input is left
+    state: Chip8State,
+}
+
+fuzz_target!(|input: Input| {
+    let mut chip8 = Chip8::with_start_address(input.start_address % 0x1000);
+    chip8.set_variant(input.variant);
+    chip8.set_pc_policy(input.pc_policy);
+    if input.skip_unknown {
+        chip8.set_unknown_opcode_policy(UnknownOpcodePolicy::Skip);
+    }
+    chip8.restore(&input.state);
+
+    let mut keys = [false; 16];
+    for cycle in 0..input.cycles.min(MAX_CYCLES) {
+        for &(_, key) in input.presses.iter().filter(|(at, _)| *at == cycle) {
+            let key = key as usize % keys.len();
+            keys[key] = !keys[key];
+            chip8.keypress(key, keys[key]);
+        }
+        if chip8.clock().is_err() {
+            // Faults leave the PC on the instruction, step past it to keep going
+            chip8.set_pc(chip8.pc().wrapping_add(2));
+        }
+        if cycle % 10 == 9 {
+            chip8.clock_timers();
+        }
+    }
+
+    // The read-only views shouldn't panic whatever state the machine was left in
+    let _ = chip8.display_hash();
+    let _ = chip8.stack();
+    let _ = chip8.call_stack();
+    let snapshot = chip8.snapshot();
+    chip8.restore(&snapshot);
+    assert_eq!(chip8.snapshot(), snapshot);
+});
@@ -0,0 +1,327 @@
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+use core::ops::Range;
+
+use crate::{Chip8, Instruction, Symbols};
+
+// What a ROM byte was found to be
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ByteKind {
+    // Part of an instruction reachable from the entry point
+    Code,
+    // Not code, but at or after an address the program points I at, so sprites, tables and the like
+    Data,
+    // Neither, so nothing the program does can reach it as far as can be told without running it
+    Unreachable,
+}
+
+// How control gets from one instruction to another
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Edge {
+    // On to the next instruction, including the return from a call and a skip not taken
+    Next,
+    Jump,
+    Call,
+    // A skip taken, past the next instruction
+    Skip,
+    // BNNN's base address, where V0 is 0
+    Computed,
+}
+
+// Something suspicious found on the way
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Issue {
+    // A jump, call or run of instructions at from leads to to, outside the ROM
+    OutOfRom { from: u16, to: u16 },
+    // An opcode the interpreter doesn't know on a path that's executed
+    UnknownOpcode { addr: u16, opcode: u16 },
+    // BNNN at addr jumps somewhere computed, only its base address was followed
+    ComputedJump { addr: u16 },
+    // FX55 or FX33 at addr writes len bytes from target, over code
+    SelfModifying { addr: u16, target: u16, len: u16 },
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Issue::OutOfRom { from, to } => write!(f, "{:03X}: leads to {:03X}, outside the ROM", from, to),
+            Issue::UnknownOpcode { addr, opcode } => write!(f, "{:03X}: unknown opcode {:04X}", addr, opcode),
+            Issue::ComputedJump { addr } => write!(f, "{:03X}: computed jump, only its base was followed", addr),
+            Issue::SelfModifying { addr, target, len } => {
+                write!(f, "{:03X}: writes over code at {:03X}-{:03X}", addr, target, target + len - 1)
+            },
+        }
+    }
+}
+
+// The result of walking a ROM's code from its entry point, following every branch and call without running
+// anything. Bytes are code if some path executes them, and the program's LD I targets mark where data
+// starts. Jumps are only followed where their target is fixed, so code reached through BNNN tables or
+// written at runtime can show up as data or unreachable
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Analysis {
+    start: u16,
+    kinds: Vec<ByteKind>,
+    issues: Vec<Issue>,
+    // Every instruction that runs, by address
+    instructions: BTreeMap<u16, Instruction>,
+    edges: Vec<(u16, u16, Edge)>,
+}
+
+impl Analysis {
+    // Analyse rom as loaded at start, running from there
+    pub fn new(rom: &[u8], start: u16) -> Self {
+        let end = start as usize + rom.len();
+        let in_rom = |addr: usize| addr >= start as usize && addr + 1 < end;
+        let mut code = vec![false; rom.len()];
+        let mut visited = vec![false; rom.len()];
+        let mut data_starts = Vec::new();
+        let mut writes = Vec::new();
+        let mut issues = Vec::new();
+        let mut instructions = BTreeMap::new();
+        let mut edges = Vec::new();
+        // Addresses to walk from, with I if it's known there and where they were reached from
+        let mut pending = vec![(start, None, start)];
+
+        while let Some((addr, mut i, mut from)) = pending.pop() {
+            let mut addr = addr as usize;
+            loop {
+                if !in_rom(addr) {
+                    issues.push(Issue::OutOfRom { from, to: addr as u16 });
+                    break;
+                }
+                let offset = addr - start as usize;
+                if visited[offset] {
+                    break;
+                }
+                visited[offset] = true;
+                code[offset] = true;
+                code[offset + 1] = true;
+                let opcode = u16::from_be_bytes([rom[offset], rom[offset + 1]]);
+                let here = addr as u16;
+                let next = here + 2;
+                let instruction = match Instruction::decode(opcode) {
+                    Some(instruction) => instruction,
+                    None => {
+                        issues.push(Issue::UnknownOpcode { addr: here, opcode });
+                        break;
+                    },
+                };
+                instructions.insert(here, instruction);
+                match instruction {
+                    Instruction::Ret | Instruction::Exit => break,
+                    Instruction::Jmp(target) => {
+                        edges.push((here, target, Edge::Jump));
+                        // Jumping to itself is how programs stop
+                        if target != here {
+                            pending.push((target, i, here));
+                        }
+                        break;
+                    },
+                    Instruction::JmpV0(target) => {
+                        issues.push(Issue::ComputedJump { addr: here });
+                        edges.push((here, target, Edge::Computed));
+                        pending.push((target, i, here));
+                        break;
+                    },
+                    Instruction::Call(target) => {
+                        edges.push((here, target, Edge::Call));
+                        pending.push((target, i, here));
+                        // The subroutine can leave I anywhere
+                        i = None;
+                    },
+                    Instruction::SkipEqImm(..)
+                    | Instruction::SkipNeImm(..)
+                    | Instruction::SkipEqReg(..)
+                    | Instruction::SkipNeReg(..)
+                    | Instruction::SkipKey(_)
+                    | Instruction::SkipNotKey(_) => {
+                        edges.push((here, next + 2, Edge::Skip));
+                        pending.push((next + 2, i, here));
+                    },
+                    Instruction::LoadI(target) => {
+                        data_starts.push(target);
+                        i = Some(target);
+                    },
+                    Instruction::Store(x) => {
+                        if let Some(target) = i {
+                            writes.push((here, target, x as u16 + 1));
+                        }
+                        // Whether I moves on depends on the quirks
+                        i = None;
+                    },
+                    Instruction::Bcd(_) => {
+                        if let Some(target) = i {
+                            writes.push((here, target, 3));
+                        }
+                    },
+                    Instruction::AddI(_) | Instruction::Font(_) | Instruction::BigFont(_) | Instruction::Load(_) => {
+                        i = None
+                    },
+                    _ => (),
+                }
+                edges.push((here, next, Edge::Next));
+                from = here;
+                addr = next as usize;
+            }
+        }
+
+        let is_code = |addr: u16| addr.checked_sub(start).and_then(|offset| code.get(offset as usize)) == Some(&true);
+        for (addr, target, len) in writes {
+            if (target..target + len).any(is_code) {
+                issues.push(Issue::SelfModifying { addr, target, len });
+            }
+        }
+        issues.sort_by_key(|issue| match *issue {
+            Issue::OutOfRom { from: addr, .. }
+            | Issue::UnknownOpcode { addr, .. }
+            | Issue::ComputedJump { addr }
+            | Issue::SelfModifying { addr, .. } => addr,
+        });
+        issues.dedup();
+
+        // Data runs from each LD I target up to the next code
+        let mut kinds: Vec<ByteKind> =
+            code.iter().map(|code| if *code { ByteKind::Code } else { ByteKind::Unreachable }).collect();
+        for target in data_starts {
+            let Some(offset) = target.checked_sub(start) else { continue };
+            for kind in kinds.iter_mut().skip(offset as usize) {
+                if *kind == ByteKind::Code {
+                    break;
+                }
+                *kind = ByteKind::Data;
+            }
+        }
+        edges.sort_by_key(|(from, to, _)| (*from, *to));
+        Self { start, kinds, issues, instructions, edges }
+    }
+
+    // Analyse the ROM chip8 last loaded, as it was loaded rather than as it is now
+    pub fn of(chip8: &Chip8) -> Self {
+        let start = chip8.start_address();
+        Self::new(&chip8.ram()[start as usize..start as usize + chip8.rom_len], start)
+    }
+
+    // What the byte at addr is, None outside the ROM
+    pub fn kind(&self, addr: u16) -> Option<ByteKind> {
+        self.kinds.get(addr.checked_sub(self.start)? as usize).copied()
+    }
+
+    pub fn is_code(&self, addr: u16) -> bool {
+        self.kind(addr) == Some(ByteKind::Code)
+    }
+
+    // In address order
+    pub fn issues(&self) -> &[Issue] {
+        &self.issues
+    }
+
+    // The ROM split into runs of the same kind, in address order
+    pub fn regions(&self) -> Vec<(Range<u16>, ByteKind)> {
+        let mut regions: Vec<(Range<u16>, ByteKind)> = Vec::new();
+        for (offset, kind) in self.kinds.iter().enumerate() {
+            let addr = self.start + offset as u16;
+            match regions.last_mut() {
+                Some((range, last)) if last == kind => range.end = addr + 1,
+                _ => regions.push((addr..addr + 1, *kind)),
+            }
+        }
+        regions
+    }
+
+    // Runs of bytes nothing reaches
+    pub fn unreachable(&self) -> Vec<Range<u16>> {
+        self.regions().into_iter().filter(|(_, kind)| *kind == ByteKind::Unreachable).map(|(range, _)| range).collect()
+    }
+
+    // Every way control moves between the instructions that run, as from, to and how, in address order. to
+    // can be outside the ROM, as reported in issues()
+    pub fn edges(&self) -> &[(u16, u16, Edge)] {
+        &self.edges
+    }
+
+    // The control flow graph in Graphviz's DOT language, for `dot -Tsvg`. Each node is a run of instructions
+    // entered only at the top and left only at the bottom, named from symbols where it has a name
+    pub fn to_dot(&self, symbols: &Symbols) -> String {
+        let mut dot = String::from("digraph rom {\n    node [shape=box fontname=monospace];\n");
+        let blocks = self.blocks();
+        for (leader, block) in &blocks {
+            let mut label = String::new();
+            if let Some(name) = symbols.name(*leader) {
+                let _ = write!(label, "{}:\\l", escape(name));
+            }
+            for addr in block {
+                let text = self.instructions[addr].with_symbols(symbols).to_string();
+                let _ = write!(label, "{:03X}: {}\\l", addr, escape(&text));
+            }
+            let _ = writeln!(dot, "    n{:03X} [label=\"{}\"];", leader, label);
+        }
+        // Only the edges out of the end of a block are between blocks
+        let ends: BTreeMap<u16, u16> = blocks.iter().map(|(leader, block)| (block[block.len() - 1], *leader)).collect();
+        for (from, to, edge) in &self.edges {
+            let Some(leader) = ends.get(from) else { continue };
+            if !blocks.contains_key(to) {
+                let _ = writeln!(dot, "    n{:03X} [label=\"{:03X}: outside the ROM\" color=red];", to, to);
+            }
+            let style = match edge {
+                Edge::Next => "",
+                Edge::Jump => " [label=jump]",
+                Edge::Call => " [label=call style=dashed]",
+                Edge::Skip => " [label=skip]",
+                Edge::Computed => " [label=computed style=dotted]",
+            };
+            let _ = writeln!(dot, "    n{:03X} -> n{:03X}{};", leader, to, style);
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    // The instruction at addr, which has to be one that runs
+    pub(crate) fn instruction(&self, addr: u16) -> Instruction {
+        self.instructions[&addr]
+    }
+
+    // Basic blocks by first address, each the addresses of its instructions
+    pub(crate) fn blocks(&self) -> BTreeMap<u16, Vec<u16>> {
+        let mut incoming: BTreeMap<u16, Vec<Edge>> = BTreeMap::new();
+        let mut outgoing: BTreeMap<u16, usize> = BTreeMap::new();
+        for (from, to, edge) in &self.edges {
+            incoming.entry(*to).or_default().push(*edge);
+            *outgoing.entry(*from).or_default() += 1;
+        }
+        // A block starts wherever control can arrive other than by running on from the one instruction before
+        let is_leader = |addr: u16| {
+            let edges = incoming.get(&addr).map_or(&[][..], Vec::as_slice);
+            addr == self.start
+                || edges.len() != 1
+                || edges[0] != Edge::Next
+                || outgoing.get(&addr.wrapping_sub(2)).copied().unwrap_or_default() > 1
+        };
+        let leaders: BTreeSet<u16> = self.instructions.keys().copied().filter(|addr| is_leader(*addr)).collect();
+        let mut blocks = BTreeMap::new();
+        for leader in &leaders {
+            let mut block = vec![*leader];
+            let mut addr = *leader;
+            // On while the only way out is to the next instruction, which nothing else leads to
+            while outgoing.get(&addr) == Some(&1) {
+                let next = addr + 2;
+                let runs_on = self.edges.contains(&(addr, next, Edge::Next));
+                if !runs_on || leaders.contains(&next) {
+                    break;
+                }
+                block.push(next);
+                addr = next;
+            }
+            blocks.insert(*leader, block);
+        }
+        blocks
+    }
+}
+
+// For a DOT string
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
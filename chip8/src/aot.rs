@@ -0,0 +1,93 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use crate::{Analysis, Instruction, Issue};
+
+// Translate the code an Analysis found into Rust, a function per basic block, for running a ROM without
+// fetching or decoding once the output is compiled in. Experimental. Register loads and moves and LD I are
+// written out, every other instruction goes through Chip8::run_opcode() so quirks and faults behave as
+// they do when interpreted. The output's run_frame() stands in for Chip8::run_frame() with Timing::Ticks,
+// running whole blocks so a frame can overrun by the rest of one, leaves any other timing and a paused
+// machine to Chip8::run_frame(), and hands any address without a block to the interpreter: computed jump
+// targets, code reached only at runtime and blocks the program writes over. Breakpoints, profiling,
+// coverage and freezes only work on what's interpreted, and the output is only good for the ROM it came
+// from, loaded at the same address
+pub fn transpile_rust(analysis: &Analysis) -> String {
+    let mut out = String::new();
+    out.push_str("// Generated by chip8::transpile_rust(), don't edit\n");
+    out.push_str("use chip8::{Chip8, Result, State, Timing};\n\n");
+    out.push_str("// Run a frame as Chip8::run_frame() does with Timing::Ticks, a block at a time. Blocks\n");
+    out.push_str("// don't count cycles, so other timings, and pauses, are left to the interpreter\n");
+    out.push_str("pub fn run_frame(c: &mut Chip8) -> Result<State> {\n");
+    out.push_str("    if c.timing() != Timing::Ticks || c.is_paused() {\n        return c.run_frame();\n    }\n");
+    out.push_str("    let mut ticks = 0;\n");
+    out.push_str("    while ticks < c.ticks_per_frame() {\n");
+    out.push_str("        if c.is_halted() {\n            return Ok(State::Halted);\n        }\n");
+    out.push_str("        ticks += step(c)?;\n    }\n");
+    out.push_str("    c.clock_timers();\n");
+    out.push_str("    Ok(if c.is_halted() { State::Halted } else { State::Running })\n}\n\n");
+
+    let overwritten: Vec<(u16, u16)> = analysis
+        .issues()
+        .iter()
+        .filter_map(|issue| match *issue {
+            Issue::SelfModifying { target, len, .. } => Some((target, target + len)),
+            _ => None,
+        })
+        .collect();
+    let blocks = analysis.blocks();
+    let blocks: Vec<_> = blocks
+        .iter()
+        .filter(|(_, block)| {
+            let (first, last) = (block[0], block[block.len() - 1] + 2);
+            !overwritten.iter().any(|(start, end)| *start < last && first < *end)
+        })
+        .collect();
+
+    out.push_str("// Run the block at the PC, returning how many instructions that was\n");
+    out.push_str("pub fn step(c: &mut Chip8) -> Result<usize> {\n    match c.pc() {\n");
+    for (leader, _) in &blocks {
+        let _ = writeln!(out, "        0x{:03X} => block_{:03x}(c),", leader, leader);
+    }
+    out.push_str("        _ => c.clock().map(|_| 1),\n    }\n}\n");
+
+    for (leader, block) in &blocks {
+        let _ = write!(out, "\nfn block_{:03x}(c: &mut Chip8) -> Result<usize> {{\n", leader);
+        for (idx, addr) in block.iter().enumerate() {
+            let instruction = analysis.instruction(*addr);
+            let (ran, next, last) = (idx + 1, addr + 2, idx + 1 == block.len());
+            let _ = writeln!(out, "    // {:03X}: {}", addr, instruction);
+            let written = match instruction {
+                Instruction::Nop => Some(String::new()),
+                Instruction::LoadImm(x, nn) => Some(format!("c.set_v({}, 0x{:02X});", x, nn)),
+                Instruction::AddImm(x, nn) => Some(format!("c.set_v({0}, c.v({0}).wrapping_add(0x{1:02X}));", x, nn)),
+                Instruction::Mov(x, y) => Some(format!("c.set_v({}, c.v({}));", x, y)),
+                Instruction::LoadI(nnn) => Some(format!("c.set_i(0x{:03X});", nnn)),
+                Instruction::Jmp(nnn) => Some(format!("c.set_pc(0x{:03X});", nnn)),
+                _ => None,
+            };
+            match written {
+                Some(code) => {
+                    if !code.is_empty() {
+                        let _ = writeln!(out, "    {}", code);
+                    }
+                    // Running on into the next block
+                    if last && !matches!(instruction, Instruction::Jmp(_)) {
+                        let _ = writeln!(out, "    c.set_pc(0x{:03X});", next);
+                    }
+                },
+                None => {
+                    let _ = writeln!(out, "    c.run_opcode(0x{:03X}, 0x{:04X})?;", addr, instruction.encode());
+                    // Drawing and key waits can redo themselves, and extensions can do anything
+                    if !last {
+                        let _ = writeln!(out, "    if c.pc() != 0x{:03X} {{\n        return Ok({});\n    }}", next, ran);
+                    }
+                },
+            }
+        }
+        let _ = writeln!(out, "    Ok({})\n}}", block.len());
+    }
+    out
+}
@@ -0,0 +1,109 @@
+use crate::Chip8;
+
+// The shape of the beep's wave
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Waveform {
+    // The harsh 1-bit tone of the original hardware
+    #[default]
+    Square,
+    Triangle,
+    Sine,
+}
+
+// How the beep sounds, see Chip8::set_buzzer(). There's only the one sound, as XO-CHIP's audio patterns aren't
+// emulated
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Buzzer {
+    // In Hz
+    pub frequency: f32,
+    // How much of each cycle the wave spends above zero, from 0 to 1
+    pub duty: f32,
+    pub waveform: Waveform,
+    // The loudest sample, from 0 to 1
+    pub volume: f32,
+}
+
+impl Default for Buzzer {
+    fn default() -> Self {
+        Self { frequency: 440.0, duty: 0.5, waveform: Waveform::Square, volume: 0.25 }
+    }
+}
+
+impl Buzzer {
+    // With anything out of range brought into it
+    fn sanitised(self) -> Self {
+        let unit = |value: f32, or: f32| if value.is_finite() { value.clamp(0.0, 1.0) } else { or };
+        Self {
+            frequency: if self.frequency.is_finite() { self.frequency.max(0.0) } else { 0.0 },
+            duty: unit(self.duty, Self::default().duty),
+            waveform: self.waveform,
+            volume: unit(self.volume, 0.0),
+        }
+    }
+
+    // The wave at phase through a cycle, from -1 to 1
+    fn sample(&self, phase: f32) -> f32 {
+        // Stretch the positive half of the cycle over duty and the negative half over the rest
+        let phase = if phase < self.duty {
+            0.5 * phase / self.duty
+        } else {
+            0.5 + 0.5 * (phase - self.duty) / (1.0 - self.duty)
+        };
+        match self.waveform {
+            Waveform::Square => {
+                if phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            },
+            Waveform::Triangle => 1.0 - 4.0 * (phase - 0.25).abs().min((phase - 1.25).abs()),
+            Waveform::Sine => {
+                // Bhaskara's approximation of each half, within 0.2%, as f32::sin() needs std
+                let (t, sign) = if phase < 0.5 { (phase * 2.0, 1.0) } else { (phase * 2.0 - 1.0, -1.0) };
+                let x = t * (1.0 - t);
+                sign * 16.0 * x / (5.0 - 4.0 * x)
+            },
+        }
+    }
+}
+
+// The beep as samples, keeping its place in the cycle between buffers so a beep spread across several
+// doesn't click
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Wave {
+    // Position through the current cycle, from 0 to 1
+    phase: f32,
+}
+
+impl Wave {
+    pub(crate) fn fill(&mut self, buzzer: &Buzzer, beeping: bool, out: &mut [f32], sample_rate: u32) {
+        let step = buzzer.frequency / sample_rate.max(1) as f32;
+        for sample in out {
+            *sample = if beeping { buzzer.volume * buzzer.sample(self.phase) } else { 0.0 };
+            // Keeping the fraction, as f32::fract() needs std
+            self.phase += step;
+            self.phase -= self.phase as u32 as f32;
+        }
+    }
+}
+
+impl Chip8 {
+    pub fn buzzer(&self) -> Buzzer {
+        self.buzzer
+    }
+
+    // Change the beep fill_audio() and the recorders make. Values out of range are clamped, and a frequency
+    // that isn't finite is silent
+    pub fn set_buzzer(&mut self, buzzer: Buzzer) {
+        self.buzzer = buzzer.sanitised();
+    }
+
+    // Fill out with the beep as the sound timer stands, silence when it isn't running, at sample_rate
+    // samples a second from -1 to 1. Made for feeding an audio callback or queue directly, calling it with
+    // each buffer, and the wave carries on smoothly from one call to the next
+    pub fn fill_audio(&mut self, out: &mut [f32], sample_rate: u32) {
+        let beeping = self.is_beeping();
+        self.wave.fill(&self.buzzer, beeping, out, sample_rate);
+    }
+}
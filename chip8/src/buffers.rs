@@ -0,0 +1,57 @@
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+use core::ops::{Deref, DerefMut};
+
+use crate::{HIRES_SCREEN_HEIGHT, RAM_SIZE};
+
+// A machine's RAM and display, 5KB of the 5.3KB it needs. Chip8::new_in() runs from the caller's Buffers, e.g.
+// a #[link_section] static handed out by cortex_m::singleton! or static_cell. new() is const so the static
+// is laid out by the linker rather than built on the stack
+pub struct Buffers {
+    pub(crate) ram: [u8; RAM_SIZE],
+    // One bit per pixel, the leftmost pixel of each row in the top bit. Sized for hi-res, lo-res only uses
+    // the top 64 bits of the first SCREEN_HEIGHT rows
+    pub(crate) display: [u128; HIRES_SCREEN_HEIGHT],
+}
+
+impl Buffers {
+    pub const fn new() -> Self {
+        Self { ram: [0; RAM_SIZE], display: [0; HIRES_SCREEN_HEIGHT] }
+    }
+}
+
+impl Default for Buffers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Where a machine's Buffers live. Either way the Chip8 itself only holds a pointer, so it stays small enough
+// to keep on a microcontroller's stack
+pub(crate) enum Storage {
+    #[cfg(feature = "alloc")]
+    Owned(Box<Buffers>),
+    Static(&'static mut Buffers),
+}
+
+impl Deref for Storage {
+    type Target = Buffers;
+
+    fn deref(&self) -> &Buffers {
+        match self {
+            #[cfg(feature = "alloc")]
+            Storage::Owned(buffers) => buffers,
+            Storage::Static(buffers) => buffers,
+        }
+    }
+}
+
+impl DerefMut for Storage {
+    fn deref_mut(&mut self) -> &mut Buffers {
+        match self {
+            #[cfg(feature = "alloc")]
+            Storage::Owned(buffers) => buffers,
+            Storage::Static(buffers) => buffers,
+        }
+    }
+}
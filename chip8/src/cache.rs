@@ -0,0 +1,39 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::dispatch::{self, Handler};
+use crate::RAM_SIZE;
+
+// Opcodes already fetched and matched to the handler that runs them, by address, so a loop is only fetched
+// and looked up in the dispatch tables the first time round. Anything written to RAM drops the entries it
+// overlaps, including an opcode starting the byte before
+pub(crate) struct DecodeCache {
+    entries: Vec<Option<(u16, Handler)>>,
+}
+
+impl DecodeCache {
+    pub(crate) fn new() -> Self {
+        Self { entries: vec![None; RAM_SIZE] }
+    }
+
+    pub(crate) fn get(&self, addr: u16) -> Option<(u16, Handler)> {
+        self.entries.get(addr as usize).copied().flatten()
+    }
+
+    pub(crate) fn insert(&mut self, addr: u16, opcode: u16) {
+        if let Some(entry) = self.entries.get_mut(addr as usize) {
+            *entry = Some((opcode, dispatch::handler(opcode)));
+        }
+    }
+
+    pub(crate) fn invalidate(&mut self, range: Range<usize>) {
+        let start = range.start.saturating_sub(1).min(RAM_SIZE);
+        let end = range.end.min(RAM_SIZE);
+        self.entries[start..end.max(start)].fill(None);
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.fill(None);
+    }
+}
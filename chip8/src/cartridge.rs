@@ -0,0 +1,180 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::{compile_octo, Chip8, Chip8Error, OctoError, Palette, Quirks, Variant};
+
+// Octo's memory size settings, the largest program each platform takes
+const SUPERCHIP_MAX_SIZE: usize = 3583;
+
+#[derive(Debug)]
+pub enum CartridgeError {
+    // Not a GIF, or it couldn't be read
+    Gif(gif::DecodingError),
+    // A GIF, but what's hidden in it isn't a cartridge
+    Payload(&'static str),
+    Json(serde_json::Error),
+    // The program didn't compile
+    Compile(OctoError),
+    Rom(Chip8Error),
+}
+
+impl fmt::Display for CartridgeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CartridgeError::Gif(err) => write!(f, "couldn't read the GIF: {}", err),
+            CartridgeError::Payload(what) => write!(f, "not an Octo cartridge: {}", what),
+            CartridgeError::Json(err) => write!(f, "not an Octo cartridge: {}", err),
+            CartridgeError::Compile(err) => write!(f, "the program doesn't compile: {}", err),
+            CartridgeError::Rom(err) => write!(f, "couldn't load the program: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for CartridgeError {}
+
+impl From<gif::DecodingError> for CartridgeError {
+    fn from(err: gif::DecodingError) -> Self {
+        CartridgeError::Gif(err)
+    }
+}
+
+impl From<serde_json::Error> for CartridgeError {
+    fn from(err: serde_json::Error) -> Self {
+        CartridgeError::Json(err)
+    }
+}
+
+// The settings saved in a cartridge, the ones Octo's options panel has that mean something here
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OctoOptions {
+    // Instructions per 60Hz frame
+    pub tickrate: usize,
+    pub quirks: Quirks,
+    // The largest program the game was built for: 3216 for CHIP-8, 3583 for SCHIP and 65024 for XO-CHIP
+    pub max_size: usize,
+    // The background and first fill colour. XO-CHIP's second plane colours aren't kept
+    pub palette: Palette,
+}
+
+impl Default for OctoOptions {
+    // Octo's defaults, which are what a cartridge gets for any option it leaves out
+    fn default() -> Self {
+        Self {
+            tickrate: 20,
+            quirks: Quirks {
+                shift: false,
+                load_store: false,
+                vf_order: false,
+                clip: false,
+                vblank: false,
+                jump: false,
+                logic: false,
+            },
+            max_size: 3584,
+            // Octo's screen colours when a cartridge doesn't give its own
+            palette: Palette::OCTO,
+        }
+    }
+}
+
+impl OctoOptions {
+    fn from_json(options: &Value) -> Self {
+        let defaults = Self::default();
+        let flag = |name: &str, default: bool| options[name].as_bool().unwrap_or(default);
+        let number = |name: &str, default: usize| {
+            // Octo saves numbers as either, depending on whether they came from a text box
+            let value = &options[name];
+            value.as_u64().or_else(|| value.as_str()?.trim().parse().ok()).map_or(default, |value| value as usize)
+        };
+        let colour = |name: &str, default: [u8; 3]| options[name].as_str().and_then(parse_colour).unwrap_or(default);
+        let quirks = defaults.quirks;
+        Self {
+            tickrate: number("tickrate", defaults.tickrate).max(1),
+            quirks: Quirks {
+                shift: flag("shiftQuirks", quirks.shift),
+                load_store: flag("loadStoreQuirks", quirks.load_store),
+                vf_order: flag("vfOrderQuirks", quirks.vf_order),
+                clip: flag("clipQuirks", quirks.clip),
+                vblank: flag("vBlankQuirks", quirks.vblank),
+                jump: flag("jumpQuirks", quirks.jump),
+                logic: flag("logicQuirks", quirks.logic),
+            },
+            max_size: number("maxSize", defaults.max_size),
+            palette: Palette {
+                off: colour("backgroundColor", defaults.palette.off),
+                on: colour("fillColor", defaults.palette.on),
+            },
+        }
+    }
+
+    // Set chip8 up to run the game as Octo would: its speed, quirks, and SCHIP for programs that asked for it
+    pub fn apply(&self, chip8: &mut Chip8) {
+        chip8.set_ticks_per_frame(self.tickrate);
+        chip8.set_quirks(self.quirks);
+        let variant = if self.max_size == SUPERCHIP_MAX_SIZE { Variant::SuperChip } else { Variant::Chip8 };
+        chip8.set_variant(variant);
+    }
+}
+
+// An Octo cartridge: a GIF with a game's source and settings hidden in it, which is how Octo games are shared
+// and how most game jam entries are published. The picture is the game's label. Pixels are kept two to a
+// byte, the low nibble of each pixel's palette index carrying half of it, high half first, running on
+// through every frame. The bytes are a 32-bit big-endian length then that much JSON, with the Octo source
+// as "program" and the options panel as "options"
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cartridge {
+    // Octo assembly, see compile_octo()
+    pub program: String,
+    pub options: OctoOptions,
+}
+
+impl Cartridge {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, CartridgeError> {
+        let file = File::open(path).map_err(gif::DecodingError::Io)?;
+        Self::read(BufReader::new(file))
+    }
+
+    pub fn read(reader: impl Read) -> Result<Self, CartridgeError> {
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::Indexed);
+        let mut decoder = options.read_info(reader)?;
+        let mut nybbles = Vec::new();
+        while let Some(frame) = decoder.read_next_frame()? {
+            nybbles.extend(frame.buffer.iter().map(|index| index & 0xF));
+        }
+        let payload: Vec<u8> = nybbles.chunks_exact(2).map(|pair| pair[0] << 4 | pair[1]).collect();
+        Self::from_payload(&payload)
+    }
+
+    // Compile the program and start it on chip8 with load_rom(), set up with the cartridge's options
+    pub fn load(&self, chip8: &mut Chip8) -> Result<(), CartridgeError> {
+        let program = compile_octo(&self.program).map_err(CartridgeError::Compile)?;
+        chip8.load_rom(&program.rom).map_err(CartridgeError::Rom)?;
+        self.options.apply(chip8);
+        Ok(())
+    }
+
+    fn from_payload(payload: &[u8]) -> Result<Self, CartridgeError> {
+        let size = payload.get(..4).ok_or(CartridgeError::Payload("the image is too small"))?;
+        let size = u32::from_be_bytes(size.try_into().unwrap()) as usize;
+        let json = payload[4..].get(..size).ok_or(CartridgeError::Payload("the image is smaller than its contents"))?;
+        let json: Value = serde_json::from_slice(json)?;
+        let program = json["program"].as_str().ok_or(CartridgeError::Payload("there's no program"))?;
+        Ok(Self { program: program.to_string(), options: OctoOptions::from_json(&json["options"]) })
+    }
+}
+
+// #RRGGBB, or #RGB as CSS allows
+fn parse_colour(text: &str) -> Option<[u8; 3]> {
+    let hex = text.trim().strip_prefix('#')?;
+    let digits: Vec<u8> = hex.chars().map(|c| c.to_digit(16).map(|digit| digit as u8)).collect::<Option<_>>()?;
+    match digits.as_slice() {
+        [r, g, b] => Some([r * 17, g * 17, b * 17]),
+        [r1, r2, g1, g2, b1, b2] => Some([r1 << 4 | r2, g1 << 4 | g2, b1 << 4 | b2]),
+        _ => None,
+    }
+}
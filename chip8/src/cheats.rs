@@ -0,0 +1,199 @@
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{Chip8, RomHash, RAM_SIZE};
+
+// When frozen addresses are written back
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FreezeTiming {
+    // On every clock_timers(), i.e. once a frame, which is enough for lives and scores
+    Frame,
+    // After every instruction as well, for values a game reads straight after changing them
+    Clock,
+}
+
+// How CheatSearch::search() narrows down the candidate addresses. All but Equal compare with the RAM as it
+// was at the previous search
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    Equal(u8),
+    Changed,
+    Unchanged,
+    Increased,
+    Decreased,
+}
+
+impl Comparison {
+    fn matches(self, before: u8, now: u8) -> bool {
+        match self {
+            Comparison::Equal(value) => now == value,
+            Comparison::Changed => now != before,
+            Comparison::Unchanged => now == before,
+            Comparison::Increased => now > before,
+            Comparison::Decreased => now < before,
+        }
+    }
+}
+
+// Hunts for where a game keeps something like its lives or score, the classic way: search for the value on
+// screen, lose a life, search for what decreased, and so on until only a few addresses are left to freeze
+pub struct CheatSearch {
+    // Addresses still in the running, in order
+    candidates: Vec<u16>,
+    // RAM at the last search
+    previous: Vec<u8>,
+}
+
+impl CheatSearch {
+    // Start with every address as a candidate
+    pub fn new(chip8: &Chip8) -> Self {
+        Self { candidates: (0..RAM_SIZE as u16).collect(), previous: chip8.ram().to_vec() }
+    }
+
+    // Keep only the candidates that pass comparison, returning how many are left
+    pub fn search(&mut self, chip8: &Chip8, comparison: Comparison) -> usize {
+        let ram = chip8.ram();
+        let previous = &self.previous;
+        self.candidates.retain(|addr| comparison.matches(previous[*addr as usize], ram[*addr as usize]));
+        self.previous.copy_from_slice(ram);
+        self.candidates.len()
+    }
+
+    pub fn candidates(&self) -> &[u16] {
+        &self.candidates
+    }
+
+    // Start over with every address as a candidate
+    pub fn reset(&mut self, chip8: &Chip8) {
+        *self = Self::new(chip8);
+    }
+}
+
+// A value to hold an address at, as shared in cheat files
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cheat {
+    pub address: u16,
+    pub value: u8,
+    pub enabled: bool,
+    // What it does, e.g. "Infinite lives", may be empty
+    pub name: String,
+}
+
+// Cheats for any number of ROMs, keyed by RomHash. The text form has a [hash] line starting each ROM's
+// cheats, then a line per cheat of hex address, hex value, on or off and a name, with # starting comments:
+//     # roms/INVADERS
+//     [f100197f0f2f05b4f3c8c31ab9c2c3930d3e9571]
+//     3F0 03 on Lives
+//     3F2 99 off Score
+// Display writes it back out the same way, so files can be edited, loaded and saved again
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CheatFile {
+    roms: Vec<(RomHash, Vec<Cheat>)>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CheatFileError {
+    // 1-based line number
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for CheatFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CheatFileError {}
+
+impl CheatFile {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn parse(text: &str) -> Result<Self, CheatFileError> {
+        let mut file = Self::new();
+        let mut rom = None;
+        for (idx, line) in text.lines().enumerate() {
+            let error = |message: &str| CheatFileError { line: idx + 1, message: message.to_string() };
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(hash) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+                rom = Some(hash.trim().parse().map_err(|_| error("expected a 40 digit hex ROM hash"))?);
+                continue;
+            }
+            let rom = rom.ok_or_else(|| error("cheat before any [ROM hash] line"))?;
+
+            let mut rest = line;
+            let address = next_field(&mut rest)
+                .and_then(|field| u16::from_str_radix(field, 16).ok())
+                .filter(|address| (*address as usize) < RAM_SIZE)
+                .ok_or_else(|| error("expected a hex address below 1000"))?;
+            let value = next_field(&mut rest)
+                .and_then(|field| u8::from_str_radix(field, 16).ok())
+                .ok_or_else(|| error("expected a hex byte value"))?;
+            let enabled = match next_field(&mut rest) {
+                Some("on") => true,
+                Some("off") => false,
+                _ => return Err(error("expected on or off")),
+            };
+            let name = rest.trim().to_string();
+            file.add(rom, Cheat { address, value, enabled, name });
+        }
+        Ok(file)
+    }
+
+    pub fn add(&mut self, rom: RomHash, cheat: Cheat) {
+        match self.roms.iter_mut().find(|(hash, _)| *hash == rom) {
+            Some((_, cheats)) => cheats.push(cheat),
+            None => self.roms.push((rom, vec![cheat])),
+        }
+    }
+
+    // The cheats for a ROM, empty if the file has none
+    pub fn cheats(&self, rom: &RomHash) -> &[Cheat] {
+        self.roms.iter().find(|(hash, _)| hash == rom).map_or(&[], |(_, cheats)| cheats)
+    }
+
+    pub fn cheats_mut(&mut self, rom: &RomHash) -> Option<&mut Vec<Cheat>> {
+        self.roms.iter_mut().find(|(hash, _)| hash == rom).map(|(_, cheats)| cheats)
+    }
+
+    pub fn roms(&self) -> impl Iterator<Item = &RomHash> {
+        self.roms.iter().map(|(hash, _)| hash)
+    }
+}
+
+impl fmt::Display for CheatFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, (rom, cheats)) in self.roms.iter().enumerate() {
+            if idx > 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "[{}]", rom)?;
+            for cheat in cheats {
+                let enabled = if cheat.enabled { "on" } else { "off" };
+                write!(f, "{:03X} {:02X} {}", cheat.address, cheat.value, enabled)?;
+                if !cheat.name.is_empty() {
+                    write!(f, " {}", cheat.name)?;
+                }
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Split the first whitespace separated field off text
+fn next_field<'a>(text: &mut &'a str) -> Option<&'a str> {
+    let trimmed = text.trim_start();
+    let end = trimmed.find(char::is_whitespace).unwrap_or(trimmed.len());
+    let (field, rest) = trimmed.split_at(end);
+    *text = rest;
+    Some(field).filter(|field| !field.is_empty())
+}
@@ -0,0 +1,261 @@
+use std::fmt;
+use std::ops::Range;
+use std::path::PathBuf;
+
+use toml_edit::{Document, Item, Key, TableLike};
+
+use crate::{Chip8, Keymap, Palette, Quirks, DEFAULT_TICKS_PER_FRAME, NUM_KEYS};
+
+// Settings shared by the frontends, read from a TOML file like:
+//     ips = 600
+//
+//     [palette]
+//     theme = "amber"
+//     on = "#FFB000"
+//
+//     [quirks]
+//     shift = false
+//
+//     [keymap]
+//     Up = "2"
+//     "Left Shift" = "5"
+//
+//     [paths]
+//     saves = "saves"
+// Everything's optional and anything left out keeps its default. A palette theme is one of Palette::THEMES's
+// names, with on or off written alongside changing its colours. Keymap entries are a host key's name and
+// the keypad key in hex it presses, see Keymap. Display writes all of it back out
+#[derive(Clone, Debug, PartialEq)]
+pub struct Config {
+    pub palette: Palette,
+    // Instructions a second, run as the nearest whole number a frame
+    pub ips: u32,
+    pub quirks: Quirks,
+    // None when the file has no [keymap], leaving the frontend its own
+    pub keymap: Option<Keymap>,
+    pub paths: Paths,
+}
+
+// Where frontends keep what they write, relative to where they're run unless absolute
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Paths {
+    // Save state slots, see SaveSlots
+    pub saves: PathBuf,
+    // Keymaps for single games, named after the ROM's hash
+    pub keymaps: PathBuf,
+    // Screenshots, recordings and core dumps
+    pub captures: PathBuf,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConfigError {
+    // 1-based line number
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            palette: Palette::default(),
+            ips: DEFAULT_TICKS_PER_FRAME as u32 * 60,
+            quirks: Quirks::default(),
+            keymap: None,
+            paths: Paths::default(),
+        }
+    }
+}
+
+impl Default for Paths {
+    fn default() -> Self {
+        Self { saves: "saves".into(), keymaps: "keymaps".into(), captures: ".".into() }
+    }
+}
+
+impl Config {
+    pub fn parse(text: &str) -> Result<Self, ConfigError> {
+        let doc = Document::parse(text).map_err(|err| error(text, err.span(), err.message()))?;
+        let mut config = Self::default();
+        for (key, item) in entries(doc.as_table()) {
+            match key.get() {
+                "ips" => {
+                    config.ips = item
+                        .as_integer()
+                        .and_then(|ips| u32::try_from(ips).ok())
+                        .filter(|ips| *ips > 0)
+                        .ok_or_else(|| error(text, item.span(), "ips should be a positive whole number"))?;
+                },
+                "palette" => {
+                    let palette = table(text, key, item)?;
+                    // A theme first, wherever it's written, so on and off can change its colours
+                    if let Some((_, item)) = palette.get_key_value("theme") {
+                        let themes: Vec<&str> = Palette::THEMES.iter().map(|(name, _)| *name).collect();
+                        let message = format!("theme should be one of {}", themes.join(", "));
+                        config.palette =
+                            item.as_str().and_then(Palette::named).ok_or_else(|| error(text, item.span(), &message))?;
+                    }
+                    for (key, item) in entries(palette) {
+                        let field = match key.get() {
+                            "theme" => continue,
+                            "on" => &mut config.palette.on,
+                            "off" => &mut config.palette.off,
+                            _ => return Err(unknown(text, key)),
+                        };
+                        *field = colour(text, item)?;
+                    }
+                },
+                "quirks" => {
+                    for (key, item) in entries(table(text, key, item)?) {
+                        let quirk = match key.get() {
+                            "shift" => &mut config.quirks.shift,
+                            "load_store" => &mut config.quirks.load_store,
+                            "vf_order" => &mut config.quirks.vf_order,
+                            "clip" => &mut config.quirks.clip,
+                            "vblank" => &mut config.quirks.vblank,
+                            "jump" => &mut config.quirks.jump,
+                            "logic" => &mut config.quirks.logic,
+                            _ => return Err(unknown(text, key)),
+                        };
+                        *quirk = item.as_bool().ok_or_else(|| error(text, item.span(), "expected true or false"))?;
+                    }
+                },
+                "keymap" => {
+                    let mut keymap = Keymap::new();
+                    for (key, item) in entries(table(text, key, item)?) {
+                        // Written as a number, 10 is decimal
+                        let keypad = item
+                            .as_str()
+                            .and_then(|keypad| usize::from_str_radix(keypad, 16).ok())
+                            .or_else(|| item.as_integer().and_then(|keypad| usize::try_from(keypad).ok()))
+                            .filter(|keypad| *keypad < NUM_KEYS)
+                            .ok_or_else(|| error(text, item.span(), "expected a hex key from \"0\" to \"F\""))?;
+                        keymap.bind(key.get(), keypad);
+                    }
+                    config.keymap = Some(keymap);
+                },
+                "paths" => {
+                    for (key, item) in entries(table(text, key, item)?) {
+                        let field = match key.get() {
+                            "saves" => &mut config.paths.saves,
+                            "keymaps" => &mut config.paths.keymaps,
+                            "captures" => &mut config.paths.captures,
+                            _ => return Err(unknown(text, key)),
+                        };
+                        *field = item.as_str().ok_or_else(|| error(text, item.span(), "expected a path"))?.into();
+                    }
+                },
+                _ => return Err(unknown(text, key)),
+            }
+        }
+        Ok(config)
+    }
+
+    // What run_frame() runs a frame for ips
+    pub fn ticks_per_frame(&self) -> usize {
+        ((self.ips as usize + 30) / 60).max(1)
+    }
+
+    // Set chip8 up with the settings it has its own place for, the quirks and speed
+    pub fn apply(&self, chip8: &mut Chip8) {
+        chip8.set_quirks(self.quirks);
+        chip8.set_ticks_per_frame(self.ticks_per_frame());
+    }
+}
+
+// get_key_value() for the key spans, which iter() doesn't give
+fn entries(table: &dyn TableLike) -> impl Iterator<Item = (&Key, &Item)> {
+    table.iter().filter_map(move |(name, _)| table.get_key_value(name))
+}
+
+fn error(text: &str, span: Option<Range<usize>>, message: &str) -> ConfigError {
+    let offset = span.map_or(0, |span| span.start.min(text.len()));
+    ConfigError { line: text[..offset].matches('\n').count() + 1, message: message.trim().to_string() }
+}
+
+fn unknown(text: &str, key: &Key) -> ConfigError {
+    error(text, key.span(), &format!("no setting called {}", key.get()))
+}
+
+fn table<'a>(text: &str, key: &Key, item: &'a Item) -> Result<&'a dyn TableLike, ConfigError> {
+    item.as_table_like().ok_or_else(|| error(text, key.span(), &format!("{} should be a table", key.get())))
+}
+
+// "#RRGGBB"
+fn colour(text: &str, item: &Item) -> Result<[u8; 3], ConfigError> {
+    let rgb = item
+        .as_str()
+        .and_then(|colour| colour.strip_prefix('#'))
+        .filter(|hex| hex.len() == 6)
+        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+        .ok_or_else(|| error(text, item.span(), "expected a colour like \"#FFB000\""))?;
+    let [_, r, g, b] = rgb.to_be_bytes();
+    Ok([r, g, b])
+}
+
+// A TOML basic string, or a bare key where that's allowed
+fn quoted(text: &str, bare: bool) -> String {
+    if bare && !text.is_empty() && text.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return text.to_string();
+    }
+    let mut out = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            },
+            c if c.is_control() => out.push_str(&format!("\\u{:04X}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+impl fmt::Display for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "ips = {}", self.ips)?;
+        writeln!(f, "\n[palette]")?;
+        match Palette::THEMES.iter().find(|(_, palette)| *palette == self.palette) {
+            Some((theme, _)) => writeln!(f, "theme = {}", quoted(theme, false))?,
+            None => {
+                for (name, [r, g, b]) in [("on", self.palette.on), ("off", self.palette.off)] {
+                    writeln!(f, "{} = \"#{:02X}{:02X}{:02X}\"", name, r, g, b)?;
+                }
+            },
+        }
+        let quirks = self.quirks;
+        writeln!(f, "\n[quirks]")?;
+        for (name, on) in [
+            ("shift", quirks.shift),
+            ("load_store", quirks.load_store),
+            ("vf_order", quirks.vf_order),
+            ("clip", quirks.clip),
+            ("vblank", quirks.vblank),
+            ("jump", quirks.jump),
+            ("logic", quirks.logic),
+        ] {
+            writeln!(f, "{} = {}", name, on)?;
+        }
+        if let Some(keymap) = &self.keymap {
+            writeln!(f, "\n[keymap]")?;
+            for (host, key) in keymap.iter() {
+                writeln!(f, "{} = \"{:X}\"", quoted(host, true), key)?;
+            }
+        }
+        writeln!(f, "\n[paths]")?;
+        let paths = &self.paths;
+        for (name, path) in [("saves", &paths.saves), ("keymaps", &paths.keymaps), ("captures", &paths.captures)] {
+            writeln!(f, "{} = {}", name, quoted(&path.to_string_lossy(), false))?;
+        }
+        Ok(())
+    }
+}
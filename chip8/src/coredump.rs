@@ -0,0 +1,98 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use crate::{Chip8, Chip8Error, Chip8State, SavestateError};
+
+const MAGIC: &[u8; 4] = b"C8CD";
+const VERSION: u16 = 1;
+
+// What a machine was doing when it faulted, for attaching to bug reports: the fault, the instructions that
+// led up to it if Chip8::set_recent_instructions() was keeping them, and the whole machine as it was before
+// the faulting instruction, which restore() puts back to look at in a debugger.
+//
+// The file is the magic "C8CD", a u16 version, the fault's message as a u16 length and UTF-8, a u32 count of
+// instructions then each as address and opcode, and a compressed savestate to the end, little-endian
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CoreDump {
+    // The fault as it was reported
+    pub error: String,
+    // Address and opcode of each instruction run before the fault, oldest first
+    pub recent: Vec<(u16, u16)>,
+    savestate: Vec<u8>,
+}
+
+impl CoreDump {
+    // Take a dump of chip8 as clock() or run_frame() left it after returning err
+    pub fn new(chip8: &Chip8, err: &Chip8Error) -> Self {
+        Self {
+            error: err.to_string(),
+            recent: chip8.recent_instructions().collect(),
+            savestate: chip8.save_state_compressed(),
+        }
+    }
+
+    pub fn state(&self) -> Chip8State {
+        Chip8State::from_bytes(&self.savestate).expect("checked when read").0
+    }
+
+    // Put chip8 back as it was, which has to have the same ROM loaded
+    pub fn restore(&self, chip8: &mut Chip8) -> Result<(), SavestateError> {
+        chip8.load_state(&self.savestate)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SavestateError> {
+        let mut file = BufWriter::new(File::create(path)?);
+        self.write(&mut file)?;
+        file.flush()?;
+        Ok(())
+    }
+
+    pub fn write(&self, mut writer: impl Write) -> Result<(), SavestateError> {
+        let error = &self.error.as_bytes()[..self.error.len().min(u16::MAX as usize)];
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&(error.len() as u16).to_le_bytes())?;
+        writer.write_all(error)?;
+        writer.write_all(&(self.recent.len() as u32).to_le_bytes())?;
+        for (addr, opcode) in &self.recent {
+            writer.write_all(&addr.to_le_bytes())?;
+            writer.write_all(&opcode.to_le_bytes())?;
+        }
+        writer.write_all(&self.savestate)?;
+        Ok(())
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, SavestateError> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+
+    pub fn read(mut reader: impl Read) -> Result<Self, SavestateError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        let mut rest = &bytes[..];
+        let mut take = |len: usize| {
+            let (taken, after) = rest.split_at_checked(len).ok_or(SavestateError::Corrupt)?;
+            rest = after;
+            Ok::<_, SavestateError>(taken)
+        };
+        let u16_at = |bytes: &[u8]| u16::from_le_bytes([bytes[0], bytes[1]]);
+        if take(4)? != MAGIC {
+            return Err(SavestateError::Corrupt);
+        }
+        let version = u16_at(take(2)?);
+        if version != VERSION {
+            return Err(SavestateError::Version(version));
+        }
+        let len = u16_at(take(2)?) as usize;
+        let error = String::from_utf8(take(len)?.to_vec()).map_err(|_| SavestateError::Corrupt)?;
+        let count = u32::from_le_bytes(take(4)?.try_into().unwrap()) as usize;
+        let recent = take(count.checked_mul(4).ok_or(SavestateError::Corrupt)?)?
+            .chunks_exact(4)
+            .map(|pair| (u16_at(&pair[..2]), u16_at(&pair[2..])))
+            .collect();
+        let savestate = rest.to_vec();
+        Chip8State::from_bytes(&savestate)?;
+        Ok(Self { error, recent, savestate })
+    }
+}
@@ -0,0 +1,257 @@
+use alloc::vec::Vec;
+
+use crate::{Chip8, Chip8State, Condition, ConditionError, Event, Instruction, Result, Rewind, State, RAM_SIZE};
+
+// Most instructions step_over() and step_out() will run looking for the return, so a subroutine
+// stuck waiting on a key can't hang the caller
+const MAX_STEP_INSTRUCTIONS: usize = 1_000_000;
+// Instructions step_back() can undo by default
+const DEFAULT_HISTORY_DEPTH: usize = 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BreakpointId(pub usize);
+
+#[derive(Debug)]
+pub enum Breakpoint {
+    // Stop before executing the instruction at this address
+    Address(u16),
+    // Stop before any instruction while the condition holds
+    Condition(Condition),
+}
+
+impl Breakpoint {
+    fn hit(&self, chip8: &Chip8) -> bool {
+        match self {
+            Breakpoint::Address(addr) => chip8.pc == *addr,
+            Breakpoint::Condition(condition) => condition.is_true(chip8),
+        }
+    }
+}
+
+// Why a debugger run came back
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stop {
+    // A frame's worth of instructions ran and the timers ticked
+    FrameComplete,
+    // A breakpoint holds for the instruction at the PC, which hasn't run yet
+    Breakpoint(BreakpointId),
+    // A step_over() or step_out() got where it was going
+    StepComplete,
+    // A step_over() or step_out() gave up after MAX_STEP_INSTRUCTIONS
+    StepLimit,
+    Halted,
+}
+
+// Machine state from before an instruction ran, along with where in the frame it was
+struct HistoryEntry {
+    state: Chip8State,
+    frame_ticks: usize,
+}
+
+// Wraps an emulator with breakpoints and stepping. Instructions are counted across every kind of
+// step, so the timers keep ticking once per frame's worth of instructions however the program is run
+pub struct Debugger {
+    chip8: Chip8,
+    breakpoints: Vec<(BreakpointId, Breakpoint)>,
+    next_id: usize,
+    // How much of the frame's budget has gone since the last timer tick, a count of instructions unless the
+    // Chip8's Timing says otherwise
+    frame_ticks: usize,
+    // Set after stopping at a breakpoint so resuming doesn't stop at the same place straight away
+    resuming: bool,
+    history: Rewind<HistoryEntry>,
+}
+
+impl Debugger {
+    pub fn new(chip8: Chip8) -> Self {
+        Self {
+            chip8,
+            breakpoints: Vec::new(),
+            next_id: 0,
+            frame_ticks: 0,
+            resuming: false,
+            history: Rewind::new(DEFAULT_HISTORY_DEPTH),
+        }
+    }
+
+    pub fn chip8(&self) -> &Chip8 {
+        &self.chip8
+    }
+
+    pub fn chip8_mut(&mut self) -> &mut Chip8 {
+        &mut self.chip8
+    }
+
+    pub fn into_inner(self) -> Chip8 {
+        self.chip8
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> BreakpointId {
+        let id = BreakpointId(self.next_id);
+        self.next_id += 1;
+        self.breakpoints.push((id, breakpoint));
+        id
+    }
+
+    // Parse and add a conditional breakpoint, e.g. "pc == 0x32A && v[3] > 10"
+    pub fn add_condition(&mut self, condition: &str) -> core::result::Result<BreakpointId, ConditionError> {
+        let condition = Condition::parse(condition)?;
+        Ok(self.add_breakpoint(Breakpoint::Condition(condition)))
+    }
+
+    // Break at a label from the machine's symbols, None if there's no such label
+    pub fn add_label_breakpoint(&mut self, label: &str) -> Option<BreakpointId> {
+        let addr = self.chip8.symbols().address(label)?;
+        Some(self.add_breakpoint(Breakpoint::Address(addr)))
+    }
+
+    pub fn remove_breakpoint(&mut self, id: BreakpointId) -> bool {
+        let len = self.breakpoints.len();
+        self.breakpoints.retain(|(bp_id, _)| *bp_id != id);
+        self.breakpoints.len() != len
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    pub fn breakpoints(&self) -> impl Iterator<Item = (BreakpointId, &Breakpoint)> {
+        self.breakpoints.iter().map(|(id, bp)| (*id, bp))
+    }
+
+    // First breakpoint that holds right now, if any
+    pub fn breakpoint_hit(&self) -> Option<BreakpointId> {
+        self.breakpoints.iter().find(|(_, bp)| bp.hit(&self.chip8)).map(|(id, _)| *id)
+    }
+
+    // Opcode at the PC, i.e. the next one to run
+    pub fn current_opcode(&self) -> u16 {
+        let pc = self.chip8.pc as usize % RAM_SIZE;
+        let high = self.chip8.buffers.ram[pc] as u16;
+        let low = self.chip8.buffers.ram[(pc + 1) % RAM_SIZE] as u16;
+        (high << 8) | low
+    }
+
+    pub fn current_instruction(&self) -> Option<Instruction> {
+        Instruction::decode(self.current_opcode())
+    }
+
+    // How many instructions step_back() can undo, 0 turns history recording off
+    pub fn set_history_depth(&mut self, depth: usize) {
+        self.history.set_capacity(depth);
+    }
+
+    pub fn history_len(&self) -> usize {
+        self.history.len()
+    }
+
+    // Execute exactly one instruction, ignoring breakpoints
+    pub fn step(&mut self) -> Result<State> {
+        self.resuming = false;
+        self.clock()
+    }
+
+    // Undo the last instruction run through the debugger. Returns false once the history is used up
+    pub fn step_back(&mut self) -> bool {
+        match self.history.pop() {
+            Some(entry) => {
+                self.chip8.restore(&entry.state);
+                self.frame_ticks = entry.frame_ticks;
+                // Carry on from here rather than stopping again at a breakpoint on this instruction
+                self.resuming = true;
+                true
+            },
+            None => false,
+        }
+    }
+
+    // Run until the end of the current frame or until a breakpoint is hit
+    pub fn run_frame(&mut self) -> Result<Stop> {
+        loop {
+            if let Some(stop) = self.check_breakpoints() {
+                return Ok(stop);
+            }
+            if self.clock()? == State::Halted {
+                return Ok(Stop::Halted);
+            }
+            // The timers just ticked, so the frame is over
+            if self.frame_ticks == 0 {
+                return Ok(Stop::FrameComplete);
+            }
+        }
+    }
+
+    // Step one instruction, but run a 2NNN subroutine call through to its return
+    pub fn step_over(&mut self) -> Result<Stop> {
+        let depth = self.chip8.call_stack().len();
+        let is_call = matches!(self.current_instruction(), Some(Instruction::Call(_)));
+        if self.step()? == State::Halted {
+            return Ok(Stop::Halted);
+        }
+        if !is_call {
+            return Ok(Stop::StepComplete);
+        }
+        self.run_until(|chip8| chip8.call_stack().len() <= depth)
+    }
+
+    // Run until the current subroutine returns with its matching 00EE. Outside a subroutine this is a single step
+    pub fn step_out(&mut self) -> Result<Stop> {
+        let depth = self.chip8.call_stack().len();
+        if depth == 0 {
+            return match self.step()? {
+                State::Halted => Ok(Stop::Halted),
+                _ => Ok(Stop::StepComplete),
+            };
+        }
+        self.run_until(|chip8| chip8.call_stack().len() < depth)
+    }
+
+    // Keep executing until done holds, stopping early on breakpoints
+    fn run_until(&mut self, done: impl Fn(&Chip8) -> bool) -> Result<Stop> {
+        for _ in 0..MAX_STEP_INSTRUCTIONS {
+            if done(&self.chip8) {
+                return Ok(Stop::StepComplete);
+            }
+            if let Some(stop) = self.check_breakpoints() {
+                return Ok(stop);
+            }
+            if self.clock()? == State::Halted {
+                return Ok(Stop::Halted);
+            }
+        }
+        Ok(Stop::StepLimit)
+    }
+
+    fn check_breakpoints(&mut self) -> Option<Stop> {
+        if self.resuming {
+            self.resuming = false;
+            return None;
+        }
+        let id = self.breakpoint_hit()?;
+        self.resuming = true;
+        self.chip8.emit(Event::Breakpoint(id));
+        Some(Stop::Breakpoint(id))
+    }
+
+    // Run one instruction, recording history and ticking the timers at the end of each frame
+    fn clock(&mut self) -> Result<State> {
+        if self.history.capacity() > 0 {
+            self.history.push(HistoryEntry { state: self.chip8.snapshot(), frame_ticks: self.frame_ticks });
+        }
+        let cost = self.chip8.frame_cost();
+        let state = match self.chip8.clock() {
+            Ok(state) => state,
+            Err(err) => {
+                // The faulting instruction had no effect, so there's nothing to step back over
+                self.history.pop();
+                return Err(err);
+            },
+        };
+        self.frame_ticks += cost;
+        if self.frame_ticks >= self.chip8.frame_budget() {
+            self.frame_ticks = 0;
+            self.chip8.clock_timers();
+        }
+        Ok(state)
+    }
+}
@@ -0,0 +1,163 @@
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+
+use crate::{Chip8State, HIRES_SCREEN_WIDTH, NUM_KEYS, NUM_REGS, RAM_SIZE};
+
+// A register as StateDiff names it. Sp is the stack depth
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Register {
+    V(u8),
+    I,
+    Pc,
+    Sp,
+    Dt,
+    St,
+}
+
+impl Register {
+    // Parse a name as Display writes it, in either case, e.g. "V5" or "dt"
+    pub fn from_name(name: &str) -> Option<Register> {
+        let register = match name.to_ascii_lowercase().as_str() {
+            "i" => Register::I,
+            "pc" => Register::Pc,
+            "sp" => Register::Sp,
+            "dt" => Register::Dt,
+            "st" => Register::St,
+            name => {
+                let x = name.strip_prefix('v').filter(|x| x.len() == 1)?;
+                Register::V(u8::from_str_radix(x, 16).ok()?)
+            },
+        };
+        Some(register)
+    }
+}
+
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Register::V(x) => write!(f, "V{:X}", x),
+            Register::I => write!(f, "I"),
+            Register::Pc => write!(f, "PC"),
+            Register::Sp => write!(f, "SP"),
+            Register::Dt => write!(f, "DT"),
+            Register::St => write!(f, "ST"),
+        }
+    }
+}
+
+// What differs between two states, from Chip8State::diff(). Each change is given as (before, after)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StateDiff {
+    // V0 to VF, then I, PC, SP, DT and ST
+    pub registers: Vec<(Register, u16, u16)>,
+    // Runs of RAM addresses whose bytes changed, in address order
+    pub memory: Vec<Range<u16>>,
+    // Pixels that flipped as (x, y), on the 128x64 grid the display is kept on whatever the resolution
+    pub pixels: Vec<(u8, u8)>,
+    // Stack entries that changed as (depth, before, after), only counting those in use in either state
+    pub stack: Vec<(usize, u16, u16)>,
+    // Keys pressed or released
+    pub keys: Vec<(usize, bool, bool)>,
+    pub hires: Option<(bool, bool)>,
+    pub halted: Option<(bool, bool)>,
+}
+
+impl StateDiff {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+// A line for a debugger, e.g. "V3 05 -> 07, I 0x300 -> 0x302, RAM 0x300-0x302, 40 pixels"
+impl fmt::Display for StateDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut sep = "";
+        let mut next = || core::mem::replace(&mut sep, ", ");
+        for (register, before, after) in &self.registers {
+            match register {
+                Register::V(_) | Register::Dt | Register::St => {
+                    write!(f, "{}{} {:02X} -> {:02X}", next(), register, before, after)?
+                },
+                Register::I | Register::Pc => write!(f, "{}{} {:#05X} -> {:#05X}", next(), register, before, after)?,
+                Register::Sp => write!(f, "{}{} {} -> {}", next(), register, before, after)?,
+            }
+        }
+        for range in &self.memory {
+            match range.len() {
+                1 => write!(f, "{}RAM {:#05X}", next(), range.start)?,
+                _ => write!(f, "{}RAM {:#05X}-{:#05X}", next(), range.start, range.end - 1)?,
+            }
+        }
+        for (depth, before, after) in &self.stack {
+            write!(f, "{}stack {} {:#05X} -> {:#05X}", next(), depth, before, after)?;
+        }
+        for (key, _, pressed) in &self.keys {
+            write!(f, "{}key {:X} {}", next(), key, if *pressed { "down" } else { "up" })?;
+        }
+        if let Some((_, hires)) = self.hires {
+            write!(f, "{}{}", next(), if hires { "hi-res" } else { "lo-res" })?;
+        }
+        if let Some((_, halted)) = self.halted {
+            write!(f, "{}{}", next(), if halted { "halted" } else { "running" })?;
+        }
+        match self.pixels.len() {
+            0 => (),
+            1 => write!(f, "{}1 pixel", next())?,
+            len => write!(f, "{}{} pixels", next(), len)?,
+        }
+        Ok(())
+    }
+}
+
+impl Chip8State {
+    // What changed going from self to other, e.g. a snapshot() before an instruction and one after
+    pub fn diff(&self, other: &Chip8State) -> StateDiff {
+        let mut diff = StateDiff::default();
+        let registers = (0..NUM_REGS)
+            .map(|x| (Register::V(x as u8), self.v_regi[x] as u16, other.v_regi[x] as u16))
+            .chain([
+                (Register::I, self.i_regi, other.i_regi),
+                (Register::Pc, self.pc, other.pc),
+                (Register::Sp, self.stkp, other.stkp),
+                (Register::Dt, self.delay_t as u16, other.delay_t as u16),
+                (Register::St, self.sound_t as u16, other.sound_t as u16),
+            ]);
+        diff.registers = registers.filter(|(_, before, after)| before != after).collect();
+
+        let mut addr = 0;
+        while addr < RAM_SIZE {
+            if self.ram[addr] == other.ram[addr] {
+                addr += 1;
+                continue;
+            }
+            let start = addr;
+            while addr < RAM_SIZE && self.ram[addr] != other.ram[addr] {
+                addr += 1;
+            }
+            diff.memory.push(start as u16..addr as u16);
+        }
+
+        for (y, (before, after)) in self.display.iter().zip(&other.display).enumerate() {
+            let mut changed = before ^ after;
+            while changed != 0 {
+                let x = changed.leading_zeros() as usize;
+                diff.pixels.push((x as u8, y as u8));
+                changed &= !(1 << (HIRES_SCREEN_WIDTH - 1 - x));
+            }
+        }
+
+        let depth = self.stkp.max(other.stkp) as usize;
+        diff.stack = (0..depth)
+            .map(|idx| (idx, self.stack[idx], other.stack[idx]))
+            .filter(|(_, before, after)| before != after)
+            .collect();
+        diff.keys = (0..NUM_KEYS)
+            .map(|key| (key, self.keys[key], other.keys[key]))
+            .filter(|(_, before, after)| before != after)
+            .collect();
+        diff.hires = (self.hires != other.hires).then_some((self.hires, other.hires));
+        diff.halted = (self.halted != other.halted).then_some((self.halted, other.halted));
+        diff
+    }
+}
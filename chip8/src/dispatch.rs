@@ -0,0 +1,526 @@
+use crate::{CallFrame, Chip8, Result, Variant, HIRES_SCREEN_WIDTH};
+#[cfg(feature = "alloc")]
+use crate::Event;
+
+// Runs one opcode. The top nibble indexes straight into a table of handlers, and the groups that share a
+// top nibble index a second table on their low bits, so there's no decoding to an Instruction on the way.
+// Instruction::decode() has to agree with these tables about which opcodes exist
+pub(crate) type Handler = fn(&mut Chip8, u16) -> Result<()>;
+
+const TOP: [Handler; 16] = [
+    group_0, jmp, call, skip_eq_imm, skip_ne_imm, skip_eq_reg, load_imm, add_imm,
+    group_8, skip_ne_reg, load_i, jmp_v0, rand, draw, group_e, group_f,
+];
+
+const GROUP_8: [Handler; 16] = [
+    mov, or, and, xor, add, sub, shr, subn,
+    unknown, unknown, unknown, unknown, unknown, unknown, shl, unknown,
+];
+
+// FX instructions by their low byte
+const GROUP_F: [Handler; 256] = {
+    let mut table: [Handler; 256] = [unknown; 256];
+    table[0x07] = load_delay;
+    table[0x0A] = wait_key;
+    table[0x15] = set_delay;
+    table[0x18] = set_sound;
+    table[0x1E] = add_i;
+    table[0x29] = font;
+    table[0x30] = big_font;
+    table[0x33] = bcd;
+    table[0x55] = store;
+    table[0x65] = load;
+    table
+};
+
+pub(crate) fn execute(chip8: &mut Chip8, opcode: u16) -> Result<()> {
+    TOP[(opcode >> 12) as usize](chip8, opcode)
+}
+
+// The handler execute() would run opcode with, looked up once for running it many times
+#[cfg(feature = "alloc")]
+pub(crate) fn handler(opcode: u16) -> Handler {
+    match opcode >> 12 {
+        0x8 => GROUP_8[(opcode & 0xF) as usize],
+        0xF => GROUP_F[(opcode & 0xFF) as usize],
+        top => TOP[top as usize],
+    }
+}
+
+fn x(opcode: u16) -> usize {
+    ((opcode >> 8) & 0xF) as usize
+}
+
+fn y(opcode: u16) -> usize {
+    ((opcode >> 4) & 0xF) as usize
+}
+
+fn nn(opcode: u16) -> u8 {
+    (opcode & 0xFF) as u8
+}
+
+fn nnn(opcode: u16) -> u16 {
+    opcode & 0xFFF
+}
+
+fn unknown(chip8: &mut Chip8, opcode: u16) -> Result<()> {
+    chip8.unknown_opcode(opcode)
+}
+
+fn clear_display(chip8: &mut Chip8) {
+    chip8.buffers.display.fill(0);
+    #[cfg(feature = "alloc")]
+    {
+        chip8.emit(Event::ScreenCleared);
+        chip8.display_changed(u64::MAX);
+    }
+}
+
+fn group_0(chip8: &mut Chip8, opcode: u16) -> Result<()> {
+    match opcode {
+        // NOP - Do nothing
+        0x0000 => (),
+
+        // CLS - Clear display
+        0x00E0 => {
+            clear_display(chip8);
+        },
+
+        // RET - Return from subroutine
+        0x00EE => {
+            let return_address: u16 = chip8.pop()?;
+            chip8.pc = return_address;
+        },
+
+        // SCROLL RIGHT - Shift the display right by 4 pixels (SCHIP)
+        0x00FB => {
+            chip8.scroll_right(4);
+        },
+
+        // SCROLL LEFT - Shift the display left by 4 pixels (SCHIP)
+        0x00FC => {
+            chip8.scroll_left(4);
+        },
+
+        // EXIT - Stop the interpreter (SCHIP)
+        0x00FD => {
+            chip8.halt();
+        },
+
+        // LORES - Switch to 64x32 mode and clear the display (SCHIP)
+        0x00FE => {
+            chip8.hires = false;
+            clear_display(chip8);
+        },
+
+        // HIRES - Switch to 128x64 mode and clear the display (SCHIP)
+        0x00FF => {
+            chip8.hires = true;
+            clear_display(chip8);
+        },
+
+        // SCROLL DOWN N - Shift the display down by N pixels (SCHIP)
+        _ if opcode & 0xFFF0 == 0x00C0 => {
+            chip8.scroll_down((opcode & 0xF) as usize);
+        },
+
+        _ => return chip8.unknown_opcode(opcode),
+    }
+    Ok(())
+}
+
+// JMP NNN - Move the program counter to a given address
+fn jmp(chip8: &mut Chip8, opcode: u16) -> Result<()> {
+    chip8.pc = nnn(opcode);
+    Ok(())
+}
+
+// CALL NNN - Call subroutine
+fn call(chip8: &mut Chip8, opcode: u16) -> Result<()> {
+    let nnn = nnn(opcode);
+    chip8.push(chip8.pc)?;
+    chip8.call_stack[chip8.stkp as usize - 1] = CallFrame {
+        call_site: chip8.op_pc,
+        target: nnn,
+        return_address: chip8.pc,
+    };
+    chip8.pc = nnn;
+    Ok(())
+}
+
+// SKIP VX == NN - Skip if equal
+fn skip_eq_imm(chip8: &mut Chip8, opcode: u16) -> Result<()> {
+    if chip8.v_regi[x(opcode)] == nn(opcode) {
+        chip8.pc += 2;
+    }
+    Ok(())
+}
+
+// SKIP VX != NN - Skip not equal
+fn skip_ne_imm(chip8: &mut Chip8, opcode: u16) -> Result<()> {
+    if chip8.v_regi[x(opcode)] != nn(opcode) {
+        chip8.pc += 2;
+    }
+    Ok(())
+}
+
+// SKIP VX == VY - Skip if VX == VY
+fn skip_eq_reg(chip8: &mut Chip8, opcode: u16) -> Result<()> {
+    if chip8.v_regi[x(opcode)] == chip8.v_regi[y(opcode)] {
+        chip8.pc += 2;
+    }
+    Ok(())
+}
+
+// VX = NN - Set V register to given value
+fn load_imm(chip8: &mut Chip8, opcode: u16) -> Result<()> {
+    chip8.v_regi[x(opcode)] = nn(opcode);
+    Ok(())
+}
+
+// VX += NN - Add given value to VX reigister
+fn add_imm(chip8: &mut Chip8, opcode: u16) -> Result<()> {
+    let x = x(opcode);
+    chip8.v_regi[x] = chip8.v_regi[x].wrapping_add(nn(opcode));
+    Ok(())
+}
+
+// 8XYN arithmetic and logic, picked by N
+fn group_8(chip8: &mut Chip8, opcode: u16) -> Result<()> {
+    GROUP_8[(opcode & 0xF) as usize](chip8, opcode)
+}
+
+// VX = VY - Set a register x to the same value as a register y
+fn mov(chip8: &mut Chip8, opcode: u16) -> Result<()> {
+    chip8.v_regi[x(opcode)] = chip8.v_regi[y(opcode)];
+    Ok(())
+}
+
+// VX |= VY - Bitwise OR
+fn or(chip8: &mut Chip8, opcode: u16) -> Result<()> {
+    chip8.v_regi[x(opcode)] |= chip8.v_regi[y(opcode)];
+    if chip8.quirks.logic {
+        chip8.v_regi[0xF] = 0;
+    }
+    Ok(())
+}
+
+// VX &= VY - Bitwise AND
+fn and(chip8: &mut Chip8, opcode: u16) -> Result<()> {
+    chip8.v_regi[x(opcode)] &= chip8.v_regi[y(opcode)];
+    if chip8.quirks.logic {
+        chip8.v_regi[0xF] = 0;
+    }
+    Ok(())
+}
+
+// VX ^= VY - Bitwise XOR
+fn xor(chip8: &mut Chip8, opcode: u16) -> Result<()> {
+    chip8.v_regi[x(opcode)] ^= chip8.v_regi[y(opcode)];
+    if chip8.quirks.logic {
+        chip8.v_regi[0xF] = 0;
+    }
+    Ok(())
+}
+
+// VX += VY - Add with carry
+fn add(chip8: &mut Chip8, opcode: u16) -> Result<()> {
+    let x = x(opcode);
+    let (new_vx, carry) = chip8.v_regi[x].overflowing_add(chip8.v_regi[y(opcode)]);
+    let new_vf = if carry { 1 } else { 0 };
+    set_with_flag(chip8, x, new_vx, new_vf);
+    Ok(())
+}
+
+// VX -= VY - Subtract with carry
+fn sub(chip8: &mut Chip8, opcode: u16) -> Result<()> {
+    let x = x(opcode);
+    let (new_vx, borrow) = chip8.v_regi[x].overflowing_sub(chip8.v_regi[y(opcode)]);
+    let new_vf = if borrow { 0 } else { 1 };
+    set_with_flag(chip8, x, new_vx, new_vf);
+    Ok(())
+}
+
+// VX >>= 1 - Shift right with dropoff stored in carry. VX = VY >> 1 without Quirks::shift
+fn shr(chip8: &mut Chip8, opcode: u16) -> Result<()> {
+    let x = x(opcode);
+    let value = chip8.v_regi[shift_source(chip8, opcode)];
+    set_with_flag(chip8, x, value >> 1, value & 1);
+    Ok(())
+}
+
+// VX = VY - VX - Subtract with carry, reversed operands
+fn subn(chip8: &mut Chip8, opcode: u16) -> Result<()> {
+    let x = x(opcode);
+    let (new_vx, borrow) = chip8.v_regi[y(opcode)].overflowing_sub(chip8.v_regi[x]);
+    let new_vf = if borrow { 0 } else { 1 };
+    set_with_flag(chip8, x, new_vx, new_vf);
+    Ok(())
+}
+
+// VX <<= 1 - Left shift with dropoff stored in flag. VX = VY << 1 without Quirks::shift
+fn shl(chip8: &mut Chip8, opcode: u16) -> Result<()> {
+    let x = x(opcode);
+    let value = chip8.v_regi[shift_source(chip8, opcode)];
+    set_with_flag(chip8, x, value << 1, value >> 7);
+    Ok(())
+}
+
+// The register 8XY6 and 8XYE shift
+fn shift_source(chip8: &Chip8, opcode: u16) -> usize {
+    if chip8.quirks.shift {
+        x(opcode)
+    } else {
+        y(opcode)
+    }
+}
+
+// Write an 8XYN result to VX and its flag to VF, in the order Quirks::vf_order picks, which matters when X is F
+fn set_with_flag(chip8: &mut Chip8, x: usize, value: u8, flag: u8) {
+    if chip8.quirks.vf_order {
+        chip8.v_regi[0xF] = flag;
+        chip8.v_regi[x] = value;
+    } else {
+        chip8.v_regi[x] = value;
+        chip8.v_regi[0xF] = flag;
+    }
+}
+
+// SKIP VX != VY - Skip if VX != VY
+fn skip_ne_reg(chip8: &mut Chip8, opcode: u16) -> Result<()> {
+    if opcode & 0xF != 0 {
+        return chip8.unknown_opcode(opcode);
+    }
+    if chip8.v_regi[x(opcode)] != chip8.v_regi[y(opcode)] {
+        chip8.pc += 2;
+    }
+    Ok(())
+}
+
+// I = NNN - Set I register
+fn load_i(chip8: &mut Chip8, opcode: u16) -> Result<()> {
+    chip8.i_regi = nnn(opcode);
+    Ok(())
+}
+
+// JMP V0 + NNN - Jump to V0 + NNN, or VX + NNN with Quirks::jump
+fn jmp_v0(chip8: &mut Chip8, opcode: u16) -> Result<()> {
+    let reg = if chip8.quirks.jump { x(opcode) } else { 0 };
+    chip8.pc = (chip8.v_regi[reg] as u16) + nnn(opcode);
+    Ok(())
+}
+
+// VX = rand() & NN - Generate random number and store in VX register
+fn rand(chip8: &mut Chip8, opcode: u16) -> Result<()> {
+    let rng: u8 = chip8.rng.next_u8();
+    chip8.v_regi[x(opcode)] = rng & nn(opcode);
+    Ok(())
+}
+
+// DRAW - Draw sprite on screen at location (VX, VY). Sprites are always 8 pixels wide, but height
+// of sprite is stored in N. Sprites are stored row by row starting from location stored in register I.
+// A height of 0 draws a 16x16 sprite from 32 bytes in SCHIP, and nothing on the original CHIP-8.
+fn draw(chip8: &mut Chip8, opcode: u16) -> Result<()> {
+    // With Quirks::vblank a second sprite in a frame waits for the next, redoing the opcode until then
+    if chip8.quirks.vblank {
+        if chip8.drawn {
+            chip8.pc = chip8.op_pc;
+            return Ok(());
+        }
+        chip8.drawn = true;
+    }
+    // Get the (x, y) coords for our sprite
+    let x = chip8.v_regi[x(opcode)] as usize;
+    let y = chip8.v_regi[y(opcode)] as usize;
+    // The last digit determines how many rows high our sprite is
+    let n = (opcode & 0xF) as usize;
+    let (sprite_width, num_rows) = match n {
+        0 if chip8.hires || chip8.variant == Variant::SuperChip => (16, 16),
+        _ => (8, n),
+    };
+    let bytes_per_row = sprite_width / 8;
+    let (width, height) = (chip8.width(), chip8.height());
+
+    // Read the whole sprite up front so a bad I faults before the screen is touched.
+    // Rows are left-aligned in a u16 so both sprite widths are masked the same way
+    let mut rows = [0u16; 16];
+    for (y_line, row) in rows.iter_mut().enumerate().take(num_rows) {
+        // Determine which memory address our row's data is stored
+        let addr = chip8.i_regi as usize + y_line * bytes_per_row;
+        *row = (chip8.mem_read(addr)? as u16) << 8;
+        if bytes_per_row == 2 {
+            *row |= chip8.mem_read(addr + 1)? as u16;
+        }
+    }
+
+    // Keep track if any pixels were flipped, and which rows changed
+    let mut flipped = false;
+    #[cfg(feature = "alloc")]
+    let mut changed = 0u64;
+    let clip = chip8.quirks.clip;
+    let (x, y) = (x % width, y % height);
+    let display = &mut chip8.buffers.display;
+    for (y_line, pixels) in rows.iter().enumerate().take(num_rows) {
+        // Clipped sprites stop at the bottom edge
+        if clip && y + y_line >= height {
+            break;
+        }
+        // Line the sprite row up with the screen row and rotate it into place, which wraps pixels off the
+        // right edge around to the left, or shift it there to drop them when clipping. Lo-res uses the top
+        // 64 bits
+        let shifted = match (width == HIRES_SCREEN_WIDTH, clip) {
+            (true, false) => ((*pixels as u128) << 112).rotate_right(x as u32),
+            (true, true) => ((*pixels as u128) << 112) >> x,
+            (false, false) => (((*pixels as u64) << 48).rotate_right(x as u32) as u128) << 64,
+            (false, true) => ((((*pixels as u64) << 48) >> x) as u128) << 64,
+        };
+        // Sprites should wrap around screen, so apply modulo
+        let row = &mut display[(y + y_line) % height];
+        flipped |= *row & shifted != 0;
+        *row ^= shifted;
+        #[cfg(feature = "alloc")]
+        if shifted != 0 {
+            changed |= 1 << ((y + y_line) % height);
+        }
+    }
+    #[cfg(feature = "alloc")]
+    if changed != 0 {
+        chip8.display_changed(changed);
+    }
+    // Populate VF register
+    if flipped {
+        chip8.v_regi[0xF] = 1;
+    } else {
+        chip8.v_regi[0xF] = 0;
+    }
+    Ok(())
+}
+
+fn group_e(chip8: &mut Chip8, opcode: u16) -> Result<()> {
+    // Only the low nibble picks a key, as on the original interpreter
+    let key = chip8.keys[(chip8.v_regi[x(opcode)] & 0xF) as usize];
+    match opcode & 0xFF {
+        // SKIP KEY PRESS - Skip if key stored in VX is pressed
+        0x9E => {
+            if key {
+                chip8.pc += 2;
+            }
+        },
+
+        // SKIP KEY RELEASE - Skip if key stored in VX isnot pressed
+        0xA1 => {
+            if !key {
+                chip8.pc += 2;
+            }
+        },
+
+        _ => return chip8.unknown_opcode(opcode),
+    }
+    Ok(())
+}
+
+// FX instructions, picked by the low byte
+fn group_f(chip8: &mut Chip8, opcode: u16) -> Result<()> {
+    GROUP_F[(opcode & 0xFF) as usize](chip8, opcode)
+}
+
+// VX = DT - Stores delay timer in a register specified by X
+fn load_delay(chip8: &mut Chip8, opcode: u16) -> Result<()> {
+    chip8.v_regi[x(opcode)] = chip8.delay_t;
+    Ok(())
+}
+
+// WAIT KEY - Block until key pressed
+fn wait_key(chip8: &mut Chip8, opcode: u16) -> Result<()> {
+    let key = waited_key(chip8);
+    match key {
+        Some(key) => chip8.v_regi[x(opcode)] = key as u8,
+        // Redo opcode. Wrapping, as the PC is 0 after an opcode at the very end of RAM wrapped around
+        None => chip8.pc = chip8.pc.wrapping_sub(2),
+    }
+    #[cfg(feature = "alloc")]
+    chip8.key_wait(key.is_none());
+    Ok(())
+}
+
+// The key FX0A takes, if there's one yet: any held down, or with the key queue the first pressed and released
+// since the wait began
+fn waited_key(chip8: &mut Chip8) -> Option<usize> {
+    #[cfg(feature = "alloc")]
+    if chip8.key_queue.is_some() {
+        return chip8.key_released();
+    }
+    chip8.keys.iter().position(|pressed| *pressed)
+}
+
+// DT = VX - Set delay timer to value in VX
+fn set_delay(chip8: &mut Chip8, opcode: u16) -> Result<()> {
+    chip8.delay_t = chip8.v_regi[x(opcode)];
+    Ok(())
+}
+
+// ST = VX - Set sound timer to value in VX
+fn set_sound(chip8: &mut Chip8, opcode: u16) -> Result<()> {
+    chip8.set_sound_timer(chip8.v_regi[x(opcode)]);
+    Ok(())
+}
+
+// I += VX - Add VX to I
+fn add_i(chip8: &mut Chip8, opcode: u16) -> Result<()> {
+    let vx = chip8.v_regi[x(opcode)] as u16;
+    chip8.i_regi = chip8.i_regi.wrapping_add(vx);
+    Ok(())
+}
+
+// I = FONT - Set I to font address
+fn font(chip8: &mut Chip8, opcode: u16) -> Result<()> {
+    let c = chip8.v_regi[x(opcode)] as u16;
+    chip8.i_regi = chip8.font_address() + c * 5;
+    Ok(())
+}
+
+// I = BIG FONT - Set I to large font address (SCHIP)
+fn big_font(chip8: &mut Chip8, opcode: u16) -> Result<()> {
+    let c = (chip8.v_regi[x(opcode)] & 0xF) as u16;
+    chip8.i_regi = chip8.large_font_address() + c * 10;
+    Ok(())
+}
+
+// BCD - Store BCD(VX) in I
+fn bcd(chip8: &mut Chip8, opcode: u16) -> Result<()> {
+    let vx = chip8.v_regi[x(opcode)];
+    let i = chip8.i_regi as usize;
+    chip8.check_range(i, 3)?;
+    chip8.mem_write(i, vx / 100)?;
+    chip8.mem_write(i + 1, vx / 10 % 10)?;
+    chip8.mem_write(i + 2, vx % 10)?;
+    Ok(())
+}
+
+// STORE V0 - VX - Store V0 - VX at I, then I += X + 1 without Quirks::load_store
+fn store(chip8: &mut Chip8, opcode: u16) -> Result<()> {
+    let x = x(opcode);
+    let i = chip8.i_regi as usize;
+    chip8.check_range(i, x + 1)?;
+    for idx in 0..=x {
+        chip8.mem_write(i + idx, chip8.v_regi[idx])?;
+    }
+    if !chip8.quirks.load_store {
+        chip8.i_regi = chip8.i_regi.wrapping_add(x as u16 + 1);
+    }
+    Ok(())
+}
+
+// LOAD V0 - VX - Load V0 - VX from I, then I += X + 1 without Quirks::load_store
+fn load(chip8: &mut Chip8, opcode: u16) -> Result<()> {
+    let x = x(opcode);
+    let i = chip8.i_regi as usize;
+    chip8.check_range(i, x + 1)?;
+    for idx in 0..=x {
+        chip8.v_regi[idx] = chip8.mem_read(i + idx)?;
+    }
+    if !chip8.quirks.load_store {
+        chip8.i_regi = chip8.i_regi.wrapping_add(x as u16 + 1);
+    }
+    Ok(())
+}
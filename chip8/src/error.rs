@@ -0,0 +1,44 @@
+use core::fmt;
+
+// Faults raised while running a program. The faulting instruction has no effect
+// and the PC is left pointing at it, so a frontend can report it and reset or carry on.
+// RomTooLarge comes from load() instead, which leaves memory as it was
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Chip8Error {
+    // An instruction read or wrote memory outside of RAM
+    MemoryOutOfBounds { addr: usize, pc: u16 },
+    // A CALL was made with all 16 stack entries in use
+    StackOverflow { pc: u16 },
+    // A RET was made with nothing on the stack
+    StackUnderflow { pc: u16 },
+    // The PC left program memory while PcPolicy::Fault is set
+    PcOutOfBounds { pc: u16 },
+    // The opcode isn't recognised and UnknownOpcodePolicy didn't handle it
+    UnknownOpcode { opcode: u16, pc: u16 },
+    // The ROM is len bytes but only max fit between the start address and the end of RAM
+    RomTooLarge { len: usize, max: usize },
+}
+
+impl fmt::Display for Chip8Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Chip8Error::MemoryOutOfBounds { addr, pc } => {
+                write!(f, "memory access out of bounds at {:#05X} (PC {:#05X})", addr, pc)
+            },
+            Chip8Error::StackOverflow { pc } => write!(f, "stack overflow (PC {:#05X})", pc),
+            Chip8Error::StackUnderflow { pc } => write!(f, "stack underflow (PC {:#05X})", pc),
+            Chip8Error::PcOutOfBounds { pc } => write!(f, "PC left program memory ({:#05X})", pc),
+            Chip8Error::UnknownOpcode { opcode, pc } => {
+                write!(f, "unknown opcode {:04X} (PC {:#05X})", opcode, pc)
+            },
+            Chip8Error::RomTooLarge { len, max } => {
+                write!(f, "ROM is {} bytes, only {} fit in memory", len, max)
+            },
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Chip8Error {}
+
+pub type Result<T> = core::result::Result<T, Chip8Error>;
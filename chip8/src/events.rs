@@ -0,0 +1,72 @@
+use alloc::collections::VecDeque;
+
+use crate::{BreakpointId, Chip8, Chip8Error, KeyEvent};
+
+// Events kept waiting to be drained, beyond which the oldest are dropped
+const MAX_EVENTS: usize = 256;
+
+// Something a frontend may want to react to, reported as it happens in place of polling flags. See
+// Chip8::enable_events()
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    // 00E0, or SCHIP's 00FE and 00FF, which clear the screen as they change resolution
+    ScreenCleared,
+    // The sound timer was set going from 0
+    BeepStarted,
+    // The sound timer ran down or was set to 0
+    BeepStopped,
+    // FX0A began waiting for a key, once per wait however many times it's redone
+    KeyWaitStarted,
+    // 00FD, or a policy halting the machine
+    Halted,
+    // A keypad key went down or up, with the key queue on
+    Key(KeyEvent),
+    // A Debugger stopped at a breakpoint
+    Breakpoint(BreakpointId),
+    // clock() or run_frame() reported the fault
+    Fault(Chip8Error),
+}
+
+pub(crate) struct Events {
+    queue: VecDeque<Event>,
+    // An FX0A wait has been reported and no key has ended it yet
+    key_wait: bool,
+}
+
+impl Chip8 {
+    // Start collecting Events for drain_events(), or stop and throw away any not yet drained. Only the
+    // latest 256 are kept, so drain them every frame or so
+    pub fn enable_events(&mut self, enabled: bool) {
+        if !enabled {
+            self.events = None;
+        } else if self.events.is_none() {
+            self.events = Some(Events { queue: VecDeque::new(), key_wait: false });
+        }
+    }
+
+    // Events since the last drain, oldest first. There are none while they're not enabled
+    pub fn drain_events(&mut self) -> impl Iterator<Item = Event> + '_ {
+        self.events.iter_mut().flat_map(|events| events.queue.drain(..))
+    }
+
+    // Whether FX0A is still waiting, reporting the start of each wait
+    pub(crate) fn key_wait(&mut self, waiting: bool) {
+        let Some(events) = &mut self.events else {
+            return;
+        };
+        let started = waiting && !events.key_wait;
+        events.key_wait = waiting;
+        if started {
+            self.emit(Event::KeyWaitStarted);
+        }
+    }
+
+    pub(crate) fn emit(&mut self, event: Event) {
+        if let Some(events) = &mut self.events {
+            if events.queue.len() == MAX_EVENTS {
+                events.queue.pop_front();
+            }
+            events.queue.push_back(event);
+        }
+    }
+}
@@ -0,0 +1,390 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::Chip8;
+
+// How deep brackets, indexes, unary operators and chains of binary operators may nest. Parsing, evaluating
+// and dropping a condition all recurse that deep, and conditions come from debugger clients
+const MAX_DEPTH: usize = 64;
+
+// A breakpoint condition such as `pc == 0x32A && v[3] > 10` or `ram[0x400] != 0`.
+//
+// Values: decimal or 0x hex literals, pc, i, dt, st, sp (stack depth), v[n] or v0 - vf, ram[addr].
+// Operators by increasing precedence: ||, &&, |, ^, &, == !=, < <= > >=, + -, and unary ! -.
+// Parentheses group as usual. Anything nonzero is true, out of range v[] and ram[] reads give 0
+pub struct Condition {
+    source: String,
+    expr: Expr,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConditionError {
+    // Byte offset into the source where parsing gave up
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ConditionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at column {}", self.message, self.position + 1)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ConditionError {}
+
+impl Condition {
+    pub fn parse(source: &str) -> Result<Condition, ConditionError> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens, pos: 0, end: source.len(), depth: 0 };
+        let expr = parser.parse_binary(0)?;
+        if let Some((position, token)) = parser.tokens.get(parser.pos) {
+            return Err(ConditionError { position: *position, message: format!("unexpected {}", token) });
+        }
+        Ok(Condition { source: source.to_string(), expr })
+    }
+
+    pub fn eval(&self, chip8: &Chip8) -> i64 {
+        self.expr.eval(chip8)
+    }
+
+    pub fn is_true(&self, chip8: &Chip8) -> bool {
+        self.eval(chip8) != 0
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl fmt::Debug for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Condition({:?})", self.source)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Var {
+    Pc,
+    I,
+    Dt,
+    St,
+    Sp,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum BinOp {
+    Or,
+    And,
+    BitOr,
+    BitXor,
+    BitAnd,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+}
+
+impl BinOp {
+    // Binding strength, higher binds tighter
+    fn precedence(self) -> u8 {
+        match self {
+            BinOp::Or => 1,
+            BinOp::And => 2,
+            BinOp::BitOr => 3,
+            BinOp::BitXor => 4,
+            BinOp::BitAnd => 5,
+            BinOp::Eq | BinOp::Ne => 6,
+            BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge => 7,
+            BinOp::Add | BinOp::Sub => 8,
+        }
+    }
+}
+
+enum Expr {
+    Num(i64),
+    Var(Var),
+    Reg(Box<Expr>),
+    Ram(Box<Expr>),
+    Not(Box<Expr>),
+    Neg(Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn eval(&self, chip8: &Chip8) -> i64 {
+        match self {
+            Expr::Num(n) => *n,
+            Expr::Var(Var::Pc) => chip8.pc as i64,
+            Expr::Var(Var::I) => chip8.i_regi as i64,
+            Expr::Var(Var::Dt) => chip8.delay_t as i64,
+            Expr::Var(Var::St) => chip8.sound_t as i64,
+            Expr::Var(Var::Sp) => chip8.stkp as i64,
+            Expr::Reg(idx) => {
+                let idx = idx.eval(chip8);
+                usize::try_from(idx).ok().and_then(|x| chip8.v_regi.get(x)).map_or(0, |v| *v as i64)
+            },
+            Expr::Ram(addr) => {
+                let addr = addr.eval(chip8);
+                usize::try_from(addr).ok().and_then(|a| chip8.buffers.ram.get(a)).map_or(0, |b| *b as i64)
+            },
+            Expr::Not(e) => (e.eval(chip8) == 0) as i64,
+            Expr::Neg(e) => e.eval(chip8).wrapping_neg(),
+            Expr::Binary(op, lhs, rhs) => {
+                let lhs = lhs.eval(chip8);
+                // Short-circuit the logical operators
+                match op {
+                    BinOp::Or if lhs != 0 => return 1,
+                    BinOp::And if lhs == 0 => return 0,
+                    _ => (),
+                }
+                let rhs = rhs.eval(chip8);
+                match op {
+                    BinOp::Or | BinOp::And => (rhs != 0) as i64,
+                    BinOp::BitOr => lhs | rhs,
+                    BinOp::BitXor => lhs ^ rhs,
+                    BinOp::BitAnd => lhs & rhs,
+                    BinOp::Eq => (lhs == rhs) as i64,
+                    BinOp::Ne => (lhs != rhs) as i64,
+                    BinOp::Lt => (lhs < rhs) as i64,
+                    BinOp::Le => (lhs <= rhs) as i64,
+                    BinOp::Gt => (lhs > rhs) as i64,
+                    BinOp::Ge => (lhs >= rhs) as i64,
+                    BinOp::Add => lhs.wrapping_add(rhs),
+                    BinOp::Sub => lhs.wrapping_sub(rhs),
+                }
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Num(i64),
+    Ident(String),
+    Op(&'static str),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Num(n) => write!(f, "number {}", n),
+            Token::Ident(name) => write!(f, "'{}'", name),
+            Token::Op(op) => write!(f, "'{}'", op),
+        }
+    }
+}
+
+// Longest operators first so "<=" isn't read as "<" then "="
+const OPERATORS: [&str; 19] = [
+    "||", "&&", "==", "!=", "<=", ">=", "<", ">", "|", "^", "&", "+", "-", "!", "(", ")", "[", "]", "=",
+];
+
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, ConditionError> {
+    let bytes = source.as_bytes();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let c = bytes[pos];
+        if c.is_ascii_whitespace() {
+            pos += 1;
+        } else if c.is_ascii_digit() {
+            let start = pos;
+            while pos < bytes.len() && bytes[pos].is_ascii_alphanumeric() {
+                pos += 1;
+            }
+            let text = &source[start..pos];
+            let value = match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+                Some(hex) => i64::from_str_radix(hex, 16),
+                None => text.parse(),
+            };
+            match value {
+                Ok(n) => tokens.push((start, Token::Num(n))),
+                Err(_) => {
+                    return Err(ConditionError { position: start, message: format!("bad number '{}'", text) })
+                },
+            }
+        } else if c.is_ascii_alphabetic() || c == b'_' {
+            let start = pos;
+            while pos < bytes.len() && (bytes[pos].is_ascii_alphanumeric() || bytes[pos] == b'_') {
+                pos += 1;
+            }
+            tokens.push((start, Token::Ident(source[start..pos].to_ascii_lowercase())));
+        } else {
+            let op = OPERATORS.iter().find(|op| source[pos..].starts_with(**op));
+            match op {
+                // A lone '=' is almost certainly a typo for '=='
+                Some(&"=") => {
+                    return Err(ConditionError { position: pos, message: String::from("'=' should be '=='") })
+                },
+                Some(op) => {
+                    tokens.push((pos, Token::Op(op)));
+                    pos += op.len();
+                },
+                None => {
+                    let ch = source[pos..].chars().next().unwrap_or('?');
+                    return Err(ConditionError { position: pos, message: format!("unexpected '{}'", ch) });
+                },
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    // Source length, reported as the position of errors at the end of input
+    end: usize,
+    // How deep the expression being parsed is nested
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, token)| token)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens.get(self.pos).map_or(self.end, |(position, _)| *position)
+    }
+
+    fn error(&self, message: String) -> ConditionError {
+        ConditionError { position: self.position(), message }
+    }
+
+    fn expect(&mut self, op: &'static str) -> Result<(), ConditionError> {
+        if self.peek() == Some(&Token::Op(op)) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(format!("expected '{}'", op)))
+        }
+    }
+
+    // Go a level deeper, failing past MAX_DEPTH. Callers put depth back when they're done
+    fn nest(&mut self) -> Result<(), ConditionError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(self.error(String::from("condition nested too deeply")));
+        }
+        Ok(())
+    }
+
+    // Precedence climbing over the binary operators
+    fn parse_binary(&mut self, min_precedence: u8) -> Result<Expr, ConditionError> {
+        let depth = self.depth;
+        let mut lhs = self.parse_unary()?;
+        while let Some(op) = self.peek().and_then(binary_op) {
+            if op.precedence() < min_precedence {
+                break;
+            }
+            // Each operator in a chain puts what's before it a level deeper
+            self.nest()?;
+            self.pos += 1;
+            let rhs = self.parse_binary(op.precedence() + 1)?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        self.depth = depth;
+        Ok(lhs)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, ConditionError> {
+        let wrap = match self.peek() {
+            Some(Token::Op("!")) => Expr::Not,
+            Some(Token::Op("-")) => Expr::Neg,
+            _ => return self.parse_primary(),
+        };
+        self.nest()?;
+        self.pos += 1;
+        let expr = wrap(Box::new(self.parse_unary()?));
+        self.depth -= 1;
+        Ok(expr)
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, ConditionError> {
+        let token = match self.peek() {
+            Some(token) => token.clone(),
+            None => return Err(self.error(String::from("unexpected end of condition"))),
+        };
+        match token {
+            Token::Num(n) => {
+                self.pos += 1;
+                Ok(Expr::Num(n))
+            },
+            Token::Op("(") => {
+                self.nest()?;
+                self.pos += 1;
+                let expr = self.parse_binary(0)?;
+                self.expect(")")?;
+                self.depth -= 1;
+                Ok(expr)
+            },
+            Token::Ident(name) => {
+                let var = match name.as_str() {
+                    "pc" => Some(Var::Pc),
+                    "i" => Some(Var::I),
+                    "dt" => Some(Var::Dt),
+                    "st" => Some(Var::St),
+                    "sp" => Some(Var::Sp),
+                    _ => None,
+                };
+                if let Some(var) = var {
+                    self.pos += 1;
+                    return Ok(Expr::Var(var));
+                }
+                // v0 - vf shorthand for v[0x0] - v[0xF]
+                if let Some(reg) = name.strip_prefix('v').filter(|r| r.len() == 1) {
+                    if let Ok(x) = i64::from_str_radix(reg, 16) {
+                        self.pos += 1;
+                        return Ok(Expr::Reg(Box::new(Expr::Num(x))));
+                    }
+                }
+                if name == "v" || name == "ram" {
+                    self.nest()?;
+                    self.pos += 1;
+                    self.expect("[")?;
+                    let idx = Box::new(self.parse_binary(0)?);
+                    self.expect("]")?;
+                    self.depth -= 1;
+                    return Ok(if name == "v" { Expr::Reg(idx) } else { Expr::Ram(idx) });
+                }
+                Err(self.error(format!("unknown name '{}'", name)))
+            },
+            Token::Op(_) => Err(self.error(format!("unexpected {}", token))),
+        }
+    }
+}
+
+fn binary_op(token: &Token) -> Option<BinOp> {
+    let op = match token {
+        Token::Op("||") => BinOp::Or,
+        Token::Op("&&") => BinOp::And,
+        Token::Op("|") => BinOp::BitOr,
+        Token::Op("^") => BinOp::BitXor,
+        Token::Op("&") => BinOp::BitAnd,
+        Token::Op("==") => BinOp::Eq,
+        Token::Op("!=") => BinOp::Ne,
+        Token::Op("<") => BinOp::Lt,
+        Token::Op("<=") => BinOp::Le,
+        Token::Op(">") => BinOp::Gt,
+        Token::Op(">=") => BinOp::Ge,
+        Token::Op("+") => BinOp::Add,
+        Token::Op("-") => BinOp::Sub,
+        _ => return None,
+    };
+    Some(op)
+}
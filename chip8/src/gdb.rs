@@ -0,0 +1,485 @@
+use std::fmt::Write as _;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{Breakpoint, BreakpointId, Chip8Error, Debugger, Stop, NUM_REGS, RAM_SIZE};
+
+const FRAME_DURATION: Duration = Duration::from_nanos(1_000_000_000 / 60);
+const PACKET_SIZE: usize = 0x1000;
+
+// Register numbers as seen by the client: V0 - VF, then I, PC, SP (stack depth), DT and ST.
+// Registers are sent little-endian, I and PC as 2 bytes and the rest as 1
+const REG_I: usize = NUM_REGS;
+const REG_PC: usize = NUM_REGS + 1;
+const REG_SP: usize = NUM_REGS + 2;
+const REG_DT: usize = NUM_REGS + 3;
+const REG_ST: usize = NUM_REGS + 4;
+const NUM_GDB_REGS: usize = NUM_REGS + 5;
+
+const TARGET_XML: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE target SYSTEM "gdb-target.dtd">
+<target version="1.0">
+  <feature name="org.chip8.core">
+    <reg name="v0" bitsize="8" type="uint8" regnum="0"/>
+    <reg name="v1" bitsize="8" type="uint8"/>
+    <reg name="v2" bitsize="8" type="uint8"/>
+    <reg name="v3" bitsize="8" type="uint8"/>
+    <reg name="v4" bitsize="8" type="uint8"/>
+    <reg name="v5" bitsize="8" type="uint8"/>
+    <reg name="v6" bitsize="8" type="uint8"/>
+    <reg name="v7" bitsize="8" type="uint8"/>
+    <reg name="v8" bitsize="8" type="uint8"/>
+    <reg name="v9" bitsize="8" type="uint8"/>
+    <reg name="va" bitsize="8" type="uint8"/>
+    <reg name="vb" bitsize="8" type="uint8"/>
+    <reg name="vc" bitsize="8" type="uint8"/>
+    <reg name="vd" bitsize="8" type="uint8"/>
+    <reg name="ve" bitsize="8" type="uint8"/>
+    <reg name="vf" bitsize="8" type="uint8"/>
+    <reg name="i" bitsize="16" type="data_ptr"/>
+    <reg name="pc" bitsize="16" type="code_ptr"/>
+    <reg name="sp" bitsize="8" type="uint8"/>
+    <reg name="dt" bitsize="8" type="uint8"/>
+    <reg name="st" bitsize="8" type="uint8"/>
+  </feature>
+</target>
+"#;
+
+// Minimal GDB remote serial protocol stub, so gdb or LLDB can attach over TCP with `target remote`.
+// Supports reading and writing registers and memory, software breakpoints, stepping and continuing.
+// While continuing the program runs at its normal 60 frames a second until a breakpoint or Ctrl-C
+pub struct GdbServer {
+    debugger: Debugger,
+    breakpoints: Vec<(u16, BreakpointId)>,
+}
+
+enum Packet {
+    Command(String),
+    // Longer than PACKET_SIZE or not ASCII, which no command we take is
+    Bad,
+}
+
+enum Reply {
+    Packet(String),
+    // Client is done with us, possibly after a final packet
+    Close(Option<String>),
+}
+
+impl GdbServer {
+    pub fn new(debugger: Debugger) -> Self {
+        Self { debugger, breakpoints: Vec::new() }
+    }
+
+    pub fn debugger(&self) -> &Debugger {
+        &self.debugger
+    }
+
+    pub fn into_debugger(self) -> Debugger {
+        self.debugger
+    }
+
+    // Wait for a single client on addr, e.g. "127.0.0.1:1234", and serve it until it detaches
+    pub fn listen(&mut self, addr: impl ToSocketAddrs) -> io::Result<()> {
+        let listener = TcpListener::bind(addr)?;
+        let (stream, _) = listener.accept()?;
+        self.serve(stream)
+    }
+
+    // Serve one client connection until it detaches, kills the target or disconnects
+    pub fn serve(&mut self, stream: TcpStream) -> io::Result<()> {
+        let mut conn = Connection { stream, pending: Vec::new() };
+        while let Some(packet) = conn.read_packet()? {
+            let packet = match packet {
+                Packet::Command(packet) => packet,
+                Packet::Bad => {
+                    conn.send(&error())?;
+                    continue;
+                },
+            };
+            match self.handle(&packet, &mut conn)? {
+                Reply::Packet(reply) => conn.send(&reply)?,
+                Reply::Close(reply) => {
+                    if let Some(reply) = reply {
+                        conn.send(&reply)?;
+                    }
+                    break;
+                },
+            }
+        }
+        self.clear_breakpoints();
+        Ok(())
+    }
+
+    fn handle(&mut self, packet: &str, conn: &mut Connection) -> io::Result<Reply> {
+        let (cmd, args) = packet.split_at(packet.chars().next().map_or(0, char::len_utf8));
+        let reply = match cmd {
+            // Stop reason, we're always stopped when not continuing
+            "?" => String::from("S05"),
+            "g" => self.read_registers(),
+            "G" => ok_or_error(self.write_registers(args)),
+            "p" => parse_hex(args).and_then(|reg| self.read_register(reg)).unwrap_or_else(error),
+            "P" => ok_or_error(self.write_register(args)),
+            "m" => self.read_memory(args).unwrap_or_else(error),
+            "M" => ok_or_error(self.write_memory(args)),
+            "s" => self.step(),
+            "c" => self.resume(conn)?,
+            "Z" | "z" => self.breakpoint(cmd == "Z", args),
+            "H" | "T" => String::from("OK"),
+            "D" => return Ok(Reply::Close(Some(String::from("OK")))),
+            "k" => return Ok(Reply::Close(None)),
+            "q" | "Q" => self.query(packet),
+            // Anything else is unsupported, which the protocol says gets an empty reply
+            _ => String::new(),
+        };
+        Ok(Reply::Packet(reply))
+    }
+
+    fn query(&self, packet: &str) -> String {
+        if packet.starts_with("qSupported") {
+            return format!("PacketSize={:x};qXfer:features:read+;swbreak+", PACKET_SIZE);
+        }
+        if let Some(args) = packet.strip_prefix("qXfer:features:read:target.xml:") {
+            return read_xfer(TARGET_XML, args).unwrap_or_else(error);
+        }
+        // LLDB asks for registers one by one instead of reading target.xml
+        if let Some(reg) = packet.strip_prefix("qRegisterInfo") {
+            return parse_hex(reg).map(register_info).unwrap_or_else(error);
+        }
+        match packet {
+            "qAttached" => String::from("1"),
+            "qC" => String::from("QC1"),
+            "qfThreadInfo" => String::from("m1"),
+            "qsThreadInfo" => String::from("l"),
+            "qHostInfo" | "qProcessInfo" => String::from("endian:little;ptrsize:2;"),
+            _ => String::new(),
+        }
+    }
+
+    fn register(&self, reg: usize) -> Option<(u16, usize)> {
+        let chip8 = self.debugger.chip8();
+        let value = match reg {
+            0..=0xF => (chip8.v(reg) as u16, 1),
+            REG_I => (chip8.i(), 2),
+            REG_PC => (chip8.pc(), 2),
+            REG_SP => (chip8.stack().len() as u16, 1),
+            REG_DT => (chip8.delay_timer() as u16, 1),
+            REG_ST => (chip8.sound_timer() as u16, 1),
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    fn set_register(&mut self, reg: usize, value: u16) {
+        let chip8 = self.debugger.chip8_mut();
+        match reg {
+            0..=0xF => chip8.set_v(reg, value as u8),
+            REG_I => chip8.set_i(value),
+            REG_PC => chip8.set_pc(value),
+            REG_DT => chip8.set_delay_timer(value as u8),
+            REG_ST => chip8.set_sound_timer(value as u8),
+            // The stack depth follows the stack itself and can't be written
+            _ => (),
+        }
+    }
+
+    fn read_registers(&self) -> String {
+        let mut reply = String::new();
+        for reg in 0..NUM_GDB_REGS {
+            if let Some((value, size)) = self.register(reg) {
+                push_le(&mut reply, value, size);
+            }
+        }
+        reply
+    }
+
+    fn read_register(&self, reg: usize) -> Option<String> {
+        let (value, size) = self.register(reg)?;
+        let mut reply = String::new();
+        push_le(&mut reply, value, size);
+        Some(reply)
+    }
+
+    fn write_registers(&mut self, args: &str) -> Option<()> {
+        let bytes = decode_hex(args)?;
+        let mut offset = 0;
+        for reg in 0..NUM_GDB_REGS {
+            let (_, size) = self.register(reg)?;
+            let value = read_le(bytes.get(offset..offset + size)?);
+            self.set_register(reg, value);
+            offset += size;
+        }
+        Some(())
+    }
+
+    fn write_register(&mut self, args: &str) -> Option<()> {
+        let (reg, value) = args.split_once('=')?;
+        let reg = parse_hex(reg)?;
+        let (_, size) = self.register(reg)?;
+        let bytes = decode_hex(value)?;
+        self.set_register(reg, read_le(bytes.get(..size)?));
+        Some(())
+    }
+
+    fn read_memory(&self, args: &str) -> Option<String> {
+        let (addr, len) = args.split_once(',')?;
+        let (addr, len) = (parse_hex(addr)?, parse_hex(len)?);
+        // Reads past the end of RAM come back short, as the protocol allows. The length is the client's, so
+        // the end saturates rather than overflowing
+        let ram = self.debugger.chip8().ram();
+        let bytes = ram.get(addr.min(RAM_SIZE)..addr.saturating_add(len).min(RAM_SIZE))?;
+        if bytes.is_empty() && len > 0 {
+            return None;
+        }
+        Some(encode_hex(bytes))
+    }
+
+    fn write_memory(&mut self, args: &str) -> Option<()> {
+        let (range, data) = args.split_once(':')?;
+        let (addr, len) = range.split_once(',')?;
+        let (addr, len) = (parse_hex(addr)?, parse_hex(len)?);
+        let bytes = decode_hex(data)?;
+        if bytes.len() != len || addr > u16::MAX as usize {
+            return None;
+        }
+        self.debugger.chip8_mut().write_ram(addr as u16, &bytes).ok()
+    }
+
+    fn breakpoint(&mut self, insert: bool, args: &str) -> String {
+        // Z0 is a software breakpoint and Z1 hardware, which are the same thing to us. Watchpoints aren't supported
+        let mut fields = args.split(',');
+        let (kind, addr) = (fields.next(), fields.next().and_then(parse_hex));
+        let addr = match (kind, addr) {
+            (Some("0") | Some("1"), Some(addr)) if addr < RAM_SIZE => addr as u16,
+            (Some("0") | Some("1"), _) => return error(),
+            _ => return String::new(),
+        };
+        if insert {
+            if !self.breakpoints.iter().any(|(bp_addr, _)| *bp_addr == addr) {
+                let id = self.debugger.add_breakpoint(Breakpoint::Address(addr));
+                self.breakpoints.push((addr, id));
+            }
+        } else if let Some(idx) = self.breakpoints.iter().position(|(bp_addr, _)| *bp_addr == addr) {
+            let (_, id) = self.breakpoints.remove(idx);
+            self.debugger.remove_breakpoint(id);
+        }
+        String::from("OK")
+    }
+
+    fn clear_breakpoints(&mut self) {
+        for (_, id) in self.breakpoints.drain(..) {
+            self.debugger.remove_breakpoint(id);
+        }
+    }
+
+    fn step(&mut self) -> String {
+        match self.debugger.step() {
+            Ok(crate::State::Halted) => String::from("W00"),
+            Ok(crate::State::Running) => String::from("S05"),
+            Err(err) => stop_reply(err),
+        }
+    }
+
+    fn resume(&mut self, conn: &mut Connection) -> io::Result<String> {
+        loop {
+            let started = Instant::now();
+            let reply = match self.debugger.run_frame() {
+                Ok(Stop::FrameComplete) => None,
+                Ok(Stop::Halted) => Some(String::from("W00")),
+                Ok(_) => Some(String::from("S05")),
+                Err(err) => Some(stop_reply(err)),
+            };
+            if let Some(reply) = reply {
+                return Ok(reply);
+            }
+            if conn.interrupted()? {
+                return Ok(String::from("S02"));
+            }
+            if let Some(remaining) = FRAME_DURATION.checked_sub(started.elapsed()) {
+                thread::sleep(remaining);
+            }
+        }
+    }
+}
+
+// Faults are reported as the signal a real CPU would raise
+fn stop_reply(err: Chip8Error) -> String {
+    match err {
+        Chip8Error::UnknownOpcode { .. } => String::from("S04"),
+        _ => String::from("S0B"),
+    }
+}
+
+struct Connection {
+    stream: TcpStream,
+    // Bytes read while checking for an interrupt that belong to the next packet
+    pending: Vec<u8>,
+}
+
+impl Connection {
+    fn read_byte(&mut self) -> io::Result<Option<u8>> {
+        if !self.pending.is_empty() {
+            return Ok(Some(self.pending.remove(0)));
+        }
+        let mut byte = [0u8];
+        match self.stream.read(&mut byte)? {
+            0 => Ok(None),
+            _ => Ok(Some(byte[0])),
+        }
+    }
+
+    // Next packet, or None once the client hangs up. Packets whose checksum is wrong are refused with '-' for
+    // the client to send again
+    fn read_packet(&mut self) -> io::Result<Option<Packet>> {
+        loop {
+            loop {
+                // Skip acks and stray interrupts until a packet starts
+                match self.read_byte()? {
+                    None => return Ok(None),
+                    Some(b'$') => break,
+                    Some(_) => (),
+                }
+            }
+            let mut payload = Vec::new();
+            // The checksum covers the bytes as sent, escapes included
+            let mut sum = 0u8;
+            let mut escaped = false;
+            loop {
+                let Some(byte) = self.read_byte()? else { return Ok(None) };
+                if byte == b'#' && !escaped {
+                    break;
+                }
+                sum = sum.wrapping_add(byte);
+                // '}' escapes the next byte by XOR-ing it with 0x20
+                let byte = match (escaped, byte) {
+                    (false, b'}') => {
+                        escaped = true;
+                        continue;
+                    },
+                    (true, byte) => byte ^ 0x20,
+                    (false, byte) => byte,
+                };
+                escaped = false;
+                // Past what qSupported said we take, the rest is read to stay in step but not kept
+                if payload.len() <= PACKET_SIZE {
+                    payload.push(byte);
+                }
+            }
+            let mut digits = [0u8; 2];
+            for digit in &mut digits {
+                match self.read_byte()? {
+                    None => return Ok(None),
+                    Some(byte) => *digit = byte,
+                }
+            }
+            let checksum = std::str::from_utf8(&digits).ok().and_then(|digits| u8::from_str_radix(digits, 16).ok());
+            if checksum != Some(sum) {
+                self.stream.write_all(b"-")?;
+                continue;
+            }
+            self.stream.write_all(b"+")?;
+            return Ok(Some(match String::from_utf8(payload) {
+                Ok(payload) if payload.is_ascii() && payload.len() <= PACKET_SIZE => Packet::Command(payload),
+                _ => Packet::Bad,
+            }));
+        }
+    }
+
+    fn send(&mut self, payload: &str) -> io::Result<()> {
+        let checksum = payload.bytes().fold(0u8, |sum, byte| sum.wrapping_add(byte));
+        write!(self.stream, "${}#{:02x}", payload, checksum)?;
+        self.stream.flush()
+    }
+
+    // Check without blocking whether the client sent Ctrl-C (0x03)
+    fn interrupted(&mut self) -> io::Result<bool> {
+        self.stream.set_nonblocking(true)?;
+        let mut buf = [0u8; 64];
+        let result = self.stream.read(&mut buf);
+        self.stream.set_nonblocking(false)?;
+        match result {
+            Ok(len) => {
+                let interrupt = buf[..len].contains(&0x03);
+                self.pending.extend(buf[..len].iter().filter(|byte| **byte != 0x03));
+                Ok(interrupt)
+            },
+            Err(err) if err.kind() == ErrorKind::WouldBlock => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+fn register_info(reg: usize) -> String {
+    let (name, size, generic) = match reg {
+        0..=0xF => (format!("v{:x}", reg), 1, ""),
+        REG_I => (String::from("i"), 2, ""),
+        REG_PC => (String::from("pc"), 2, "generic:pc;"),
+        REG_SP => (String::from("sp"), 1, ""),
+        REG_DT => (String::from("dt"), 1, ""),
+        REG_ST => (String::from("st"), 1, ""),
+        _ => return String::from("E45"),
+    };
+    let offset: usize = (0..reg).map(|r| if r == REG_I || r == REG_PC { 2 } else { 1 }).sum();
+    format!(
+        "name:{};bitsize:{};offset:{};encoding:uint;format:hex;set:General Purpose Registers;{}",
+        name,
+        size * 8,
+        offset,
+        generic
+    )
+}
+
+// qXfer reads take "offset,length" and answer 'm' for more to come or 'l' for the last chunk
+fn read_xfer(document: &str, args: &str) -> Option<String> {
+    let (offset, len) = args.split_once(',')?;
+    let (offset, len) = (parse_hex(offset)?, parse_hex(len)?);
+    let bytes = document.as_bytes();
+    let start = offset.min(bytes.len());
+    let end = offset.saturating_add(len).clamp(start, bytes.len());
+    let marker = if end < bytes.len() { 'm' } else { 'l' };
+    Some(format!("{}{}", marker, &document[start..end]))
+}
+
+fn ok_or_error(result: Option<()>) -> String {
+    match result {
+        Some(()) => String::from("OK"),
+        None => error(),
+    }
+}
+
+fn error() -> String {
+    String::from("E01")
+}
+
+fn parse_hex(text: &str) -> Option<usize> {
+    usize::from_str_radix(text, 16).ok()
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        let _ = write!(text, "{:02x}", byte);
+    }
+    text
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|idx| u8::from_str_radix(text.get(idx..idx + 2)?, 16).ok())
+        .collect()
+}
+
+fn push_le(text: &mut String, value: u16, size: usize) {
+    for byte in value.to_le_bytes().iter().take(size) {
+        let _ = write!(text, "{:02x}", byte);
+    }
+}
+
+fn read_le(bytes: &[u8]) -> u16 {
+    bytes.iter().rev().fold(0, |value, byte| (value << 8) | *byte as u16)
+}
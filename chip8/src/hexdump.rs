@@ -0,0 +1,69 @@
+use core::fmt;
+use core::ops::Range;
+
+use crate::Chip8;
+
+// Bytes to a row of hexdump()
+pub const HEX_ROW_LEN: usize = 16;
+
+// One row of a hex dump, borrowing the bytes it shows from RAM. Display writes it as a monitor would:
+//     200: 00 E0 A2 2A 60 0C 61 08 D0 1F 70 09 A2 39 D0 1F ...*`.a...p..9..
+// with a short last row padded so its text lines up with the rows above
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HexRow<'a> {
+    pub addr: u16,
+    pub bytes: &'a [u8],
+}
+
+impl<'a> HexRow<'a> {
+    // The bytes as hex, two digits each with a space between
+    pub fn hex(&self) -> impl fmt::Display + 'a {
+        Hex(self.bytes)
+    }
+
+    // The bytes as text, ASCII letters, digits and punctuation as themselves and anything else as '.'
+    pub fn ascii(&self) -> impl Iterator<Item = char> + 'a {
+        self.bytes.iter().map(|byte| if byte.is_ascii_graphic() { *byte as char } else { '.' })
+    }
+}
+
+struct Hex<'a>(&'a [u8]);
+
+impl fmt::Display for Hex<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, byte) in self.0.iter().enumerate() {
+            if index > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{:02X}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for HexRow<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:03X}: {}", self.addr, self.hex())?;
+        // Three columns for each byte a short row lacks, and a space before the text
+        let missing = HEX_ROW_LEN - self.bytes.len().min(HEX_ROW_LEN);
+        write!(f, "{:1$}", "", missing * 3 + 1)?;
+        for c in self.ascii() {
+            write!(f, "{}", c)?;
+        }
+        Ok(())
+    }
+}
+
+impl Chip8 {
+    // RAM in range as rows of HEX_ROW_LEN bytes from range.start, the last cut short at range.end. Anything
+    // past the end of RAM is left off
+    pub fn hexdump(&self, range: Range<usize>) -> impl Iterator<Item = HexRow<'_>> {
+        let ram = self.ram();
+        let end = range.end.min(ram.len());
+        let start = range.start.min(end);
+        ram[start..end]
+            .chunks(HEX_ROW_LEN)
+            .enumerate()
+            .map(move |(row, bytes)| HexRow { addr: (start + row * HEX_ROW_LEN) as u16, bytes })
+    }
+}
@@ -0,0 +1,158 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::mem;
+
+use crate::{Chip8, Instruction, Register};
+
+// Called with the machine, the instruction's address and the instruction, None for opcodes that don't
+// decode, e.g. ones an opcode extension or UnknownOpcodePolicy deals with
+pub type InstructionHook = Box<dyn FnMut(&mut Chip8, u16, Option<Instruction>) + Send>;
+
+// Called with the machine and the display rows that just changed, bit y set for row y
+pub type DisplayObserver = Box<dyn FnMut(&Chip8, u64) + Send>;
+
+// A watched register changing, see Chip8::watch_register()
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RegisterChange {
+    pub register: Register,
+    // Of the instruction that changed it
+    pub addr: u16,
+    pub before: u16,
+    pub after: u16,
+}
+
+pub type RegisterWatch = Box<dyn FnMut(&mut Chip8, RegisterChange) + Send>;
+
+#[derive(Default)]
+pub(crate) struct Hooks {
+    before: Vec<InstructionHook>,
+    after: Vec<InstructionHook>,
+    // Each with the register's value before the instruction running
+    watches: Vec<(Register, u16, RegisterWatch)>,
+}
+
+impl Hooks {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.before.is_empty() && self.after.is_empty() && self.watches.is_empty()
+    }
+
+    fn route(&mut self, after: bool) -> &mut Vec<InstructionHook> {
+        if after {
+            &mut self.after
+        } else {
+            &mut self.before
+        }
+    }
+}
+
+impl Chip8 {
+    // Run hook before every instruction, once it's fetched. The PC already
+    // points past it. Hooks run in the order they were added
+    pub fn hook_before_instruction<F>(&mut self, hook: F)
+    where
+        F: FnMut(&mut Chip8, u16, Option<Instruction>) + Send + 'static,
+    {
+        self.hooks.before.push(Box::new(hook));
+    }
+
+    // Run hook after every instruction that doesn't fault, e.g. to check for achievements
+    pub fn hook_after_instruction<F>(&mut self, hook: F)
+    where
+        F: FnMut(&mut Chip8, u16, Option<Instruction>) + Send + 'static,
+    {
+        self.hooks.after.push(Box::new(hook));
+    }
+
+    // Call watch whenever an instruction changes register, e.g. to find what writes a variable kept in V5
+    // without breaking on every instruction. The timers counting down on their own aren't changes
+    pub fn watch_register<F>(&mut self, register: Register, watch: F)
+    where
+        F: FnMut(&mut Chip8, RegisterChange) + Send + 'static,
+    {
+        let value = self.register(register);
+        self.hooks.watches.push((register, value, Box::new(watch)));
+    }
+
+    // Stop watching register, returning whether it was
+    pub fn unwatch_register(&mut self, register: Register) -> bool {
+        let len = self.hooks.watches.len();
+        self.hooks.watches.retain(|(watched, _, _)| *watched != register);
+        self.hooks.watches.len() != len
+    }
+
+    // Remove every hook and register watch
+    pub fn clear_instruction_hooks(&mut self) {
+        self.hooks = Hooks::default();
+    }
+
+    // Call observer whenever an instruction changes the screen, e.g. to redraw only then: after DXYN with the
+    // rows the sprite changed, and after a clear or SCHIP scroll with every row. Replaces any observer
+    // already set
+    pub fn set_display_observer<F>(&mut self, observer: F)
+    where
+        F: FnMut(&Chip8, u64) + Send + 'static,
+    {
+        self.display_observer = Some(Box::new(observer));
+    }
+
+    pub fn clear_display_observer(&mut self) {
+        self.display_observer = None;
+    }
+
+    // Tell the observer rows have changed, or the whole screen with u64::MAX
+    pub(crate) fn display_changed(&mut self, rows: u64) {
+        if let Some(mut observer) = self.display_observer.take() {
+            observer(self, rows & (u64::MAX >> (64 - self.height())));
+            self.display_observer = Some(observer);
+        }
+    }
+
+    pub(crate) fn register(&self, register: Register) -> u16 {
+        match register {
+            Register::V(x) => self.v_regi[x as usize & 0xF] as u16,
+            Register::I => self.i_regi,
+            Register::Pc => self.pc,
+            Register::Sp => self.stkp,
+            Register::Dt => self.delay_t as u16,
+            Register::St => self.sound_t as u16,
+        }
+    }
+
+    // Give the hooks on the route given opcode, which is about to run or has just run at op_pc
+    pub(crate) fn run_hooks(&mut self, after: bool, opcode: u16) {
+        if after {
+            self.check_watches();
+        }
+        if !self.hooks.route(after).is_empty() {
+            // Take the hooks out so each can be given the whole emulator, then put them back with any added
+            let mut hooks = mem::take(self.hooks.route(after));
+            let instruction = Instruction::decode(opcode);
+            for hook in &mut hooks {
+                hook(self, self.op_pc, instruction);
+            }
+            hooks.append(self.hooks.route(after));
+            *self.hooks.route(after) = hooks;
+        }
+        // Only what the instruction itself does counts as a change, not what the before hooks did
+        if !after {
+            let mut watches = mem::take(&mut self.hooks.watches);
+            for (register, before, _) in &mut watches {
+                *before = self.register(*register);
+            }
+            self.hooks.watches = watches;
+        }
+    }
+
+    // Report the watched registers the instruction at op_pc changed
+    fn check_watches(&mut self) {
+        let mut watches = mem::take(&mut self.hooks.watches);
+        for (register, before, watch) in &mut watches {
+            let after = self.register(*register);
+            if after != *before {
+                watch(self, RegisterChange { register: *register, addr: self.op_pc, before: *before, after });
+            }
+        }
+        watches.append(&mut self.hooks.watches);
+        self.hooks.watches = watches;
+    }
+}
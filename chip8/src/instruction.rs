@@ -0,0 +1,255 @@
+use core::fmt;
+
+// A decoded opcode. X and Y are register indices, N/NN/NNN are the immediate nibble, byte and address
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction {
+    // 0000
+    Nop,
+    // 00CN (SCHIP)
+    ScrollDown(u8),
+    // 00E0
+    Cls,
+    // 00EE
+    Ret,
+    // 00FB (SCHIP)
+    ScrollRight,
+    // 00FC (SCHIP)
+    ScrollLeft,
+    // 00FD (SCHIP)
+    Exit,
+    // 00FE (SCHIP)
+    Lores,
+    // 00FF (SCHIP)
+    Hires,
+    // 1NNN
+    Jmp(u16),
+    // 2NNN
+    Call(u16),
+    // 3XNN
+    SkipEqImm(usize, u8),
+    // 4XNN
+    SkipNeImm(usize, u8),
+    // 5XY0
+    SkipEqReg(usize, usize),
+    // 6XNN
+    LoadImm(usize, u8),
+    // 7XNN
+    AddImm(usize, u8),
+    // 8XY0
+    Mov(usize, usize),
+    // 8XY1
+    Or(usize, usize),
+    // 8XY2
+    And(usize, usize),
+    // 8XY3
+    Xor(usize, usize),
+    // 8XY4
+    Add(usize, usize),
+    // 8XY5
+    Sub(usize, usize),
+    // 8XY6
+    Shr(usize, usize),
+    // 8XY7
+    Subn(usize, usize),
+    // 8XYE
+    Shl(usize, usize),
+    // 9XY0
+    SkipNeReg(usize, usize),
+    // ANNN
+    LoadI(u16),
+    // BNNN
+    JmpV0(u16),
+    // CXNN
+    Rand(usize, u8),
+    // DXYN
+    Draw(usize, usize, u8),
+    // EX9E
+    SkipKey(usize),
+    // EXA1
+    SkipNotKey(usize),
+    // FX07
+    LoadDelay(usize),
+    // FX0A
+    WaitKey(usize),
+    // FX15
+    SetDelay(usize),
+    // FX18
+    SetSound(usize),
+    // FX1E
+    AddI(usize),
+    // FX29
+    Font(usize),
+    // FX30 (SCHIP)
+    BigFont(usize),
+    // FX33
+    Bcd(usize),
+    // FX55
+    Store(usize),
+    // FX65
+    Load(usize),
+}
+
+impl Instruction {
+    // Returns None for opcodes the interpreter doesn't recognise
+    pub fn decode(opcode: u16) -> Option<Instruction> {
+        let d1: u16 = (opcode & 0xF000) >> 12;
+        let d2: u16 = (opcode & 0x0F00) >> 8;
+        let d3: u16 = (opcode & 0x00F0) >> 4;
+        let d4: u16 = opcode & 0x000F;
+
+        let x = d2 as usize;
+        let y = d3 as usize;
+        let n = d4 as u8;
+        let nn = (opcode & 0xFF) as u8;
+        let nnn = opcode & 0xFFF;
+
+        let instruction = match (d1, d2, d3, d4) {
+            (0, 0, 0, 0) => Instruction::Nop,
+            (0, 0, 0xC, _) => Instruction::ScrollDown(n),
+            (0, 0, 0xE, 0) => Instruction::Cls,
+            (0, 0, 0xE, 0xE) => Instruction::Ret,
+            (0, 0, 0xF, 0xB) => Instruction::ScrollRight,
+            (0, 0, 0xF, 0xC) => Instruction::ScrollLeft,
+            (0, 0, 0xF, 0xD) => Instruction::Exit,
+            (0, 0, 0xF, 0xE) => Instruction::Lores,
+            (0, 0, 0xF, 0xF) => Instruction::Hires,
+            (1, _, _, _) => Instruction::Jmp(nnn),
+            (2, _, _, _) => Instruction::Call(nnn),
+            (3, _, _, _) => Instruction::SkipEqImm(x, nn),
+            (4, _, _, _) => Instruction::SkipNeImm(x, nn),
+            (5, _, _, _) => Instruction::SkipEqReg(x, y),
+            (6, _, _, _) => Instruction::LoadImm(x, nn),
+            (7, _, _, _) => Instruction::AddImm(x, nn),
+            (8, _, _, 0) => Instruction::Mov(x, y),
+            (8, _, _, 1) => Instruction::Or(x, y),
+            (8, _, _, 2) => Instruction::And(x, y),
+            (8, _, _, 3) => Instruction::Xor(x, y),
+            (8, _, _, 4) => Instruction::Add(x, y),
+            (8, _, _, 5) => Instruction::Sub(x, y),
+            (8, _, _, 6) => Instruction::Shr(x, y),
+            (8, _, _, 7) => Instruction::Subn(x, y),
+            (8, _, _, 0xE) => Instruction::Shl(x, y),
+            (9, _, _, 0) => Instruction::SkipNeReg(x, y),
+            (0xA, _, _, _) => Instruction::LoadI(nnn),
+            (0xB, _, _, _) => Instruction::JmpV0(nnn),
+            (0xC, _, _, _) => Instruction::Rand(x, nn),
+            (0xD, _, _, _) => Instruction::Draw(x, y, n),
+            (0xE, _, 9, 0xE) => Instruction::SkipKey(x),
+            (0xE, _, 0xA, 1) => Instruction::SkipNotKey(x),
+            (0xF, _, 0, 7) => Instruction::LoadDelay(x),
+            (0xF, _, 0, 0xA) => Instruction::WaitKey(x),
+            (0xF, _, 1, 5) => Instruction::SetDelay(x),
+            (0xF, _, 1, 8) => Instruction::SetSound(x),
+            (0xF, _, 1, 0xE) => Instruction::AddI(x),
+            (0xF, _, 2, 9) => Instruction::Font(x),
+            (0xF, _, 3, 0) => Instruction::BigFont(x),
+            (0xF, _, 3, 3) => Instruction::Bcd(x),
+            (0xF, _, 5, 5) => Instruction::Store(x),
+            (0xF, _, 6, 5) => Instruction::Load(x),
+            (_, _, _, _) => return None,
+        };
+        Some(instruction)
+    }
+
+    // The opcode for this instruction, the inverse of decode(). Fields are masked to their nibbles, and
+    // bits decode() ignores, like the N of 5XY0, come out as zero
+    pub fn encode(&self) -> u16 {
+        let xy = |op: u16, x: usize, y: usize, n: u16| op | (x as u16 & 0xF) << 8 | (y as u16 & 0xF) << 4 | n;
+        let xnn = |op: u16, x: usize, nn: u8| op | (x as u16 & 0xF) << 8 | nn as u16;
+        let fx = |x: usize, nn: u16| 0xF000 | (x as u16 & 0xF) << 8 | nn;
+        match *self {
+            Instruction::Nop => 0x0000,
+            Instruction::ScrollDown(n) => 0x00C0 | (n as u16 & 0xF),
+            Instruction::Cls => 0x00E0,
+            Instruction::Ret => 0x00EE,
+            Instruction::ScrollRight => 0x00FB,
+            Instruction::ScrollLeft => 0x00FC,
+            Instruction::Exit => 0x00FD,
+            Instruction::Lores => 0x00FE,
+            Instruction::Hires => 0x00FF,
+            Instruction::Jmp(nnn) => 0x1000 | (nnn & 0xFFF),
+            Instruction::Call(nnn) => 0x2000 | (nnn & 0xFFF),
+            Instruction::SkipEqImm(x, nn) => xnn(0x3000, x, nn),
+            Instruction::SkipNeImm(x, nn) => xnn(0x4000, x, nn),
+            Instruction::SkipEqReg(x, y) => xy(0x5000, x, y, 0),
+            Instruction::LoadImm(x, nn) => xnn(0x6000, x, nn),
+            Instruction::AddImm(x, nn) => xnn(0x7000, x, nn),
+            Instruction::Mov(x, y) => xy(0x8000, x, y, 0),
+            Instruction::Or(x, y) => xy(0x8000, x, y, 1),
+            Instruction::And(x, y) => xy(0x8000, x, y, 2),
+            Instruction::Xor(x, y) => xy(0x8000, x, y, 3),
+            Instruction::Add(x, y) => xy(0x8000, x, y, 4),
+            Instruction::Sub(x, y) => xy(0x8000, x, y, 5),
+            Instruction::Shr(x, y) => xy(0x8000, x, y, 6),
+            Instruction::Subn(x, y) => xy(0x8000, x, y, 7),
+            Instruction::Shl(x, y) => xy(0x8000, x, y, 0xE),
+            Instruction::SkipNeReg(x, y) => xy(0x9000, x, y, 0),
+            Instruction::LoadI(nnn) => 0xA000 | (nnn & 0xFFF),
+            Instruction::JmpV0(nnn) => 0xB000 | (nnn & 0xFFF),
+            Instruction::Rand(x, nn) => xnn(0xC000, x, nn),
+            Instruction::Draw(x, y, n) => xy(0xD000, x, y, n as u16 & 0xF),
+            Instruction::SkipKey(x) => xnn(0xE000, x, 0x9E),
+            Instruction::SkipNotKey(x) => xnn(0xE000, x, 0xA1),
+            Instruction::LoadDelay(x) => fx(x, 0x07),
+            Instruction::WaitKey(x) => fx(x, 0x0A),
+            Instruction::SetDelay(x) => fx(x, 0x15),
+            Instruction::SetSound(x) => fx(x, 0x18),
+            Instruction::AddI(x) => fx(x, 0x1E),
+            Instruction::Font(x) => fx(x, 0x29),
+            Instruction::BigFont(x) => fx(x, 0x30),
+            Instruction::Bcd(x) => fx(x, 0x33),
+            Instruction::Store(x) => fx(x, 0x55),
+            Instruction::Load(x) => fx(x, 0x65),
+        }
+    }
+}
+
+// Assembly mnemonics in the usual Cowgod style, e.g. "LD V3, 0x0A" or "CALL 0x2F2"
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Instruction::Nop => write!(f, "NOP"),
+            Instruction::ScrollDown(n) => write!(f, "SCD {}", n),
+            Instruction::Cls => write!(f, "CLS"),
+            Instruction::Ret => write!(f, "RET"),
+            Instruction::ScrollRight => write!(f, "SCR"),
+            Instruction::ScrollLeft => write!(f, "SCL"),
+            Instruction::Exit => write!(f, "EXIT"),
+            Instruction::Lores => write!(f, "LOW"),
+            Instruction::Hires => write!(f, "HIGH"),
+            Instruction::Jmp(nnn) => write!(f, "JP {:#05X}", nnn),
+            Instruction::Call(nnn) => write!(f, "CALL {:#05X}", nnn),
+            Instruction::SkipEqImm(x, nn) => write!(f, "SE V{:X}, {:#04X}", x, nn),
+            Instruction::SkipNeImm(x, nn) => write!(f, "SNE V{:X}, {:#04X}", x, nn),
+            Instruction::SkipEqReg(x, y) => write!(f, "SE V{:X}, V{:X}", x, y),
+            Instruction::LoadImm(x, nn) => write!(f, "LD V{:X}, {:#04X}", x, nn),
+            Instruction::AddImm(x, nn) => write!(f, "ADD V{:X}, {:#04X}", x, nn),
+            Instruction::Mov(x, y) => write!(f, "LD V{:X}, V{:X}", x, y),
+            Instruction::Or(x, y) => write!(f, "OR V{:X}, V{:X}", x, y),
+            Instruction::And(x, y) => write!(f, "AND V{:X}, V{:X}", x, y),
+            Instruction::Xor(x, y) => write!(f, "XOR V{:X}, V{:X}", x, y),
+            Instruction::Add(x, y) => write!(f, "ADD V{:X}, V{:X}", x, y),
+            Instruction::Sub(x, y) => write!(f, "SUB V{:X}, V{:X}", x, y),
+            Instruction::Shr(x, y) => write!(f, "SHR V{:X}, V{:X}", x, y),
+            Instruction::Subn(x, y) => write!(f, "SUBN V{:X}, V{:X}", x, y),
+            Instruction::Shl(x, y) => write!(f, "SHL V{:X}, V{:X}", x, y),
+            Instruction::SkipNeReg(x, y) => write!(f, "SNE V{:X}, V{:X}", x, y),
+            Instruction::LoadI(nnn) => write!(f, "LD I, {:#05X}", nnn),
+            Instruction::JmpV0(nnn) => write!(f, "JP V0, {:#05X}", nnn),
+            Instruction::Rand(x, nn) => write!(f, "RND V{:X}, {:#04X}", x, nn),
+            Instruction::Draw(x, y, n) => write!(f, "DRW V{:X}, V{:X}, {}", x, y, n),
+            Instruction::SkipKey(x) => write!(f, "SKP V{:X}", x),
+            Instruction::SkipNotKey(x) => write!(f, "SKNP V{:X}", x),
+            Instruction::LoadDelay(x) => write!(f, "LD V{:X}, DT", x),
+            Instruction::WaitKey(x) => write!(f, "LD V{:X}, K", x),
+            Instruction::SetDelay(x) => write!(f, "LD DT, V{:X}", x),
+            Instruction::SetSound(x) => write!(f, "LD ST, V{:X}", x),
+            Instruction::AddI(x) => write!(f, "ADD I, V{:X}", x),
+            Instruction::Font(x) => write!(f, "LD F, V{:X}", x),
+            Instruction::BigFont(x) => write!(f, "LD HF, V{:X}", x),
+            Instruction::Bcd(x) => write!(f, "LD B, V{:X}", x),
+            Instruction::Store(x) => write!(f, "LD [I], V{:X}", x),
+            Instruction::Load(x) => write!(f, "LD V{:X}, [I]", x),
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{Chip8, NUM_KEYS};
+
+// The keypad laid over the left of a QWERTY keyboard, as most emulators do:
+//     1 2 3 C      1 2 3 4
+//     4 5 6 D  ->  Q W E R
+//     7 8 9 E      A S D F
+//     A 0 B F      Z X C V
+const QWERTY: [(&str, usize); NUM_KEYS] = [
+    ("1", 0x1),
+    ("2", 0x2),
+    ("3", 0x3),
+    ("4", 0xC),
+    ("Q", 0x4),
+    ("W", 0x5),
+    ("E", 0x6),
+    ("R", 0xD),
+    ("A", 0x7),
+    ("S", 0x8),
+    ("D", 0x9),
+    ("F", 0xE),
+    ("Z", 0xA),
+    ("X", 0x0),
+    ("C", 0xB),
+    ("V", 0xF),
+];
+
+// Which host keys press which keypad keys, so frontends needn't each hard-code the layout. Host keys are
+// names, compared ignoring case, e.g. the names SDL gives its keycodes like "Q" or "Left Shift", or a scancode
+// written as a number. A keypad key can have any number of host keys. The text form is a line per host key of
+// the keypad key in hex then the host key's name, with # starting comments:
+//     # Arrow keys for games that move with 2, 4, 6 and 8
+//     2 Up
+//     8 Down
+// Display writes it back out the same way
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Keymap {
+    keys: Vec<(String, usize)>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeymapError {
+    // 1-based line number
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for KeymapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for KeymapError {}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::qwerty()
+    }
+}
+
+impl Keymap {
+    // One with nothing mapped
+    pub fn new() -> Self {
+        Self { keys: Vec::new() }
+    }
+
+    pub fn qwerty() -> Self {
+        let mut keymap = Self::new();
+        for (host, key) in QWERTY {
+            keymap.bind(host, key);
+        }
+        keymap
+    }
+
+    // Only the keys the text maps are mapped, start from qwerty() and bind() to change a few
+    pub fn parse(text: &str) -> Result<Self, KeymapError> {
+        let mut keymap = Self::new();
+        for (idx, line) in text.lines().enumerate() {
+            let error = |message: &str| KeymapError { line: idx + 1, message: message.to_string() };
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, host) = line.split_once(char::is_whitespace).ok_or_else(|| error("expected a key and a name"))?;
+            let key = usize::from_str_radix(key, 16)
+                .ok()
+                .filter(|key| *key < NUM_KEYS)
+                .ok_or_else(|| error("expected a hex key from 0 to F"))?;
+            keymap.bind(host.trim(), key);
+        }
+        Ok(keymap)
+    }
+
+    // Make host press key, in place of whatever it pressed before. Panics if key is not in 0..=0xF
+    pub fn bind(&mut self, host: &str, key: usize) {
+        assert!(key < NUM_KEYS, "no keypad key {:X}", key);
+        self.unbind(host);
+        self.keys.push((host.to_string(), key));
+    }
+
+    // Returns whether host was mapped
+    pub fn unbind(&mut self, host: &str) -> bool {
+        let len = self.keys.len();
+        self.keys.retain(|(name, _)| !name.eq_ignore_ascii_case(host));
+        self.keys.len() != len
+    }
+
+    // The keypad key host presses
+    pub fn key(&self, host: &str) -> Option<usize> {
+        self.keys.iter().find(|(name, _)| name.eq_ignore_ascii_case(host)).map(|(_, key)| *key)
+    }
+
+    // Every host key and the keypad key it presses, in the order they were mapped
+    pub fn iter(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.keys.iter().map(|(name, key)| (name.as_str(), *key))
+    }
+
+    // Pass a host key going down or up on to chip8, returning whether it's mapped
+    pub fn apply(&self, host: &str, pressed: bool, chip8: &mut Chip8) -> bool {
+        match self.key(host) {
+            Some(key) => {
+                chip8.keypress(key, pressed);
+                true
+            },
+            None => false,
+        }
+    }
+}
+
+impl fmt::Display for Keymap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (host, key) in self.iter() {
+            writeln!(f, "{:X} {}", key, host)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,119 @@
+use alloc::collections::VecDeque;
+
+use crate::{Chip8, Event};
+
+// A keypad key going down or up, as keypress() queues it with the key queue on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyEvent {
+    pub key: usize,
+    pub pressed: bool,
+    // Frames since the queue was enabled, when keypress() was called
+    pub frame: u64,
+}
+
+pub(crate) struct KeyQueue {
+    pending: VecDeque<KeyEvent>,
+    frame: u64,
+    // Keys that went down this frame, whose release waits for the next so the program sees them held
+    fresh: u16,
+    // An FX0A is waiting, the keys that went down since it started, and the one first let go of after
+    waiting: bool,
+    down_in_wait: u16,
+    released: Option<usize>,
+}
+
+impl Chip8 {
+    // Have keypress() queue each change in place of setting the key straight away, so none are lost however
+    // quickly they come. A change is made at once unless changes queued before it are waiting, or it
+    // releases a key that went down since the frame began, which holds the key until the end of the frame.
+    // That way a tap between two frames is still seen as a press by EX9E and EXA1. FX0A then waits for a key
+    // to be pressed and let go, on the release as the original interpreter did, instead of taking whichever
+    // key is down. Turning the queue off makes any changes still waiting
+    pub fn enable_key_queue(&mut self, enabled: bool) {
+        if !enabled {
+            if let Some(mut queue) = self.key_queue.take() {
+                for event in queue.pending.drain(..) {
+                    self.keys[event.key] = event.pressed;
+                }
+            }
+        } else if self.key_queue.is_none() {
+            self.key_queue = Some(KeyQueue {
+                pending: VecDeque::new(),
+                frame: 0,
+                fresh: 0,
+                waiting: false,
+                down_in_wait: 0,
+                released: None,
+            });
+        }
+    }
+
+    // Changes keypress() has queued that are yet to be made, oldest first
+    pub fn pending_keys(&self) -> impl Iterator<Item = &KeyEvent> {
+        self.key_queue.iter().flat_map(|queue| queue.pending.iter())
+    }
+
+    // keypress() with the queue on, returning false with it off
+    pub(crate) fn queue_key(&mut self, key: usize, pressed: bool) -> bool {
+        let Some(queue) = &mut self.key_queue else {
+            return false;
+        };
+        // Repeats change nothing, which saves frontends that set every key every frame filling the queue
+        let latest = queue.pending.iter().rev().find(|event| event.key == key).map(|event| event.pressed);
+        if latest.unwrap_or(self.keys[key]) != pressed {
+            queue.pending.push_back(KeyEvent { key, pressed, frame: queue.frame });
+            self.deliver_keys();
+        }
+        true
+    }
+
+    // Now a frame is over, let go of keys held for it and make the changes that were waiting on them
+    pub(crate) fn key_frame(&mut self) {
+        if let Some(queue) = &mut self.key_queue {
+            queue.frame += 1;
+            queue.fresh = 0;
+            self.deliver_keys();
+        }
+    }
+
+    // Make queued changes in order up to the first that has to wait for the next frame, or none while paused
+    pub(crate) fn deliver_keys(&mut self) {
+        if self.is_paused() {
+            return;
+        }
+        loop {
+            let Some(queue) = &mut self.key_queue else {
+                return;
+            };
+            let Some(event) = queue.pending.front().copied() else {
+                return;
+            };
+            let bit = 1 << event.key;
+            if !event.pressed && queue.fresh & bit != 0 {
+                return;
+            }
+            queue.pending.pop_front();
+            if event.pressed {
+                queue.fresh |= bit;
+                queue.down_in_wait |= bit;
+            } else if queue.waiting && queue.down_in_wait & bit != 0 && queue.released.is_none() {
+                queue.released = Some(event.key);
+            }
+            self.keys[event.key] = event.pressed;
+            self.emit(Event::Key(event));
+        }
+    }
+
+    // The key FX0A takes with the queue on, the first pressed and let go since the wait began
+    pub(crate) fn key_released(&mut self) -> Option<usize> {
+        let queue = self.key_queue.as_mut()?;
+        if !queue.waiting {
+            queue.waiting = true;
+            queue.down_in_wait = 0;
+            queue.released = None;
+        }
+        let released = queue.released.take();
+        queue.waiting = released.is_none();
+        released
+    }
+}
@@ -1,13 +1,196 @@
-use rand::Rng;
+// no_std unless the std feature is on, which it is by default. The bare interpreter never allocates, alloc adds
+// the debugger, profilers, opcode extensions and unknown opcode handlers
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "alloc")]
+use cache::DecodeCache;
+#[cfg(feature = "alloc")]
+use events::Events;
+#[cfg(feature = "alloc")]
+use hooks::Hooks;
+#[cfg(feature = "alloc")]
+use keys::KeyQueue;
+use audio::Wave;
+use core::mem;
+
+#[cfg(feature = "alloc")]
+mod analyze;
+#[cfg(feature = "alloc")]
+mod aot;
+mod audio;
+mod buffers;
+#[cfg(feature = "cartridge")]
+mod cartridge;
+#[cfg(feature = "alloc")]
+mod cache;
+#[cfg(feature = "alloc")]
+mod cheats;
+#[cfg(feature = "config")]
+mod config;
+#[cfg(feature = "std")]
+mod coredump;
+#[cfg(feature = "alloc")]
+mod debugger;
+#[cfg(feature = "alloc")]
+mod diff;
+mod dispatch;
+mod error;
+#[cfg(feature = "alloc")]
+mod events;
+#[cfg(feature = "alloc")]
+mod expr;
+#[cfg(feature = "std")]
+mod gdb;
+mod hexdump;
+#[cfg(feature = "alloc")]
+mod hooks;
+mod instruction;
+#[cfg(feature = "alloc")]
+mod keymap;
+#[cfg(feature = "alloc")]
+mod keys;
+#[cfg(feature = "std")]
+mod load;
+#[cfg(feature = "alloc")]
+mod machines;
+#[cfg(feature = "std")]
+mod media;
+#[cfg(feature = "alloc")]
+mod movie;
+#[cfg(feature = "std")]
+mod netplay;
+#[cfg(feature = "alloc")]
+mod octo;
+#[cfg(feature = "alloc")]
+mod profile;
+#[cfg(feature = "alloc")]
+mod rewind;
+mod rng;
+mod rom;
+#[cfg(feature = "romdb")]
+mod romdb;
+#[cfg(feature = "alloc")]
+mod savestate;
+#[cfg(feature = "std")]
+mod shared;
+mod speed;
+mod sprites;
+mod state;
+#[cfg(feature = "alloc")]
+mod symbols;
+#[cfg(feature = "alloc")]
+mod text;
+mod time;
+mod timing;
+mod touch;
+#[cfg(feature = "log")]
+mod trace;
+mod viewport;
+
+#[cfg(feature = "alloc")]
+pub use analyze::{Analysis, ByteKind, Edge, Issue};
+#[cfg(feature = "alloc")]
+pub use aot::transpile_rust;
+pub use audio::{Buzzer, Waveform};
+pub use buffers::Buffers;
+#[cfg(feature = "cartridge")]
+pub use cartridge::{Cartridge, CartridgeError, OctoOptions};
+#[cfg(feature = "alloc")]
+pub use cheats::{Cheat, CheatFile, CheatFileError, CheatSearch, Comparison, FreezeTiming};
+#[cfg(feature = "config")]
+pub use config::{Config, ConfigError, Paths};
+#[cfg(feature = "std")]
+pub use coredump::CoreDump;
+#[cfg(feature = "alloc")]
+pub use debugger::{Breakpoint, BreakpointId, Debugger, Stop};
+#[cfg(feature = "alloc")]
+pub use diff::{Register, StateDiff};
+pub use error::{Chip8Error, Result};
+#[cfg(feature = "alloc")]
+pub use events::Event;
+#[cfg(feature = "alloc")]
+pub use expr::{Condition, ConditionError};
+#[cfg(feature = "std")]
+pub use gdb::GdbServer;
+pub use hexdump::{HexRow, HEX_ROW_LEN};
+#[cfg(feature = "alloc")]
+pub use hooks::{DisplayObserver, InstructionHook, RegisterChange, RegisterWatch};
+pub use instruction::Instruction;
+#[cfg(feature = "alloc")]
+pub use keymap::{Keymap, KeymapError};
+#[cfg(feature = "alloc")]
+pub use keys::KeyEvent;
+#[cfg(feature = "std")]
+pub use load::LoadError;
+#[cfg(feature = "alloc")]
+pub use machines::{MachineId, Machines};
+#[cfg(feature = "gif")]
+pub use media::GifRecorder;
+#[cfg(feature = "std")]
+pub use media::{Palette, VideoRecorder, WavRecorder, DEFAULT_SAMPLE_RATE};
+#[cfg(feature = "alloc")]
+pub use movie::{Movie, MovieError, ReplayError};
+#[cfg(feature = "std")]
+pub use netplay::{Netplay, NetplayError};
+#[cfg(feature = "alloc")]
+pub use octo::{compile_octo, OctoError, OctoProgram};
+#[cfg(feature = "alloc")]
+pub use profile::{Coverage, Heatmap, Profile};
+#[cfg(feature = "alloc")]
+pub use rewind::Rewind;
+pub use rom::RomHash;
+#[cfg(feature = "romdb")]
+pub use romdb::{Action, RomInfo};
+#[cfg(feature = "std")]
+pub use savestate::SaveSlots;
+#[cfg(feature = "alloc")]
+pub use savestate::{CompressedState, SavestateError, SAVESTATE_VERSION};
+#[cfg(feature = "std")]
+pub use shared::SharedChip8;
+pub use speed::SpeedControl;
+pub use sprites::{Glyph, MAX_SPRITE_ROWS};
+pub use state::Chip8State;
+#[cfg(feature = "alloc")]
+pub use symbols::{Symbolic, Symbols, SymbolsError};
+#[cfg(feature = "alloc")]
+pub use text::TextStyle;
+#[cfg(feature = "std")]
+pub use time::SystemClock;
+pub use time::{ManualClock, TimeSource, TIMER_HZ};
+pub use timing::Timing;
+pub use touch::{KeyRect, TouchKeypad, KEYPAD_LAYOUT};
+pub use viewport::Viewport;
+
+use buffers::Storage;
+use rng::Pcg32;
+use time::TimerSync;
 
 const RAM_SIZE: usize = 4096;
 pub const SCREEN_WIDTH: usize = 64;
 pub const SCREEN_HEIGHT: usize = 32;
+// SCHIP hi-res mode doubles the resolution in both directions
+pub const HIRES_SCREEN_WIDTH: usize = 128;
+pub const HIRES_SCREEN_HEIGHT: usize = 64;
+// Lo-res pixels sit in the top half of each u128 display row, the rest of the row is kept blank
+const LORES_ROW_MASK: u128 = !(u64::MAX as u128);
 const NUM_REGS: usize = 16;
 const STACK_SIZE: usize = 16;
-const START_ADDRESS: u16 = 0x200;
+pub const START_ADDRESS: u16 = 0x200;
+// ETI-660 programs are loaded and run from 0x600
+pub const ETI660_START_ADDRESS: u16 = 0x600;
 const NUM_KEYS: usize = 16;
-const FONTSET_SIZE: usize = 80;
+pub const DEFAULT_TICKS_PER_FRAME: usize = 10;
+pub const FONTSET_SIZE: usize = 80;
+const FONT_ADDRESS: u16 = 0x000;
+const LARGE_FONTSET_SIZE: usize = 160;
+const LARGE_FONT_ADDRESS: u16 = FONT_ADDRESS + FONTSET_SIZE as u16;
 
 // Fontset holds 16 digits from 0 -> F,
 // 1,
@@ -33,415 +216,1285 @@ const FONTSET: [u8; FONTSET_SIZE] = [
     0xF0, 0x80, 0xF0, 0x80, 0x80
 ];
 
+// SCHIP large fontset holds 16 digits from 0 -> F, 8x10 pixels each
+
+const LARGE_FONTSET: [u8; LARGE_FONTSET_SIZE] = [
+    0xFF, 0xFF, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF,
+    0x18, 0x78, 0x78, 0x18, 0x18, 0x18, 0x18, 0x18, 0xFF, 0xFF,
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF,
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF,
+    0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0x03, 0x03,
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF,
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF,
+    0xFF, 0xFF, 0x03, 0x03, 0x06, 0x0C, 0x18, 0x18, 0x18, 0x18,
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF,
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF,
+    0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3,
+    0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC,
+    0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C,
+    0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC,
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF,
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0
+];
+
+// Execution state reported after each clock
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum State {
+    Running,
+    // The program exited via 00FD and no further instructions will run until reset
+    Halted,
+}
+
+// An instruction step() ran
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StepResult {
+    pub pc_before: u16,
+    // As fetched, even if the instruction went on to overwrite itself
+    pub opcode: u16,
+    // None for opcodes that only an extension or the unknown opcode policy handles
+    pub instruction: Option<Instruction>,
+    // What it took out of run_frame()'s budget, 1 with Timing::Ticks or the VIP's machine cycles
+    pub cycles: usize,
+}
+
+// The beep turning on or off, from sound_edge()
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SoundEdge {
+    // The sound timer became nonzero
+    Started,
+    // The sound timer hit zero
+    Stopped,
+}
+
+// A subroutine call in progress, kept alongside the raw stack so a debugger can show where it came from
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct CallFrame {
+    // Address of the 2NNN instruction
+    pub call_site: u16,
+    // Start of the subroutine
+    pub target: u16,
+    // Where 00EE will resume
+    pub return_address: u16,
+}
+
+// Interpreter flavour, for opcodes whose behaviour differs between platforms
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum Variant {
+    Chip8,
+    SuperChip,
+}
+
+// Behaviours that differ between interpreters in ways programs depend on, named as in Octo. The defaults are
+// this emulator's own, which suit most games written for the HP48 era interpreters
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct Quirks {
+    // 8XY6 and 8XYE shift VX in place instead of putting VY shifted into VX
+    pub shift: bool,
+    // FX55 and FX65 leave I alone instead of advancing it past the last register
+    pub load_store: bool,
+    // 8XY4 to 8XYE write VF before VX, so with X as F the result is kept rather than the flag
+    pub vf_order: bool,
+    // DXYN cuts sprites off at the edges of the screen instead of wrapping them round
+    pub clip: bool,
+    // DXYN draws at most one sprite a frame, as interpreters waiting for the display interrupt did. Later
+    // draws wait for the next frame
+    pub vblank: bool,
+    // BNNN jumps to NNN + VX, X being N's top nibble, instead of NNN + V0
+    pub jump: bool,
+    // 8XY1, 8XY2 and 8XY3 clear VF
+    pub logic: bool,
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Self { shift: true, load_store: true, vf_order: false, clip: false, vblank: false, jump: false, logic: false }
+    }
+}
+
+// What to do when the PC leaves program memory, i.e. lands below the start address or at the end of RAM.
+// Wrap by default, so programs that jump into the font area or past the end run as they always have
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum PcPolicy {
+    // Keep running from wherever the PC is, wrapping around the end of RAM
+    #[default]
+    Wrap,
+    // Report Chip8Error::PcOutOfBounds
+    Fault,
+    // Stop as if the program had exited via 00FD
+    Halt,
+}
+
+// Callback for unrecognised opcodes, returns whether it consumed the opcode
+#[cfg(feature = "alloc")]
+pub type UnknownOpcodeHandler = Box<dyn FnMut(&mut Chip8, u16) -> bool + Send>;
+
+// What to do with opcodes the interpreter doesn't recognise, e.g. data embedded in the code path
+pub enum UnknownOpcodePolicy {
+    // Report Chip8Error::UnknownOpcode
+    Fault,
+    // Treat the opcode as a NOP and carry on
+    Skip,
+    // Stop as if the program had exited via 00FD
+    Halt,
+    // Hand the opcode to a callback, which returns whether it consumed it. Unconsumed opcodes fault.
+    // The PC already points past the opcode when the callback runs
+    #[cfg(feature = "alloc")]
+    Handler(UnknownOpcodeHandler),
+}
+
+// Handler for a registered opcode pattern, runs instead of the built-in instruction
+#[cfg(feature = "alloc")]
+pub type OpcodeExtension = Box<dyn FnMut(&mut Chip8, u16) -> Result<()> + Send>;
+
+#[cfg(feature = "alloc")]
+struct Extension {
+    mask: u16,
+    pattern: u16,
+    handler: OpcodeExtension,
+}
+
+// 64-bit FNV-1a, for hashes that have to be the same across versions and platforms
+struct Fnv64(u64);
+
+impl Fnv64 {
+    fn new() -> Self {
+        Self(0xCBF2_9CE4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01B3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
 pub struct Chip8 {
     pc: u16,
-    ram: [u8; RAM_SIZE],
+    // RAM and the display
+    buffers: Storage,
     v_regi: [u8; NUM_REGS],
     i_regi: u16,
-    display: [bool; SCREEN_WIDTH * SCREEN_HEIGHT],
+    hires: bool,
     stack: [u16; STACK_SIZE],
     stkp: u16,
+    // Shadow of stack with call details, the same depth as stkp
+    call_stack: [CallFrame; STACK_SIZE],
     delay_t: u8,
     sound_t: u8,
     keys: [bool; NUM_KEYS],
+    halted: bool,
+    // See pause(), and how much of the frame a pause cut short had been run
+    paused: bool,
+    frame_spent: usize,
+    // Where tick_timers() is counting from, until the first call, and the time advance() has run of this
+    // frame, in 60ths of a nanosecond
+    timer_sync: Option<TimerSync>,
+    frame_time: u64,
+    start_address: u16,
+    fontset: [u8; FONTSET_SIZE],
+    variant: Variant,
+    quirks: Quirks,
+    // A sprite has been drawn this frame, for Quirks::vblank
+    drawn: bool,
+    // Address of the instruction currently executing, for fault reports
+    op_pc: u16,
+    pc_policy: PcPolicy,
+    unknown_opcode_policy: UnknownOpcodePolicy,
+    #[cfg(feature = "alloc")]
+    extensions: Vec<Extension>,
+    ticks_per_frame: usize,
+    timing: Timing,
+    #[cfg(feature = "alloc")]
+    profile: Option<Profile>,
+    #[cfg(feature = "alloc")]
+    heatmap: Option<Heatmap>,
+    #[cfg(feature = "alloc")]
+    coverage: Option<Coverage>,
+    #[cfg(feature = "alloc")]
+    decode_cache: Option<Box<DecodeCache>>,
+    // The last instructions run as address and opcode, for core dumps
+    #[cfg(feature = "alloc")]
+    recent: Option<Rewind<(u16, u16)>>,
+    #[cfg(feature = "alloc")]
+    events: Option<Events>,
+    #[cfg(feature = "alloc")]
+    hooks: Hooks,
+    #[cfg(feature = "alloc")]
+    key_queue: Option<KeyQueue>,
+    #[cfg(feature = "alloc")]
+    display_observer: Option<DisplayObserver>,
+    // Addresses held at a value by freeze()
+    #[cfg(feature = "alloc")]
+    freezes: Vec<(u16, u8)>,
+    #[cfg(feature = "alloc")]
+    freeze_timing: FreezeTiming,
+    // Names for addresses, for traces and debuggers
+    #[cfg(feature = "alloc")]
+    symbols: Symbols,
+    // For fill_audio()
+    buzzer: Buzzer,
+    wave: Wave,
+    // Whether the last sound_edge() reported a beep, and whether one has started since
+    edge_beeping: bool,
+    beep_started: bool,
+    // Size of the last loaded ROM
+    rom_len: usize,
+    rom_hash: Option<RomHash>,
+    // Source of CXNN's random numbers
+    rng: Pcg32,
 }
 
 impl Chip8 {
+    #[cfg(feature = "alloc")]
     pub fn init() -> Self {
+        Self::with_start_address(START_ADDRESS)
+    }
+
+    // Create an emulator that loads and runs programs from a given address, e.g. 0x600 for ETI-660 ROMs
+    #[cfg(feature = "alloc")]
+    pub fn with_start_address(start_address: u16) -> Self {
+        Self::with_storage(Storage::Owned(Box::default()), start_address)
+    }
+
+    // Create an emulator whose RAM and display are the caller's, for machines without a heap or that want
+    // them in a particular memory section. Whatever was in buffers is cleared
+    pub fn new_in(buffers: &'static mut Buffers) -> Self {
+        Self::with_storage(Storage::Static(buffers), START_ADDRESS)
+    }
+
+    fn with_storage(mut buffers: Storage, start_address: u16) -> Self {
+        buffers.ram.fill(0);
+        buffers.display.fill(0);
         let mut chip8_emu: Chip8 = Self {
-            pc: START_ADDRESS,
-            ram: [0; RAM_SIZE],
+            pc: start_address,
+            buffers,
             v_regi: [0; NUM_REGS],
             i_regi: 0,
-            display: [false; SCREEN_WIDTH * SCREEN_HEIGHT],
+            hires: false,
             stack: [0; STACK_SIZE],
             stkp: 0,
+            call_stack: [CallFrame::default(); STACK_SIZE],
             delay_t: 0,
             sound_t: 0,
-            keys: [false; NUM_KEYS]
+            keys: [false; NUM_KEYS],
+            halted: false,
+            paused: false,
+            frame_spent: 0,
+            timer_sync: None,
+            frame_time: 0,
+            start_address,
+            fontset: FONTSET,
+            variant: Variant::Chip8,
+            quirks: Quirks::default(),
+            drawn: false,
+            op_pc: start_address,
+            pc_policy: PcPolicy::Wrap,
+            unknown_opcode_policy: UnknownOpcodePolicy::Fault,
+            #[cfg(feature = "alloc")]
+            extensions: Vec::new(),
+            ticks_per_frame: DEFAULT_TICKS_PER_FRAME,
+            timing: Timing::Ticks,
+            #[cfg(feature = "alloc")]
+            profile: None,
+            #[cfg(feature = "alloc")]
+            heatmap: None,
+            #[cfg(feature = "alloc")]
+            coverage: None,
+            #[cfg(feature = "alloc")]
+            decode_cache: None,
+            #[cfg(feature = "alloc")]
+            recent: None,
+            #[cfg(feature = "alloc")]
+            events: None,
+            #[cfg(feature = "alloc")]
+            hooks: Hooks::default(),
+            #[cfg(feature = "alloc")]
+            key_queue: None,
+            #[cfg(feature = "alloc")]
+            display_observer: None,
+            #[cfg(feature = "alloc")]
+            freezes: Vec::new(),
+            #[cfg(feature = "alloc")]
+            freeze_timing: FreezeTiming::Frame,
+            #[cfg(feature = "alloc")]
+            symbols: Symbols::new(),
+            buzzer: Buzzer::default(),
+            wave: Wave::default(),
+            edge_beeping: false,
+            beep_started: false,
+            rom_len: 0,
+            rom_hash: None,
+            rng: Pcg32::unseeded(),
         };  
 
-        chip8_emu.ram[..FONTSET_SIZE].copy_from_slice(&FONTSET);
+        chip8_emu.install_fontset();
         chip8_emu
     }
 
-    fn push(&mut self, data: u16) {
+    // Replace the built-in hex digit glyphs, e.g. with the Octo or DREAM6800 fonts. Survives reset()
+    pub fn set_fontset(&mut self, fontset: &[u8; FONTSET_SIZE]) {
+        self.fontset = *fontset;
+        self.install_fontset();
+    }
+
+    // Address of the glyph for digit 0, each following digit is 5 bytes further
+    pub fn font_address(&self) -> u16 {
+        FONT_ADDRESS
+    }
+
+    // Address of the large glyph for digit 0, each following digit is 10 bytes further
+    pub fn large_font_address(&self) -> u16 {
+        LARGE_FONT_ADDRESS
+    }
+
+    fn install_fontset(&mut self) {
+        let start = FONT_ADDRESS as usize;
+        self.buffers.ram[start..start + FONTSET_SIZE].copy_from_slice(&self.fontset);
+        let start = LARGE_FONT_ADDRESS as usize;
+        self.buffers.ram[start..start + LARGE_FONTSET_SIZE].copy_from_slice(&LARGE_FONTSET);
+        self.ram_written(0..RAM_SIZE);
+    }
+
+    fn push(&mut self, data: u16) -> Result<()> {
+        if self.stkp as usize >= STACK_SIZE {
+            return Err(Chip8Error::StackOverflow { pc: self.op_pc });
+        }
         self.stack[self.stkp as usize] = data;
         self.stkp += 1;
+        Ok(())
     }
 
-    fn pop(&mut self) -> u16 {
+    fn pop(&mut self) -> Result<u16> {
+        if self.stkp == 0 {
+            return Err(Chip8Error::StackUnderflow { pc: self.op_pc });
+        }
         self.stkp -= 1;
-        self.stack[self.stkp as usize]
+        Ok(self.stack[self.stkp as usize])
+    }
+
+    pub fn pc_policy(&self) -> PcPolicy {
+        self.pc_policy
+    }
+
+    pub fn set_pc_policy(&mut self, policy: PcPolicy) {
+        self.pc_policy = policy;
+    }
+
+    pub fn set_unknown_opcode_policy(&mut self, policy: UnknownOpcodePolicy) {
+        self.unknown_opcode_policy = policy;
+    }
+
+    // Run handler for every opcode where opcode & mask == pattern, e.g. (0xF000, 0x0000) for all 0NNN
+    // machine code calls. Extensions are checked before the built-in instructions, first registered wins
+    #[cfg(feature = "alloc")]
+    pub fn register_opcode<F>(&mut self, mask: u16, pattern: u16, handler: F)
+    where
+        F: FnMut(&mut Chip8, u16) -> Result<()> + Send + 'static,
+    {
+        self.extensions.push(Extension { mask, pattern, handler: Box::new(handler) });
+        // Cached opcodes the extension takes over would skip it
+        if let Some(cache) = &mut self.decode_cache {
+            cache.clear();
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn clear_opcode_extensions(&mut self) {
+        self.extensions.clear();
+    }
+
+    // Remember each address's opcode and the handler that runs it the first time it's run, so loops skip the
+    // fetch and dispatch after that. It costs 16KB. Writes to RAM by any route drop what they overwrite,
+    // so self-modifying code runs as it does without
+    #[cfg(feature = "alloc")]
+    pub fn enable_decode_cache(&mut self, enabled: bool) {
+        if !enabled {
+            self.decode_cache = None;
+        } else if self.decode_cache.is_none() {
+            self.decode_cache = Some(Box::new(DecodeCache::new()));
+        }
+    }
+
+    // Keep the last len instructions run, for a CoreDump to show how a fault came about. 0 stops and forgets
+    #[cfg(feature = "alloc")]
+    pub fn set_recent_instructions(&mut self, len: usize) {
+        match &mut self.recent {
+            _ if len == 0 => self.recent = None,
+            Some(recent) => recent.set_capacity(len),
+            None => self.recent = Some(Rewind::new(len)),
+        }
+    }
+
+    // The instructions kept by set_recent_instructions() as address and opcode, oldest first
+    #[cfg(feature = "alloc")]
+    pub fn recent_instructions(&self) -> impl Iterator<Item = (u16, u16)> + '_ {
+        self.recent.iter().flat_map(|recent| recent.iter().copied())
+    }
+
+    // Start counting executions per opcode and per address, or stop and throw the counts away
+    #[cfg(feature = "alloc")]
+    pub fn enable_profiling(&mut self, enabled: bool) {
+        if !enabled {
+            self.profile = None;
+        } else if self.profile.is_none() {
+            self.profile = Some(Profile::new());
+        }
+    }
+
+    // Counts gathered since profiling was enabled, None while it's off
+    #[cfg(feature = "alloc")]
+    pub fn profile(&self) -> Option<&Profile> {
+        self.profile.as_ref()
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn profile_mut(&mut self) -> Option<&mut Profile> {
+        self.profile.as_mut()
+    }
+
+    // Start counting reads, writes and executes per address, or stop and throw the counts away
+    #[cfg(feature = "alloc")]
+    pub fn enable_heatmap(&mut self, enabled: bool) {
+        if !enabled {
+            self.heatmap = None;
+        } else if self.heatmap.is_none() {
+            self.heatmap = Some(Heatmap::new());
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn heatmap(&self) -> Option<&Heatmap> {
+        self.heatmap.as_ref()
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn heatmap_mut(&mut self) -> Option<&mut Heatmap> {
+        self.heatmap.as_mut()
+    }
+
+    // Start recording which addresses instructions run from, or stop and throw the record away
+    #[cfg(feature = "alloc")]
+    pub fn enable_coverage(&mut self, enabled: bool) {
+        if !enabled {
+            self.coverage = None;
+        } else if self.coverage.is_none() {
+            self.coverage = Some(Coverage::new());
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn coverage(&self) -> Option<&Coverage> {
+        self.coverage.as_ref()
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn coverage_mut(&mut self) -> Option<&mut Coverage> {
+        self.coverage.as_mut()
+    }
+
+    // Share of the loaded ROM's bytes that have been executed, None while coverage is off
+    #[cfg(feature = "alloc")]
+    pub fn coverage_percentage(&self) -> Option<f32> {
+        let coverage = self.coverage.as_ref()?;
+        Some(coverage.percentage(self.start_address, self.rom_len))
+    }
+
+    // Hold addr at value, e.g. to keep a game's lives topped up. It's written now and again when the
+    // FreezeTiming says, replacing any value addr was frozen at before
+    #[cfg(feature = "alloc")]
+    pub fn freeze(&mut self, addr: u16, value: u8) -> Result<()> {
+        self.write_byte(addr as usize, value)?;
+        match self.freezes.iter_mut().find(|(frozen, _)| *frozen == addr) {
+            Some(freeze) => freeze.1 = value,
+            None => self.freezes.push((addr, value)),
+        }
+        Ok(())
+    }
+
+    // Let the program change addr again, returns false if it wasn't frozen
+    #[cfg(feature = "alloc")]
+    pub fn unfreeze(&mut self, addr: u16) -> bool {
+        let len = self.freezes.len();
+        self.freezes.retain(|(frozen, _)| *frozen != addr);
+        self.freezes.len() != len
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn clear_freezes(&mut self) {
+        self.freezes.clear();
+    }
+
+    // Frozen addresses with their values, in the order they were frozen
+    #[cfg(feature = "alloc")]
+    pub fn freezes(&self) -> &[(u16, u8)] {
+        &self.freezes
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn freeze_timing(&self) -> FreezeTiming {
+        self.freeze_timing
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn set_freeze_timing(&mut self, timing: FreezeTiming) {
+        self.freeze_timing = timing;
+    }
+
+    // Freeze the addresses of the loaded ROM's enabled cheats and unfreeze those of its disabled ones, so
+    // toggling a cheat and applying the file again takes effect. Returns how many are enabled
+    #[cfg(feature = "alloc")]
+    pub fn apply_cheats(&mut self, cheats: &CheatFile) -> Result<usize> {
+        let Some(rom) = self.rom_hash else {
+            return Ok(0);
+        };
+        let mut enabled = 0;
+        for cheat in cheats.cheats(&rom) {
+            if cheat.enabled {
+                self.freeze(cheat.address, cheat.value)?;
+                enabled += 1;
+            } else {
+                self.unfreeze(cheat.address);
+            }
+        }
+        Ok(enabled)
+    }
+
+    #[cfg(feature = "alloc")]
+    fn apply_freezes(&mut self) {
+        for (addr, value) in &self.freezes {
+            self.buffers.ram[*addr as usize] = *value;
+            if let Some(cache) = &mut self.decode_cache {
+                cache.invalidate(*addr as usize..*addr as usize + 1);
+            }
+        }
+    }
+
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    pub fn set_variant(&mut self, variant: Variant) {
+        self.variant = variant;
+    }
+
+    pub fn quirks(&self) -> Quirks {
+        self.quirks
+    }
+
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+    }
+
+    // Restart CXNN's random numbers from seed, so the same seed and inputs play out the same way every time.
+    // Without a seed they come from the OS, or with no_std always start from the same fixed seed
+    pub fn seed_rng(&mut self, seed: u64) {
+        self.rng = Pcg32::new(seed);
+    }
+
+    // Display pixels for the current resolution, row by row, unpacked from display_rows() into a new Vec
+    #[cfg(feature = "alloc")]
+    pub fn get_display(&self) -> Vec<bool> {
+        let width = self.width();
+        let mut pixels = Vec::with_capacity(width * self.height());
+        for row in self.display_rows() {
+            pixels.extend((0..width).map(|x| row & (1 << (127 - x)) != 0));
+        }
+        pixels
+    }
+
+    // The display a row at a time for the current resolution, top to bottom, one bit per pixel with the
+    // leftmost pixel in the top bit. Lo-res rows only use the top 64 bits
+    pub fn display_rows(&self) -> &[u128] {
+        &self.buffers.display[..self.height()]
+    }
+
+    // Whether the pixel at (x, y) is lit, anywhere off the screen isn't
+    pub fn pixel(&self, x: usize, y: usize) -> bool {
+        x < self.width() && y < self.height() && self.buffers.display[y] & (1 << (127 - x)) != 0
+    }
+
+    pub fn is_hires(&self) -> bool {
+        self.hires
+    }
+
+    pub fn width(&self) -> usize {
+        if self.hires { HIRES_SCREEN_WIDTH } else { SCREEN_WIDTH }
+    }
+
+    pub fn height(&self) -> usize {
+        if self.hires { HIRES_SCREEN_HEIGHT } else { SCREEN_HEIGHT }
+    }
+
+    // 64-bit FNV-1a hash of the resolution and the display packed 8 pixels to a byte, leftmost pixel in the
+    // top bit. Stable across versions and platforms, so tests can assert on it instead of storing images
+    pub fn display_hash(&self) -> u64 {
+        let mut hash = Fnv64::new();
+        hash.write(&[self.width() as u8, self.height() as u8]);
+        let bytes_per_row = self.width() / 8;
+        for row in self.display_rows() {
+            hash.write(&row.to_be_bytes()[..bytes_per_row]);
+        }
+        hash.finish()
+    }
+
+    pub fn pc(&self) -> u16 {
+        self.pc
+    }
+
+    pub fn i(&self) -> u16 {
+        self.i_regi
+    }
+
+    // Value of register VX, panics if x is not in 0..=0xF
+    pub fn v(&self, x: usize) -> u8 {
+        self.v_regi[x]
+    }
+
+    pub fn delay_timer(&self) -> u8 {
+        self.delay_t
+    }
+
+    pub fn sound_timer(&self) -> u8 {
+        self.sound_t
+    }
+
+    // The buzzer sounds for as long as the sound timer is nonzero, unless paused
+    pub fn is_beeping(&self) -> bool {
+        self.sound_t > 0 && !self.paused
+    }
+
+    // Whether the beep has started or stopped since the last call, for starting and stopping an audio
+    // device, e.g. once after each run_frame(). A beep that started and ended between calls is still
+    // reported, as starting and then stopping at the next call
+    pub fn sound_edge(&mut self) -> Option<SoundEdge> {
+        let started = mem::take(&mut self.beep_started);
+        let edge = match (self.edge_beeping, self.is_beeping()) {
+            (false, beeping) if beeping || started => SoundEdge::Started,
+            (true, false) if !started => SoundEdge::Stopped,
+            _ => return None,
+        };
+        self.edge_beeping = edge == SoundEdge::Started;
+        Some(edge)
+    }
+
+    // Return addresses currently on the stack, oldest first
+    pub fn stack(&self) -> &[u16] {
+        &self.stack[..self.stkp as usize]
+    }
+
+    // Subroutine calls in progress, outermost first
+    pub fn call_stack(&self) -> &[CallFrame] {
+        &self.call_stack[..self.stkp as usize]
+    }
+
+    pub fn ram(&self) -> &[u8] {
+        &self.buffers.ram
     }
 
-    pub fn get_display(&self) -> &[bool] {
-        &self.display        
+    pub fn set_pc(&mut self, pc: u16) {
+        self.pc = pc;
     }
 
+    pub fn set_i(&mut self, i: u16) {
+        self.i_regi = i;
+    }
+
+    // Set register VX, panics if x is not in 0..=0xF
+    pub fn set_v(&mut self, x: usize, value: u8) {
+        self.v_regi[x] = value;
+    }
+
+    pub fn set_delay_timer(&mut self, value: u8) {
+        self.delay_t = value;
+    }
+
+    pub fn set_sound_timer(&mut self, value: u8) {
+        self.beep_started |= self.sound_t == 0 && value > 0;
+        #[cfg(feature = "alloc")]
+        match (self.sound_t, value) {
+            (0, 1..) => self.emit(Event::BeepStarted),
+            (1.., 0) => self.emit(Event::BeepStopped),
+            _ => (),
+        }
+        self.sound_t = value;
+    }
+
+    // Copy bytes into RAM starting at addr, nothing is written if they don't fit
+    pub fn write_ram(&mut self, addr: u16, bytes: &[u8]) -> Result<()> {
+        let start = addr as usize;
+        self.check_range(start, bytes.len())?;
+        self.buffers.ram[start..start + bytes.len()].copy_from_slice(bytes);
+        self.ram_written(start..start + bytes.len());
+        Ok(())
+    }
+
+    pub fn read_byte(&self, addr: usize) -> Result<u8> {
+        match self.buffers.ram.get(addr) {
+            Some(byte) => Ok(*byte),
+            None => Err(self.out_of_bounds(addr)),
+        }
+    }
+
+    pub fn write_byte(&mut self, addr: usize, val: u8) -> Result<()> {
+        let err = self.out_of_bounds(addr);
+        match self.buffers.ram.get_mut(addr) {
+            Some(byte) => {
+                *byte = val;
+                self.ram_written(addr..addr + 1);
+                Ok(())
+            },
+            None => Err(err),
+        }
+    }
+
+    // Drop any cached decoding of what's been overwritten
+    #[cfg_attr(not(feature = "alloc"), allow(unused_variables))]
+    fn ram_written(&mut self, range: core::ops::Range<usize>) {
+        #[cfg(feature = "alloc")]
+        if let Some(cache) = &mut self.decode_cache {
+            cache.invalidate(range);
+        }
+    }
+
+    // Memory accesses made by running instructions, which show up in the heatmap
+    fn mem_read(&mut self, addr: usize) -> Result<u8> {
+        let byte = self.read_byte(addr)?;
+        #[cfg(feature = "alloc")]
+        if let Some(heatmap) = &mut self.heatmap {
+            heatmap.record_read(addr);
+        }
+        Ok(byte)
+    }
+
+    fn mem_write(&mut self, addr: usize, val: u8) -> Result<()> {
+        self.write_byte(addr, val)?;
+        #[cfg(feature = "alloc")]
+        if let Some(heatmap) = &mut self.heatmap {
+            heatmap.record_write(addr);
+        }
+        Ok(())
+    }
+
+    // Make sure len bytes from addr are all in RAM, so multi-byte writes either fully happen or not at all
+    fn check_range(&self, addr: usize, len: usize) -> Result<()> {
+        if addr + len > RAM_SIZE {
+            return Err(self.out_of_bounds(addr.max(RAM_SIZE)));
+        }
+        Ok(())
+    }
+
+    fn out_of_bounds(&self, addr: usize) -> Chip8Error {
+        Chip8Error::MemoryOutOfBounds { addr, pc: self.op_pc }
+    }
+
+    // Press or release a keypad key, through the key queue when it's on
     pub fn keypress(&mut self, idx: usize, pressed: bool) {
+        #[cfg(feature = "alloc")]
+        if self.queue_key(idx, pressed) {
+            return;
+        }
         self.keys[idx] = pressed;
     }
 
-    pub fn load(&mut self, data: &[u8]) {
-        let start = START_ADDRESS as usize;
-        let end = (START_ADDRESS as usize) + data.len();
-        self.ram[start..end].copy_from_slice(data);
+    pub fn start_address(&self) -> u16 {
+        self.start_address
+    }
+
+    // Hash of the last loaded ROM, None until one is loaded and again after reset()
+    pub fn rom_hash(&self) -> Option<RomHash> {
+        self.rom_hash
+    }
+
+    // Names for the program's addresses, shown in traces and used by the debugger. Kept across reset()
+    // and load() until replaced
+    #[cfg(feature = "alloc")]
+    pub fn set_symbols(&mut self, symbols: Symbols) {
+        self.symbols = symbols;
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn symbols(&self) -> &Symbols {
+        &self.symbols
+    }
+
+    // What the ROM database knows about the last loaded ROM, for showing a title rather than a file name
+    #[cfg(feature = "romdb")]
+    pub fn rom_info(&self) -> Option<&'static RomInfo> {
+        RomInfo::lookup(&self.rom_hash?)
+    }
+
+    // Copy a ROM in at the start address, refusing it with RomTooLarge if it runs past the end of RAM.
+    // Odd lengths are loaded but warned about, as instructions are two bytes and the last is cut short.
+    // The ROM's SHA-1 is kept as rom_hash() to check what's loaded against a known good dump
+    pub fn load(&mut self, data: &[u8]) -> Result<()> {
+        let start = self.start_address as usize;
+        let max = self.rom_space();
+        if data.len() > max {
+            return Err(Chip8Error::RomTooLarge { len: data.len(), max });
+        }
+        if !data.len().is_multiple_of(2) {
+            #[cfg(feature = "log")]
+            log::warn!("ROM is an odd length ({} bytes), its last instruction is incomplete", data.len());
+            #[cfg(feature = "tracing")]
+            tracing::warn!(len = data.len(), "ROM is an odd length, its last instruction is incomplete");
+        }
+        self.buffers.ram[start..start + data.len()].copy_from_slice(data);
+        self.ram_written(start..start + data.len());
+        self.rom_len = data.len();
+        self.rom_hash = Some(RomHash::of(data));
+        Ok(())
+    }
+
+    // Switch to another game on the same machine, as reset() then load() but refusing a ROM load() would before
+    // changing anything, so the last game carries on. Everything set up on the machine stays, e.g. quirks,
+    // speed, the buzzer, hooks, observers and the key queue, while what belonged to the last game goes: its
+    // symbols, frozen addresses, recent instructions and profiling counts
+    pub fn load_rom(&mut self, data: &[u8]) -> Result<()> {
+        let max = self.rom_space();
+        if data.len() > max {
+            return Err(Chip8Error::RomTooLarge { len: data.len(), max });
+        }
+        self.reset();
+        #[cfg(feature = "alloc")]
+        {
+            self.symbols = Symbols::new();
+            self.freezes.clear();
+            if let Some(recent) = &mut self.recent {
+                recent.clear();
+            }
+            if let Some(profile) = &mut self.profile {
+                profile.clear();
+            }
+            if let Some(heatmap) = &mut self.heatmap {
+                heatmap.clear();
+            }
+            if let Some(coverage) = &mut self.coverage {
+                coverage.clear();
+            }
+        }
+        self.load(data)
+    }
+
+    // Bytes of RAM from the start address to the end, the largest ROM load() takes
+    pub(crate) fn rom_space(&self) -> usize {
+        RAM_SIZE.saturating_sub(self.start_address as usize)
     }
 
     // Reset emulator as needed
     pub fn reset(&mut self) {
-        self.pc = START_ADDRESS;
-        self.ram = [0; RAM_SIZE];
-        self.display = [false; SCREEN_WIDTH * SCREEN_HEIGHT];
+        self.pc = self.start_address;
+        self.buffers.ram.fill(0);
+        self.ram_written(0..RAM_SIZE);
+        self.buffers.display.fill(0);
+        self.hires = false;
         self.v_regi = [0; NUM_REGS];
         self.i_regi = 0;
         self.stkp = 0;
         self.stack = [0; STACK_SIZE];
+        self.call_stack = [CallFrame::default(); STACK_SIZE];
         self.keys = [false; NUM_KEYS];
+        // Changes queued for the last program don't carry over
+        #[cfg(feature = "alloc")]
+        if self.key_queue.is_some() {
+            self.key_queue = None;
+            self.enable_key_queue(true);
+        }
         self.delay_t = 0;
         self.sound_t = 0;
-        self.ram[..FONTSET_SIZE].copy_from_slice(&FONTSET);
+        self.halted = false;
+        self.paused = false;
+        self.frame_spent = 0;
+        self.timer_sync = None;
+        self.frame_time = 0;
+        self.drawn = false;
+        self.rom_len = 0;
+        self.rom_hash = None;
+        self.install_fontset();
     }
 
-    pub fn clock(&mut self) {
-        // Fetch
-        let opcode: u16 = self.fetch();
-        // Decode -> Execute
-        self.execute(opcode);
+    pub fn ticks_per_frame(&self) -> usize {
+        self.ticks_per_frame
     }
 
-    fn fetch(&mut self) -> u16 {
-        let high: u16 = self.ram[self.pc as usize] as u16;
-        let low: u16 = self.ram[(self.pc + 1) as usize] as u16;
-        let opcode: u16 = (high << 8) | low;
-        self.pc += 2;
-        opcode
-    }
-
-    fn execute(&mut self, opcode: u16) {
-        let d1: u16 = (opcode & 0xF000) >> 12;
-        let d2: u16 = (opcode & 0x0F00) >> 8;
-        let d3: u16 = (opcode & 0x00F0) >> 4;
-        let d4: u16 = opcode & 0x000F;
-
-        match (d1, d2, d3, d4) {
-           
-            // NOP - Do nothing
-            (0, 0, 0, 0) => return,
- 
-            // CLS - Clear display
-            (0, 0, 0xE, 0) => {
-                self.display = [false; SCREEN_WIDTH * SCREEN_HEIGHT];
-            },
-
-            // RET - Return from subroutine
-            (0, 0, 0xE, 0xE) => {
-                let return_address: u16 = self.pop();
-                self.pc = return_address;
-            },
-
-            // JMP NNN - Move the program counter to a given address
-            (1, _, _, _) => {
-                let nnn: u16 = opcode & 0xFFF;
-                self.pc = nnn;
-            },
+    // Number of instructions run_frame() executes per 60Hz frame, with Timing::Ticks
+    pub fn set_ticks_per_frame(&mut self, ticks: usize) {
+        self.ticks_per_frame = ticks;
+    }
 
-            // CALL NNN - Call subroutine
-            (2, _, _, _) => {
-                let nnn: u16 = opcode & 0xFFF;
-                self.push(self.pc);
-                self.pc = nnn;
-            },
-    
-            // SKIP VX == NN - Skip if equal
-            (3, _, _, _) => {
-                let x: usize = d2 as usize;
-                let nn: u8 = (opcode & 0xFF) as u8;
-                if self.v_regi[x] == nn {
-                    self.pc += 2;
-                }
-            },
+    // Run one 60Hz frame: a frame's worth of instructions, as the Timing counts them, followed by a timer
+    // tick. The instruction that uses up the frame's budget still runs in it
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    pub fn run_frame(&mut self) -> Result<State> {
+        if self.paused {
+            return Ok(self.state());
+        }
+        let budget = self.frame_budget();
+        let mut spent = mem::take(&mut self.frame_spent);
+        while spent < budget {
+            let cost = self.frame_cost();
+            if self.clock()? == State::Halted {
+                return Ok(State::Halted);
+            }
+            spent += cost;
+            // Paused by a hook, so the rest of the frame is run on resuming
+            if self.paused {
+                self.frame_spent = spent;
+                return Ok(self.state());
+            }
+        }
+        self.clock_timers();
+        Ok(self.state())
+    }
 
-            // SKIP VX != NN - Skip not equal
-            (4, _, _, _) => {
-                let x: usize = d2 as usize;
-                let nn: u8 = (opcode & 0xFF) as u8;
-                if self.v_regi[x] != nn {
-                    self.pc += 2;
-                }
-            },
+    // Frame advance for tool-assisted runs: hold exactly keys, run one frame with its timer tick and return the
+    // display_hash() it ends on, which a replay can compare to check it hasn't drifted. The keys stay held
+    pub fn advance_frame_with_input(&mut self, keys: [bool; NUM_KEYS]) -> Result<u64> {
+        self.keys = keys;
+        self.run_frame()?;
+        Ok(self.display_hash())
+    }
 
-            // SKIP VX == VY - Skip if VX == VY
-            (5, _, _, _) => {
-                let x: usize = d2 as usize;
-                let y: usize = d3 as usize;
-                if self.v_regi[x] == self.v_regi[y] {
-                    self.pc += 2;
-                }
-            },
+    // Run opcode as though it had just been fetched from addr, leaving the PC wherever it sends it, for code
+    // translated ahead of time by transpile_rust(). A fault leaves the PC on addr, as clock() does
+    pub fn run_opcode(&mut self, addr: u16, opcode: u16) -> Result<()> {
+        self.op_pc = addr;
+        self.pc = addr.wrapping_add(2);
+        if let Err(err) = self.execute(opcode) {
+            self.pc = addr;
+            return Err(err);
+        }
+        Ok(())
+    }
 
-            // VX = NN - Set V register to given value
-            (6, _, _, _) => {
-                let x: usize = d2 as usize;
-                let nn: u8 = (opcode & 0xFF) as u8;
-                self.v_regi[x] = nn;
-            },
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
 
-            // VX += NN - Add given value to VX reigister
-            (7, _, _, _) => {
-                let x: usize = d2 as usize;
-                let nn: u8 = (opcode & 0xFF) as u8;
-                self.v_regi[x] = self.v_regi[x].wrapping_add(nn);
-            },
+    // Stop run_frame() running anything until resume(), with the timers frozen and the beep silent. Paused from
+    // a hook partway through a frame, the frame stops after that instruction and resuming runs the rest of it
+    // before the timers tick, so a pause never changes what the program sees. An FX0A wait carries on waiting,
+    // and with the key queue on, keys pressed while paused wait in the queue until resume(), so none is taken
+    // as pressed during the pause. clock() still runs an instruction when called, for stepping through a
+    // paused game in a debugger
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
 
-            // VX = VY - Set a register x to the same value as a register y
-            (8, _, _, 0) => {
-                let x: usize = d2 as usize;
-                let y: usize = d3 as usize;
-                self.v_regi[x] = self.v_regi[y];
-            },
-    
-            // VX |= VY - Bitwise OR
-            (8, _, _, 1) => {
-                let x: usize = d2 as usize;
-                let y: usize = d3 as usize;
-                self.v_regi[x] |= self.v_regi[y];
-            },
+    pub fn resume(&mut self) {
+        self.paused = false;
+        #[cfg(feature = "alloc")]
+        self.deliver_keys();
+    }
 
-            // VX &= VY - Bitwise AND
-            (8, _, _, 2) => {
-                let x: usize = d2 as usize;
-                let y: usize = d3 as usize;
-                self.v_regi[x] &= self.v_regi[y];
-            },
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
 
-            // VX ^= VY - Bitwise XOR
-            (8, _, _, 3) => {
-                let x: usize = d2 as usize;
-                let y: usize = d3 as usize;
-                self.v_regi[x] ^= self.v_regi[y];
-            },
+    // Stop as 00FD does
+    fn halt(&mut self) {
+        self.halted = true;
+        #[cfg(feature = "alloc")]
+        self.emit(Event::Halted);
+    }
 
-            // VX += VY - Add with carry
-            (8, _, _, 4) => {
-                let x: usize = d2 as usize;
-                let y: usize = d3 as usize;
-                let (new_vx, carry) = self.v_regi[x].overflowing_add(self.v_regi[y]);
-                let new_vf = if carry { 1 } else { 0 };
-                self.v_regi[x] = new_vx;
-                self.v_regi[0xF] = new_vf;
-            },
+    pub fn clock(&mut self) -> Result<State> {
+        self.run_next()?;
+        Ok(self.state())
+    }
 
-            // VX -= VY - Subtract with carry
-            (8, _, _, 5) => {
-                let x: usize = d2 as usize;
-                let y: usize = d3 as usize;
-                let (new_vx, borrow) = self.v_regi[x].overflowing_sub(self.v_regi[y]);
-                let new_vf = if borrow { 0 } else { 1 };
-                self.v_regi[x] = new_vx;
-                self.v_regi[0xF] = new_vf;
-            },
+    // clock(), returning what it ran for tracers, debuggers and tests, so they needn't fetch and decode it
+    // again themselves. None when nothing ran: the machine was halted, or the PC was outside program memory
+    // with PcPolicy::Halt, which halts it
+    pub fn step(&mut self) -> Result<Option<StepResult>> {
+        let pc_before = self.pc;
+        let pc = pc_before as usize;
+        let opcode = u16::from_be_bytes([self.buffers.ram[pc % RAM_SIZE], self.buffers.ram[(pc + 1) % RAM_SIZE]]);
+        let cycles = self.frame_cost();
+        if !self.run_next()? {
+            return Ok(None);
+        }
+        Ok(Some(StepResult { pc_before, opcode, instruction: Instruction::decode(opcode), cycles }))
+    }
 
-            // VX >>= 1 - Shift right with dropoff stored in carry
-            (8, _, _, 6) => {
-                let x = d2 as usize;
-                let lsb = self.v_regi[x] & 1;
-                self.v_regi[x] >>= 1;
-                self.v_regi[0xF] = lsb;
-            },
+    // Run the instruction at the PC, returning whether there was one to run
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    fn run_next(&mut self) -> Result<bool> {
+        // A halted machine stays put until reset
+        if self.halted {
+            return Ok(false);
+        }
+        self.op_pc = self.pc;
+        // Both opcode bytes have to sit in program memory
+        let in_bounds = self.pc >= self.start_address && (self.pc as usize) < RAM_SIZE - 1;
+        if !in_bounds {
+            match self.pc_policy {
+                PcPolicy::Wrap => (),
+                PcPolicy::Fault => {
+                    let err = Chip8Error::PcOutOfBounds { pc: self.pc };
+                    #[cfg(feature = "alloc")]
+                    self.emit(Event::Fault(err));
+                    return Err(err);
+                },
+                PcPolicy::Halt => {
+                    self.halt();
+                    return Ok(false);
+                },
+            }
+        }
+        if let Err(err) = self.step_instruction() {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(%err, "emulator fault");
+            // Leave the PC on the faulting instruction
+            self.pc = self.op_pc;
+            #[cfg(feature = "alloc")]
+            self.emit(Event::Fault(err));
+            return Err(err);
+        }
+        Ok(true)
+    }
 
-            // VX = VY - VX - Subtract with carry, reversed operands
-            (8, _, _, 7) => {
-                let x: usize = d2 as usize;
-                let y: usize = d3 as usize;
-                let (new_vx, borrow) = self.v_regi[y].overflowing_sub(self.v_regi[x]);
-                let new_vf = if borrow { 0 } else { 1 };
-                self.v_regi[x] = new_vx;
-                self.v_regi[0xF] = new_vf;
-            },
+    fn step_instruction(&mut self) -> Result<()> {
+        #[cfg(feature = "alloc")]
+        if self.decode_cache.is_some() {
+            return self.step_cached();
+        }
+        // Fetch
+        let opcode: u16 = self.fetch()?;
+        #[cfg(feature = "alloc")]
+        if !self.hooks.is_empty() {
+            return self.step_hooked(opcode, Self::execute);
+        }
+        #[cfg(feature = "log")]
+        let before = trace::Registers::capture(self);
+        // Decode -> Execute
+        self.execute(opcode)?;
+        self.after_instruction(
+            opcode,
+            #[cfg(feature = "log")]
+            &before,
+        );
+        Ok(())
+    }
 
-            // VX <<= 1 - Left shift with dropoff stored in flag
-            (8, _, _, 0xE) => {
-                let x: usize = d2 as usize;
-                let msb = (self.v_regi[x] >> 7) & 1;
-                self.v_regi[x] <<= 1;
-                self.v_regi[0xF] = msb;
-            },
-    
-            // SKIP VX != VY - Skip if VX == VY
-            (9, _, _, 0) => {
-                let x: usize = d2 as usize;
-                let y: usize = d3 as usize;
-                if self.v_regi[x] != self.v_regi[y] {
-                    self.pc += 2;
+    // step_instruction() through the decode cache, out of line so running without one stays tight
+    #[cfg(feature = "alloc")]
+    #[inline(never)]
+    fn step_cached(&mut self) -> Result<()> {
+        let opcode = match self.cached_fetch() {
+            Some((opcode, handler)) => {
+                if !self.hooks.is_empty() {
+                    return self.step_hooked(opcode, handler);
                 }
+                #[cfg(feature = "log")]
+                let before = trace::Registers::capture(self);
+                handler(self, opcode)?;
+                self.after_instruction(
+                    opcode,
+                    #[cfg(feature = "log")]
+                    &before,
+                );
+                return Ok(());
             },
+            None => self.fetch()?,
+        };
+        self.cache_opcode(opcode);
+        if !self.hooks.is_empty() {
+            return self.step_hooked(opcode, Self::execute);
+        }
+        #[cfg(feature = "log")]
+        let before = trace::Registers::capture(self);
+        self.execute(opcode)?;
+        self.after_instruction(
+            opcode,
+            #[cfg(feature = "log")]
+            &before,
+        );
+        Ok(())
+    }
 
-            // I = NNN - Set I register
-            (0xA, _, _, _) => {
-                let nnn = opcode & 0xFFF;
-                self.i_regi = nnn;
-            },
-    
-            // JMP V0 + NNN - Jump to V0 + NNN
-            (0xB, _, _, _) => {
-                let nnn = opcode & 0xFFF;
-                self.pc = (self.v_regi[0] as u16) + nnn;
-            },
+    // The rest of step_instruction() with the instruction hooks run around it, out of line like step_cached()
+    #[cfg(feature = "alloc")]
+    #[inline(never)]
+    fn step_hooked(&mut self, opcode: u16, handler: dispatch::Handler) -> Result<()> {
+        self.run_hooks(false, opcode);
+        #[cfg(feature = "log")]
+        let before = trace::Registers::capture(self);
+        handler(self, opcode)?;
+        self.after_instruction(
+            opcode,
+            #[cfg(feature = "log")]
+            &before,
+        );
+        self.run_hooks(true, opcode);
+        Ok(())
+    }
 
-            // VX = rand() & NN - Generate random number and store in VX register
-            (0xC, _, _, _) => {
-                let x: usize = d2 as usize;
-                let nn: u8 = (opcode & 0xFF) as u8;
-                let rng: u8 = rand::thread_rng().gen();
-                self.v_regi[x] = rng & nn;
-            },
+    // Record the instruction just run at op_pc wherever it's being watched
+    #[cfg_attr(not(feature = "alloc"), allow(unused_variables))]
+    #[inline(always)]
+    fn after_instruction(&mut self, opcode: u16, #[cfg(feature = "log")] before: &trace::Registers) {
+        #[cfg(feature = "alloc")]
+        if let Some(profile) = &mut self.profile {
+            profile.record(self.op_pc, opcode);
+        }
+        #[cfg(feature = "alloc")]
+        if let Some(coverage) = &mut self.coverage {
+            coverage.record(self.op_pc);
+        }
+        #[cfg(feature = "alloc")]
+        if let Some(recent) = &mut self.recent {
+            recent.push((self.op_pc, opcode));
+        }
+        #[cfg(feature = "alloc")]
+        if self.freeze_timing == FreezeTiming::Clock {
+            self.apply_freezes();
+        }
+        #[cfg(feature = "log")]
+        trace::log_instruction(self, opcode, before);
+    }
 
-            // DRAW - Draw sprite on screen at location (d2, d3). Sprites are always 8 pixels wide, but height
-            // of sprite is stored in d4. Sprites are stored row by row starting from location stored in register I.
-            (0xD, _, _, _) => {
-                // Get the (x, y) coords for our sprite
-                let x = self.v_regi[d2 as usize] as u16;
-                let y = self.v_regi[d3 as usize] as u16;
-                // The last digit determines how many rows high our sprite is
-                let num_rows = d4;
-
-                // Keep track if any pixels were flipped
-                let mut flipped = false;
-                // Iterate over each row of our sprite
-                for y_line in 0..num_rows {
-                    // Determine which memory address our row's data is stored
-                    let addr = self.i_regi + y_line as u16;
-                    let pixels = self.ram[addr as usize];
-                    // Iterate over each column in our row
-                    for x_line in 0..8 {
-                        // Use a mask to fetch current pixel's bit. Only flip if a 1
-                        if (pixels & (0b1000_0000 >> x_line)) != 0 {
-                            // Sprites should wrap around screen, so apply modulo
-                            let x = (x + x_line) as usize % SCREEN_WIDTH;
-                            let y = (y + y_line) as usize % SCREEN_HEIGHT;
-
-                            // Get our pixel's index in the 1D screen array
-                            let idx = x + SCREEN_WIDTH * y;
-                            // Check if we're about to flip the pixel and set
-                            flipped |= self.display[idx];
-                            self.display[idx] ^= true;
-                        }
-                    }
-                }
-                // Populate VF register
-                if flipped {
-                    self.v_regi[0xF] = 1;
-                } else {
-                    self.v_regi[0xF] = 0;
-                }
-            },
+    fn state(&self) -> State {
+        if self.halted {
+            State::Halted
+        } else {
+            State::Running
+        }
+    }
 
-            // SKIP KEY PRESS - Skip if key stored in VX is pressed
-            (0xE, _, 9, 0xE) => {
-                let x: usize = d2 as usize;
-                let vx: u8 = self.v_regi[x];
-                let key: bool = self.keys[vx as usize];
-                if key {
-                    self.pc += 2;
-                }
-            },
+    #[cfg(feature = "alloc")]
+    fn cached_fetch(&mut self) -> Option<(u16, dispatch::Handler)> {
+        let hit = self.decode_cache.as_ref()?.get(self.pc)?;
+        if let Some(heatmap) = &mut self.heatmap {
+            heatmap.record_execute(self.pc as usize);
+            heatmap.record_execute(self.pc as usize + 1);
+        }
+        self.pc += 2;
+        Some(hit)
+    }
 
-            // SKIP KEY RELEASE - Skip if key stored in VX isnot pressed
-            (0xE, _, 0xA, 1) => {
-                let x = d2 as usize;
-                let vx = self.v_regi[x];
-                let key = self.keys[vx as usize];
-                if !key {
-                    self.pc += 2;
-                }
-            },
+    // Remember the opcode just fetched from op_pc, unless an extension runs it or fetching it wrapped
+    #[cfg(feature = "alloc")]
+    fn cache_opcode(&mut self, opcode: u16) {
+        let cacheable = (self.op_pc as usize) < RAM_SIZE - 1 && !self.has_extension(opcode);
+        if let Some(cache) = self.decode_cache.as_mut().filter(|_| cacheable) {
+            cache.insert(self.op_pc, opcode);
+        }
+    }
 
-            // VX = DT - Stores delay timer in a register specified by d2
-            (0xF, _, 0, 7) => {
-                let x: usize = d2 as usize;
-                self.v_regi[x] = self.delay_t;
-            },
-    
-            // WAIT KEY - Block until key pressed
-            (0xF, _, 0, 0xA) => {
-                let x = d2 as usize;
-                let mut pressed = false;
-                for i in 0..self.keys.len() {
-                    if self.keys[i] {
-                        self.v_regi[x] = i as u8;
-                        pressed = true;
-                        break;
-                    }
-                }
-                if !pressed {
-                    // Redo opcode
-                    self.pc -= 2;
-                }
-            },
+    fn fetch(&mut self) -> Result<u16> {
+        let wrap = self.pc_policy == PcPolicy::Wrap;
+        let high_addr = if wrap { self.pc as usize % RAM_SIZE } else { self.pc as usize };
+        // Opcodes straddling the end of RAM take their low byte from address 0 when wrapping
+        let low_addr = if wrap { (high_addr + 1) % RAM_SIZE } else { high_addr + 1 };
 
-            // DT = VX - Set delay timer to value in VX
-             (0xF, _, 1, 5) => {
-                let x = d2 as usize;
-                self.delay_t = self.v_regi[x];
-            },
+        let high: u16 = self.read_byte(high_addr)? as u16;
+        let low: u16 = self.read_byte(low_addr)? as u16;
+        #[cfg(feature = "alloc")]
+        if let Some(heatmap) = &mut self.heatmap {
+            heatmap.record_execute(high_addr);
+            heatmap.record_execute(low_addr);
+        }
+        let opcode: u16 = (high << 8) | low;
+        self.pc = if wrap { ((high_addr + 2) % RAM_SIZE) as u16 } else { self.pc + 2 };
+        Ok(opcode)
+    }
 
-            // ST = VX - Set sound timer to value in VX
-            (0xF, _, 1, 8) => {
-                let x = d2 as usize;
-                self.sound_t = self.v_regi[x];
-            },
-    
-            // I += VX - Add VX to I
-            (0xF, _, 1, 0xE) => {
-                let x = d2 as usize;
-                let vx = self.v_regi[x] as u16;
-                self.i_regi = self.i_regi.wrapping_add(vx);
-            },
-    
-            // I = FONT - Set I to font address
-            (0xF, _, 2, 9) => {
-                let x = d2 as usize;
-                let c = self.v_regi[x] as u16;
-                self.i_regi = c * 5;
-            },
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip_all, fields(opcode = %format_args!("{:04X}", opcode)))
+    )]
+    fn execute(&mut self, opcode: u16) -> Result<()> {
+        #[cfg(feature = "alloc")]
+        if let Some(result) = self.run_extension(opcode) {
+            return result;
+        }
 
-            // BCD - Store BCD(VX) in I
-            (0xF, _, 3, 3) => {
-                let x = d2 as usize;
-                let vx = self.v_regi[x] as f32;
+        dispatch::execute(self, opcode)
+    }
+
+    // Whether a registered extension takes opcode over
+    #[cfg(feature = "alloc")]
+    pub(crate) fn has_extension(&self, opcode: u16) -> bool {
+        self.extensions.iter().any(|ext| opcode & ext.mask == ext.pattern)
+    }
 
-                let hundreds: u8 = (vx / 100.0).floor() as u8;
-                let tens: u8 = ((vx / 10.0) % 10.0).floor() as u8;
-                let ones: u8 = (vx % 10.0) as u8;
+    #[cfg(feature = "alloc")]
+    fn run_extension(&mut self, opcode: u16) -> Option<Result<()>> {
+        let idx = self.extensions.iter().position(|ext| opcode & ext.mask == ext.pattern)?;
+        // Take the registry out so the handler can be given the whole emulator, then put it back
+        let mut extensions = mem::take(&mut self.extensions);
+        let result = (extensions[idx].handler)(self, opcode);
+        // Keep anything the handler registered while it ran
+        extensions.append(&mut self.extensions);
+        self.extensions = extensions;
+        Some(result)
+    }
 
-                self.ram[self.i_regi as usize] = hundreds;
-                self.ram[(self.i_regi + 1) as usize] = tens;
-                self.ram[(self.i_regi + 2) as usize] = ones;
+    fn unknown_opcode(&mut self, opcode: u16) -> Result<()> {
+        let fault = Chip8Error::UnknownOpcode { opcode, pc: self.op_pc };
+        // Take the policy out so a handler can be given the whole emulator, then put it back
+        let mut policy = mem::replace(&mut self.unknown_opcode_policy, UnknownOpcodePolicy::Fault);
+        let result = match &mut policy {
+            UnknownOpcodePolicy::Fault => Err(fault),
+            UnknownOpcodePolicy::Skip => Ok(()),
+            UnknownOpcodePolicy::Halt => {
+                self.halt();
+                Ok(())
             },
-            
-            // STORE V0 - VX - Store V0 - VX in I register
-            (0xF, _, 5, 5) => {
-                let x = d2 as usize;
-                let i = self.i_regi as usize;
-                for idx in 0..=x {
-                    self.ram[i + idx] = self.v_regi[idx];
-                }
+            #[cfg(feature = "alloc")]
+            UnknownOpcodePolicy::Handler(handler) => {
+                if handler(self, opcode) { Ok(()) } else { Err(fault) }
             },
+        };
+        self.unknown_opcode_policy = policy;
+        result
+    }
 
-            // LOAD V0 - VX - Load I into V0 - VX
-            (0xF, _, 6, 5) => {
-                let x = d2 as usize;
-                let i = self.i_regi as usize;
-                for idx in 0..=x {
-                    self.v_regi[idx] = self.ram[i + idx];
-                }
-            },
-    
-            (_, _, _, _) => unimplemented!("Received unimplemented opcode: {}", opcode),
+    // Move every row down, filling the vacated rows at the top with blank pixels
+    fn scroll_down(&mut self, n: usize) {
+        let height = self.height();
+        let n = n.min(height);
+        self.buffers.display.copy_within(0..height - n, n);
+        self.buffers.display[..n].fill(0);
+        #[cfg(feature = "alloc")]
+        self.display_changed(u64::MAX);
+    }
+
+    // Move every pixel right within its row, pixels pushed off the edge are lost
+    fn scroll_right(&mut self, n: usize) {
+        let mask = self.row_mask();
+        let height = self.height();
+        for row in &mut self.buffers.display[..height] {
+            *row = (*row >> n) & mask;
+        }
+        #[cfg(feature = "alloc")]
+        self.display_changed(u64::MAX);
+    }
+
+    // Move every pixel left within its row, pixels pushed off the edge are lost
+    fn scroll_left(&mut self, n: usize) {
+        let mask = self.row_mask();
+        let height = self.height();
+        for row in &mut self.buffers.display[..height] {
+            *row = (*row << n) & mask;
         }
-    }  
+        #[cfg(feature = "alloc")]
+        self.display_changed(u64::MAX);
+    }
+
+    // The bits of a display row that are on screen in the current resolution
+    fn row_mask(&self) -> u128 {
+        if self.hires { u128::MAX } else { LORES_ROW_MASK }
+    }
 
     pub fn clock_timers(&mut self) {
         if self.delay_t > 0 {
@@ -450,9 +1503,18 @@ impl Chip8 {
 
         if self.sound_t > 0 {
             if self.sound_t == 1 {
-                // BEEP
+                // The beep ends with this tick
+                #[cfg(feature = "alloc")]
+                self.emit(Event::BeepStopped);
             }
             self.sound_t -= 1;
         }
+        self.drawn = false;
+
+        #[cfg(feature = "alloc")]
+        {
+            self.key_frame();
+            self.apply_freezes();
+        }
     } 
 }
\ No newline at end of file
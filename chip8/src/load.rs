@@ -0,0 +1,82 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use crate::Chip8;
+
+// Why load_file() or load_reader() couldn't load a ROM. path is the file's for load_file(), None for a reader
+#[derive(Debug)]
+pub enum LoadError {
+    Io { path: Option<PathBuf>, err: io::Error },
+    // The ROM is len bytes but only max fit in memory, as Chip8Error::RomTooLarge
+    TooLarge { path: Option<PathBuf>, len: u64, max: usize },
+}
+
+impl LoadError {
+    fn with_path(self, path: &Path) -> Self {
+        let path = Some(path.to_path_buf());
+        match self {
+            LoadError::Io { err, .. } => LoadError::Io { path, err },
+            LoadError::TooLarge { len, max, .. } => LoadError::TooLarge { path, len, max },
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (LoadError::Io { path, .. } | LoadError::TooLarge { path, .. }) = self;
+        if let Some(path) = path {
+            write!(f, "{}: ", path.display())?;
+        }
+        match self {
+            LoadError::Io { err, .. } => write!(f, "couldn't read ROM: {}", err),
+            LoadError::TooLarge { len, max, .. } => write!(f, "ROM is {} bytes, only {} fit in memory", len, max),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LoadError::Io { err, .. } => Some(err),
+            LoadError::TooLarge { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for LoadError {
+    fn from(err: io::Error) -> Self {
+        LoadError::Io { path: None, err }
+    }
+}
+
+impl Chip8 {
+    // Read a ROM file and load() it. Files too large to fit are refused without reading them
+    pub fn load_file(&mut self, path: impl AsRef<Path>) -> Result<(), LoadError> {
+        let path = path.as_ref();
+        let load = |chip8: &mut Chip8| {
+            let file = File::open(path)?;
+            let len = file.metadata()?.len();
+            let max = chip8.rom_space();
+            if len > max as u64 {
+                return Err(LoadError::TooLarge { path: None, len, max });
+            }
+            chip8.load_reader(file)
+        };
+        load(self).map_err(|err| err.with_path(path))
+    }
+
+    // Read a ROM to the end and load() it. Reading stops once more has come than fits, so a stream that
+    // never ends is reported as too large rather than read forever, with len how much was read
+    pub fn load_reader(&mut self, reader: impl Read) -> Result<(), LoadError> {
+        let max = self.rom_space();
+        let mut rom = Vec::new();
+        reader.take(max as u64 + 1).read_to_end(&mut rom)?;
+        if rom.len() > max {
+            return Err(LoadError::TooLarge { path: None, len: rom.len() as u64, max });
+        }
+        self.load(&rom).expect("ROM checked to fit");
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+use alloc::vec::Vec;
+
+use crate::{Chip8, Chip8Error, State};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MachineId(pub usize);
+
+struct Machine {
+    id: MachineId,
+    chip8: Chip8,
+    // Set when the last frame faulted, the machine sits out frames until resume()
+    fault: Option<Chip8Error>,
+}
+
+// Many emulators run side by side, e.g. a wall of games. run_frame() advances all of them a frame at a time
+// and keys and displays are routed by the MachineId add() handed out. A machine that faults or halts stops
+// there without holding up the rest
+#[derive(Default)]
+pub struct Machines {
+    // In id order, ids only ever increase so lookups can binary search
+    machines: Vec<Machine>,
+    next_id: usize,
+}
+
+impl Machines {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, chip8: Chip8) -> MachineId {
+        let id = MachineId(self.next_id);
+        self.next_id += 1;
+        self.machines.push(Machine { id, chip8, fault: None });
+        id
+    }
+
+    pub fn remove(&mut self, id: MachineId) -> Option<Chip8> {
+        let idx = self.index(id)?;
+        Some(self.machines.remove(idx).chip8)
+    }
+
+    pub fn len(&self) -> usize {
+        self.machines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.machines.is_empty()
+    }
+
+    pub fn get(&self, id: MachineId) -> Option<&Chip8> {
+        self.index(id).map(|idx| &self.machines[idx].chip8)
+    }
+
+    pub fn get_mut(&mut self, id: MachineId) -> Option<&mut Chip8> {
+        self.index(id).map(|idx| &mut self.machines[idx].chip8)
+    }
+
+    // Every machine in the order they were added, e.g. for drawing them all
+    pub fn iter(&self) -> impl Iterator<Item = (MachineId, &Chip8)> {
+        self.machines.iter().map(|machine| (machine.id, &machine.chip8))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (MachineId, &mut Chip8)> {
+        self.machines.iter_mut().map(|machine| (machine.id, &mut machine.chip8))
+    }
+
+    // Press or release a key on one machine, returns false if there's no such machine
+    pub fn keypress(&mut self, id: MachineId, idx: usize, pressed: bool) -> bool {
+        match self.get_mut(id) {
+            Some(chip8) => {
+                chip8.keypress(idx, pressed);
+                true
+            },
+            None => false,
+        }
+    }
+
+    // The display of one machine, see Chip8::display_rows()
+    pub fn display_rows(&self, id: MachineId) -> Option<&[u128]> {
+        self.get(id).map(Chip8::display_rows)
+    }
+
+    // Run a frame on every machine that's still going. Returns how many are, i.e. haven't halted or faulted
+    pub fn run_frame(&mut self) -> usize {
+        let mut running = 0;
+        for machine in self.machines.iter_mut().filter(|machine| machine.fault.is_none()) {
+            match machine.chip8.run_frame() {
+                Ok(State::Running) => running += 1,
+                Ok(State::Halted) => (),
+                Err(err) => machine.fault = Some(err),
+            }
+        }
+        running
+    }
+
+    // Why a machine stopped running frames, None while it's fine or if there's no such machine
+    pub fn fault(&self, id: MachineId) -> Option<Chip8Error> {
+        self.machines[self.index(id)?].fault
+    }
+
+    // Every machine whose last frame faulted, with the fault
+    pub fn faults(&self) -> impl Iterator<Item = (MachineId, Chip8Error)> + '_ {
+        self.machines.iter().filter_map(|machine| machine.fault.map(|fault| (machine.id, fault)))
+    }
+
+    // Let a faulted machine run frames again, e.g. after resetting it or fixing what it tripped over.
+    // The faulting instruction is the next to run
+    pub fn resume(&mut self, id: MachineId) -> bool {
+        match self.index(id) {
+            Some(idx) => {
+                self.machines[idx].fault = None;
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn index(&self, id: MachineId) -> Option<usize> {
+        self.machines.binary_search_by_key(&id, |machine| machine.id).ok()
+    }
+}
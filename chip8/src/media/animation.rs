@@ -0,0 +1,135 @@
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use ::gif::{Encoder, EncodingError, Frame, Repeat};
+
+use super::{render, Palette};
+use crate::{Chip8, HIRES_SCREEN_HEIGHT, HIRES_SCREEN_WIDTH};
+
+// Shortest delay GIF viewers honour, in hundredths of a second. Anything shorter is usually slowed right down
+const MIN_DELAY: u64 = 2;
+
+// Records the display into an animated two colour GIF. Call capture() once per frame, e.g. after each
+// run_frame(), and it only writes anything between start() and stop(). Frames are always the size of the
+// hi-res screen times the scale, low-res ones are doubled up, so resolution switches don't change the size
+pub struct GifRecorder {
+    scale: usize,
+    palette: Palette,
+    encoder: Option<Encoder<Box<dyn Write + Send>>>,
+    // Latest distinct frame, held back until we know how long it stays on screen
+    pending: Option<Vec<u8>>,
+    // Frames captured since start()
+    frames: u64,
+    // Length of the GIF written so far, in hundredths of a second
+    written: u64,
+}
+
+impl GifRecorder {
+    pub fn new(scale: usize) -> Self {
+        Self { scale: scale.max(1), palette: Palette::default(), encoder: None, pending: None, frames: 0, written: 0 }
+    }
+
+    // Takes effect from the next start()
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.encoder.is_some()
+    }
+
+    pub fn start_file(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        let file = File::create(path)?;
+        self.start(BufWriter::new(file))
+    }
+
+    // Begin a new recording into writer, finishing any recording already in progress
+    pub fn start(&mut self, writer: impl Write + Send + 'static) -> io::Result<()> {
+        self.stop()?;
+        let (width, height) = self.size()?;
+        let palette: Vec<u8> = self.palette.off.iter().chain(self.palette.on.iter()).copied().collect();
+        let writer: Box<dyn Write + Send> = Box::new(writer);
+        let mut encoder = Encoder::new(writer, width, height, &palette).map_err(gif_error)?;
+        encoder.set_repeat(Repeat::Infinite).map_err(gif_error)?;
+        self.encoder = Some(encoder);
+        Ok(())
+    }
+
+    pub fn capture(&mut self, chip8: &Chip8) -> io::Result<()> {
+        if self.encoder.is_none() {
+            return Ok(());
+        }
+        let (_, _, pixels) = render(chip8, self.scale * HIRES_SCREEN_WIDTH / chip8.width());
+        if self.pending.as_ref() != Some(&pixels) {
+            // A frame that was up too briefly to show is dropped, and the next one takes its time
+            if self.delay() >= MIN_DELAY {
+                self.write_pending()?;
+            }
+            self.pending = Some(pixels);
+        }
+        self.frames += 1;
+        Ok(())
+    }
+
+    // Finish the GIF. Does nothing if not recording
+    pub fn stop(&mut self) -> io::Result<()> {
+        self.write_pending()?;
+        self.frames = 0;
+        self.written = 0;
+        match self.encoder.take() {
+            Some(encoder) => encoder.into_inner()?.flush(),
+            None => Ok(()),
+        }
+    }
+
+    // How long the pending frame has been up. Working from the totals keeps the GIF in step with 60 frames
+    // a second, despite delays only being in whole hundredths
+    fn delay(&self) -> u64 {
+        (self.frames * 100 / 60).saturating_sub(self.written)
+    }
+
+    // GIF dimensions are 16-bit, which rules out scales above 511
+    fn size(&self) -> io::Result<(u16, u16)> {
+        let side = |len: usize| len.checked_mul(self.scale).and_then(|len| u16::try_from(len).ok());
+        match (side(HIRES_SCREEN_WIDTH), side(HIRES_SCREEN_HEIGHT)) {
+            (Some(width), Some(height)) => Ok((width, height)),
+            _ => {
+                let message = format!("scale {} is too large for a GIF", self.scale);
+                Err(io::Error::new(io::ErrorKind::InvalidInput, message))
+            },
+        }
+    }
+
+    fn write_pending(&mut self) -> io::Result<()> {
+        let mut delay = self.delay().max(MIN_DELAY);
+        let (width, height) = self.size()?;
+        let (encoder, pixels) = match (self.encoder.as_mut(), self.pending.take()) {
+            (Some(encoder), Some(pixels)) => (encoder, pixels),
+            _ => return Ok(()),
+        };
+        let mut frame = Frame { width, height, buffer: Cow::Owned(pixels), ..Frame::default() };
+        // Delays are 16-bit too, so a screen that stays up longer than that is written as several frames
+        while delay > 0 {
+            frame.delay = delay.min(u16::MAX.into()) as u16;
+            encoder.write_frame(&frame).map_err(gif_error)?;
+            delay -= u64::from(frame.delay);
+            self.written += u64::from(frame.delay);
+        }
+        Ok(())
+    }
+}
+
+impl Drop for GifRecorder {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+fn gif_error(err: EncodingError) -> io::Error {
+    match err {
+        EncodingError::Io(err) => err,
+        err => io::Error::other(err),
+    }
+}
@@ -0,0 +1,68 @@
+#[cfg(feature = "gif")]
+mod animation;
+mod rgba;
+#[cfg(feature = "png")]
+mod screenshot;
+mod video;
+mod wav;
+
+#[cfg(feature = "gif")]
+pub use animation::GifRecorder;
+pub use video::VideoRecorder;
+pub use wav::{WavRecorder, DEFAULT_SAMPLE_RATE};
+
+// RGB colours for unlit and lit pixels
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Palette {
+    pub off: [u8; 3],
+    pub on: [u8; 3],
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self::CLASSIC
+    }
+}
+
+impl Palette {
+    pub const CLASSIC: Self = Self { off: [0, 0, 0], on: [0xFF, 0xFF, 0xFF] };
+    pub const GREEN_PHOSPHOR: Self = Self { off: [0x0A, 0x14, 0x0A], on: [0x33, 0xFF, 0x66] };
+    pub const AMBER: Self = Self { off: [0x1A, 0x0F, 0x00], on: [0xFF, 0xB0, 0x00] };
+    // The greens of an early handheld's reflective screen
+    pub const LCD: Self = Self { off: [0x9B, 0xBC, 0x0F], on: [0x0F, 0x38, 0x0F] };
+    pub const HIGH_CONTRAST: Self = Self { off: [0, 0, 0], on: [0xFF, 0xFF, 0x00] };
+    // Octo's own colours, which cartridges default to
+    pub const OCTO: Self = Self { off: [0x99, 0x66, 0x00], on: [0xFF, 0xCC, 0x00] };
+
+    // Each built-in palette by the name named() takes
+    pub const THEMES: [(&'static str, Self); 6] = [
+        ("classic", Self::CLASSIC),
+        ("green", Self::GREEN_PHOSPHOR),
+        ("amber", Self::AMBER),
+        ("lcd", Self::LCD),
+        ("high-contrast", Self::HIGH_CONTRAST),
+        ("octo", Self::OCTO),
+    ];
+
+    // A built-in palette by name, ignoring case
+    pub fn named(name: &str) -> Option<Self> {
+        Self::THEMES.iter().find(|(theme, _)| theme.eq_ignore_ascii_case(name)).map(|(_, palette)| *palette)
+    }
+}
+
+// The current display with each pixel blown up to a scale x scale square, one byte per output pixel that's
+// 1 when lit. Returns the output width and height along with the pixels
+pub(crate) fn render(chip8: &crate::Chip8, scale: usize) -> (usize, usize, Vec<u8>) {
+    let (width, height) = (chip8.width() * scale, chip8.height() * scale);
+    let mut pixels = Vec::with_capacity(width * height);
+    for row in chip8.get_display().chunks(chip8.width()) {
+        let line_start = pixels.len();
+        for pixel in row {
+            pixels.extend(std::iter::repeat_n(*pixel as u8, scale));
+        }
+        for _ in 1..scale {
+            pixels.extend_from_within(line_start..line_start + width);
+        }
+    }
+    (width, height, pixels)
+}
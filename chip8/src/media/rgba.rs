@@ -0,0 +1,13 @@
+use super::Palette;
+use crate::Chip8;
+
+impl Chip8 {
+    // The display in palette's colours as 8-bit RGBA, 4 bytes a pixel and a row at a time from the top left,
+    // replacing what was in buffer. For frontends that upload a texture each frame, e.g. through wgpu or
+    // egui, which can keep the one buffer rather than allocating every frame
+    pub fn render_rgba(&self, palette: Palette, buffer: &mut Vec<u8>) {
+        let [off, on] = [palette.off, palette.on].map(|[r, g, b]| [r, g, b, 0xFF]);
+        buffer.clear();
+        buffer.extend(self.get_display().iter().flat_map(|pixel| if *pixel { on } else { off }));
+    }
+}
@@ -0,0 +1,43 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use ::png::{BitDepth, ColorType, Encoder, EncodingError};
+
+use super::{render, Palette};
+use crate::Chip8;
+
+impl Chip8 {
+    // Save the display as a PNG, each pixel scaled up to a scale x scale square
+    pub fn screenshot_png(&self, path: impl AsRef<Path>, palette: Palette, scale: usize) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        self.write_png(&mut file, palette, scale)?;
+        file.flush()
+    }
+
+    // Encode the display as a two colour PNG into writer
+    pub fn write_png(&self, writer: impl Write, palette: Palette, scale: usize) -> io::Result<()> {
+        let (width, height, pixels) = render(self, scale.max(1));
+        let mut encoder = Encoder::new(writer, width as u32, height as u32);
+        encoder.set_color(ColorType::Indexed);
+        encoder.set_depth(BitDepth::One);
+        encoder.set_palette(palette.off.iter().chain(palette.on.iter()).copied().collect::<Vec<u8>>());
+        let mut writer = encoder.write_header().map_err(png_error)?;
+        // One bit per pixel, each row padded out to a whole byte
+        let packed: Vec<u8> = pixels.chunks(width).flat_map(|row| row.chunks(8).map(pack_bits)).collect();
+        writer.write_image_data(&packed).map_err(png_error)?;
+        writer.finish().map_err(png_error)
+    }
+}
+
+// Up to 8 pixels into a byte, leftmost in the high bit
+fn pack_bits(bits: &[u8]) -> u8 {
+    bits.iter().enumerate().fold(0, |byte, (idx, bit)| byte | bit << (7 - idx))
+}
+
+fn png_error(err: EncodingError) -> io::Error {
+    match err {
+        EncodingError::IoError(err) => err,
+        err => io::Error::other(err),
+    }
+}
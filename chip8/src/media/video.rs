@@ -0,0 +1,334 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use super::wav::{Beeper, WriteSeek};
+use super::{render, Palette};
+use crate::{Chip8, HIRES_SCREEN_HEIGHT, HIRES_SCREEN_WIDTH};
+
+const MICROSECONDS_PER_FRAME: u32 = 1_000_000 / 60;
+// AVIF_HASINDEX in the main header and AVIIF_KEYFRAME in the index, every frame is a key frame
+const HAS_INDEX: u32 = 0x10;
+const KEYFRAME: u32 = 0x10;
+
+// Where the lengths that are only known at the end sit in the header
+struct Placeholders {
+    total_frames: u64,
+    video_length: u64,
+    audio_length: u64,
+    movi_size: u64,
+}
+
+// Records the display and the beeper together into an uncompressed AVI, 24-bit video at 60 frames a second
+// plus 16-bit mono PCM audio, which any player or ffmpeg can read. Call capture() once per frame, e.g. after
+// each run_frame(). Like GifRecorder, frames are the hi-res screen size times the scale whatever the mode
+pub struct VideoRecorder {
+    scale: usize,
+    palette: Palette,
+    beeper: Beeper,
+    writer: Option<Box<dyn WriteSeek>>,
+    placeholders: Placeholders,
+    // Where the 'movi' list's fourcc is, which the index offsets count from
+    movi_start: u64,
+    // Every chunk written, as (id, offset from movi_start, size)
+    index: Vec<([u8; 4], u32, u32)>,
+    frames: u32,
+}
+
+impl VideoRecorder {
+    pub fn new(scale: usize, sample_rate: u32) -> Self {
+        Self {
+            scale: scale.max(1),
+            palette: Palette::default(),
+            beeper: Beeper::new(sample_rate),
+            writer: None,
+            placeholders: Placeholders { total_frames: 0, video_length: 0, audio_length: 0, movi_size: 0 },
+            movi_start: 0,
+            index: Vec::new(),
+            frames: 0,
+        }
+    }
+
+    // Takes effect from the next frame
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.writer.is_some()
+    }
+
+    pub fn start_file(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        let file = File::create(path)?;
+        self.start(BufWriter::new(file))
+    }
+
+    // Begin a new recording into writer, finishing any recording already in progress. The header is
+    // filled in by stop(), which is why the writer has to be seekable
+    pub fn start(&mut self, writer: impl Write + Seek + Send + 'static) -> io::Result<()> {
+        self.stop()?;
+        let mut writer: Box<dyn WriteSeek> = Box::new(writer);
+        let (header, placeholders) = self.header()?;
+        writer.write_all(&header)?;
+        self.movi_start = header.len() as u64 - 4;
+        self.placeholders = placeholders;
+        self.writer = Some(writer);
+        Ok(())
+    }
+
+    pub fn capture(&mut self, chip8: &Chip8) -> io::Result<()> {
+        if self.writer.is_none() {
+            return Ok(());
+        }
+        let video = self.frame_pixels(chip8);
+        let audio = self.beeper.frame(chip8);
+        self.write_chunk(*b"00db", &video)?;
+        self.write_chunk(*b"01wb", &audio)?;
+        self.frames += 1;
+        Ok(())
+    }
+
+    // Finish the AVI. Does nothing if not recording
+    pub fn stop(&mut self) -> io::Result<()> {
+        let result = self.finish();
+        self.writer = None;
+        self.index.clear();
+        self.frames = 0;
+        self.beeper.reset();
+        result
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        let writer = match self.writer.as_mut() {
+            Some(writer) => writer,
+            None => return Ok(()),
+        };
+        let movi_end = writer.stream_position()?;
+        let mut idx1 = Vec::with_capacity(self.index.len() * 16);
+        for (id, offset, size) in &self.index {
+            idx1.extend_from_slice(id);
+            idx1.extend_from_slice(&KEYFRAME.to_le_bytes());
+            idx1.extend_from_slice(&offset.to_le_bytes());
+            idx1.extend_from_slice(&size.to_le_bytes());
+        }
+        writer.write_all(b"idx1")?;
+        writer.write_all(&riff_size(idx1.len() as u64)?.to_le_bytes())?;
+        writer.write_all(&idx1)?;
+        let file_end = writer.stream_position()?;
+
+        let p = &self.placeholders;
+        let patches = [
+            (4, riff_size(file_end - 8)?),
+            (p.total_frames, self.frames),
+            (p.video_length, self.frames),
+            (p.audio_length, riff_size(self.beeper.samples())?),
+            (p.movi_size, riff_size(movi_end - self.movi_start)?),
+        ];
+        for (pos, value) in patches {
+            writer.seek(SeekFrom::Start(pos))?;
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        writer.seek(SeekFrom::End(0))?;
+        writer.flush()
+    }
+
+    fn write_chunk(&mut self, id: [u8; 4], data: &[u8]) -> io::Result<()> {
+        let writer = match self.writer.as_mut() {
+            Some(writer) => writer,
+            None => return Ok(()),
+        };
+        let position = writer.stream_position()?;
+        // Chunks are padded to an even length
+        let padded = data.len().next_multiple_of(2) as u64;
+        // Refuse a chunk that would take the file, with the index still to come, past what the RIFF size can
+        // hold, so what's already been written still finishes as a complete AVI. The RIFF size leaves out its
+        // own 8 byte header, which the chunk's header makes up for
+        let index_len = 8 + (self.index.len() as u64 + 1) * 16;
+        riff_size(position + padded + index_len)?;
+        let offset = riff_size(position - self.movi_start)?;
+        let len = riff_size(data.len() as u64)?;
+        writer.write_all(&id)?;
+        writer.write_all(&len.to_le_bytes())?;
+        writer.write_all(data)?;
+        if padded > data.len() as u64 {
+            writer.write_all(&[0])?;
+        }
+        self.index.push((id, offset, len));
+        Ok(())
+    }
+
+    // A frame has to fit the stream header's 16-bit dimensions and a chunk's 32-bit size, which rules out
+    // scales above 418
+    fn size(&self) -> io::Result<(u16, u16, u32)> {
+        let too_large = || {
+            io::Error::new(io::ErrorKind::InvalidInput, format!("scale {} is too large for an AVI", self.scale))
+        };
+        let side = |len: usize| len.checked_mul(self.scale).and_then(|len| u16::try_from(len).ok());
+        let (width, height) = match (side(HIRES_SCREEN_WIDTH), side(HIRES_SCREEN_HEIGHT)) {
+            (Some(width), Some(height)) => (width, height),
+            _ => return Err(too_large()),
+        };
+        let frame_len = u32::try_from(u64::from(width) * u64::from(height) * 3).map_err(|_| too_large())?;
+        Ok((width, height, frame_len))
+    }
+
+    // Bottom-up BGR rows as bitmaps store them. The width is a multiple of 128, so rows need no padding
+    fn frame_pixels(&self, chip8: &Chip8) -> Vec<u8> {
+        let (width, _, pixels) = render(chip8, self.scale * HIRES_SCREEN_WIDTH / chip8.width());
+        let [off, on] = [self.palette.off, self.palette.on].map(|[r, g, b]| [b, g, r]);
+        let mut bgr = Vec::with_capacity(pixels.len() * 3);
+        for row in pixels.chunks(width).rev() {
+            for pixel in row {
+                bgr.extend_from_slice(if *pixel == 1 { &on } else { &off });
+            }
+        }
+        bgr
+    }
+
+    // Everything up to and including the 'movi' fourcc, with zeros for the lengths that aren't known yet
+    fn header(&self) -> io::Result<(Vec<u8>, Placeholders)> {
+        let (width, height, frame_len) = self.size()?;
+        let sample_rate = self.beeper.sample_rate();
+        let mut h = Header(Vec::new());
+
+        h.bytes(b"RIFF");
+        h.u32(0);
+        h.bytes(b"AVI ");
+        let hdrl = h.list(b"hdrl");
+
+        h.bytes(b"avih");
+        h.u32(56);
+        h.u32(MICROSECONDS_PER_FRAME);
+        // Only a hint to players, so it's left at the most it can be if the true rate doesn't fit
+        h.u32(frame_len.saturating_mul(60).saturating_add(sample_rate.saturating_mul(2)));
+        h.u32(0);
+        h.u32(HAS_INDEX);
+        let total_frames = h.placeholder();
+        h.u32(0);
+        // Two streams
+        h.u32(2);
+        h.u32(frame_len);
+        h.u32(width.into());
+        h.u32(height.into());
+        h.bytes(&[0; 16]);
+
+        let strl = h.list(b"strl");
+        let video_length = h.stream_header(b"vids", b"DIB ", 60, frame_len, 0, (width, height));
+        // BITMAPINFOHEADER for bottom-up 24-bit RGB
+        h.bytes(b"strf");
+        h.u32(40);
+        h.u32(40);
+        h.u32(width.into());
+        h.u32(height.into());
+        h.u16(1);
+        h.u16(24);
+        h.u32(0);
+        h.u32(frame_len);
+        h.bytes(&[0; 16]);
+        h.end_list(strl);
+
+        let strl = h.list(b"strl");
+        let audio_length = h.stream_header(b"auds", &[0; 4], sample_rate, sample_rate * 2 / 60 + 2, 2, (0, 0));
+        // WAVEFORMATEX for 16-bit mono PCM
+        h.bytes(b"strf");
+        h.u32(18);
+        h.u16(1);
+        h.u16(1);
+        h.u32(sample_rate);
+        h.u32(sample_rate * 2);
+        h.u16(2);
+        h.u16(16);
+        h.u16(0);
+        h.end_list(strl);
+        h.end_list(hdrl);
+
+        h.bytes(b"LIST");
+        let movi_size = h.placeholder();
+        h.bytes(b"movi");
+        Ok((h.0, Placeholders { total_frames, video_length, audio_length, movi_size }))
+    }
+}
+
+impl Drop for VideoRecorder {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+// RIFF sizes and offsets are 32-bit, capping a recording at 4 GB
+fn riff_size(value: u64) -> io::Result<u32> {
+    u32::try_from(value).map_err(|_| io::Error::other("the recording has reached the 4 GB an AVI can hold"))
+}
+
+// Little-endian RIFF building blocks
+struct Header(Vec<u8>);
+
+impl Header {
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.0.extend_from_slice(bytes);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    // A zero to be overwritten later, returning where it is
+    fn placeholder(&mut self) -> u64 {
+        let pos = self.0.len() as u64;
+        self.u32(0);
+        pos
+    }
+
+    // Start a LIST, returning where its size goes
+    fn list(&mut self, kind: &[u8; 4]) -> usize {
+        self.bytes(b"LIST");
+        let pos = self.0.len();
+        self.u32(0);
+        self.bytes(kind);
+        pos
+    }
+
+    fn end_list(&mut self, pos: usize) {
+        let size = (self.0.len() - pos - 4) as u32;
+        self.0[pos..pos + 4].copy_from_slice(&size.to_le_bytes());
+    }
+
+    // AVISTREAMHEADER for a stream of rate units a second, returning where its length in units goes
+    fn stream_header(
+        &mut self,
+        kind: &[u8; 4],
+        handler: &[u8; 4],
+        rate: u32,
+        buffer_size: u32,
+        sample_size: u32,
+        (width, height): (u16, u16),
+    ) -> u64 {
+        self.bytes(b"strh");
+        self.u32(56);
+        self.bytes(kind);
+        self.bytes(handler);
+        // Flags, priority and language, then initial frames
+        self.u32(0);
+        self.u32(0);
+        self.u32(0);
+        // Scale, so each unit is 1 / rate seconds
+        self.u32(1);
+        self.u32(rate);
+        // Start
+        self.u32(0);
+        let length = self.placeholder();
+        self.u32(buffer_size);
+        // Default quality
+        self.u32(u32::MAX);
+        self.u32(sample_size);
+        self.u16(0);
+        self.u16(0);
+        self.u16(width);
+        self.u16(height);
+        length
+    }
+}
@@ -0,0 +1,141 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::audio::Wave;
+use crate::Chip8;
+
+pub const DEFAULT_SAMPLE_RATE: u32 = 44_100;
+const FRAMES_PER_SECOND: u64 = 60;
+// RIFF header with the format chunk and the start of the data chunk
+const HEADER_LEN: u32 = 44;
+
+pub(crate) trait WriteSeek: Write + Seek + Send {}
+
+impl<T: Write + Seek + Send> WriteSeek for T {}
+
+// Turns the sound timer into 16-bit samples a frame at a time
+pub(crate) struct Beeper {
+    sample_rate: u32,
+    frames: u64,
+    samples: u64,
+    wave: Wave,
+}
+
+impl Beeper {
+    pub(crate) fn new(sample_rate: u32) -> Self {
+        Self { sample_rate: sample_rate.max(1), frames: 0, samples: 0, wave: Wave::default() }
+    }
+
+    pub(crate) fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    // Samples generated since the last reset()
+    pub(crate) fn samples(&self) -> u64 {
+        self.samples
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.frames = 0;
+        self.samples = 0;
+        self.wave = Wave::default();
+    }
+
+    // One frame's worth of little-endian samples. That's the whole samples up to the end of the frame,
+    // so the fractions don't add up to drift
+    pub(crate) fn frame(&mut self, chip8: &Chip8) -> Vec<u8> {
+        self.frames += 1;
+        let end = self.frames * self.sample_rate as u64 / FRAMES_PER_SECOND;
+        let count = (end - self.samples) as usize;
+        let mut samples = vec![0.0; count];
+        self.wave.fill(&chip8.buzzer(), chip8.is_beeping(), &mut samples, self.sample_rate);
+        self.samples = end;
+        samples.iter().flat_map(|sample| ((sample * i16::MAX as f32) as i16).to_le_bytes()).collect()
+    }
+}
+
+// Records the beeper into a 16-bit mono WAV file. Call capture() once per frame, e.g. after each run_frame(),
+// and exactly a frame's worth of samples is written each time, so the audio stays in sync with the frame count
+pub struct WavRecorder {
+    beeper: Beeper,
+    writer: Option<Box<dyn WriteSeek>>,
+}
+
+impl WavRecorder {
+    pub fn new(sample_rate: u32) -> Self {
+        Self { beeper: Beeper::new(sample_rate), writer: None }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.beeper.sample_rate()
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.writer.is_some()
+    }
+
+    pub fn start_file(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        let file = File::create(path)?;
+        self.start(BufWriter::new(file))
+    }
+
+    // Begin a new recording into writer, finishing any recording already in progress. The header is
+    // filled in by stop(), which is why the writer has to be seekable
+    pub fn start(&mut self, writer: impl Write + Seek + Send + 'static) -> io::Result<()> {
+        self.stop()?;
+        let mut writer: Box<dyn WriteSeek> = Box::new(writer);
+        write_header(&mut writer, self.beeper.sample_rate(), 0)?;
+        self.writer = Some(writer);
+        Ok(())
+    }
+
+    pub fn capture(&mut self, chip8: &Chip8) -> io::Result<()> {
+        match self.writer.as_mut() {
+            Some(writer) => writer.write_all(&self.beeper.frame(chip8)),
+            None => Ok(()),
+        }
+    }
+
+    // Finish the WAV file. Does nothing if not recording
+    pub fn stop(&mut self) -> io::Result<()> {
+        let result = match self.writer.as_mut() {
+            Some(writer) => {
+                // Go back and fill in the lengths now they're known
+                writer.seek(SeekFrom::Start(0))?;
+                write_header(writer, self.beeper.sample_rate(), (self.beeper.samples() * 2) as u32)?;
+                writer.seek(SeekFrom::End(0))?;
+                writer.flush()
+            },
+            None => Ok(()),
+        };
+        self.writer = None;
+        self.beeper.reset();
+        result
+    }
+}
+
+impl Drop for WavRecorder {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+fn write_header(writer: &mut dyn Write, sample_rate: u32, data_len: u32) -> io::Result<()> {
+    let mut header = Vec::with_capacity(HEADER_LEN as usize);
+    header.extend_from_slice(b"RIFF");
+    header.extend_from_slice(&(HEADER_LEN - 8 + data_len).to_le_bytes());
+    header.extend_from_slice(b"WAVEfmt ");
+    header.extend_from_slice(&16u32.to_le_bytes());
+    // PCM, one channel
+    header.extend_from_slice(&1u16.to_le_bytes());
+    header.extend_from_slice(&1u16.to_le_bytes());
+    header.extend_from_slice(&sample_rate.to_le_bytes());
+    // Bytes per second, bytes per sample and bits per sample
+    header.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+    header.extend_from_slice(&2u16.to_le_bytes());
+    header.extend_from_slice(&16u16.to_le_bytes());
+    header.extend_from_slice(b"data");
+    header.extend_from_slice(&data_len.to_le_bytes());
+    writer.write_all(&header)
+}
@@ -0,0 +1,262 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+
+use crate::{Chip8, Chip8Error, Chip8State, Result, RomHash, NUM_KEYS};
+
+// The keys held on every frame of a run from power on, which replayed through advance_frame_with_input()
+// on the same ROM with the same CXNN seed plays out exactly the same. Keys are a bit per key, key 0 in the
+// low bit, as Netplay sends them. Each recorded frame also keeps the digest() of the state it ended on, which
+// Chip8::verify_replay() checks the replay against.
+//
+// That's the determinism contract: the same ROM, seed and keys on every frame, on a machine set up the same
+// way (variant, quirks and instructions a frame), give the same state after every frame, on any platform and
+// whether or not the decode cache is on. Nothing reads the host's clock or entropy once the seed is
+// set. Hooks and opcode handlers that change the machine are outside it, as is the key queue, which
+// advance_frame_with_input() bypasses.
+//
+// Tool-assisted runs are made by re-recording: save states along the way, and when a stretch goes wrong
+// resume() from an earlier one, which cuts the movie back to that frame so recording carries on from
+// there. Stretches from other takes can be put in with segment() and splice().
+//
+// The text form has the ROM, seed and re-record count, then a line per run of frames with the same keys,
+// as hex keys and an optional count:
+//     rom 0123456789abcdef0123456789abcdef01234567
+//     seed 2a
+//     rerecords 3
+//     0000 120
+//     0020 4
+// A frame with a digest is a line of its own with the digest last:
+//     0020 1 9a3f0c2e5b7d6e81
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Movie {
+    rom: RomHash,
+    seed: u64,
+    frames: Vec<u16>,
+    // The digest after each frame, where there is one
+    digests: Vec<Option<u64>>,
+    // Times recording has gone back to an earlier frame
+    rerecords: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MovieError {
+    // 1-based line number
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for MovieError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MovieError {}
+
+// Why verify_replay() stopped, with frames counted from 0
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReplayError {
+    // The machine has a different ROM, or none, loaded
+    WrongRom,
+    // The state after frame wasn't the one recorded
+    Desync { frame: usize, expected: u64, actual: u64 },
+    Fault { frame: usize, error: Chip8Error },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::WrongRom => write!(f, "the movie is of another ROM"),
+            ReplayError::Desync { frame, expected, actual } => {
+                write!(f, "desync after frame {}: digest {:016x}, recorded {:016x}", frame, actual, expected)
+            },
+            ReplayError::Fault { frame, error } => write!(f, "fault in frame {}: {}", frame, error),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ReplayError {}
+
+impl Movie {
+    // An empty movie of rom run with CXNN seeded from seed
+    pub fn new(rom: RomHash, seed: u64) -> Self {
+        Self { rom, seed, frames: Vec::new(), digests: Vec::new(), rerecords: 0 }
+    }
+
+    pub fn parse(text: &str) -> core::result::Result<Self, MovieError> {
+        let mut rom = None;
+        let mut seed = None;
+        let mut rerecords = 0;
+        let mut frames = Vec::new();
+        let mut digests = Vec::new();
+        for (idx, line) in text.lines().enumerate() {
+            let error = |message: &str| MovieError { line: idx + 1, message: message.to_string() };
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let mut words = line.split_whitespace();
+            let (first, second, third) = (words.next().unwrap_or_default(), words.next(), words.next());
+            if words.next().is_some() {
+                return Err(error("too many fields"));
+            }
+            match (first, second, third) {
+                ("rom", Some(hash), None) => {
+                    rom = Some(hash.parse().map_err(|_| error("expected a 40 digit hex ROM hash"))?);
+                },
+                ("seed", Some(value), None) => {
+                    seed = Some(u64::from_str_radix(value, 16).map_err(|_| error("expected a hex seed"))?);
+                },
+                ("rerecords", Some(count), None) => rerecords = count.parse().map_err(|_| error("expected a count"))?,
+                (_, count, Some(_)) if count != Some("1") => return Err(error("a digest is for one frame")),
+                (keys, count, digest) => {
+                    let keys = u16::from_str_radix(keys, 16).map_err(|_| error("expected hex keys"))?;
+                    let count = count.map_or(Ok(1), str::parse).map_err(|_| error("expected a frame count"))?;
+                    let digest = digest.map(|digest| u64::from_str_radix(digest, 16)).transpose();
+                    let digest = digest.map_err(|_| error("expected a hex digest"))?;
+                    frames.extend(core::iter::repeat_n(keys, count));
+                    digests.extend(core::iter::repeat_n(digest, count));
+                },
+            }
+        }
+        let rom = rom.ok_or_else(|| MovieError { line: 1, message: "no rom line".to_string() })?;
+        let seed = seed.ok_or_else(|| MovieError { line: 1, message: "no seed line".to_string() })?;
+        Ok(Self { rom, seed, frames, digests, rerecords })
+    }
+
+    pub fn rom(&self) -> RomHash {
+        self.rom
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn rerecords(&self) -> u64 {
+        self.rerecords
+    }
+
+    // Keys held on each frame in order
+    pub fn frames(&self) -> &[u16] {
+        &self.frames
+    }
+
+    // The digest() of the state recorded after frame, if it was kept
+    pub fn digest(&self, frame: usize) -> Option<u64> {
+        self.digests.get(frame).copied().flatten()
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    // Get chip8, with the movie's ROM freshly loaded, ready to play or record from the first frame. Returns
+    // false and leaves it alone if a different ROM is loaded
+    pub fn start(&self, chip8: &mut Chip8) -> bool {
+        if chip8.rom_hash() != Some(self.rom) {
+            return false;
+        }
+        chip8.seed_rng(self.seed);
+        true
+    }
+
+    // Run frame on chip8 with the keys recorded for it, returning the display hash it ends on. None once
+    // the movie has run out
+    pub fn play_frame(&self, chip8: &mut Chip8, frame: usize) -> Option<Result<u64>> {
+        let keys = *self.frames.get(frame)?;
+        Some(chip8.advance_frame_with_input(unpack(keys)))
+    }
+
+    // Run a frame on chip8 holding keys and add it to the end of the movie, with the digest of the state it
+    // ends on. A frame that faults isn't added
+    pub fn record_frame(&mut self, chip8: &mut Chip8, keys: u16) -> Result<u64> {
+        let hash = chip8.advance_frame_with_input(unpack(keys))?;
+        self.frames.push(keys);
+        self.digests.push(Some(chip8.snapshot().digest()));
+        Ok(hash)
+    }
+
+    // Keep the first frames frames, dropping the rest
+    pub fn truncate(&mut self, frames: usize) {
+        self.frames.truncate(frames);
+        self.digests.truncate(frames);
+    }
+
+    // Go back to state, saved with snapshot() just before frame was played or recorded, to record from there
+    // again. The movie is cut back to frame and counts a re-record
+    pub fn resume(&mut self, chip8: &mut Chip8, frame: usize, state: &Chip8State) {
+        chip8.restore(state);
+        self.truncate(frame);
+        self.rerecords += 1;
+    }
+
+    // The frames in range as a movie of their own, for splice(). Panics if range goes past the end
+    pub fn segment(&self, range: Range<usize>) -> Movie {
+        let digests = self.digests[range.clone()].to_vec();
+        Self { frames: self.frames[range].to_vec(), digests, ..Self::new(self.rom, self.seed) }
+    }
+
+    // Replace the frames in range with all of segment's, which needn't be the same length, so frames after
+    // it move. Panics if range goes past the end, as Vec::splice does. Digests from range on are dropped, as
+    // the states they were taken from no longer follow
+    pub fn splice(&mut self, range: Range<usize>, segment: &Movie) {
+        let start = range.start;
+        self.frames.splice(range, segment.frames.iter().copied());
+        self.digests.truncate(start);
+        self.digests.resize(self.frames.len(), None);
+    }
+}
+
+impl fmt::Display for Movie {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "rom {}", self.rom)?;
+        writeln!(f, "seed {:x}", self.seed)?;
+        writeln!(f, "rerecords {}", self.rerecords)?;
+        let frames: Vec<_> = self.frames.iter().zip(&self.digests).collect();
+        for run in frames.chunk_by(|(a, a_digest), (b, b_digest)| a == b && a_digest.is_none() && b_digest.is_none()) {
+            match (run.len(), run[0]) {
+                (_, (keys, Some(digest))) => writeln!(f, "{:04x} 1 {:016x}", keys, digest)?,
+                (1, (keys, None)) => writeln!(f, "{:04x}", keys)?,
+                (len, (keys, None)) => writeln!(f, "{:04x} {}", keys, len)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Chip8 {
+    // Play movie from the first frame, comparing the state after each with the digest recorded for it, and
+    // stop at the first that differs. The movie's ROM should be freshly loaded, as for Movie::start().
+    // Returns the number of frames checked, those with digests
+    pub fn verify_replay(&mut self, movie: &Movie) -> core::result::Result<usize, ReplayError> {
+        if !movie.start(self) {
+            return Err(ReplayError::WrongRom);
+        }
+        let mut checked = 0;
+        for frame in 0..movie.len() {
+            if let Some(Err(error)) = movie.play_frame(self, frame) {
+                return Err(ReplayError::Fault { frame, error });
+            }
+            if let Some(expected) = movie.digest(frame) {
+                let actual = self.snapshot().digest();
+                if actual != expected {
+                    return Err(ReplayError::Desync { frame, expected, actual });
+                }
+                checked += 1;
+            }
+        }
+        Ok(checked)
+    }
+}
+
+fn unpack(keys: u16) -> [bool; NUM_KEYS] {
+    core::array::from_fn(|idx| keys & (1 << idx) != 0)
+}
@@ -0,0 +1,161 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use crate::{Chip8, Chip8Error, State, NUM_KEYS};
+
+const MAGIC: &[u8; 4] = b"C8NP";
+const VERSION: u8 = 1;
+// Magic, version, CXNN seed and delay
+const HELLO_SIZE: usize = 4 + 1 + 8 + 1;
+// Frame number, keys for frame + delay, then the digest from the start of the frame
+const MESSAGE_SIZE: usize = 8 + 2 + 8;
+
+#[derive(Debug)]
+pub enum NetplayError {
+    Io(io::Error),
+    // The other side isn't speaking this protocol, or a different version of it
+    Protocol(&'static str),
+    // The machines' states differed at the start of frame, e.g. different ROMs, variants or quirks
+    Desync { frame: u64, local: u64, remote: u64 },
+    Emulator(Chip8Error),
+}
+
+impl fmt::Display for NetplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetplayError::Io(err) => write!(f, "connection failed: {}", err),
+            NetplayError::Protocol(what) => write!(f, "protocol error: {}", what),
+            NetplayError::Desync { frame, local, remote } => {
+                write!(f, "desync at frame {}: state {:016x} here, {:016x} there", frame, local, remote)
+            },
+            NetplayError::Emulator(err) => write!(f, "emulator fault: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for NetplayError {}
+
+impl From<io::Error> for NetplayError {
+    fn from(err: io::Error) -> Self {
+        NetplayError::Io(err)
+    }
+}
+
+impl From<Chip8Error> for NetplayError {
+    fn from(err: Chip8Error) -> Self {
+        NetplayError::Emulator(err)
+    }
+}
+
+// Two machines kept in lockstep over a connection, one on each peer, playing the same ROM. Every frame each
+// side sends the keys it has held, a bit per key, and both machines run the frame with everything either
+// player holds. Keys are sent delay frames before they take effect, so with enough delay to cover the
+// connection's latency the other side's have arrived by the time they're needed and no frame waits.
+//
+// The host picks the CXNN seed so both machines roll the same numbers, and each message carries a digest
+// of the sender's state so the first frame on which they differ is reported as NetplayError::Desync.
+// The stream should have small writes sent straight away, i.e. TcpStream::set_nodelay(true)
+pub struct Netplay<S> {
+    stream: S,
+    delay: u64,
+    frame: u64,
+    // Keys for the coming frames, from the next onwards
+    local: VecDeque<u16>,
+    remote: VecDeque<u16>,
+    // Digests of the frames the other side hasn't reported on yet, oldest first
+    digests: VecDeque<u64>,
+}
+
+impl<S: Read + Write> Netplay<S> {
+    // Start a session with chip8 ready to run the ROM, waiting for the other side to join()
+    pub fn host(mut stream: S, chip8: &mut Chip8, seed: u64, delay: u8) -> Result<Self, NetplayError> {
+        let mut hello = Vec::with_capacity(HELLO_SIZE);
+        hello.extend_from_slice(MAGIC);
+        hello.push(VERSION);
+        hello.extend_from_slice(&seed.to_le_bytes());
+        hello.push(delay);
+        stream.write_all(&hello)?;
+        stream.flush()?;
+        Ok(Self::start(stream, chip8, seed, delay))
+    }
+
+    // Join a hosted session, chip8 should be set up just as the host's is, same ROM and configuration
+    pub fn join(mut stream: S, chip8: &mut Chip8) -> Result<Self, NetplayError> {
+        let mut hello = [0; HELLO_SIZE];
+        stream.read_exact(&mut hello)?;
+        if &hello[..4] != MAGIC {
+            return Err(NetplayError::Protocol("not a netplay host"));
+        }
+        if hello[4] != VERSION {
+            return Err(NetplayError::Protocol("host runs a different version"));
+        }
+        let seed = u64::from_le_bytes(hello[5..13].try_into().unwrap());
+        Ok(Self::start(stream, chip8, seed, hello[13]))
+    }
+
+    fn start(stream: S, chip8: &mut Chip8, seed: u64, delay: u8) -> Self {
+        chip8.seed_rng(seed);
+        // Nobody has pressed anything in the frames before the first inputs arrive
+        let idle = VecDeque::from(vec![0; delay as usize]);
+        Self { stream, delay: delay as u64, frame: 0, local: idle.clone(), remote: idle, digests: VecDeque::new() }
+    }
+
+    // Frames run since the session started
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    // Run one frame on chip8 with this side's keys, waiting for the other side's keys for it if they
+    // haven't arrived. The keys given take effect delay frames later on both machines, and a desync is
+    // noticed delay frames after it happened
+    pub fn run_frame(&mut self, chip8: &mut Chip8, keys: u16) -> Result<State, NetplayError> {
+        let digest = chip8.snapshot().digest();
+        let mut message = [0; MESSAGE_SIZE];
+        message[..8].copy_from_slice(&self.frame.to_le_bytes());
+        message[8..10].copy_from_slice(&keys.to_le_bytes());
+        message[10..].copy_from_slice(&digest.to_le_bytes());
+        self.stream.write_all(&message)?;
+        self.stream.flush()?;
+        self.local.push_back(keys);
+        self.digests.push_back(digest);
+        self.frame += 1;
+
+        // The other side's keys for this frame came with its message from delay frames ago
+        if self.frame > self.delay {
+            let keys = self.receive()?;
+            self.remote.push_back(keys);
+        }
+
+        let held = self.local.pop_front().unwrap_or(0) | self.remote.pop_front().unwrap_or(0);
+        for idx in 0..NUM_KEYS {
+            chip8.keypress(idx, held & (1 << idx) != 0);
+        }
+        Ok(chip8.run_frame()?)
+    }
+
+    // End the session after the same number of frames on both sides, reading the other side's last
+    // messages so they're checked and neither connection is closed with them unread
+    pub fn finish(mut self) -> Result<S, NetplayError> {
+        while !self.digests.is_empty() {
+            self.receive()?;
+        }
+        Ok(self.stream)
+    }
+
+    // Read the other side's next message, checking it against the oldest digest waiting, and return its keys
+    fn receive(&mut self) -> Result<u16, NetplayError> {
+        let frame = self.frame - self.digests.len() as u64;
+        let mut message = [0; MESSAGE_SIZE];
+        self.stream.read_exact(&mut message)?;
+        if u64::from_le_bytes(message[..8].try_into().unwrap()) != frame {
+            return Err(NetplayError::Protocol("frames out of step"));
+        }
+        let local = self.digests.pop_front().unwrap_or_default();
+        let remote = u64::from_le_bytes(message[10..].try_into().unwrap());
+        if remote != local {
+            return Err(NetplayError::Desync { frame, local, remote });
+        }
+        Ok(u16::from_le_bytes([message[8], message[9]]))
+    }
+}
@@ -0,0 +1,818 @@
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{Symbols, RAM_SIZE, START_ADDRESS};
+
+// Macro expansions a program is allowed, far more than a real one uses, so one that expands itself forever
+// is an error instead of a hang
+const MAX_EXPANSIONS: usize = 100_000;
+
+// A compiled Octo program, with its labels for debuggers and symbol maps
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OctoProgram {
+    // Bytes to load at the start address
+    pub rom: Vec<u8>,
+    // Every label and its address, in address order
+    pub labels: Vec<(String, u16)>,
+}
+
+impl OctoProgram {
+    // The labels as a symbol map, for Chip8::set_symbols() or saving alongside the ROM
+    pub fn symbols(&self) -> Symbols {
+        let mut symbols = Symbols::new();
+        for (name, addr) in &self.labels {
+            symbols.insert(name, *addr);
+        }
+        symbols
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OctoError {
+    // 1-based line number
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for OctoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for OctoError {}
+
+// Compile Octo assembly, the language of John Earnest's Octo IDE, to a ROM. Supported are labels (: name),
+// calls by naming a label, :const, :alias, :calc, :macro, :org, :next, :unpack, :call, bytes as bare numbers,
+// if ... then, if ... begin ... else ... end, loop ... while ... again, and the comparison pseudo-ops
+// <, >, <= and >=, which use VF as Octo's do. The program starts with a jump to main. XO-CHIP instructions,
+// :stringmode and the debugging directives other than :breakpoint and :monitor, which are skipped, aren't
+pub fn compile_octo(source: &str) -> Result<OctoProgram, OctoError> {
+    let mut compiler = Compiler::new(source);
+    compiler.compile()?;
+    compiler.finish()
+}
+
+#[derive(Clone, Debug)]
+struct Token {
+    text: String,
+    line: usize,
+}
+
+// What a forward reference to a label is patched into once the label's defined
+#[derive(Clone, Copy, Debug)]
+enum Fixup {
+    // The NNN of the opcode at this offset
+    Address,
+    // The two 6XNN opcodes of an :unpack, with the nibble going above the address
+    Unpack(u8),
+}
+
+enum Block {
+    // if ... begin, with the offset of the jump past the block
+    If { jump: usize, line: usize },
+    Else { jump: usize, line: usize },
+    // loop, with its start and the offsets of its while jumps
+    Loop { start: u16, whiles: Vec<usize>, line: usize },
+}
+
+// A test if, while and the comparison pseudo-ops build on
+#[derive(Clone, Copy)]
+enum Condition {
+    Eq(usize, Operand),
+    Ne(usize, Operand),
+    Key(usize),
+    NotKey(usize),
+}
+
+#[derive(Clone, Copy)]
+enum Operand {
+    Register(usize),
+    Byte(u8),
+}
+
+#[derive(Clone)]
+struct Macro {
+    params: Vec<String>,
+    body: Vec<Token>,
+}
+
+struct Compiler {
+    tokens: VecDeque<Token>,
+    // Program bytes from the start address
+    rom: Vec<u8>,
+    here: u16,
+    labels: BTreeMap<String, u16>,
+    consts: BTreeMap<String, i64>,
+    aliases: BTreeMap<String, usize>,
+    macros: BTreeMap<String, Macro>,
+    // References to labels not defined yet: name, offset, how to patch it and the line it was on
+    fixups: Vec<(String, usize, Fixup, usize)>,
+    blocks: Vec<Block>,
+    line: usize,
+    // Macros expanded so far
+    expansions: usize,
+}
+
+impl Compiler {
+    fn new(source: &str) -> Self {
+        let mut tokens = VecDeque::new();
+        for (idx, line) in source.lines().enumerate() {
+            let line_text = line.split('#').next().unwrap_or_default();
+            for text in line_text.split_whitespace() {
+                tokens.push_back(Token { text: text.to_string(), line: idx + 1 });
+            }
+        }
+        Self {
+            tokens,
+            rom: Vec::new(),
+            here: START_ADDRESS,
+            labels: BTreeMap::new(),
+            consts: BTreeMap::new(),
+            aliases: BTreeMap::new(),
+            macros: BTreeMap::new(),
+            fixups: Vec::new(),
+            blocks: Vec::new(),
+            line: 1,
+            expansions: 0,
+        }
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, OctoError> {
+        Err(OctoError { line: self.line, message: message.into() })
+    }
+
+    fn next(&mut self) -> Result<String, OctoError> {
+        match self.tokens.pop_front() {
+            Some(token) => {
+                self.line = token.line;
+                Ok(token.text)
+            },
+            None => self.error("unexpected end of program"),
+        }
+    }
+
+    fn peek(&self) -> Option<&str> {
+        self.tokens.front().map(|token| token.text.as_str())
+    }
+
+    fn expect(&mut self, what: &str) -> Result<(), OctoError> {
+        let token = self.next()?;
+        if token != what {
+            return self.error(format!("expected '{}', found '{}'", what, token));
+        }
+        Ok(())
+    }
+
+    fn compile(&mut self) -> Result<(), OctoError> {
+        // Jump to main, patched once main is known
+        self.emit_address(0x1000, "main")?;
+        while !self.tokens.is_empty() {
+            self.statement()?;
+        }
+        if let Some(block) = self.blocks.last() {
+            let (what, line) = match block {
+                Block::If { line, .. } | Block::Else { line, .. } => ("begin without end", *line),
+                Block::Loop { line, .. } => ("loop without again", *line),
+            };
+            return Err(OctoError { line, message: what.to_string() });
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<OctoProgram, OctoError> {
+        if !self.labels.contains_key("main") {
+            return Err(OctoError { line: 1, message: "there's no main label to start from".to_string() });
+        }
+        for (name, offset, fixup, line) in core::mem::take(&mut self.fixups) {
+            let addr = match self.labels.get(&name) {
+                Some(addr) => *addr,
+                None => return Err(OctoError { line, message: format!("undefined name '{}'", name) }),
+            };
+            match fixup {
+                Fixup::Address => {
+                    self.rom[offset] |= (addr >> 8) as u8 & 0xF;
+                    self.rom[offset + 1] = addr as u8;
+                },
+                Fixup::Unpack(nibble) => {
+                    self.rom[offset + 1] = nibble << 4 | (addr >> 8) as u8 & 0xF;
+                    self.rom[offset + 3] = addr as u8;
+                },
+            }
+        }
+        let mut labels: Vec<(String, u16)> = self.labels.into_iter().collect();
+        labels.sort_by_key(|(name, addr)| (*addr, name.clone()));
+        Ok(OctoProgram { rom: self.rom, labels })
+    }
+
+    fn statement(&mut self) -> Result<(), OctoError> {
+        let token = self.next()?;
+        if let Some(body) = self.macros.get(&token).cloned() {
+            return self.expand(&body);
+        }
+        match token.as_str() {
+            ":" => {
+                let name = self.name()?;
+                self.define_label(name, self.here)
+            },
+            ":const" => {
+                let name = self.name()?;
+                let value = self.value()?;
+                self.consts.insert(name, value);
+                Ok(())
+            },
+            ":alias" => {
+                let name = self.name()?;
+                let reg = self.register()?;
+                self.aliases.insert(name, reg);
+                Ok(())
+            },
+            ":calc" => {
+                let name = self.name()?;
+                self.expect("{")?;
+                let value = self.calc_until_brace()?;
+                self.consts.insert(name, value);
+                Ok(())
+            },
+            ":macro" => self.define_macro(),
+            ":org" => {
+                let addr = self.value()?;
+                if !(START_ADDRESS as i64..RAM_SIZE as i64).contains(&addr) {
+                    return self.error(format!(":org {:#X} is outside program memory", addr));
+                }
+                self.here = addr as u16;
+                Ok(())
+            },
+            ":next" => {
+                let name = self.name()?;
+                self.define_label(name, self.here + 1)
+            },
+            ":unpack" => {
+                let nibble = self.value()?;
+                if !(0..16).contains(&nibble) {
+                    return self.error(":unpack takes a nibble before the label");
+                }
+                let name = self.next()?;
+                let offset = self.offset();
+                self.emit(0x6000)?;
+                self.emit(0x6100)?;
+                self.reference(name, offset, Fixup::Unpack(nibble as u8))
+            },
+            ":call" => {
+                let name = self.next()?;
+                self.emit_address(0x2000, &name)
+            },
+            ":breakpoint" => self.next().map(drop),
+            ":monitor" => {
+                self.next()?;
+                self.next().map(drop)
+            },
+            "clear" => self.emit(0x00E0),
+            "return" | ";" => self.emit(0x00EE),
+            "hires" => self.emit(0x00FF),
+            "lores" => self.emit(0x00FE),
+            "exit" => self.emit(0x00FD),
+            "scroll-left" => self.emit(0x00FC),
+            "scroll-right" => self.emit(0x00FB),
+            "scroll-down" => {
+                let n = self.nibble()?;
+                self.emit(0x00C0 | n)
+            },
+            "jump" => {
+                let name = self.next()?;
+                self.emit_address(0x1000, &name)
+            },
+            "jump0" => {
+                let name = self.next()?;
+                self.emit_address(0xB000, &name)
+            },
+            "native" => {
+                let name = self.next()?;
+                self.emit_address(0x0000, &name)
+            },
+            "bcd" => self.fx(0x33),
+            "save" => self.fx(0x55),
+            "load" => self.fx(0x65),
+            "sprite" => {
+                let x = self.register()?;
+                let y = self.register()?;
+                let n = self.nibble()?;
+                self.emit(0xD000 | (x as u16) << 8 | (y as u16) << 4 | n)
+            },
+            "delay" | "buzzer" => {
+                self.expect(":=")?;
+                let x = self.register()? as u16;
+                self.emit(0xF000 | x << 8 | if token == "delay" { 0x15 } else { 0x18 })
+            },
+            "i" => self.assign_i(),
+            "if" => self.if_statement(),
+            "else" => match self.blocks.pop() {
+                Some(Block::If { jump, line }) => {
+                    let end = self.offset();
+                    self.emit(0x1000)?;
+                    self.patch_jump(jump, self.here);
+                    self.blocks.push(Block::Else { jump: end, line });
+                    Ok(())
+                },
+                _ => self.error("else without if ... begin"),
+            },
+            "end" => match self.blocks.pop() {
+                Some(Block::If { jump, .. } | Block::Else { jump, .. }) => {
+                    self.patch_jump(jump, self.here);
+                    Ok(())
+                },
+                _ => self.error("end without if ... begin"),
+            },
+            "loop" => {
+                self.blocks.push(Block::Loop { start: self.here, whiles: Vec::new(), line: self.line });
+                Ok(())
+            },
+            "while" => {
+                if !self.blocks.iter().any(|block| matches!(block, Block::Loop { .. })) {
+                    return self.error("while outside a loop");
+                }
+                let condition = self.condition()?;
+                let jump = self.skip_jump_if(condition)?;
+                self.emit(0x1000)?;
+                for block in self.blocks.iter_mut().rev() {
+                    if let Block::Loop { whiles, .. } = block {
+                        whiles.push(jump);
+                        break;
+                    }
+                }
+                Ok(())
+            },
+            "again" => match self.blocks.pop() {
+                Some(Block::Loop { start, whiles, .. }) => {
+                    self.emit(0x1000 | start)?;
+                    for jump in whiles {
+                        self.patch_jump(jump, self.here);
+                    }
+                    Ok(())
+                },
+                _ => self.error("again without loop"),
+            },
+            _ => {
+                if let Some(x) = self.register_name(&token) {
+                    return self.assign_register(x);
+                }
+                // Numbers and constants are data bytes
+                if let Some(value) = parse_number(&token).or_else(|| self.consts.get(&token).copied()) {
+                    if !(-128..256).contains(&value) {
+                        return self.error(format!("{} doesn't fit in a byte", value));
+                    }
+                    return self.emit_byte(value as u8);
+                }
+                if token.starts_with(':') {
+                    return self.error(format!("{} isn't supported", token));
+                }
+                // Anything else names a subroutine to call
+                self.emit_address(0x2000, &token)
+            },
+        }
+    }
+
+    fn if_statement(&mut self) -> Result<(), OctoError> {
+        let condition = self.condition()?;
+        match self.next()?.as_str() {
+            "then" => {
+                // Skip the next statement when the condition fails
+                self.skip_if(negate(condition))
+            },
+            "begin" => {
+                let jump = self.skip_jump_if(condition)?;
+                self.emit(0x1000)?;
+                self.blocks.push(Block::If { jump, line: self.line });
+                Ok(())
+            },
+            other => self.error(format!("expected then or begin, found '{}'", other)),
+        }
+    }
+
+    // Emit a skip over the jump that comes next when condition holds, returning where the jump goes
+    fn skip_jump_if(&mut self, condition: Condition) -> Result<usize, OctoError> {
+        self.skip_if(condition)?;
+        Ok(self.offset())
+    }
+
+    fn skip_if(&mut self, condition: Condition) -> Result<(), OctoError> {
+        let opcode = match condition {
+            Condition::Eq(x, Operand::Byte(nn)) => 0x3000 | (x as u16) << 8 | nn as u16,
+            Condition::Ne(x, Operand::Byte(nn)) => 0x4000 | (x as u16) << 8 | nn as u16,
+            Condition::Eq(x, Operand::Register(y)) => 0x5000 | (x as u16) << 8 | (y as u16) << 4,
+            Condition::Ne(x, Operand::Register(y)) => 0x9000 | (x as u16) << 8 | (y as u16) << 4,
+            Condition::Key(x) => 0xE09E | (x as u16) << 8,
+            Condition::NotKey(x) => 0xE0A1 | (x as u16) << 8,
+        };
+        self.emit(opcode)
+    }
+
+    // vX == operand, vX != operand, vX key, vX -key, or one of <, >, <= and >=, which compute their
+    // answer into VF first
+    fn condition(&mut self) -> Result<Condition, OctoError> {
+        let x = self.register()?;
+        let op = self.next()?;
+        match op.as_str() {
+            "key" => return Ok(Condition::Key(x)),
+            "-key" => return Ok(Condition::NotKey(x)),
+            _ => (),
+        }
+        let operand = self.operand()?;
+        match op.as_str() {
+            "==" => Ok(Condition::Eq(x, operand)),
+            "!=" => Ok(Condition::Ne(x, operand)),
+            // VF := b, VF =- a leaves VF 1 exactly when a >= b
+            ">=" | "<" => {
+                self.at_least(x, operand)?;
+                Ok(if op == ">=" { Condition::Eq(0xF, Operand::Byte(1)) } else { Condition::Eq(0xF, Operand::Byte(0)) })
+            },
+            ">" | "<=" => {
+                // a > b is a >= b + 1 against a byte, or b < a against a register
+                match operand {
+                    Operand::Register(y) => self.at_least(y, Operand::Register(x))?,
+                    Operand::Byte(255) => return self.error(format!("{} 255 is always the same, so isn't supported", op)),
+                    Operand::Byte(nn) => {
+                        self.at_least(x, Operand::Byte(nn + 1))?;
+                        let flag = if op == ">" { 1 } else { 0 };
+                        return Ok(Condition::Eq(0xF, Operand::Byte(flag)));
+                    },
+                }
+                Ok(if op == "<=" { Condition::Eq(0xF, Operand::Byte(1)) } else { Condition::Eq(0xF, Operand::Byte(0)) })
+            },
+            _ => self.error(format!("'{}' isn't a comparison", op)),
+        }
+    }
+
+    // Leave VF 1 if vX >= operand, 0 if not
+    fn at_least(&mut self, x: usize, operand: Operand) -> Result<(), OctoError> {
+        if x == 0xF {
+            return self.error("vf can't be compared with <, >, <= or >=, they use it");
+        }
+        match operand {
+            Operand::Register(y) => self.emit(0x8F00 | (y as u16) << 4)?,
+            Operand::Byte(nn) => self.emit(0x6F00 | nn as u16)?,
+        }
+        self.emit(0x8F07 | (x as u16) << 4)
+    }
+
+    fn assign_register(&mut self, x: usize) -> Result<(), OctoError> {
+        let xy = |op: u16, y: usize| 0x8000 | (x as u16) << 8 | (y as u16) << 4 | op;
+        let op = self.next()?;
+        match op.as_str() {
+            ":=" => match self.peek() {
+                Some("random") => {
+                    self.next()?;
+                    let nn = self.byte()?;
+                    self.emit(0xC000 | (x as u16) << 8 | nn as u16)
+                },
+                Some("key") => {
+                    self.next()?;
+                    self.emit(0xF00A | (x as u16) << 8)
+                },
+                Some("delay") => {
+                    self.next()?;
+                    self.emit(0xF007 | (x as u16) << 8)
+                },
+                _ => match self.operand()? {
+                    Operand::Register(y) => self.emit(xy(0, y)),
+                    Operand::Byte(nn) => self.emit(0x6000 | (x as u16) << 8 | nn as u16),
+                },
+            },
+            "+=" => match self.operand()? {
+                Operand::Register(y) => self.emit(xy(4, y)),
+                Operand::Byte(nn) => self.emit(0x7000 | (x as u16) << 8 | nn as u16),
+            },
+            "-=" => match self.operand()? {
+                Operand::Register(y) => self.emit(xy(5, y)),
+                Operand::Byte(nn) => self.emit(0x7000 | (x as u16) << 8 | nn.wrapping_neg() as u16),
+            },
+            "|=" | "&=" | "^=" | "=-" | ">>=" | "<<=" => {
+                let y = self.register()?;
+                let n = match op.as_str() {
+                    "|=" => 1,
+                    "&=" => 2,
+                    "^=" => 3,
+                    "=-" => 7,
+                    ">>=" => 6,
+                    _ => 0xE,
+                };
+                self.emit(xy(n, y))
+            },
+            _ => self.error(format!("'{}' isn't an operator for registers", op)),
+        }
+    }
+
+    fn assign_i(&mut self) -> Result<(), OctoError> {
+        let op = self.next()?;
+        match op.as_str() {
+            ":=" => match self.peek() {
+                Some("hex") => {
+                    self.next()?;
+                    self.fx(0x29)
+                },
+                Some("bighex") => {
+                    self.next()?;
+                    self.fx(0x30)
+                },
+                Some("long") => self.error("i := long is XO-CHIP, which isn't supported"),
+                _ => {
+                    let name = self.next()?;
+                    self.emit_address(0xA000, &name)
+                },
+            },
+            "+=" => self.fx(0x1E),
+            _ => self.error(format!("'{}' isn't an operator for i", op)),
+        }
+    }
+
+    fn fx(&mut self, low: u16) -> Result<(), OctoError> {
+        let x = self.register()? as u16;
+        self.emit(0xF000 | x << 8 | low)
+    }
+
+    fn define_label(&mut self, name: String, addr: u16) -> Result<(), OctoError> {
+        if self.labels.insert(name.clone(), addr).is_some() {
+            return self.error(format!("'{}' is already defined", name));
+        }
+        Ok(())
+    }
+
+    fn define_macro(&mut self) -> Result<(), OctoError> {
+        let name = self.name()?;
+        let mut params = Vec::new();
+        loop {
+            let token = self.next()?;
+            if token == "{" {
+                break;
+            }
+            params.push(token);
+        }
+        let mut body = Vec::new();
+        let mut depth = 0;
+        loop {
+            let token = self.tokens.pop_front().ok_or(OctoError { line: self.line, message: "macro without }".into() })?;
+            match token.text.as_str() {
+                "{" => depth += 1,
+                "}" if depth == 0 => break,
+                "}" => depth -= 1,
+                _ => (),
+            }
+            body.push(token);
+        }
+        self.macros.insert(name, Macro { params, body });
+        Ok(())
+    }
+
+    // Put a macro's body back on the front of the token stream with its parameters swapped for arguments
+    fn expand(&mut self, body: &Macro) -> Result<(), OctoError> {
+        self.expansions += 1;
+        if self.expansions > MAX_EXPANSIONS {
+            return self.error("too many macro expansions, is a macro expanding itself?");
+        }
+        let mut args = BTreeMap::new();
+        for param in &body.params {
+            let arg = self.next()?;
+            args.insert(param.as_str(), arg);
+        }
+        let line = self.line;
+        for token in body.body.iter().rev() {
+            let text = args.get(token.text.as_str()).cloned().unwrap_or_else(|| token.text.clone());
+            // Errors inside the expansion are reported where the macro was used
+            self.tokens.push_front(Token { text, line });
+        }
+        Ok(())
+    }
+
+    // The expression up to the closing brace of a :calc, evaluated Octo's way: right to left, with every
+    // operator the same precedence unless there are brackets
+    fn calc_until_brace(&mut self) -> Result<i64, OctoError> {
+        let mut tokens = Vec::new();
+        loop {
+            let token = self.next()?;
+            if token == "}" {
+                break;
+            }
+            tokens.push(token);
+        }
+        let mut tokens: VecDeque<String> = tokens.into();
+        let value = self.calc(&mut tokens)?;
+        if let Some(extra) = tokens.front() {
+            return self.error(format!("unexpected '{}' in :calc", extra));
+        }
+        Ok(value)
+    }
+
+    fn calc(&self, tokens: &mut VecDeque<String>) -> Result<i64, OctoError> {
+        let left = self.calc_term(tokens)?;
+        let op = match tokens.front() {
+            Some(op) if op != ")" => op.clone(),
+            _ => return Ok(left),
+        };
+        tokens.pop_front();
+        let right = self.calc(tokens)?;
+        let value = match op.as_str() {
+            "+" => left.wrapping_add(right),
+            "-" => left.wrapping_sub(right),
+            "*" => left.wrapping_mul(right),
+            "/" | "%" if right == 0 => return self.error("division by zero in :calc"),
+            "/" => match left.checked_div(right) {
+                Some(value) => value,
+                None => return self.error("division overflows in :calc"),
+            },
+            "%" => match left.checked_rem(right) {
+                Some(value) => value,
+                None => return self.error("division overflows in :calc"),
+            },
+            "&" => left & right,
+            "|" => left | right,
+            "^" => left ^ right,
+            "<<" => left.wrapping_shl(right as u32),
+            ">>" => left.wrapping_shr(right as u32),
+            "<" => (left < right) as i64,
+            ">" => (left > right) as i64,
+            "<=" => (left <= right) as i64,
+            ">=" => (left >= right) as i64,
+            "==" => (left == right) as i64,
+            "!=" => (left != right) as i64,
+            "min" => left.min(right),
+            "max" => left.max(right),
+            _ => return self.error(format!("'{}' isn't a :calc operator", op)),
+        };
+        Ok(value)
+    }
+
+    fn calc_term(&self, tokens: &mut VecDeque<String>) -> Result<i64, OctoError> {
+        let token = match tokens.pop_front() {
+            Some(token) => token,
+            None => return self.error("missing value in :calc"),
+        };
+        match token.as_str() {
+            "(" => {
+                let value = self.calc(tokens)?;
+                match tokens.pop_front().as_deref() {
+                    Some(")") => Ok(value),
+                    _ => self.error("missing ) in :calc"),
+                }
+            },
+            "-" => Ok(self.calc_term(tokens)?.wrapping_neg()),
+            "~" => Ok(!self.calc_term(tokens)?),
+            "!" => Ok((self.calc_term(tokens)? == 0) as i64),
+            "HERE" => Ok(self.here as i64),
+            _ => self.lookup_value(&token),
+        }
+    }
+
+    fn name(&mut self) -> Result<String, OctoError> {
+        let name = self.next()?;
+        // Aliases can be pointed somewhere else, but v0 to vf can't be renamed
+        if parse_register(&name).is_some() || parse_number(&name).is_some() {
+            return self.error(format!("'{}' can't be used as a name", name));
+        }
+        Ok(name)
+    }
+
+    fn register_name(&self, token: &str) -> Option<usize> {
+        self.aliases.get(token).copied().or_else(|| parse_register(token))
+    }
+
+    fn register(&mut self) -> Result<usize, OctoError> {
+        let token = self.next()?;
+        match self.register_name(&token) {
+            Some(reg) => Ok(reg),
+            None => self.error(format!("expected a register, found '{}'", token)),
+        }
+    }
+
+    fn operand(&mut self) -> Result<Operand, OctoError> {
+        if let Some(reg) = self.peek().and_then(|token| self.register_name(token)) {
+            self.next()?;
+            return Ok(Operand::Register(reg));
+        }
+        self.byte().map(Operand::Byte)
+    }
+
+    // A number, constant or label already defined
+    fn lookup_value(&self, token: &str) -> Result<i64, OctoError> {
+        if let Some(value) = parse_number(token) {
+            return Ok(value);
+        }
+        if let Some(value) = self.consts.get(token) {
+            return Ok(*value);
+        }
+        match self.labels.get(token) {
+            Some(addr) => Ok(*addr as i64),
+            None => self.error(format!("undefined name '{}'", token)),
+        }
+    }
+
+    fn value(&mut self) -> Result<i64, OctoError> {
+        let token = self.next()?;
+        self.lookup_value(&token)
+    }
+
+    // A byte, with negative numbers down to -128 giving their two's complement
+    fn byte(&mut self) -> Result<u8, OctoError> {
+        let value = self.value()?;
+        if !(-128..256).contains(&value) {
+            return self.error(format!("{} doesn't fit in a byte", value));
+        }
+        Ok(value as u8)
+    }
+
+    fn nibble(&mut self) -> Result<u16, OctoError> {
+        let value = self.value()?;
+        if !(0..16).contains(&value) {
+            return self.error(format!("{} doesn't fit in a nibble", value));
+        }
+        Ok(value as u16)
+    }
+
+    // Offset into rom of the next byte emitted
+    fn offset(&self) -> usize {
+        (self.here - START_ADDRESS) as usize
+    }
+
+    fn emit_byte(&mut self, byte: u8) -> Result<(), OctoError> {
+        if self.here as usize >= RAM_SIZE {
+            return self.error("the program doesn't fit in memory");
+        }
+        let offset = self.offset();
+        if self.rom.len() <= offset {
+            self.rom.resize(offset + 1, 0);
+        }
+        self.rom[offset] = byte;
+        self.here += 1;
+        Ok(())
+    }
+
+    fn emit(&mut self, opcode: u16) -> Result<(), OctoError> {
+        self.emit_byte((opcode >> 8) as u8)?;
+        self.emit_byte(opcode as u8)
+    }
+
+    // An NNN opcode whose address is a number, constant or label, patched later if it's a label still to come
+    fn emit_address(&mut self, opcode: u16, name: &str) -> Result<(), OctoError> {
+        let offset = self.offset();
+        match self.lookup_value(name) {
+            Ok(addr) if (0..0x1000).contains(&addr) => self.emit(opcode | addr as u16),
+            Ok(addr) => self.error(format!("{:#X} isn't a 12-bit address", addr)),
+            Err(_) => {
+                self.emit(opcode)?;
+                self.reference(name.to_string(), offset, Fixup::Address)
+            },
+        }
+    }
+
+    fn reference(&mut self, name: String, offset: usize, fixup: Fixup) -> Result<(), OctoError> {
+        if self.register_name(&name).is_some() {
+            return self.error(format!("expected an address, found '{}'", name));
+        }
+        self.fixups.push((name, offset, fixup, self.line));
+        Ok(())
+    }
+
+    fn patch_jump(&mut self, offset: usize, target: u16) {
+        self.rom[offset] = 0x10 | (target >> 8) as u8 & 0xF;
+        self.rom[offset + 1] = target as u8;
+    }
+}
+
+fn negate(condition: Condition) -> Condition {
+    match condition {
+        Condition::Eq(x, operand) => Condition::Ne(x, operand),
+        Condition::Ne(x, operand) => Condition::Eq(x, operand),
+        Condition::Key(x) => Condition::NotKey(x),
+        Condition::NotKey(x) => Condition::Key(x),
+    }
+}
+
+// v0 to vf, in either case
+fn parse_register(token: &str) -> Option<usize> {
+    let digit = token.strip_prefix(['v', 'V'])?;
+    match digit.len() {
+        1 => u8::from_str_radix(digit, 16).ok().map(|reg| reg as usize),
+        _ => None,
+    }
+}
+
+// Decimal, 0x hex or 0b binary, optionally negative
+fn parse_number(token: &str) -> Option<i64> {
+    let (negative, digits) = match token.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, token),
+    };
+    let value = if let Some(hex) = digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        i64::from_str_radix(hex, 16).ok()?
+    } else if let Some(binary) = digits.strip_prefix("0b").or_else(|| digits.strip_prefix("0B")) {
+        i64::from_str_radix(binary, 2).ok()?
+    } else if digits.bytes().all(|byte| byte.is_ascii_digit()) && !digits.is_empty() {
+        digits.parse().ok()?
+    } else {
+        return None;
+    };
+    Some(if negative { -value } else { value })
+}
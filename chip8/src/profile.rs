@@ -0,0 +1,171 @@
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::RAM_SIZE;
+
+const NUM_OPCODES: usize = 0x10000;
+
+// Execution counts gathered while profiling is enabled, per opcode and per address
+pub struct Profile {
+    opcodes: Box<[u64]>,
+    pcs: Box<[u64]>,
+    total: u64,
+}
+
+impl Profile {
+    pub(crate) fn new() -> Self {
+        Self {
+            opcodes: vec![0; NUM_OPCODES].into_boxed_slice(),
+            pcs: vec![0; RAM_SIZE].into_boxed_slice(),
+            total: 0,
+        }
+    }
+
+    pub(crate) fn record(&mut self, pc: u16, opcode: u16) {
+        self.opcodes[opcode as usize] += 1;
+        self.pcs[pc as usize % RAM_SIZE] += 1;
+        self.total += 1;
+    }
+
+    // Total number of instructions executed while profiling
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    pub fn opcode_count(&self, opcode: u16) -> u64 {
+        self.opcodes[opcode as usize]
+    }
+
+    pub fn pc_count(&self, addr: u16) -> u64 {
+        self.pcs[addr as usize % RAM_SIZE]
+    }
+
+    // The n most executed opcodes with their counts, highest first
+    pub fn hot_opcodes(&self, n: usize) -> Vec<(u16, u64)> {
+        hottest(&self.opcodes, n)
+    }
+
+    // The n most executed addresses with their counts, highest first. Good for spotting hot loops
+    pub fn hot_pcs(&self, n: usize) -> Vec<(u16, u64)> {
+        hottest(&self.pcs, n)
+    }
+
+    pub fn clear(&mut self) {
+        self.opcodes.fill(0);
+        self.pcs.fill(0);
+        self.total = 0;
+    }
+}
+
+fn hottest(counts: &[u64], n: usize) -> Vec<(u16, u64)> {
+    let mut hot: Vec<(u16, u64)> = counts
+        .iter()
+        .enumerate()
+        .filter(|(_, count)| **count > 0)
+        .map(|(idx, count)| (idx as u16, *count))
+        .collect();
+    // Ties go to the lower opcode/address so results are stable
+    hot.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    hot.truncate(n);
+    hot
+}
+
+// Per-address read, write and execute counts, e.g. to tell code from graphics data in a mystery ROM.
+// Only accesses made by running instructions are counted, not debugger peeks through Chip8::ram()
+pub struct Heatmap {
+    reads: Box<[u64]>,
+    writes: Box<[u64]>,
+    executes: Box<[u64]>,
+}
+
+impl Heatmap {
+    pub(crate) fn new() -> Self {
+        Self {
+            reads: vec![0; RAM_SIZE].into_boxed_slice(),
+            writes: vec![0; RAM_SIZE].into_boxed_slice(),
+            executes: vec![0; RAM_SIZE].into_boxed_slice(),
+        }
+    }
+
+    pub(crate) fn record_read(&mut self, addr: usize) {
+        self.reads[addr] += 1;
+    }
+
+    pub(crate) fn record_write(&mut self, addr: usize) {
+        self.writes[addr] += 1;
+    }
+
+    pub(crate) fn record_execute(&mut self, addr: usize) {
+        self.executes[addr] += 1;
+    }
+
+    // Counts indexed by address, RAM_SIZE entries each
+    pub fn reads(&self) -> &[u64] {
+        &self.reads
+    }
+
+    pub fn writes(&self) -> &[u64] {
+        &self.writes
+    }
+
+    // Both bytes of an opcode count as executed
+    pub fn executes(&self) -> &[u64] {
+        &self.executes
+    }
+
+    pub fn clear(&mut self) {
+        self.reads.fill(0);
+        self.writes.fill(0);
+        self.executes.fill(0);
+    }
+}
+
+// One bit per address, set once an instruction has been executed from there
+pub struct Coverage {
+    executed: [u64; RAM_SIZE / 64],
+}
+
+impl Coverage {
+    pub(crate) fn new() -> Self {
+        Self { executed: [0; RAM_SIZE / 64] }
+    }
+
+    pub(crate) fn record(&mut self, pc: u16) {
+        let addr = pc as usize % RAM_SIZE;
+        self.executed[addr / 64] |= 1 << (addr % 64);
+    }
+
+    pub fn is_executed(&self, addr: u16) -> bool {
+        let addr = addr as usize % RAM_SIZE;
+        self.executed[addr / 64] & (1 << (addr % 64)) != 0
+    }
+
+    // Bit n of word n / 64 is set if an instruction ran from address n
+    pub fn bitmap(&self) -> &[u64] {
+        &self.executed
+    }
+
+    // Number of distinct addresses instructions ran from
+    pub fn executed_count(&self) -> usize {
+        self.executed.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    // Share of the len bytes from start that belong to an executed instruction, 0.0 to 100.0
+    pub fn percentage(&self, start: u16, len: usize) -> f32 {
+        if len == 0 {
+            return 0.0;
+        }
+        let covered = (start as usize..start as usize + len)
+            .filter(|addr| {
+                // The second byte of an opcode is covered by the instruction one byte earlier
+                self.is_executed(*addr as u16) || (*addr > 0 && self.is_executed(*addr as u16 - 1))
+            })
+            .count();
+        covered as f32 * 100.0 / len as f32
+    }
+
+    pub fn clear(&mut self) {
+        self.executed = [0; RAM_SIZE / 64];
+    }
+}
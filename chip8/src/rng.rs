@@ -0,0 +1,60 @@
+// PCG32 (XSH RR), a small fast generator for CXNN that can be seeded for reproducible runs and copied
+// into savestates, so rewinding and replaying roll the same numbers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Pcg32 {
+    state: u64,
+}
+
+const MULTIPLIER: u64 = 6_364_136_223_846_793_005;
+const INCREMENT: u64 = 1_442_695_040_888_963_407;
+#[cfg(not(feature = "std"))]
+const DEFAULT_SEED: u64 = 0x853C_49E6_748F_EA9B;
+
+impl Pcg32 {
+    pub(crate) fn new(seed: u64) -> Self {
+        let mut rng = Self { state: 0 };
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.next_u32();
+        rng
+    }
+
+    // What a new machine starts with. Seeded from the OS with std, so unseeded machines play differently each
+    // time like they always have. no_std has nothing to ask, so boards should seed_rng() from a hardware source
+    pub(crate) fn unseeded() -> Self {
+        #[cfg(feature = "std")]
+        let seed = rand::random();
+        #[cfg(not(feature = "std"))]
+        let seed = DEFAULT_SEED;
+        Self::new(seed)
+    }
+
+    pub(crate) fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old.wrapping_mul(MULTIPLIER).wrapping_add(INCREMENT);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        xorshifted.rotate_right((old >> 59) as u32)
+    }
+
+    pub(crate) fn state(&self) -> u64 {
+        self.state
+    }
+
+    // Carry on from a state() saved earlier
+    #[cfg(feature = "alloc")]
+    pub(crate) fn from_state(state: u64) -> Self {
+        Self { state }
+    }
+
+    // The top bits are the best mixed
+    pub(crate) fn next_u8(&mut self) -> u8 {
+        (self.next_u32() >> 24) as u8
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Pcg32 {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self::new(u.arbitrary()?))
+    }
+}
@@ -0,0 +1,34 @@
+use core::fmt;
+use core::str::FromStr;
+
+// SHA-1 of a ROM's bytes, which is how cheat files and ROM databases recognise a game whatever its file is
+// called. Shown and parsed as 40 hex digits
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RomHash(pub [u8; 20]);
+
+impl RomHash {
+    pub fn of(rom: &[u8]) -> Self {
+        RomHash(sha1_smol::Sha1::from(rom).digest().bytes())
+    }
+}
+
+impl fmt::Display for RomHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+    }
+}
+
+impl FromStr for RomHash {
+    type Err = ();
+
+    fn from_str(text: &str) -> Result<Self, ()> {
+        if text.len() != 40 || !text.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            return Err(());
+        }
+        let mut hash = [0; 20];
+        for (idx, byte) in hash.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&text[idx * 2..idx * 2 + 2], 16).map_err(|_| ())?;
+        }
+        Ok(RomHash(hash))
+    }
+}
@@ -0,0 +1,124 @@
+use core::fmt;
+
+use crate::{RomHash, Variant};
+use Action::{Down, Fire, Left, Other, Right, Up};
+
+// What's known about a ROM, from the table below
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RomInfo {
+    sha1: &'static str,
+    pub title: &'static str,
+    pub author: Option<&'static str>,
+    pub year: Option<u16>,
+    pub variant: Variant,
+    // A bit per key the game plays with, e.g. 1 << 0x4 | 1 << 0x6 for left and right. Frontends can put
+    // just these somewhere comfortable. 0 when the game only ever waits for any key
+    pub keys: u16,
+    // What the main keys do, for showing players, in the order worth telling them. Empty when there's
+    // nothing to say beyond keys
+    pub controls: &'static [(usize, Action)],
+}
+
+// What a key does in a game. Moving is Up, Down, Left or Right, so a frontend can put it on arrows or a d-pad
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    Fire,
+    Other(&'static str),
+}
+
+impl Action {
+    pub fn is_direction(&self) -> bool {
+        matches!(self, Up | Down | Left | Right)
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Up => "up",
+            Down => "down",
+            Left => "left",
+            Right => "right",
+            Fire => "fire",
+            Other(action) => action,
+        })
+    }
+}
+
+// RomInfo::hints()
+struct Hints(&'static [(usize, Action)]);
+
+impl fmt::Display for Hints {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, (key, action)) in self.0.iter().enumerate() {
+            write!(f, "{}{:X} {}", if idx == 0 { "" } else { ", " }, key, action)?;
+        }
+        Ok(())
+    }
+}
+
+impl RomInfo {
+    // The info for a ROM, if it's one the table knows
+    pub fn lookup(hash: &RomHash) -> Option<&'static RomInfo> {
+        ROMS.iter().find(|info| info.sha1.parse() == Ok(*hash))
+    }
+
+    // Every ROM the table knows
+    pub fn all() -> &'static [RomInfo] {
+        ROMS
+    }
+
+    pub fn hash(&self) -> RomHash {
+        self.sha1.parse().unwrap()
+    }
+
+    // The controls on a line for a status bar or the terminal, e.g. "4 left, 6 right, 5 fire". Frontends that
+    // want their own keys' names in place of the keypad's can go through controls themselves
+    pub fn hints(&self) -> impl fmt::Display {
+        Hints(self.controls)
+    }
+}
+
+const fn keys(list: &[u8]) -> u16 {
+    let mut bits = 0;
+    let mut idx = 0;
+    while idx < list.len() {
+        bits |= 1 << list[idx];
+        idx += 1;
+    }
+    bits
+}
+
+// The ROMs in roms/. Keys were found by playing each with random presses and noting which keys it tests
+// with EX9E and EXA1 or compares FX0A's result with, apart from Tic-Tac-Toe's, which works out the square
+// from the key. Controls are from the games' instructions, left out where they only say to press any key
+#[rustfmt::skip]
+const ROMS: &[RomInfo] = &[
+    RomInfo { sha1: "ea9af3c09b0d9e265fcd92bcc5d51a2939fdf27a", title: "15 Puzzle", author: Some("Roger Ivie"), year: None, variant: Variant::Chip8, keys: 0xFFFF, controls: &[] },
+    RomInfo { sha1: "d40abc54374e4343639f993e897e00904ddf85d9", title: "Blinky", author: Some("Hans Christian Egeberg"), year: Some(1991), variant: Variant::Chip8, keys: keys(&[0x1, 0x3, 0x6, 0x7, 0x8, 0xF]), controls: &[(0x3, Up), (0x6, Down), (0x7, Left), (0x8, Right)] },
+    RomInfo { sha1: "6f6509f38220e057a7e32ebb22dd353c1078e3e7", title: "Blitz", author: Some("David Winter"), year: None, variant: Variant::Chip8, keys: 0, controls: &[] },
+    RomInfo { sha1: "f13766c14aeb02ad8d4d103cb5eadd282d20cddc", title: "Brix", author: Some("Andreas Gustafsson"), year: Some(1990), variant: Variant::Chip8, keys: keys(&[0x4, 0x6]), controls: &[(0x4, Left), (0x6, Right)] },
+    RomInfo { sha1: "2d10c07b532f4fa7c07a07324ba26ca39fe484fd", title: "Connect 4", author: Some("David Winter"), year: None, variant: Variant::Chip8, keys: keys(&[0x4, 0x5, 0x6]), controls: &[(0x4, Left), (0x6, Right), (0x5, Other("drop"))] },
+    RomInfo { sha1: "5260f8931e0e9f41e555b382a14a88368e3ed886", title: "Guess", author: Some("David Winter"), year: None, variant: Variant::Chip8, keys: keys(&[0x5]), controls: &[(0x5, Other("my number's there"))] },
+    RomInfo { sha1: "050f07a54371da79f924dd0227b89d07b4f2aed0", title: "Hidden", author: Some("David Winter"), year: Some(1996), variant: Variant::Chip8, keys: keys(&[0x2, 0x4, 0x5, 0x6, 0x8]), controls: &[(0x2, Up), (0x8, Down), (0x4, Left), (0x6, Right), (0x5, Other("turn over"))] },
+    RomInfo { sha1: "f100197f0f2f05b4f3c8c31ab9c2c3930d3e9571", title: "Space Invaders", author: Some("David Winter"), year: None, variant: Variant::Chip8, keys: keys(&[0x4, 0x5, 0x6]), controls: &[(0x4, Left), (0x6, Right), (0x5, Fire)] },
+    RomInfo { sha1: "d6fa9dc9005dc0496f39ba52fef56f9fd0a5a158", title: "Kaleidoscope", author: Some("Joseph Weisbecker"), year: Some(1978), variant: Variant::Chip8, keys: keys(&[0x0, 0x2, 0x4, 0x6, 0x8]), controls: &[(0x2, Up), (0x8, Down), (0x4, Left), (0x6, Right), (0x0, Other("repeat the pattern"))] },
+    RomInfo { sha1: "b9272ae1acdaaa79ab649f6b48b72088ca2b1d74", title: "Maze", author: Some("David Winter"), year: None, variant: Variant::Chip8, keys: 0, controls: &[] },
+    RomInfo { sha1: "d979858bb9ffd07b48f52f92a8bcac0199f3623e", title: "Merlin", author: Some("David Winter"), year: None, variant: Variant::Chip8, keys: keys(&[0x4, 0x5, 0x7]), controls: &[] },
+    RomInfo { sha1: "0d0cc129dad3c45ba672f85fec71a668232212cc", title: "Missile Command", author: Some("David Winter"), year: None, variant: Variant::Chip8, keys: keys(&[0x8]), controls: &[(0x8, Fire)] },
+    RomInfo { sha1: "b232ef880bd6060fb45fa6effed7edf0ae95670e", title: "Pong", author: Some("Paul Vervalin"), year: Some(1990), variant: Variant::Chip8, keys: keys(&[0x1, 0x4, 0xC, 0xD]), controls: &[(0x1, Up), (0x4, Down), (0xC, Other("player 2 up")), (0xD, Other("player 2 down"))] },
+    RomInfo { sha1: "a60611339661e3ab2d8af024ad1da5880a6f8665", title: "Pong 2", author: Some("David Winter"), year: None, variant: Variant::Chip8, keys: keys(&[0x1, 0x4, 0xC, 0xD]), controls: &[(0x1, Up), (0x4, Down), (0xC, Other("player 2 up")), (0xD, Other("player 2 down"))] },
+    RomInfo { sha1: "1293db0ccccbe7dd3fc5a09a2abc5d7b175e18e0", title: "Puzzle", author: None, year: None, variant: Variant::Chip8, keys: keys(&[0x2, 0x4, 0x6, 0x8]), controls: &[(0x2, Up), (0x8, Down), (0x4, Left), (0x6, Right)] },
+    RomInfo { sha1: "1bdb4ddaa7049266fa3226851f28855a365cfd12", title: "Syzygy", author: Some("Roy Trevino"), year: Some(1990), variant: Variant::Chip8, keys: keys(&[0x3, 0x6, 0x7, 0x8, 0xB, 0xE, 0xF]), controls: &[(0x3, Up), (0x6, Down), (0x7, Left), (0x8, Right)] },
+    RomInfo { sha1: "18b9d15f4c159e1f0ed58c2d8ec1d89325d3a3b6", title: "Tank", author: None, year: None, variant: Variant::Chip8, keys: keys(&[0x2, 0x4, 0x5, 0x6, 0x8]), controls: &[(0x2, Up), (0x8, Down), (0x4, Left), (0x6, Right), (0x5, Fire)] },
+    RomInfo { sha1: "5f518084744bf3cb8733f6e5454dfd1634320563", title: "Tetris", author: Some("Fran Dachille"), year: Some(1991), variant: Variant::Chip8, keys: keys(&[0x4, 0x5, 0x6, 0x7]), controls: &[(0x5, Left), (0x6, Right), (0x4, Other("rotate")), (0x7, Other("drop"))] },
+    RomInfo { sha1: "429d455a4bc53167942bf6fd934d72b0f648dce3", title: "Tic-Tac-Toe", author: Some("David Winter"), year: None, variant: Variant::Chip8, keys: keys(&[0x1, 0x2, 0x3, 0x4, 0x5, 0x6, 0x7, 0x8, 0x9]), controls: &[] },
+    RomInfo { sha1: "bdb92475acfe11bc7814a2f5eade13fcd09b756a", title: "UFO", author: Some("Lutz V"), year: Some(1992), variant: Variant::Chip8, keys: keys(&[0x4, 0x5, 0x6]), controls: &[(0x5, Fire), (0x4, Other("fire left")), (0x6, Other("fire right"))] },
+    RomInfo { sha1: "da710f631f8e35534d0b9170bcf892a60f49c43d", title: "Vertical Brix", author: Some("Paul Robson"), year: Some(1996), variant: Variant::Chip8, keys: keys(&[0x1, 0x4, 0x7]), controls: &[(0x1, Up), (0x4, Down), (0x7, Other("serve"))] },
+    RomInfo { sha1: "ade839585ddeb0e3633177df03c1d91589e629eb", title: "Vers", author: Some("JMN"), year: Some(1991), variant: Variant::Chip8, keys: keys(&[0x1, 0x2, 0x7, 0xA, 0xB, 0xC, 0xD, 0xF]), controls: &[] },
+    RomInfo { sha1: "d666688a8fce468a7d88b536bc1ef5f35ba12031", title: "Wipe Off", author: Some("Joseph Weisbecker"), year: None, variant: Variant::Chip8, keys: keys(&[0x4, 0x6]), controls: &[(0x4, Left), (0x6, Right)] },
+];
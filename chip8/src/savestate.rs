@@ -0,0 +1,350 @@
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::fs;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::path::PathBuf;
+
+use crate::rng::Pcg32;
+use crate::{CallFrame, Chip8, Chip8State, RomHash, HIRES_SCREEN_HEIGHT, NUM_KEYS, NUM_REGS, RAM_SIZE, STACK_SIZE};
+
+const MAGIC: &[u8; 4] = b"C8ST";
+// Bumped whenever the layout changes. Older versions are refused rather than misread
+pub const SAVESTATE_VERSION: u16 = 2;
+// Header flag for a body that's been through pack()
+const COMPRESSED: u8 = 1;
+
+#[derive(Debug)]
+pub enum SavestateError {
+    // Not a savestate, or one that's been cut short or damaged
+    Corrupt,
+    // Written in a version of the format this build doesn't read
+    Version(u16),
+    // Saved from a different ROM than the one loaded, None being no ROM at all
+    WrongRom { saved: Option<RomHash>, loaded: Option<RomHash> },
+    // Nothing has been saved in the slot for this ROM
+    #[cfg(feature = "std")]
+    EmptySlot(usize),
+    #[cfg(feature = "std")]
+    Io(io::Error),
+}
+
+impl fmt::Display for SavestateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SavestateError::Corrupt => write!(f, "not a savestate, or a damaged one"),
+            SavestateError::Version(version) => {
+                write!(f, "savestate is version {}, only version {} can be loaded", version, SAVESTATE_VERSION)
+            },
+            SavestateError::WrongRom { .. } => write!(f, "savestate is from a different ROM"),
+            #[cfg(feature = "std")]
+            SavestateError::EmptySlot(slot) => write!(f, "nothing saved in slot {}", slot),
+            #[cfg(feature = "std")]
+            SavestateError::Io(err) => write!(f, "couldn't access savestate: {}", err),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SavestateError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SavestateError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for SavestateError {
+    fn from(err: io::Error) -> Self {
+        SavestateError::Io(err)
+    }
+}
+
+impl Chip8State {
+    // The stable binary form: the magic, the version and a flags byte, then the body, which is run length
+    // encoded by pack() if the flags say so. The body is whether a ROM was loaded and its hash, then every
+    // field little-endian in the order digest() takes them, and the digest itself so damage is noticed
+    pub(crate) fn to_bytes(&self, rom: Option<RomHash>, compress: bool) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&SAVESTATE_VERSION.to_le_bytes());
+        out.push(if compress { COMPRESSED } else { 0 });
+        let body = self.body(rom);
+        if compress {
+            pack(&body, &mut out);
+        } else {
+            out.extend_from_slice(&body);
+        }
+        out
+    }
+
+    fn body(&self, rom: Option<RomHash>) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.push(rom.is_some() as u8);
+        out.extend_from_slice(&rom.map_or([0; 20], |rom| rom.0));
+        out.extend_from_slice(&self.pc.to_le_bytes());
+        out.extend_from_slice(&self.ram);
+        out.extend_from_slice(&self.v_regi);
+        out.extend_from_slice(&self.i_regi.to_le_bytes());
+        self.display.iter().for_each(|row| out.extend_from_slice(&row.to_le_bytes()));
+        out.push(self.hires as u8);
+        self.stack.iter().for_each(|addr| out.extend_from_slice(&addr.to_le_bytes()));
+        out.extend_from_slice(&self.stkp.to_le_bytes());
+        for frame in &self.call_stack {
+            for addr in [frame.call_site, frame.target, frame.return_address] {
+                out.extend_from_slice(&addr.to_le_bytes());
+            }
+        }
+        out.extend_from_slice(&[self.delay_t, self.sound_t, self.halted as u8, self.drawn as u8]);
+        self.keys.iter().for_each(|key| out.push(*key as u8));
+        out.extend_from_slice(&self.rng.state().to_le_bytes());
+        out.extend_from_slice(&self.digest().to_le_bytes());
+        out
+    }
+
+    // Read to_bytes() back, with the ROM it was saved from
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Result<(Self, Option<RomHash>), SavestateError> {
+        let mut reader = Reader(bytes);
+        if reader.take(4)? != MAGIC {
+            return Err(SavestateError::Corrupt);
+        }
+        let version = reader.u16()?;
+        if version != SAVESTATE_VERSION {
+            return Err(SavestateError::Version(version));
+        }
+        match reader.array()? {
+            [0] => Self::from_body(reader.0),
+            [COMPRESSED] => Self::from_body(&unpack(reader.0)?),
+            _ => Err(SavestateError::Corrupt),
+        }
+    }
+
+    fn from_body(body: &[u8]) -> Result<(Self, Option<RomHash>), SavestateError> {
+        let mut reader = Reader(body);
+        let has_rom = reader.bool()?;
+        let hash = RomHash(reader.array()?);
+        let rom = has_rom.then_some(hash);
+        let pc = reader.u16()?;
+        let ram = reader.array::<RAM_SIZE>()?;
+        let v_regi = reader.array::<NUM_REGS>()?;
+        let i_regi = reader.u16()?;
+        let mut display = [0; HIRES_SCREEN_HEIGHT];
+        for row in &mut display {
+            *row = u128::from_le_bytes(reader.array()?);
+        }
+        let hires = reader.bool()?;
+        let mut stack = [0; STACK_SIZE];
+        for addr in &mut stack {
+            *addr = reader.u16()?;
+        }
+        let stkp = reader.u16()?;
+        let mut call_stack = [CallFrame::default(); STACK_SIZE];
+        for frame in &mut call_stack {
+            *frame = CallFrame { call_site: reader.u16()?, target: reader.u16()?, return_address: reader.u16()? };
+        }
+        let [delay_t, sound_t] = reader.array()?;
+        let halted = reader.bool()?;
+        let drawn = reader.bool()?;
+        let mut keys = [false; NUM_KEYS];
+        for key in &mut keys {
+            *key = reader.bool()?;
+        }
+        let rng = Pcg32::from_state(u64::from_le_bytes(reader.array()?));
+        let digest = u64::from_le_bytes(reader.array()?);
+        let state = Chip8State {
+            pc,
+            ram,
+            v_regi,
+            i_regi,
+            display,
+            hires,
+            stack,
+            stkp,
+            call_stack,
+            delay_t,
+            sound_t,
+            keys,
+            halted,
+            drawn,
+            rng,
+        };
+        if !reader.0.is_empty() || stkp as usize > STACK_SIZE || state.digest() != digest {
+            return Err(SavestateError::Corrupt);
+        }
+        Ok((state, rom))
+    }
+}
+
+// A copy of a Chip8State run length encoded, between half a KB and 1.5KB for the ROMs in roms/ against the
+// 5KB of the state itself, as most of RAM and the display is blank. For keeping deep histories small, e.g. as a Rewind<CompressedState>
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompressedState(Vec<u8>);
+
+impl CompressedState {
+    pub fn decompress(&self) -> Chip8State {
+        Chip8State::from_bytes(&self.0).expect("compressed from a whole state").0
+    }
+
+    // Bytes taken up
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Chip8State {
+    pub fn compress(&self) -> CompressedState {
+        CompressedState(self.to_bytes(None, true))
+    }
+}
+
+// Longest run of one byte a single control byte covers, and the shortest worth one
+const MAX_RUN: usize = 130;
+const MIN_RUN: usize = 3;
+const MAX_LITERALS: usize = 128;
+
+// PackBits style run length encoding: a control byte below 128 is followed by that many plus one bytes
+// copied as they are, one of 128 and up by a byte repeated control - 125 times
+fn pack(bytes: &[u8], out: &mut Vec<u8>) {
+    let mut literals = 0..0;
+    let mut idx = 0;
+    while idx < bytes.len() {
+        let run = bytes[idx..].iter().take(MAX_RUN).take_while(|byte| **byte == bytes[idx]).count();
+        if run >= MIN_RUN {
+            flush_literals(&bytes[literals], out);
+            out.extend_from_slice(&[(run + 125) as u8, bytes[idx]]);
+            idx += run;
+            literals = idx..idx;
+        } else {
+            idx += 1;
+            literals.end = idx;
+            if literals.len() == MAX_LITERALS {
+                flush_literals(&bytes[literals], out);
+                literals = idx..idx;
+            }
+        }
+    }
+    flush_literals(&bytes[literals], out);
+}
+
+fn flush_literals(literals: &[u8], out: &mut Vec<u8>) {
+    if !literals.is_empty() {
+        out.push(literals.len() as u8 - 1);
+        out.extend_from_slice(literals);
+    }
+}
+
+fn unpack(mut bytes: &[u8]) -> Result<Vec<u8>, SavestateError> {
+    let mut out = Vec::new();
+    while let [control, rest @ ..] = bytes {
+        let control = *control as usize;
+        if control < MAX_LITERALS {
+            let literals = rest.get(..control + 1).ok_or(SavestateError::Corrupt)?;
+            out.extend_from_slice(literals);
+            bytes = &rest[control + 1..];
+        } else {
+            let byte = *rest.first().ok_or(SavestateError::Corrupt)?;
+            out.extend(core::iter::repeat_n(byte, control - 125));
+            bytes = &rest[1..];
+        }
+    }
+    Ok(out)
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn take(&mut self, len: usize) -> Result<&[u8], SavestateError> {
+        if self.0.len() < len {
+            return Err(SavestateError::Corrupt);
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], SavestateError> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn u16(&mut self) -> Result<u16, SavestateError> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+
+    fn bool(&mut self) -> Result<bool, SavestateError> {
+        match self.array()? {
+            [0] => Ok(false),
+            [1] => Ok(true),
+            _ => Err(SavestateError::Corrupt),
+        }
+    }
+}
+
+impl Chip8 {
+    // A snapshot() in the savestate format, marked with the loaded ROM
+    pub fn save_state(&self) -> Vec<u8> {
+        self.snapshot().to_bytes(self.rom_hash, false)
+    }
+
+    // save_state() run length encoded, which load_state() reads just the same
+    pub fn save_state_compressed(&self) -> Vec<u8> {
+        self.snapshot().to_bytes(self.rom_hash, true)
+    }
+
+    // Restore what save_state() gave, refusing states of another version or from another ROM than the one
+    // loaded. A refused state leaves the machine as it was
+    pub fn load_state(&mut self, bytes: &[u8]) -> Result<(), SavestateError> {
+        let (state, rom) = Chip8State::from_bytes(bytes)?;
+        if rom != self.rom_hash {
+            return Err(SavestateError::WrongRom { saved: rom, loaded: self.rom_hash });
+        }
+        self.restore(&state);
+        Ok(())
+    }
+}
+
+// Numbered savestates kept as files in a directory, each ROM with its own set so slot 1 of one game doesn't
+// load into another, as <dir>/<ROM hash>.<slot>.state
+#[cfg(feature = "std")]
+pub struct SaveSlots {
+    dir: PathBuf,
+}
+
+#[cfg(feature = "std")]
+impl SaveSlots {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    // Where chip8's loaded ROM keeps slot
+    pub fn path(&self, chip8: &Chip8, slot: usize) -> PathBuf {
+        let rom = chip8.rom_hash().map_or_else(|| String::from("none"), |rom| rom.to_string());
+        self.dir.join(format!("{}.{}.state", rom, slot))
+    }
+
+    // Save chip8 in slot, compressed, replacing what was there. The file is written whole before it replaces the old
+    // one, so a crash part way leaves the last save
+    pub fn save_slot(&self, chip8: &Chip8, slot: usize) -> Result<(), SavestateError> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.path(chip8, slot);
+        let partial = path.with_extension("state.partial");
+        fs::write(&partial, chip8.save_state_compressed())?;
+        fs::rename(&partial, &path)?;
+        Ok(())
+    }
+
+    pub fn load_slot(&self, chip8: &mut Chip8, slot: usize) -> Result<(), SavestateError> {
+        let bytes = match fs::read(self.path(chip8, slot)) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Err(SavestateError::EmptySlot(slot)),
+            bytes => bytes?,
+        };
+        chip8.load_state(&bytes)
+    }
+}
@@ -0,0 +1,76 @@
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+use crate::{Chip8, Result, State, NUM_KEYS};
+
+// A Chip8 shared between threads, typically an emulation thread calling run_frame() and a UI thread sending
+// keys and drawing. Clones are handles to the same machine.
+//
+// Locking: the machine sits behind a single mutex, held by run_frame(), advance() and clock() while they run
+// and by lock() for everything else, e.g. loading, resetting and reading the display. A frame takes
+// microseconds, so nobody waits long unless a lock() guard is kept around. keypress() never takes the lock,
+// it sets bits that are copied into the machine at the start of the next run_frame(), advance() or clock().
+// A key pressed and released in between is still held for that call, so quick taps aren't lost. Keys set
+// through lock() are overwritten then. A panic while the lock is held doesn't poison the machine for the
+// other handles, it's used as it was left
+#[derive(Clone)]
+pub struct SharedChip8 {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    chip8: Mutex<Chip8>,
+    // A bit per key currently held
+    held: AtomicU16,
+    // A bit per key pressed since keys were last handed to the machine
+    pressed: AtomicU16,
+}
+
+impl SharedChip8 {
+    pub fn new(chip8: Chip8) -> Self {
+        let inner = Inner { chip8: Mutex::new(chip8), held: AtomicU16::new(0), pressed: AtomicU16::new(0) };
+        Self { inner: Arc::new(inner) }
+    }
+
+    // Press or release a key without waiting for the emulation thread, panics if idx is not in 0..=0xF
+    pub fn keypress(&self, idx: usize, pressed: bool) {
+        assert!(idx < NUM_KEYS, "key {} out of range", idx);
+        let bit = 1 << idx;
+        if pressed {
+            self.inner.held.fetch_or(bit, Ordering::Relaxed);
+            self.inner.pressed.fetch_or(bit, Ordering::Relaxed);
+        } else {
+            self.inner.held.fetch_and(!bit, Ordering::Relaxed);
+        }
+    }
+
+    pub fn run_frame(&self) -> Result<State> {
+        let mut chip8 = self.lock_with_keys();
+        chip8.run_frame()
+    }
+
+    pub fn advance(&self, dt: Duration) -> Result<State> {
+        let mut chip8 = self.lock_with_keys();
+        chip8.advance(dt)
+    }
+
+    pub fn clock(&self) -> Result<State> {
+        let mut chip8 = self.lock_with_keys();
+        chip8.clock()
+    }
+
+    // Exclusive access to the machine until the guard is dropped, which holds up the emulation thread
+    pub fn lock(&self) -> MutexGuard<'_, Chip8> {
+        self.inner.chip8.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn lock_with_keys(&self) -> MutexGuard<'_, Chip8> {
+        let mut chip8 = self.lock();
+        let keys = self.inner.held.load(Ordering::Relaxed) | self.inner.pressed.swap(0, Ordering::Relaxed);
+        for idx in 0..NUM_KEYS {
+            chip8.keypress(idx, keys & (1 << idx) != 0);
+        }
+        chip8
+    }
+}
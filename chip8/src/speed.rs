@@ -0,0 +1,70 @@
+use crate::{Chip8, Result, State};
+
+// Runs a Chip8 faster or slower than real time for a frontend that calls run() once every 60th of a second,
+// e.g. on each vsync. The machine always moves a whole frame at a time, so its timers stay in step with its
+// instructions: at speed 4 each call runs four frames, at 0.25 one call in four runs one. Pausing stops it
+// without losing track of a part frame that's owed
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpeedControl {
+    speed: f32,
+    paused: bool,
+    // Frames owed but not yet run, always below one between calls
+    owed: f32,
+}
+
+impl Default for SpeedControl {
+    fn default() -> Self {
+        Self { speed: 1.0, paused: false, owed: 0.0 }
+    }
+}
+
+impl SpeedControl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    // Emulated frames per real one, 1 for normal speed. Anything but a positive finite number is taken as 0,
+    // which stops the machine as pause() does without counting as paused
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = if speed.is_finite() && speed > 0.0 { speed } else { 0.0 };
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    // How many frames this real frame's worth of time asks for, counting them as run. For frontends that do
+    // something after every emulated frame, e.g. recording it, and so call Chip8::run_frame() themselves
+    pub fn frames_due(&mut self) -> usize {
+        if self.paused {
+            return 0;
+        }
+        self.owed += self.speed;
+        let frames = self.owed as usize;
+        self.owed -= frames as f32;
+        frames
+    }
+
+    // Run the frames due, stopping early if the machine halts. Returns the state after the last one, or as
+    // it was when none are due
+    pub fn run(&mut self, chip8: &mut Chip8) -> Result<State> {
+        for _ in 0..self.frames_due() {
+            if chip8.run_frame()? == State::Halted {
+                return Ok(State::Halted);
+            }
+        }
+        Ok(if chip8.is_halted() { State::Halted } else { State::Running })
+    }
+}
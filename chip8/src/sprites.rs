@@ -0,0 +1,65 @@
+use crate::Chip8;
+
+// Rows a DXYN draws at most, N being a single digit
+pub const MAX_SPRITE_ROWS: usize = 15;
+// The fonts have a glyph for each hex digit, of 5 bytes for the small font and 10 for the large
+const GLYPHS: usize = 16;
+const SMALL_GLYPH_ROWS: usize = 5;
+const LARGE_GLYPH_ROWS: usize = 10;
+
+// One of a font's hex digits as it is in RAM, so one a ROM has written over shows what it wrote
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Glyph {
+    pub digit: usize,
+    // Where FX29, or FX30 for a large glyph, points I for the digit
+    pub addr: u16,
+    rows: [[bool; 8]; LARGE_GLYPH_ROWS],
+    height: usize,
+}
+
+impl Glyph {
+    // Rows of 8 pixels with the high bit leftmost, 5 for a small glyph and 10 for a large one. The small
+    // font's glyphs are drawn in the left 4 columns
+    pub fn rows(&self) -> &[[bool; 8]] {
+        &self.rows[..self.height]
+    }
+}
+
+impl Chip8 {
+    // What a DXYN of height would draw from I, before it draws it, a row of 8 pixels to each byte with the
+    // high bit leftmost. Rows past height are blank, as are any past the end of RAM that the DXYN would fault
+    // on. For debuggers to show, as a garbled sprite is often I pointing somewhere it shouldn't
+    pub fn sprite_preview(&self, height: usize) -> [[bool; 8]; MAX_SPRITE_ROWS] {
+        let mut rows = [[false; 8]; MAX_SPRITE_ROWS];
+        let bytes = self.ram().get(self.i() as usize..).unwrap_or_default();
+        for (row, byte) in rows.iter_mut().zip(bytes).take(height) {
+            *row = pixels(*byte);
+        }
+        rows
+    }
+
+    // The 16 glyphs FX29 points I at, 0 to F, for a debugger's character map
+    pub fn font_glyphs(&self) -> impl Iterator<Item = Glyph> + '_ {
+        self.glyphs(self.font_address(), SMALL_GLYPH_ROWS)
+    }
+
+    // The SCHIP's large glyphs FX30 points I at
+    pub fn large_font_glyphs(&self) -> impl Iterator<Item = Glyph> + '_ {
+        self.glyphs(self.large_font_address(), LARGE_GLYPH_ROWS)
+    }
+
+    fn glyphs(&self, start: u16, height: usize) -> impl Iterator<Item = Glyph> + '_ {
+        (0..GLYPHS).map(move |digit| {
+            let addr = start + (digit * height) as u16;
+            let mut rows = [[false; 8]; LARGE_GLYPH_ROWS];
+            for (row, byte) in rows.iter_mut().zip(&self.ram()[addr as usize..]).take(height) {
+                *row = pixels(*byte);
+            }
+            Glyph { digit, addr, rows, height }
+        })
+    }
+}
+
+fn pixels(byte: u8) -> [bool; 8] {
+    core::array::from_fn(|bit| byte & 0x80 >> bit != 0)
+}
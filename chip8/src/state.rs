@@ -0,0 +1,130 @@
+use crate::rng::Pcg32;
+use crate::{CallFrame, Chip8, Fnv64, HIRES_SCREEN_HEIGHT, NUM_KEYS, NUM_REGS, RAM_SIZE, STACK_SIZE};
+#[cfg(feature = "arbitrary")]
+use crate::{LORES_ROW_MASK, SCREEN_HEIGHT};
+
+// A copy of everything a running program can observe or change, for savestates, rewind and
+// stepping backwards. Configuration such as policies, variant and attached profilers isn't included
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Chip8State {
+    pub(crate) pc: u16,
+    pub(crate) ram: [u8; RAM_SIZE],
+    pub(crate) v_regi: [u8; NUM_REGS],
+    pub(crate) i_regi: u16,
+    pub(crate) display: [u128; HIRES_SCREEN_HEIGHT],
+    pub(crate) hires: bool,
+    pub(crate) stack: [u16; STACK_SIZE],
+    pub(crate) stkp: u16,
+    pub(crate) call_stack: [CallFrame; STACK_SIZE],
+    pub(crate) delay_t: u8,
+    pub(crate) sound_t: u8,
+    pub(crate) keys: [bool; NUM_KEYS],
+    pub(crate) halted: bool,
+    pub(crate) drawn: bool,
+    pub(crate) rng: Pcg32,
+}
+
+// Any state at all for fuzzing, apart from the stack pointer which never goes past the end of the stack
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Chip8State {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        // Fields are taken in order from the fuzzer's bytes, so the small ones that steer execution come
+        // first and RAM and the display soak up whatever is left
+        Ok(Chip8State {
+            pc: u.arbitrary()?,
+            v_regi: u.arbitrary()?,
+            i_regi: u.arbitrary()?,
+            stkp: u.int_in_range(0..=STACK_SIZE as u16)?,
+            stack: u.arbitrary()?,
+            delay_t: u.arbitrary()?,
+            sound_t: u.arbitrary()?,
+            keys: u.arbitrary()?,
+            hires: u.arbitrary()?,
+            halted: u.arbitrary()?,
+            drawn: u.arbitrary()?,
+            call_stack: u.arbitrary()?,
+            ram: u.arbitrary()?,
+            display: u.arbitrary()?,
+            rng: u.arbitrary()?,
+        }
+        .with_clean_display())
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl Chip8State {
+    // Blank the parts of the display rows that are off screen, which a real machine always keeps clear
+    fn with_clean_display(mut self) -> Self {
+        if !self.hires {
+            self.display[SCREEN_HEIGHT..].fill(0);
+            self.display.iter_mut().for_each(|row| *row &= LORES_ROW_MASK);
+        }
+        self
+    }
+}
+
+impl Chip8State {
+    // 64-bit FNV-1a over the whole state in a fixed byte order, so it's the same on every platform. Netplay
+    // peers compare these to notice when their machines have drifted apart
+    pub fn digest(&self) -> u64 {
+        let mut hash = Fnv64::new();
+        hash.write(&self.pc.to_le_bytes());
+        hash.write(&self.ram);
+        hash.write(&self.v_regi);
+        hash.write(&self.i_regi.to_le_bytes());
+        self.display.iter().for_each(|row| hash.write(&row.to_le_bytes()));
+        hash.write(&[self.hires as u8]);
+        self.stack.iter().for_each(|addr| hash.write(&addr.to_le_bytes()));
+        hash.write(&self.stkp.to_le_bytes());
+        for frame in &self.call_stack {
+            for addr in [frame.call_site, frame.target, frame.return_address] {
+                hash.write(&addr.to_le_bytes());
+            }
+        }
+        hash.write(&[self.delay_t, self.sound_t, self.halted as u8, self.drawn as u8]);
+        self.keys.iter().for_each(|key| hash.write(&[*key as u8]));
+        hash.write(&self.rng.state().to_le_bytes());
+        hash.finish()
+    }
+}
+
+impl Chip8 {
+    pub fn snapshot(&self) -> Chip8State {
+        Chip8State {
+            pc: self.pc,
+            ram: self.buffers.ram,
+            v_regi: self.v_regi,
+            i_regi: self.i_regi,
+            display: self.buffers.display,
+            hires: self.hires,
+            stack: self.stack,
+            stkp: self.stkp,
+            call_stack: self.call_stack,
+            delay_t: self.delay_t,
+            sound_t: self.sound_t,
+            keys: self.keys,
+            halted: self.halted,
+            drawn: self.drawn,
+            rng: self.rng,
+        }
+    }
+
+    pub fn restore(&mut self, state: &Chip8State) {
+        self.pc = state.pc;
+        self.buffers.ram = state.ram;
+        self.ram_written(0..RAM_SIZE);
+        self.v_regi = state.v_regi;
+        self.i_regi = state.i_regi;
+        self.buffers.display = state.display;
+        self.hires = state.hires;
+        self.stack = state.stack;
+        self.stkp = state.stkp;
+        self.call_stack = state.call_stack;
+        self.delay_t = state.delay_t;
+        self.sound_t = state.sound_t;
+        self.keys = state.keys;
+        self.halted = state.halted;
+        self.drawn = state.drawn;
+        self.rng = state.rng;
+    }
+}
@@ -0,0 +1,133 @@
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{Instruction, RAM_SIZE};
+
+// Names for addresses, from compile_octo()'s labels or a symbol file, so addresses can be shown and typed as
+// names. The text form is a line per symbol of hex address then name, with # starting comments:
+//     202 main
+//     2F2 draw-sprite
+// When several names share an address the first given is the one shown
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Symbols {
+    names: BTreeMap<u16, String>,
+    addresses: BTreeMap<String, u16>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SymbolsError {
+    // 1-based line number
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for SymbolsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SymbolsError {}
+
+impl Symbols {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn parse(text: &str) -> Result<Self, SymbolsError> {
+        let mut symbols = Self::new();
+        for (idx, line) in text.lines().enumerate() {
+            let error = |message: &str| SymbolsError { line: idx + 1, message: message.to_string() };
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (addr, name) = line.split_once(char::is_whitespace).ok_or_else(|| error("expected an address and a name"))?;
+            let addr = u16::from_str_radix(addr.trim_start_matches("0x"), 16)
+                .ok()
+                .filter(|addr| (*addr as usize) < RAM_SIZE)
+                .ok_or_else(|| error("expected a hex address below 1000"))?;
+            symbols.insert(name.trim(), addr);
+        }
+        Ok(symbols)
+    }
+
+    // Name addr, replacing whatever name was there before
+    pub fn insert(&mut self, name: &str, addr: u16) {
+        if let Some(old) = self.addresses.insert(name.to_string(), addr) {
+            // Show another name for the old address if it has one
+            if self.names.get(&old).map(String::as_str) == Some(name) {
+                self.names.remove(&old);
+                if let Some((other, _)) = self.addresses.iter().find(|(_, other)| **other == old) {
+                    self.names.insert(old, other.clone());
+                }
+            }
+        }
+        self.names.entry(addr).or_insert_with(|| name.to_string());
+    }
+
+    // The name shown for addr
+    pub fn name(&self, addr: u16) -> Option<&str> {
+        self.names.get(&addr).map(String::as_str)
+    }
+
+    pub fn address(&self, name: &str) -> Option<u16> {
+        self.addresses.get(name).copied()
+    }
+
+    // Every name and its address, in name order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u16)> {
+        self.addresses.iter().map(|(name, addr)| (name.as_str(), *addr))
+    }
+
+    pub fn len(&self) -> usize {
+        self.addresses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty()
+    }
+}
+
+// Written in address order, so the file reads like the program
+impl fmt::Display for Symbols {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut by_addr: Vec<(u16, &str)> = self.iter().map(|(name, addr)| (addr, name)).collect();
+        by_addr.sort();
+        for (addr, name) in by_addr {
+            writeln!(f, "{:03X} {}", addr, name)?;
+        }
+        Ok(())
+    }
+}
+
+// An Instruction shown with names for the addresses it uses, e.g. "CALL draw-sprite" rather than "CALL 0x2F2"
+pub struct Symbolic<'a> {
+    instruction: Instruction,
+    symbols: &'a Symbols,
+}
+
+impl Instruction {
+    pub fn with_symbols(self, symbols: &Symbols) -> Symbolic<'_> {
+        Symbolic { instruction: self, symbols }
+    }
+}
+
+impl fmt::Display for Symbolic<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (mnemonic, addr) = match self.instruction {
+            Instruction::Jmp(addr) => ("JP", addr),
+            Instruction::Call(addr) => ("CALL", addr),
+            Instruction::LoadI(addr) => ("LD I,", addr),
+            Instruction::JmpV0(addr) => ("JP V0,", addr),
+            instruction => return write!(f, "{}", instruction),
+        };
+        match self.symbols.name(addr) {
+            Some(name) => write!(f, "{} {}", mnemonic, name),
+            None => write!(f, "{}", self.instruction),
+        }
+    }
+}
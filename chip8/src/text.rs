@@ -0,0 +1,71 @@
+use alloc::string::String;
+
+use crate::Chip8;
+
+// How render_text() draws the display, from the plainest to the most compact
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextStyle {
+    // '#' for lit and '.' for unlit, a character a pixel, readable anywhere
+    Ascii,
+    // '▀', '▄', '█' and ' ', two pixels down to a character, so the display keeps its shape in a terminal
+    HalfBlock,
+    // Braille cells two pixels across and four down, so a lo-res display fits in 32 by 8
+    Braille,
+}
+
+impl TextStyle {
+    // Each style by the name named() takes
+    pub const NAMES: [(&'static str, Self); 3] =
+        [("ascii", Self::Ascii), ("half-block", Self::HalfBlock), ("braille", Self::Braille)];
+
+    // A style by name, ignoring case
+    pub fn named(name: &str) -> Option<Self> {
+        Self::NAMES.iter().find(|(style, _)| style.eq_ignore_ascii_case(name)).map(|(_, style)| *style)
+    }
+}
+
+// Braille dots by their place in a cell, which Unicode numbers down the left column and then the right, with
+// the bottom row added later
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+impl Chip8 {
+    // The display as lines of text, without a newline after the last, e.g. for a frame in a failing test's
+    // message or a terminal frontend
+    pub fn render_text(&self, style: TextStyle) -> String {
+        let (cell_width, cell_height) = match style {
+            TextStyle::Ascii => (1, 1),
+            TextStyle::HalfBlock => (1, 2),
+            TextStyle::Braille => (2, 4),
+        };
+        // pixel() is unlit off the screen
+        let lit = |x, y| self.pixel(x, y);
+        let mut text = String::new();
+        for y in (0..self.height()).step_by(cell_height) {
+            if y > 0 {
+                text.push('\n');
+            }
+            text.extend((0..self.width()).step_by(cell_width).map(|x| match style {
+                TextStyle::Ascii if lit(x, y) => '#',
+                TextStyle::Ascii => '.',
+                TextStyle::HalfBlock => match (lit(x, y), lit(x, y + 1)) {
+                    (true, true) => '█',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (false, false) => ' ',
+                },
+                TextStyle::Braille => {
+                    let mut dots = 0;
+                    for (dy, row) in BRAILLE_DOTS.iter().enumerate() {
+                        for (dx, dot) in row.iter().enumerate() {
+                            if lit(x + dx, y + dy) {
+                                dots |= dot;
+                            }
+                        }
+                    }
+                    char::from_u32(0x2800 + dots).unwrap_or(' ')
+                }
+            }));
+        }
+        text
+    }
+}
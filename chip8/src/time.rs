@@ -0,0 +1,149 @@
+use core::time::Duration;
+
+use crate::{Chip8, Result, State};
+
+// Times a second the delay and sound timers count down
+pub const TIMER_HZ: u32 = 60;
+
+// advance() counts time in 60ths of a nanosecond, so a frame is exactly this many
+const FRAME: u64 = 1_000_000_000;
+// Frames advance() catches up with at most, so a stall doesn't turn into a burst of them
+const MAX_FRAMES_OWED: u64 = 4;
+
+// Ticks tick_timers() makes in one call at most. The timers are a byte, so after this many they're at 0
+// whatever they started at, and a clock that jumps ahead doesn't mean thousands of catching up
+const MAX_TICKS: u64 = u8::MAX as u64 + 1;
+
+// Where tick_timers() gets the time: the host's clock, a test's own clock, or a hardware timer on a board.
+// now() counts up from wherever it likes, only the time between calls matters. Any FnMut() -> Duration is
+// one, e.g. a closure reading a microsecond counter register
+pub trait TimeSource {
+    fn now(&mut self) -> Duration;
+}
+
+impl<F: FnMut() -> Duration> TimeSource for F {
+    fn now(&mut self) -> Duration {
+        self()
+    }
+}
+
+// The host's monotonic clock, counting from when it was made
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug)]
+pub struct SystemClock {
+    start: std::time::Instant,
+}
+
+#[cfg(feature = "std")]
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self { start: std::time::Instant::now() }
+    }
+}
+
+#[cfg(feature = "std")]
+impl SystemClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[cfg(feature = "std")]
+impl TimeSource for SystemClock {
+    fn now(&mut self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+// A clock that only moves when it's told to, for tests, or for following something else that keeps time
+// such as the audio samples played
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ManualClock {
+    now: Duration,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn advance(&mut self, by: Duration) {
+        self.now += by;
+    }
+}
+
+impl TimeSource for ManualClock {
+    fn now(&mut self) -> Duration {
+        self.now
+    }
+}
+
+// When tick_timers() started counting, and the ticks it's made since
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct TimerSync {
+    epoch: Duration,
+    ticks: u64,
+}
+
+impl Chip8 {
+    // Tick the timers once for every 60th of a second that source says has passed since the last call, for
+    // frontends that run instructions with clock() at their own pace in place of calling clock_timers()
+    // about 60 times a second. Counted from the first call, so no time is lost to rounding however often
+    // it's called, and returns the ticks made. Time passing while paused, or a clock going backwards, isn't
+    // counted, the count starts again from the next call. run_frame() ticks the timers itself, so it
+    // shouldn't be mixed with this
+    pub fn tick_timers(&mut self, source: &mut impl TimeSource) -> u32 {
+        let now = source.now();
+        let sync = match self.timer_sync {
+            Some(sync) if !self.paused && now >= sync.epoch => sync,
+            _ => {
+                self.timer_sync = (!self.paused).then_some(TimerSync { epoch: now, ticks: 0 });
+                return 0;
+            },
+        };
+        let ticks = ((now - sync.epoch).as_nanos() * TIMER_HZ as u128 / 1_000_000_000) as u64;
+        let due = ticks.saturating_sub(sync.ticks).min(MAX_TICKS);
+        for _ in 0..due {
+            self.clock_timers();
+        }
+        self.timer_sync = Some(TimerSync { ticks: ticks.max(sync.ticks), ..sync });
+        due as u32
+    }
+
+    // Run dt's worth of emulation: instructions at the pace run_frame() sets, ticks_per_frame() sixty
+    // times a second or the VIP's with Timing::Vip, with the timers ticking each 60th of a second in
+    // between. Time is carried over between calls, so it can be called with the time since the last frame
+    // drawn whatever the refresh rate, and partway through a frame it runs the part that's due, leaving the
+    // rest for the next call. More than a few frames behind, e.g. after the window was dragged, the rest is
+    // dropped instead of run in a burst. Nothing runs while paused. Returns the state after the last
+    // instruction. It keeps its own place in the frame, so shouldn't be mixed with run_frame()
+    pub fn advance(&mut self, dt: Duration) -> Result<State> {
+        if self.paused {
+            return Ok(self.state());
+        }
+        let owed = self.frame_time as u128 + dt.as_nanos() * TIMER_HZ as u128;
+        self.frame_time = owed.min((MAX_FRAMES_OWED * FRAME) as u128) as u64;
+        let budget = self.frame_budget() as u64;
+        loop {
+            let frame_over = self.frame_time >= FRAME;
+            let due = if frame_over { budget } else { budget * self.frame_time / FRAME };
+            while (self.frame_spent as u64) < due {
+                let cost = self.frame_cost();
+                if self.clock()? == State::Halted {
+                    return Ok(State::Halted);
+                }
+                self.frame_spent += cost;
+                // Paused by a hook, the time owed waits for resuming
+                if self.paused {
+                    return Ok(self.state());
+                }
+            }
+            if !frame_over {
+                return Ok(self.state());
+            }
+            self.frame_time -= FRAME;
+            self.frame_spent = 0;
+            self.clock_timers();
+        }
+    }
+}
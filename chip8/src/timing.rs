@@ -0,0 +1,81 @@
+use crate::{Chip8, RAM_SIZE};
+
+// How run_frame() decides how much to run in a frame
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum Timing {
+    // ticks_per_frame() instructions, however long each would have taken
+    #[default]
+    Ticks,
+    // As many instructions as the COSMAC VIP's interpreter got through in a frame, each costing roughly the
+    // machine cycles it took there, so games tuned on the VIP run at the pace they were written for
+    Vip,
+}
+
+// The VIP's 1802 ran at 1.76MHz, 8 clocks a machine cycle, giving 3668 machine cycles a frame. The display
+// DMA takes 1024 of those, 8 bytes for each of 128 scanlines, and the interrupt routine around 50 more
+pub(crate) const VIP_CYCLES_PER_FRAME: usize = 3668 - 1024 - 50;
+
+// Approximate machine cycles the VIP interpreter took over opcode, fetching and decoding included. Where
+// it depended on the operands this is the usual case, apart from the long ones that scale with their work
+fn vip_cycles(opcode: u16) -> usize {
+    let x = (opcode >> 8 & 0xF) as usize;
+    let n = (opcode & 0xF) as usize;
+    match opcode >> 12 {
+        0x0 if opcode == 0x00E0 => 1600,
+        0x0 => 50,
+        0x1 => 52,
+        0x2 => 60,
+        0x3 | 0x4 => 52,
+        0x5 | 0x9 => 58,
+        0x6 => 46,
+        0x7 => 54,
+        // The VIP ran these by writing an 1802 instruction into RAM and calling it
+        0x8 => 88,
+        0xA => 52,
+        0xB => 64,
+        0xC => 90,
+        // Shifting and XORing each row into the display
+        0xD => 170 + 72 * if n == 0 { 16 } else { n },
+        0xE => 60,
+        _ => match opcode & 0xFF {
+            0x1E => 62,
+            0x29 => 64,
+            // Repeated subtraction for each digit
+            0x33 => 210,
+            0x55 | 0x65 => 70 + 28 * (x + 1),
+            _ => 50,
+        },
+    }
+}
+
+impl Chip8 {
+    pub fn timing(&self) -> Timing {
+        self.timing
+    }
+
+    // Choose how much run_frame() runs. Timing::Vip ignores ticks_per_frame()
+    pub fn set_timing(&mut self, timing: Timing) {
+        self.timing = timing;
+    }
+
+    // What a frame can spend, in the units frame_cost() counts
+    pub(crate) fn frame_budget(&self) -> usize {
+        match self.timing {
+            Timing::Ticks => self.ticks_per_frame,
+            Timing::Vip => VIP_CYCLES_PER_FRAME,
+        }
+    }
+
+    // What the instruction at the PC takes out of the frame's budget
+    pub(crate) fn frame_cost(&self) -> usize {
+        match self.timing {
+            Timing::Ticks => 1,
+            Timing::Vip => {
+                let pc = self.pc as usize;
+                let ram = &self.buffers.ram;
+                vip_cycles(u16::from_be_bytes([ram[pc % RAM_SIZE], ram[(pc + 1) % RAM_SIZE]]))
+            },
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use crate::{Chip8, NUM_KEYS};
+
+// The keypad as the COSMAC VIP lays it out, top row first
+pub const KEYPAD_LAYOUT: [[usize; 4]; 4] =
+    [[0x1, 0x2, 0x3, 0xC], [0x4, 0x5, 0x6, 0xD], [0x7, 0x8, 0x9, 0xE], [0xA, 0x0, 0xB, 0xF]];
+// Fingers TouchKeypad follows at once, beyond which new ones are ignored
+const MAX_TOUCHES: usize = 10;
+
+// Where one key of a TouchKeypad is drawn, in the frontend's own units, e.g. pixels with y down
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KeyRect {
+    pub key: usize,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl KeyRect {
+    // The hex digit to draw on the key
+    pub fn label(&self) -> char {
+        char::from_digit(self.key as u32, 16).unwrap_or('?').to_ascii_uppercase()
+    }
+
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+}
+
+// An on-screen 4x4 keypad for touch screens, laid out for a frontend to draw, that turns touches into
+// keypresses. Each finger is followed by the id the platform gives it, so several keys can be held at once
+// and sliding a finger from one key to another moves the press with it
+#[derive(Clone, Debug)]
+pub struct TouchKeypad {
+    keys: [KeyRect; NUM_KEYS],
+    // Each finger down, by id, and the key under it if any
+    touches: [Option<(u64, Option<usize>)>; MAX_TOUCHES],
+}
+
+impl TouchKeypad {
+    // Keys filling the rectangle at (x, y), with a gap around each of gap
+    pub fn new(x: f32, y: f32, width: f32, height: f32, gap: f32) -> Self {
+        let (key_width, key_height) = (width / 4.0, height / 4.0);
+        let mut keys = [KeyRect { key: 0, x: 0.0, y: 0.0, width: 0.0, height: 0.0 }; NUM_KEYS];
+        let mut rects = keys.iter_mut();
+        for (row, row_keys) in KEYPAD_LAYOUT.iter().enumerate() {
+            for (column, key) in row_keys.iter().enumerate() {
+                *rects.next().expect("16 keys") = KeyRect {
+                    key: *key,
+                    x: x + column as f32 * key_width + gap,
+                    y: y + row as f32 * key_height + gap,
+                    width: (key_width - 2.0 * gap).max(0.0),
+                    height: (key_height - 2.0 * gap).max(0.0),
+                };
+            }
+        }
+        Self { keys, touches: [None; MAX_TOUCHES] }
+    }
+
+    // A square keypad for a screen of width by height, under the display when the screen's taller than it's
+    // wide and to the right of it otherwise, leaving the display at least half the screen
+    pub fn for_screen(width: f32, height: f32) -> Self {
+        let (x, y, side) = if height > width {
+            let side = width.min(height / 2.0);
+            ((width - side) / 2.0, height - side, side)
+        } else {
+            let side = height.min(width / 2.0);
+            (width - side, (height - side) / 2.0, side)
+        };
+        Self::new(x, y, side, side, side / 64.0)
+    }
+
+    // In KEYPAD_LAYOUT's order, for drawing
+    pub fn keys(&self) -> &[KeyRect; NUM_KEYS] {
+        &self.keys
+    }
+
+    // The key at (x, y), if any. The gaps between keys aren't any key's
+    pub fn hit(&self, x: f32, y: f32) -> Option<usize> {
+        self.keys.iter().find(|rect| rect.contains(x, y)).map(|rect| rect.key)
+    }
+
+    // Whether a finger is holding key
+    pub fn is_held(&self, key: usize) -> bool {
+        self.touches.iter().flatten().any(|(_, held)| *held == Some(key))
+    }
+
+    // A finger with id went down or moved to (x, y), pressing the key under it on chip8 and letting go of the
+    // one it was on before
+    pub fn touch(&mut self, chip8: &mut Chip8, id: u64, x: f32, y: f32) {
+        let key = self.hit(x, y);
+        let Some(slot) = self.slot(id).or_else(|| self.touches.iter().position(Option::is_none)) else {
+            return;
+        };
+        let before = self.touches[slot].and_then(|(_, key)| key);
+        self.touches[slot] = Some((id, key));
+        if before != key {
+            self.update(chip8, before);
+            self.update(chip8, key);
+        }
+    }
+
+    // The finger with id came up, letting go of its key
+    pub fn release(&mut self, chip8: &mut Chip8, id: u64) {
+        if let Some(slot) = self.slot(id) {
+            let key = self.touches[slot].take().and_then(|(_, key)| key);
+            self.update(chip8, key);
+        }
+    }
+
+    // Let go of everything, e.g. when the platform cancels the touches
+    pub fn release_all(&mut self, chip8: &mut Chip8) {
+        for touch in &mut self.touches {
+            if let Some((_, Some(key))) = touch.take() {
+                chip8.keypress(key, false);
+            }
+        }
+    }
+
+    fn slot(&self, id: u64) -> Option<usize> {
+        self.touches.iter().position(|touch| matches!(touch, Some((touch_id, _)) if *touch_id == id))
+    }
+
+    // Hold key down as long as any finger's on it
+    fn update(&self, chip8: &mut Chip8, key: Option<usize>) {
+        if let Some(key) = key {
+            chip8.keypress(key, self.is_held(key));
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use alloc::string::{String, ToString};
+use core::fmt::Write;
+
+use crate::{Chip8, Instruction, NUM_REGS};
+
+// Registers compared before and after an instruction to report what it changed
+pub(crate) struct Registers {
+    v: [u8; NUM_REGS],
+    i: u16,
+    delay: u8,
+    sound: u8,
+}
+
+impl Registers {
+    pub(crate) fn capture(chip8: &Chip8) -> Self {
+        Self {
+            v: chip8.v_regi,
+            i: chip8.i_regi,
+            delay: chip8.delay_t,
+            sound: chip8.sound_t,
+        }
+    }
+}
+
+// Emit one trace line per instruction, e.g. "204: 7A01  ADD VA, 0x01      VA 02->03", with addresses shown
+// by name where the machine has symbols
+pub(crate) fn log_instruction(chip8: &Chip8, opcode: u16, before: &Registers) {
+    if !log::log_enabled!(log::Level::Trace) {
+        return;
+    }
+    let mnemonic = match Instruction::decode(opcode) {
+        Some(instruction) => instruction.with_symbols(&chip8.symbols).to_string(),
+        None => String::from("???"),
+    };
+
+    let after = Registers::capture(chip8);
+    let mut deltas = String::new();
+    for x in 0..NUM_REGS {
+        if before.v[x] != after.v[x] {
+            let _ = write!(deltas, " V{:X} {:02X}->{:02X}", x, before.v[x], after.v[x]);
+        }
+    }
+    if before.i != after.i {
+        let _ = write!(deltas, " I {:03X}->{:03X}", before.i, after.i);
+    }
+    if before.delay != after.delay {
+        let _ = write!(deltas, " DT {:02X}->{:02X}", before.delay, after.delay);
+    }
+    if before.sound != after.sound {
+        let _ = write!(deltas, " ST {:02X}->{:02X}", before.sound, after.sound);
+    }
+
+    log::trace!("{:03X}: {:04X}  {:<16}{}", chip8.op_pc, opcode, mnemonic, deltas);
+}
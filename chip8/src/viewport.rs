@@ -0,0 +1,41 @@
+// Where a frontend draws the display in a window: as large as fits in whole window pixels to each display
+// pixel, so nothing's blurred or stretched, and centred with the rest of the window left as borders. The
+// same for any window size, e.g. fullscreen, and for hi-res, which just gets a smaller scale
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Viewport {
+    pub x: u32,
+    pub y: u32,
+    // Window pixels across and down each display pixel, at least 1 however small the window
+    pub scale: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Viewport {
+    pub fn fit(window_width: u32, window_height: u32, display_width: usize, display_height: usize) -> Self {
+        let (display_width, display_height) = (display_width.max(1) as u32, display_height.max(1) as u32);
+        let scale = (window_width / display_width).min(window_height / display_height).max(1);
+        let (width, height) = (display_width * scale, display_height * scale);
+        Self {
+            x: window_width.saturating_sub(width) / 2,
+            y: window_height.saturating_sub(height) / 2,
+            scale,
+            width,
+            height,
+        }
+    }
+
+    // Top left corner in the window of the display pixel at (x, y)
+    pub fn pixel(&self, x: usize, y: usize) -> (u32, u32) {
+        (self.x + x as u32 * self.scale, self.y + y as u32 * self.scale)
+    }
+
+    // The display pixel under a point in the window, if it's on the display, e.g. for a mouse
+    pub fn to_display(&self, x: u32, y: u32) -> Option<(usize, usize)> {
+        let (x, y) = (x.checked_sub(self.x)?, y.checked_sub(self.y)?);
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        Some(((x / self.scale) as usize, (y / self.scale) as usize))
+    }
+}
@@ -0,0 +1,96 @@
+// Checks transpile_rust() against output checked in under tests/aot, which this compiles in, and runs the
+// compiled ROMs beside the interpreter with the same scripted keys, comparing the whole machine after every
+// block. After a deliberate change to what transpile_rust() writes, rerun with CHIP8_BLESS=1 to rewrite the
+// checked in output, and look over the diff
+#![cfg(feature = "alloc")]
+
+mod common;
+#[path = "aot/pong.rs"]
+mod pong;
+#[path = "aot/tetris.rs"]
+mod tetris;
+
+use std::fs;
+use std::path::PathBuf;
+
+use chip8::{transpile_rust, Analysis, Chip8, Result, State, Timing};
+use common::{machine, press, rom, script, FRAMES};
+
+type Transpiled = (&'static str, &'static str, fn(&mut Chip8) -> Result<usize>, fn(&mut Chip8) -> Result<State>);
+
+const TRANSPILED: [Transpiled; 2] = [
+    ("PONG", include_str!("aot/pong.rs"), pong::step, pong::run_frame),
+    ("TETRIS", include_str!("aot/tetris.rs"), tetris::step, tetris::run_frame),
+];
+// How far the interpreter is let run to reach a fault the compiled code hit partway through a block
+const MAX_BLOCK: usize = 256;
+
+#[test]
+fn output_is_unchanged() {
+    let bless = std::env::var_os("CHIP8_BLESS").is_some();
+    for (name, expected, _, _) in TRANSPILED {
+        let output = transpile_rust(&Analysis::of(&machine(&rom(name))));
+        if bless {
+            let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/aot");
+            fs::write(dir.join(format!("{}.rs", name.to_lowercase())), &output).unwrap();
+        } else {
+            // Not assert_eq!, which would print both files whole
+            assert!(output == expected, "{}: output changed, rerun with CHIP8_BLESS=1 if it should have", name);
+        }
+    }
+}
+
+#[test]
+fn blocks_match_the_interpreter() {
+    for (name, _, step, _) in TRANSPILED {
+        let (mut compiled, mut interpreted) = (machine(&rom(name)), machine(&rom(name)));
+        let mut blocks = 0;
+        'frames: for frame in 0..FRAMES {
+            press(&mut compiled, script(frame));
+            press(&mut interpreted, script(frame));
+            let mut ticks = 0;
+            while ticks < compiled.ticks_per_frame() {
+                if compiled.is_halted() {
+                    break 'frames;
+                }
+                let pc = compiled.pc();
+                match step(&mut compiled) {
+                    Ok(ran) => {
+                        for _ in 0..ran {
+                            interpreted.clock().unwrap_or_else(|err| panic!("{} block at {:#05X}: {}", name, pc, err));
+                        }
+                        ticks += ran;
+                    },
+                    Err(err) => {
+                        let fault = (0..MAX_BLOCK).find_map(|_| interpreted.clock().err());
+                        assert_eq!(fault, Some(err), "{} block at {:#05X}", name, pc);
+                        let same = compiled.snapshot().digest() == interpreted.snapshot().digest();
+                        assert!(same, "{} fault in block at {:#05X}: machines differ", name, pc);
+                        break 'frames;
+                    },
+                }
+                blocks += 1;
+                let same = compiled.snapshot().digest() == interpreted.snapshot().digest();
+                assert!(same, "{} frame {} block at {:#05X}: machines differ", name, frame, pc);
+            }
+            compiled.clock_timers();
+            interpreted.clock_timers();
+        }
+        assert!(blocks > 0, "{} ran no blocks", name);
+    }
+}
+
+#[test]
+fn other_timings_are_interpreted() {
+    for (name, _, _, run_frame) in TRANSPILED {
+        let (mut compiled, mut interpreted) = (machine(&rom(name)), machine(&rom(name)));
+        compiled.set_timing(Timing::Vip);
+        interpreted.set_timing(Timing::Vip);
+        for frame in 0..FRAMES {
+            press(&mut compiled, script(frame));
+            press(&mut interpreted, script(frame));
+            assert_eq!(run_frame(&mut compiled), interpreted.run_frame(), "{} frame {}", name, frame);
+            assert_eq!(compiled.snapshot().digest(), interpreted.snapshot().digest(), "{} frame {}", name, frame);
+        }
+    }
+}
@@ -0,0 +1,604 @@
+// Generated by chip8::transpile_rust(), don't edit
+use chip8::{Chip8, Result, State, Timing};
+
+// Run a frame as Chip8::run_frame() does with Timing::Ticks, a block at a time. Blocks
+// don't count cycles, so other timings, and pauses, are left to the interpreter
+pub fn run_frame(c: &mut Chip8) -> Result<State> {
+    if c.timing() != Timing::Ticks || c.is_paused() {
+        return c.run_frame();
+    }
+    let mut ticks = 0;
+    while ticks < c.ticks_per_frame() {
+        if c.is_halted() {
+            return Ok(State::Halted);
+        }
+        ticks += step(c)?;
+    }
+    c.clock_timers();
+    Ok(if c.is_halted() { State::Halted } else { State::Running })
+}
+
+// Run the block at the PC, returning how many instructions that was
+pub fn step(c: &mut Chip8) -> Result<usize> {
+    match c.pc() {
+        0x200 => block_200(c),
+        0x212 => block_212(c),
+        0x216 => block_216(c),
+        0x21A => block_21a(c),
+        0x21E => block_21e(c),
+        0x220 => block_220(c),
+        0x22A => block_22a(c),
+        0x234 => block_234(c),
+        0x236 => block_236(c),
+        0x23A => block_23a(c),
+        0x23C => block_23c(c),
+        0x246 => block_246(c),
+        0x248 => block_248(c),
+        0x24C => block_24c(c),
+        0x24E => block_24e(c),
+        0x266 => block_266(c),
+        0x268 => block_268(c),
+        0x26A => block_26a(c),
+        0x26C => block_26c(c),
+        0x26E => block_26e(c),
+        0x270 => block_270(c),
+        0x272 => block_272(c),
+        0x274 => block_274(c),
+        0x278 => block_278(c),
+        0x282 => block_282(c),
+        0x28A => block_28a(c),
+        0x28C => block_28c(c),
+        0x28E => block_28e(c),
+        0x294 => block_294(c),
+        0x296 => block_296(c),
+        0x29A => block_29a(c),
+        0x29C => block_29c(c),
+        0x2A0 => block_2a0(c),
+        0x2A2 => block_2a2(c),
+        0x2A8 => block_2a8(c),
+        0x2AC => block_2ac(c),
+        0x2B0 => block_2b0(c),
+        0x2B2 => block_2b2(c),
+        0x2B6 => block_2b6(c),
+        0x2B8 => block_2b8(c),
+        0x2BA => block_2ba(c),
+        0x2BE => block_2be(c),
+        0x2C0 => block_2c0(c),
+        0x2C2 => block_2c2(c),
+        0x2C6 => block_2c6(c),
+        0x2C8 => block_2c8(c),
+        0x2D0 => block_2d0(c),
+        0x2D2 => block_2d2(c),
+        0x2D4 => block_2d4(c),
+        _ => c.clock().map(|_| 1),
+    }
+}
+
+fn block_200(c: &mut Chip8) -> Result<usize> {
+    // 200: LD VA, 0x02
+    c.set_v(10, 0x02);
+    // 202: LD VB, 0x0C
+    c.set_v(11, 0x0C);
+    // 204: LD VC, 0x3F
+    c.set_v(12, 0x3F);
+    // 206: LD VD, 0x0C
+    c.set_v(13, 0x0C);
+    // 208: LD I, 0x2EA
+    c.set_i(0x2EA);
+    // 20A: DRW VA, VB, 6
+    c.run_opcode(0x20A, 0xDAB6)?;
+    if c.pc() != 0x20C {
+        return Ok(6);
+    }
+    // 20C: DRW VC, VD, 6
+    c.run_opcode(0x20C, 0xDCD6)?;
+    if c.pc() != 0x20E {
+        return Ok(7);
+    }
+    // 20E: LD VE, 0x00
+    c.set_v(14, 0x00);
+    // 210: CALL 0x2D4
+    c.run_opcode(0x210, 0x22D4)?;
+    Ok(9)
+}
+
+fn block_212(c: &mut Chip8) -> Result<usize> {
+    // 212: LD V6, 0x03
+    c.set_v(6, 0x03);
+    // 214: LD V8, 0x02
+    c.set_v(8, 0x02);
+    c.set_pc(0x216);
+    Ok(2)
+}
+
+fn block_216(c: &mut Chip8) -> Result<usize> {
+    // 216: LD V0, 0x60
+    c.set_v(0, 0x60);
+    // 218: LD DT, V0
+    c.run_opcode(0x218, 0xF015)?;
+    Ok(2)
+}
+
+fn block_21a(c: &mut Chip8) -> Result<usize> {
+    // 21A: LD V0, DT
+    c.run_opcode(0x21A, 0xF007)?;
+    if c.pc() != 0x21C {
+        return Ok(1);
+    }
+    // 21C: SE V0, 0x00
+    c.run_opcode(0x21C, 0x3000)?;
+    Ok(2)
+}
+
+fn block_21e(c: &mut Chip8) -> Result<usize> {
+    // 21E: JP 0x21A
+    c.set_pc(0x21A);
+    Ok(1)
+}
+
+fn block_220(c: &mut Chip8) -> Result<usize> {
+    // 220: RND V7, 0x17
+    c.run_opcode(0x220, 0xC717)?;
+    if c.pc() != 0x222 {
+        return Ok(1);
+    }
+    // 222: ADD V7, 0x08
+    c.set_v(7, c.v(7).wrapping_add(0x08));
+    // 224: LD V9, 0xFF
+    c.set_v(9, 0xFF);
+    // 226: LD I, 0x2F0
+    c.set_i(0x2F0);
+    // 228: DRW V6, V7, 1
+    c.run_opcode(0x228, 0xD671)?;
+    Ok(5)
+}
+
+fn block_22a(c: &mut Chip8) -> Result<usize> {
+    // 22A: LD I, 0x2EA
+    c.set_i(0x2EA);
+    // 22C: DRW VA, VB, 6
+    c.run_opcode(0x22C, 0xDAB6)?;
+    if c.pc() != 0x22E {
+        return Ok(2);
+    }
+    // 22E: DRW VC, VD, 6
+    c.run_opcode(0x22E, 0xDCD6)?;
+    if c.pc() != 0x230 {
+        return Ok(3);
+    }
+    // 230: LD V0, 0x01
+    c.set_v(0, 0x01);
+    // 232: SKNP V0
+    c.run_opcode(0x232, 0xE0A1)?;
+    Ok(5)
+}
+
+fn block_234(c: &mut Chip8) -> Result<usize> {
+    // 234: ADD VB, 0xFE
+    c.set_v(11, c.v(11).wrapping_add(0xFE));
+    c.set_pc(0x236);
+    Ok(1)
+}
+
+fn block_236(c: &mut Chip8) -> Result<usize> {
+    // 236: LD V0, 0x04
+    c.set_v(0, 0x04);
+    // 238: SKNP V0
+    c.run_opcode(0x238, 0xE0A1)?;
+    Ok(2)
+}
+
+fn block_23a(c: &mut Chip8) -> Result<usize> {
+    // 23A: ADD VB, 0x02
+    c.set_v(11, c.v(11).wrapping_add(0x02));
+    c.set_pc(0x23C);
+    Ok(1)
+}
+
+fn block_23c(c: &mut Chip8) -> Result<usize> {
+    // 23C: LD V0, 0x1F
+    c.set_v(0, 0x1F);
+    // 23E: AND VB, V0
+    c.run_opcode(0x23E, 0x8B02)?;
+    if c.pc() != 0x240 {
+        return Ok(2);
+    }
+    // 240: DRW VA, VB, 6
+    c.run_opcode(0x240, 0xDAB6)?;
+    if c.pc() != 0x242 {
+        return Ok(3);
+    }
+    // 242: LD V0, 0x0C
+    c.set_v(0, 0x0C);
+    // 244: SKNP V0
+    c.run_opcode(0x244, 0xE0A1)?;
+    Ok(5)
+}
+
+fn block_246(c: &mut Chip8) -> Result<usize> {
+    // 246: ADD VD, 0xFE
+    c.set_v(13, c.v(13).wrapping_add(0xFE));
+    c.set_pc(0x248);
+    Ok(1)
+}
+
+fn block_248(c: &mut Chip8) -> Result<usize> {
+    // 248: LD V0, 0x0D
+    c.set_v(0, 0x0D);
+    // 24A: SKNP V0
+    c.run_opcode(0x24A, 0xE0A1)?;
+    Ok(2)
+}
+
+fn block_24c(c: &mut Chip8) -> Result<usize> {
+    // 24C: ADD VD, 0x02
+    c.set_v(13, c.v(13).wrapping_add(0x02));
+    c.set_pc(0x24E);
+    Ok(1)
+}
+
+fn block_24e(c: &mut Chip8) -> Result<usize> {
+    // 24E: LD V0, 0x1F
+    c.set_v(0, 0x1F);
+    // 250: AND VD, V0
+    c.run_opcode(0x250, 0x8D02)?;
+    if c.pc() != 0x252 {
+        return Ok(2);
+    }
+    // 252: DRW VC, VD, 6
+    c.run_opcode(0x252, 0xDCD6)?;
+    if c.pc() != 0x254 {
+        return Ok(3);
+    }
+    // 254: LD I, 0x2F0
+    c.set_i(0x2F0);
+    // 256: DRW V6, V7, 1
+    c.run_opcode(0x256, 0xD671)?;
+    if c.pc() != 0x258 {
+        return Ok(5);
+    }
+    // 258: ADD V6, V8
+    c.run_opcode(0x258, 0x8684)?;
+    if c.pc() != 0x25A {
+        return Ok(6);
+    }
+    // 25A: ADD V7, V9
+    c.run_opcode(0x25A, 0x8794)?;
+    if c.pc() != 0x25C {
+        return Ok(7);
+    }
+    // 25C: LD V0, 0x3F
+    c.set_v(0, 0x3F);
+    // 25E: AND V6, V0
+    c.run_opcode(0x25E, 0x8602)?;
+    if c.pc() != 0x260 {
+        return Ok(9);
+    }
+    // 260: LD V1, 0x1F
+    c.set_v(1, 0x1F);
+    // 262: AND V7, V1
+    c.run_opcode(0x262, 0x8712)?;
+    if c.pc() != 0x264 {
+        return Ok(11);
+    }
+    // 264: SNE V6, 0x02
+    c.run_opcode(0x264, 0x4602)?;
+    Ok(12)
+}
+
+fn block_266(c: &mut Chip8) -> Result<usize> {
+    // 266: JP 0x278
+    c.set_pc(0x278);
+    Ok(1)
+}
+
+fn block_268(c: &mut Chip8) -> Result<usize> {
+    // 268: SNE V6, 0x3F
+    c.run_opcode(0x268, 0x463F)?;
+    Ok(1)
+}
+
+fn block_26a(c: &mut Chip8) -> Result<usize> {
+    // 26A: JP 0x282
+    c.set_pc(0x282);
+    Ok(1)
+}
+
+fn block_26c(c: &mut Chip8) -> Result<usize> {
+    // 26C: SNE V7, 0x1F
+    c.run_opcode(0x26C, 0x471F)?;
+    Ok(1)
+}
+
+fn block_26e(c: &mut Chip8) -> Result<usize> {
+    // 26E: LD V9, 0xFF
+    c.set_v(9, 0xFF);
+    c.set_pc(0x270);
+    Ok(1)
+}
+
+fn block_270(c: &mut Chip8) -> Result<usize> {
+    // 270: SNE V7, 0x00
+    c.run_opcode(0x270, 0x4700)?;
+    Ok(1)
+}
+
+fn block_272(c: &mut Chip8) -> Result<usize> {
+    // 272: LD V9, 0x01
+    c.set_v(9, 0x01);
+    c.set_pc(0x274);
+    Ok(1)
+}
+
+fn block_274(c: &mut Chip8) -> Result<usize> {
+    // 274: DRW V6, V7, 1
+    c.run_opcode(0x274, 0xD671)?;
+    if c.pc() != 0x276 {
+        return Ok(1);
+    }
+    // 276: JP 0x22A
+    c.set_pc(0x22A);
+    Ok(2)
+}
+
+fn block_278(c: &mut Chip8) -> Result<usize> {
+    // 278: LD V8, 0x02
+    c.set_v(8, 0x02);
+    // 27A: LD V3, 0x01
+    c.set_v(3, 0x01);
+    // 27C: LD V0, V7
+    c.set_v(0, c.v(7));
+    // 27E: SUB V0, VB
+    c.run_opcode(0x27E, 0x80B5)?;
+    if c.pc() != 0x280 {
+        return Ok(4);
+    }
+    // 280: JP 0x28A
+    c.set_pc(0x28A);
+    Ok(5)
+}
+
+fn block_282(c: &mut Chip8) -> Result<usize> {
+    // 282: LD V8, 0xFE
+    c.set_v(8, 0xFE);
+    // 284: LD V3, 0x0A
+    c.set_v(3, 0x0A);
+    // 286: LD V0, V7
+    c.set_v(0, c.v(7));
+    // 288: SUB V0, VD
+    c.run_opcode(0x288, 0x80D5)?;
+    Ok(4)
+}
+
+fn block_28a(c: &mut Chip8) -> Result<usize> {
+    // 28A: SE VF, 0x01
+    c.run_opcode(0x28A, 0x3F01)?;
+    Ok(1)
+}
+
+fn block_28c(c: &mut Chip8) -> Result<usize> {
+    // 28C: JP 0x2A2
+    c.set_pc(0x2A2);
+    Ok(1)
+}
+
+fn block_28e(c: &mut Chip8) -> Result<usize> {
+    // 28E: LD V1, 0x02
+    c.set_v(1, 0x02);
+    // 290: SUB V0, V1
+    c.run_opcode(0x290, 0x8015)?;
+    if c.pc() != 0x292 {
+        return Ok(2);
+    }
+    // 292: SE VF, 0x01
+    c.run_opcode(0x292, 0x3F01)?;
+    Ok(3)
+}
+
+fn block_294(c: &mut Chip8) -> Result<usize> {
+    // 294: JP 0x2BA
+    c.set_pc(0x2BA);
+    Ok(1)
+}
+
+fn block_296(c: &mut Chip8) -> Result<usize> {
+    // 296: SUB V0, V1
+    c.run_opcode(0x296, 0x8015)?;
+    if c.pc() != 0x298 {
+        return Ok(1);
+    }
+    // 298: SE VF, 0x01
+    c.run_opcode(0x298, 0x3F01)?;
+    Ok(2)
+}
+
+fn block_29a(c: &mut Chip8) -> Result<usize> {
+    // 29A: JP 0x2C8
+    c.set_pc(0x2C8);
+    Ok(1)
+}
+
+fn block_29c(c: &mut Chip8) -> Result<usize> {
+    // 29C: SUB V0, V1
+    c.run_opcode(0x29C, 0x8015)?;
+    if c.pc() != 0x29E {
+        return Ok(1);
+    }
+    // 29E: SE VF, 0x01
+    c.run_opcode(0x29E, 0x3F01)?;
+    Ok(2)
+}
+
+fn block_2a0(c: &mut Chip8) -> Result<usize> {
+    // 2A0: JP 0x2C2
+    c.set_pc(0x2C2);
+    Ok(1)
+}
+
+fn block_2a2(c: &mut Chip8) -> Result<usize> {
+    // 2A2: LD V0, 0x20
+    c.set_v(0, 0x20);
+    // 2A4: LD ST, V0
+    c.run_opcode(0x2A4, 0xF018)?;
+    if c.pc() != 0x2A6 {
+        return Ok(2);
+    }
+    // 2A6: CALL 0x2D4
+    c.run_opcode(0x2A6, 0x22D4)?;
+    Ok(3)
+}
+
+fn block_2a8(c: &mut Chip8) -> Result<usize> {
+    // 2A8: ADD VE, V3
+    c.run_opcode(0x2A8, 0x8E34)?;
+    if c.pc() != 0x2AA {
+        return Ok(1);
+    }
+    // 2AA: CALL 0x2D4
+    c.run_opcode(0x2AA, 0x22D4)?;
+    Ok(2)
+}
+
+fn block_2ac(c: &mut Chip8) -> Result<usize> {
+    // 2AC: LD V6, 0x3E
+    c.set_v(6, 0x3E);
+    // 2AE: SE V3, 0x01
+    c.run_opcode(0x2AE, 0x3301)?;
+    Ok(2)
+}
+
+fn block_2b0(c: &mut Chip8) -> Result<usize> {
+    // 2B0: LD V6, 0x03
+    c.set_v(6, 0x03);
+    c.set_pc(0x2B2);
+    Ok(1)
+}
+
+fn block_2b2(c: &mut Chip8) -> Result<usize> {
+    // 2B2: LD V8, 0xFE
+    c.set_v(8, 0xFE);
+    // 2B4: SE V3, 0x01
+    c.run_opcode(0x2B4, 0x3301)?;
+    Ok(2)
+}
+
+fn block_2b6(c: &mut Chip8) -> Result<usize> {
+    // 2B6: LD V8, 0x02
+    c.set_v(8, 0x02);
+    c.set_pc(0x2B8);
+    Ok(1)
+}
+
+fn block_2b8(c: &mut Chip8) -> Result<usize> {
+    // 2B8: JP 0x216
+    c.set_pc(0x216);
+    Ok(1)
+}
+
+fn block_2ba(c: &mut Chip8) -> Result<usize> {
+    // 2BA: ADD V9, 0xFF
+    c.set_v(9, c.v(9).wrapping_add(0xFF));
+    // 2BC: SNE V9, 0xFE
+    c.run_opcode(0x2BC, 0x49FE)?;
+    Ok(2)
+}
+
+fn block_2be(c: &mut Chip8) -> Result<usize> {
+    // 2BE: LD V9, 0xFF
+    c.set_v(9, 0xFF);
+    c.set_pc(0x2C0);
+    Ok(1)
+}
+
+fn block_2c0(c: &mut Chip8) -> Result<usize> {
+    // 2C0: JP 0x2C8
+    c.set_pc(0x2C8);
+    Ok(1)
+}
+
+fn block_2c2(c: &mut Chip8) -> Result<usize> {
+    // 2C2: ADD V9, 0x01
+    c.set_v(9, c.v(9).wrapping_add(0x01));
+    // 2C4: SNE V9, 0x02
+    c.run_opcode(0x2C4, 0x4902)?;
+    Ok(2)
+}
+
+fn block_2c6(c: &mut Chip8) -> Result<usize> {
+    // 2C6: LD V9, 0x01
+    c.set_v(9, 0x01);
+    c.set_pc(0x2C8);
+    Ok(1)
+}
+
+fn block_2c8(c: &mut Chip8) -> Result<usize> {
+    // 2C8: LD V0, 0x04
+    c.set_v(0, 0x04);
+    // 2CA: LD ST, V0
+    c.run_opcode(0x2CA, 0xF018)?;
+    if c.pc() != 0x2CC {
+        return Ok(2);
+    }
+    // 2CC: ADD V6, 0x01
+    c.set_v(6, c.v(6).wrapping_add(0x01));
+    // 2CE: SNE V6, 0x40
+    c.run_opcode(0x2CE, 0x4640)?;
+    Ok(4)
+}
+
+fn block_2d0(c: &mut Chip8) -> Result<usize> {
+    // 2D0: ADD V6, 0xFE
+    c.set_v(6, c.v(6).wrapping_add(0xFE));
+    c.set_pc(0x2D2);
+    Ok(1)
+}
+
+fn block_2d2(c: &mut Chip8) -> Result<usize> {
+    // 2D2: JP 0x26C
+    c.set_pc(0x26C);
+    Ok(1)
+}
+
+fn block_2d4(c: &mut Chip8) -> Result<usize> {
+    // 2D4: LD I, 0x2F2
+    c.set_i(0x2F2);
+    // 2D6: LD B, VE
+    c.run_opcode(0x2D6, 0xFE33)?;
+    if c.pc() != 0x2D8 {
+        return Ok(2);
+    }
+    // 2D8: LD V2, [I]
+    c.run_opcode(0x2D8, 0xF265)?;
+    if c.pc() != 0x2DA {
+        return Ok(3);
+    }
+    // 2DA: LD F, V1
+    c.run_opcode(0x2DA, 0xF129)?;
+    if c.pc() != 0x2DC {
+        return Ok(4);
+    }
+    // 2DC: LD V4, 0x14
+    c.set_v(4, 0x14);
+    // 2DE: LD V5, 0x00
+    c.set_v(5, 0x00);
+    // 2E0: DRW V4, V5, 5
+    c.run_opcode(0x2E0, 0xD455)?;
+    if c.pc() != 0x2E2 {
+        return Ok(7);
+    }
+    // 2E2: ADD V4, 0x15
+    c.set_v(4, c.v(4).wrapping_add(0x15));
+    // 2E4: LD F, V2
+    c.run_opcode(0x2E4, 0xF229)?;
+    if c.pc() != 0x2E6 {
+        return Ok(9);
+    }
+    // 2E6: DRW V4, V5, 5
+    c.run_opcode(0x2E6, 0xD455)?;
+    if c.pc() != 0x2E8 {
+        return Ok(10);
+    }
+    // 2E8: RET
+    c.run_opcode(0x2E8, 0x00EE)?;
+    Ok(11)
+}
@@ -0,0 +1,1042 @@
+// Generated by chip8::transpile_rust(), don't edit
+use chip8::{Chip8, Result, State, Timing};
+
+// Run a frame as Chip8::run_frame() does with Timing::Ticks, a block at a time. Blocks
+// don't count cycles, so other timings, and pauses, are left to the interpreter
+pub fn run_frame(c: &mut Chip8) -> Result<State> {
+    if c.timing() != Timing::Ticks || c.is_paused() {
+        return c.run_frame();
+    }
+    let mut ticks = 0;
+    while ticks < c.ticks_per_frame() {
+        if c.is_halted() {
+            return Ok(State::Halted);
+        }
+        ticks += step(c)?;
+    }
+    c.clock_timers();
+    Ok(if c.is_halted() { State::Halted } else { State::Running })
+}
+
+// Run the block at the PC, returning how many instructions that was
+pub fn step(c: &mut Chip8) -> Result<usize> {
+    match c.pc() {
+        0x200 => block_200(c),
+        0x204 => block_204(c),
+        0x206 => block_206(c),
+        0x20C => block_20c(c),
+        0x20E => block_20e(c),
+        0x21A => block_21a(c),
+        0x21C => block_21c(c),
+        0x220 => block_220(c),
+        0x222 => block_222(c),
+        0x22A => block_22a(c),
+        0x230 => block_230(c),
+        0x232 => block_232(c),
+        0x23A => block_23a(c),
+        0x23C => block_23c(c),
+        0x23E => block_23e(c),
+        0x240 => block_240(c),
+        0x242 => block_242(c),
+        0x244 => block_244(c),
+        0x246 => block_246(c),
+        0x248 => block_248(c),
+        0x24A => block_24a(c),
+        0x24C => block_24c(c),
+        0x250 => block_250(c),
+        0x254 => block_254(c),
+        0x256 => block_256(c),
+        0x25C => block_25c(c),
+        0x264 => block_264(c),
+        0x266 => block_266(c),
+        0x268 => block_268(c),
+        0x26A => block_26a(c),
+        0x26C => block_26c(c),
+        0x26E => block_26e(c),
+        0x272 => block_272(c),
+        0x278 => block_278(c),
+        0x27A => block_27a(c),
+        0x27C => block_27c(c),
+        0x282 => block_282(c),
+        0x284 => block_284(c),
+        0x28A => block_28a(c),
+        0x28C => block_28c(c),
+        0x28E => block_28e(c),
+        0x294 => block_294(c),
+        0x296 => block_296(c),
+        0x29C => block_29c(c),
+        0x29E => block_29e(c),
+        0x2A0 => block_2a0(c),
+        0x2A2 => block_2a2(c),
+        0x2A4 => block_2a4(c),
+        0x2A6 => block_2a6(c),
+        0x2AC => block_2ac(c),
+        0x2AE => block_2ae(c),
+        0x2B0 => block_2b0(c),
+        0x2B2 => block_2b2(c),
+        0x2B6 => block_2b6(c),
+        0x334 => block_334(c),
+        0x338 => block_338(c),
+        0x33C => block_33c(c),
+        0x33E => block_33e(c),
+        0x340 => block_340(c),
+        0x346 => block_346(c),
+        0x348 => block_348(c),
+        0x34A => block_34a(c),
+        0x34C => block_34c(c),
+        0x34E => block_34e(c),
+        0x350 => block_350(c),
+        0x352 => block_352(c),
+        0x354 => block_354(c),
+        0x356 => block_356(c),
+        0x358 => block_358(c),
+        0x35A => block_35a(c),
+        0x35E => block_35e(c),
+        0x362 => block_362(c),
+        0x366 => block_366(c),
+        0x368 => block_368(c),
+        0x36E => block_36e(c),
+        0x370 => block_370(c),
+        0x372 => block_372(c),
+        0x374 => block_374(c),
+        0x37A => block_37a(c),
+        0x37C => block_37c(c),
+        0x382 => block_382(c),
+        0x386 => block_386(c),
+        0x38A => block_38a(c),
+        0x38C => block_38c(c),
+        0x390 => block_390(c),
+        0x394 => block_394(c),
+        0x398 => block_398(c),
+        0x39A => block_39a(c),
+        0x39C => block_39c(c),
+        0x39E => block_39e(c),
+        0x3A4 => block_3a4(c),
+        0x3A6 => block_3a6(c),
+        0x3A8 => block_3a8(c),
+        0x3AA => block_3aa(c),
+        0x3AC => block_3ac(c),
+        0x3B0 => block_3b0(c),
+        0x3B8 => block_3b8(c),
+        0x3BA => block_3ba(c),
+        0x3BC => block_3bc(c),
+        0x3BE => block_3be(c),
+        0x3C0 => block_3c0(c),
+        0x3E6 => block_3e6(c),
+        _ => c.clock().map(|_| 1),
+    }
+}
+
+fn block_200(c: &mut Chip8) -> Result<usize> {
+    // 200: LD I, 0x2B4
+    c.set_i(0x2B4);
+    // 202: CALL 0x3E6
+    c.run_opcode(0x202, 0x23E6)?;
+    Ok(2)
+}
+
+fn block_204(c: &mut Chip8) -> Result<usize> {
+    // 204: CALL 0x2B6
+    c.run_opcode(0x204, 0x22B6)?;
+    Ok(1)
+}
+
+fn block_206(c: &mut Chip8) -> Result<usize> {
+    // 206: ADD V0, 0x01
+    c.set_v(0, c.v(0).wrapping_add(0x01));
+    // 208: DRW V0, V1, 1
+    c.run_opcode(0x208, 0xD011)?;
+    if c.pc() != 0x20A {
+        return Ok(2);
+    }
+    // 20A: SE V0, 0x25
+    c.run_opcode(0x20A, 0x3025)?;
+    Ok(3)
+}
+
+fn block_20c(c: &mut Chip8) -> Result<usize> {
+    // 20C: JP 0x206
+    c.set_pc(0x206);
+    Ok(1)
+}
+
+fn block_20e(c: &mut Chip8) -> Result<usize> {
+    // 20E: ADD V1, 0xFF
+    c.set_v(1, c.v(1).wrapping_add(0xFF));
+    // 210: DRW V0, V1, 1
+    c.run_opcode(0x210, 0xD011)?;
+    if c.pc() != 0x212 {
+        return Ok(2);
+    }
+    // 212: LD V0, 0x1A
+    c.set_v(0, 0x1A);
+    // 214: DRW V0, V1, 1
+    c.run_opcode(0x214, 0xD011)?;
+    if c.pc() != 0x216 {
+        return Ok(4);
+    }
+    // 216: LD V0, 0x25
+    c.set_v(0, 0x25);
+    // 218: SE V1, 0x00
+    c.run_opcode(0x218, 0x3100)?;
+    Ok(6)
+}
+
+fn block_21a(c: &mut Chip8) -> Result<usize> {
+    // 21A: JP 0x20E
+    c.set_pc(0x20E);
+    Ok(1)
+}
+
+fn block_21c(c: &mut Chip8) -> Result<usize> {
+    // 21C: RND V4, 0x70
+    c.run_opcode(0x21C, 0xC470)?;
+    if c.pc() != 0x21E {
+        return Ok(1);
+    }
+    // 21E: SNE V4, 0x70
+    c.run_opcode(0x21E, 0x4470)?;
+    Ok(2)
+}
+
+fn block_220(c: &mut Chip8) -> Result<usize> {
+    // 220: JP 0x21C
+    c.set_pc(0x21C);
+    Ok(1)
+}
+
+fn block_222(c: &mut Chip8) -> Result<usize> {
+    // 222: RND V3, 0x03
+    c.run_opcode(0x222, 0xC303)?;
+    if c.pc() != 0x224 {
+        return Ok(1);
+    }
+    // 224: LD V0, 0x1E
+    c.set_v(0, 0x1E);
+    // 226: LD V1, 0x03
+    c.set_v(1, 0x03);
+    // 228: CALL 0x25C
+    c.run_opcode(0x228, 0x225C)?;
+    Ok(4)
+}
+
+fn block_22a(c: &mut Chip8) -> Result<usize> {
+    // 22A: LD DT, V5
+    c.run_opcode(0x22A, 0xF515)?;
+    if c.pc() != 0x22C {
+        return Ok(1);
+    }
+    // 22C: DRW V0, V1, 4
+    c.run_opcode(0x22C, 0xD014)?;
+    if c.pc() != 0x22E {
+        return Ok(2);
+    }
+    // 22E: SE VF, 0x01
+    c.run_opcode(0x22E, 0x3F01)?;
+    Ok(3)
+}
+
+fn block_230(c: &mut Chip8) -> Result<usize> {
+    // 230: JP 0x23C
+    c.set_pc(0x23C);
+    Ok(1)
+}
+
+fn block_232(c: &mut Chip8) -> Result<usize> {
+    // 232: DRW V0, V1, 4
+    c.run_opcode(0x232, 0xD014)?;
+    if c.pc() != 0x234 {
+        return Ok(1);
+    }
+    // 234: ADD V1, 0xFF
+    c.set_v(1, c.v(1).wrapping_add(0xFF));
+    // 236: DRW V0, V1, 4
+    c.run_opcode(0x236, 0xD014)?;
+    if c.pc() != 0x238 {
+        return Ok(3);
+    }
+    // 238: CALL 0x340
+    c.run_opcode(0x238, 0x2340)?;
+    Ok(4)
+}
+
+fn block_23a(c: &mut Chip8) -> Result<usize> {
+    // 23A: JP 0x21C
+    c.set_pc(0x21C);
+    Ok(1)
+}
+
+fn block_23c(c: &mut Chip8) -> Result<usize> {
+    // 23C: SKNP V7
+    c.run_opcode(0x23C, 0xE7A1)?;
+    Ok(1)
+}
+
+fn block_23e(c: &mut Chip8) -> Result<usize> {
+    // 23E: CALL 0x272
+    c.run_opcode(0x23E, 0x2272)?;
+    Ok(1)
+}
+
+fn block_240(c: &mut Chip8) -> Result<usize> {
+    // 240: SKNP V8
+    c.run_opcode(0x240, 0xE8A1)?;
+    Ok(1)
+}
+
+fn block_242(c: &mut Chip8) -> Result<usize> {
+    // 242: CALL 0x284
+    c.run_opcode(0x242, 0x2284)?;
+    Ok(1)
+}
+
+fn block_244(c: &mut Chip8) -> Result<usize> {
+    // 244: SKNP V9
+    c.run_opcode(0x244, 0xE9A1)?;
+    Ok(1)
+}
+
+fn block_246(c: &mut Chip8) -> Result<usize> {
+    // 246: CALL 0x296
+    c.run_opcode(0x246, 0x2296)?;
+    Ok(1)
+}
+
+fn block_248(c: &mut Chip8) -> Result<usize> {
+    // 248: SKP V2
+    c.run_opcode(0x248, 0xE29E)?;
+    Ok(1)
+}
+
+fn block_24a(c: &mut Chip8) -> Result<usize> {
+    // 24A: JP 0x250
+    c.set_pc(0x250);
+    Ok(1)
+}
+
+fn block_24c(c: &mut Chip8) -> Result<usize> {
+    // 24C: LD V6, 0x00
+    c.set_v(6, 0x00);
+    // 24E: LD DT, V6
+    c.run_opcode(0x24E, 0xF615)?;
+    Ok(2)
+}
+
+fn block_250(c: &mut Chip8) -> Result<usize> {
+    // 250: LD V6, DT
+    c.run_opcode(0x250, 0xF607)?;
+    if c.pc() != 0x252 {
+        return Ok(1);
+    }
+    // 252: SE V6, 0x00
+    c.run_opcode(0x252, 0x3600)?;
+    Ok(2)
+}
+
+fn block_254(c: &mut Chip8) -> Result<usize> {
+    // 254: JP 0x23C
+    c.set_pc(0x23C);
+    Ok(1)
+}
+
+fn block_256(c: &mut Chip8) -> Result<usize> {
+    // 256: DRW V0, V1, 4
+    c.run_opcode(0x256, 0xD014)?;
+    if c.pc() != 0x258 {
+        return Ok(1);
+    }
+    // 258: ADD V1, 0x01
+    c.set_v(1, c.v(1).wrapping_add(0x01));
+    // 25A: JP 0x22A
+    c.set_pc(0x22A);
+    Ok(3)
+}
+
+fn block_25c(c: &mut Chip8) -> Result<usize> {
+    // 25C: LD I, 0x2C4
+    c.set_i(0x2C4);
+    // 25E: ADD I, V4
+    c.run_opcode(0x25E, 0xF41E)?;
+    if c.pc() != 0x260 {
+        return Ok(2);
+    }
+    // 260: LD V6, 0x00
+    c.set_v(6, 0x00);
+    // 262: SNE V3, 0x01
+    c.run_opcode(0x262, 0x4301)?;
+    Ok(4)
+}
+
+fn block_264(c: &mut Chip8) -> Result<usize> {
+    // 264: LD V6, 0x04
+    c.set_v(6, 0x04);
+    c.set_pc(0x266);
+    Ok(1)
+}
+
+fn block_266(c: &mut Chip8) -> Result<usize> {
+    // 266: SNE V3, 0x02
+    c.run_opcode(0x266, 0x4302)?;
+    Ok(1)
+}
+
+fn block_268(c: &mut Chip8) -> Result<usize> {
+    // 268: LD V6, 0x08
+    c.set_v(6, 0x08);
+    c.set_pc(0x26A);
+    Ok(1)
+}
+
+fn block_26a(c: &mut Chip8) -> Result<usize> {
+    // 26A: SNE V3, 0x03
+    c.run_opcode(0x26A, 0x4303)?;
+    Ok(1)
+}
+
+fn block_26c(c: &mut Chip8) -> Result<usize> {
+    // 26C: LD V6, 0x0C
+    c.set_v(6, 0x0C);
+    c.set_pc(0x26E);
+    Ok(1)
+}
+
+fn block_26e(c: &mut Chip8) -> Result<usize> {
+    // 26E: ADD I, V6
+    c.run_opcode(0x26E, 0xF61E)?;
+    if c.pc() != 0x270 {
+        return Ok(1);
+    }
+    // 270: RET
+    c.run_opcode(0x270, 0x00EE)?;
+    Ok(2)
+}
+
+fn block_272(c: &mut Chip8) -> Result<usize> {
+    // 272: DRW V0, V1, 4
+    c.run_opcode(0x272, 0xD014)?;
+    if c.pc() != 0x274 {
+        return Ok(1);
+    }
+    // 274: ADD V0, 0xFF
+    c.set_v(0, c.v(0).wrapping_add(0xFF));
+    // 276: CALL 0x334
+    c.run_opcode(0x276, 0x2334)?;
+    Ok(3)
+}
+
+fn block_278(c: &mut Chip8) -> Result<usize> {
+    // 278: SE VF, 0x01
+    c.run_opcode(0x278, 0x3F01)?;
+    Ok(1)
+}
+
+fn block_27a(c: &mut Chip8) -> Result<usize> {
+    // 27A: RET
+    c.run_opcode(0x27A, 0x00EE)?;
+    Ok(1)
+}
+
+fn block_27c(c: &mut Chip8) -> Result<usize> {
+    // 27C: DRW V0, V1, 4
+    c.run_opcode(0x27C, 0xD014)?;
+    if c.pc() != 0x27E {
+        return Ok(1);
+    }
+    // 27E: ADD V0, 0x01
+    c.set_v(0, c.v(0).wrapping_add(0x01));
+    // 280: CALL 0x334
+    c.run_opcode(0x280, 0x2334)?;
+    Ok(3)
+}
+
+fn block_282(c: &mut Chip8) -> Result<usize> {
+    // 282: RET
+    c.run_opcode(0x282, 0x00EE)?;
+    Ok(1)
+}
+
+fn block_284(c: &mut Chip8) -> Result<usize> {
+    // 284: DRW V0, V1, 4
+    c.run_opcode(0x284, 0xD014)?;
+    if c.pc() != 0x286 {
+        return Ok(1);
+    }
+    // 286: ADD V0, 0x01
+    c.set_v(0, c.v(0).wrapping_add(0x01));
+    // 288: CALL 0x334
+    c.run_opcode(0x288, 0x2334)?;
+    Ok(3)
+}
+
+fn block_28a(c: &mut Chip8) -> Result<usize> {
+    // 28A: SE VF, 0x01
+    c.run_opcode(0x28A, 0x3F01)?;
+    Ok(1)
+}
+
+fn block_28c(c: &mut Chip8) -> Result<usize> {
+    // 28C: RET
+    c.run_opcode(0x28C, 0x00EE)?;
+    Ok(1)
+}
+
+fn block_28e(c: &mut Chip8) -> Result<usize> {
+    // 28E: DRW V0, V1, 4
+    c.run_opcode(0x28E, 0xD014)?;
+    if c.pc() != 0x290 {
+        return Ok(1);
+    }
+    // 290: ADD V0, 0xFF
+    c.set_v(0, c.v(0).wrapping_add(0xFF));
+    // 292: CALL 0x334
+    c.run_opcode(0x292, 0x2334)?;
+    Ok(3)
+}
+
+fn block_294(c: &mut Chip8) -> Result<usize> {
+    // 294: RET
+    c.run_opcode(0x294, 0x00EE)?;
+    Ok(1)
+}
+
+fn block_296(c: &mut Chip8) -> Result<usize> {
+    // 296: DRW V0, V1, 4
+    c.run_opcode(0x296, 0xD014)?;
+    if c.pc() != 0x298 {
+        return Ok(1);
+    }
+    // 298: ADD V3, 0x01
+    c.set_v(3, c.v(3).wrapping_add(0x01));
+    // 29A: SNE V3, 0x04
+    c.run_opcode(0x29A, 0x4304)?;
+    Ok(3)
+}
+
+fn block_29c(c: &mut Chip8) -> Result<usize> {
+    // 29C: LD V3, 0x00
+    c.set_v(3, 0x00);
+    c.set_pc(0x29E);
+    Ok(1)
+}
+
+fn block_29e(c: &mut Chip8) -> Result<usize> {
+    // 29E: CALL 0x25C
+    c.run_opcode(0x29E, 0x225C)?;
+    Ok(1)
+}
+
+fn block_2a0(c: &mut Chip8) -> Result<usize> {
+    // 2A0: CALL 0x334
+    c.run_opcode(0x2A0, 0x2334)?;
+    Ok(1)
+}
+
+fn block_2a2(c: &mut Chip8) -> Result<usize> {
+    // 2A2: SE VF, 0x01
+    c.run_opcode(0x2A2, 0x3F01)?;
+    Ok(1)
+}
+
+fn block_2a4(c: &mut Chip8) -> Result<usize> {
+    // 2A4: RET
+    c.run_opcode(0x2A4, 0x00EE)?;
+    Ok(1)
+}
+
+fn block_2a6(c: &mut Chip8) -> Result<usize> {
+    // 2A6: DRW V0, V1, 4
+    c.run_opcode(0x2A6, 0xD014)?;
+    if c.pc() != 0x2A8 {
+        return Ok(1);
+    }
+    // 2A8: ADD V3, 0xFF
+    c.set_v(3, c.v(3).wrapping_add(0xFF));
+    // 2AA: SNE V3, 0xFF
+    c.run_opcode(0x2AA, 0x43FF)?;
+    Ok(3)
+}
+
+fn block_2ac(c: &mut Chip8) -> Result<usize> {
+    // 2AC: LD V3, 0x03
+    c.set_v(3, 0x03);
+    c.set_pc(0x2AE);
+    Ok(1)
+}
+
+fn block_2ae(c: &mut Chip8) -> Result<usize> {
+    // 2AE: CALL 0x25C
+    c.run_opcode(0x2AE, 0x225C)?;
+    Ok(1)
+}
+
+fn block_2b0(c: &mut Chip8) -> Result<usize> {
+    // 2B0: CALL 0x334
+    c.run_opcode(0x2B0, 0x2334)?;
+    Ok(1)
+}
+
+fn block_2b2(c: &mut Chip8) -> Result<usize> {
+    // 2B2: RET
+    c.run_opcode(0x2B2, 0x00EE)?;
+    Ok(1)
+}
+
+fn block_2b6(c: &mut Chip8) -> Result<usize> {
+    // 2B6: LD V7, 0x05
+    c.set_v(7, 0x05);
+    // 2B8: LD V8, 0x06
+    c.set_v(8, 0x06);
+    // 2BA: LD V9, 0x04
+    c.set_v(9, 0x04);
+    // 2BC: LD V1, 0x1F
+    c.set_v(1, 0x1F);
+    // 2BE: LD V5, 0x10
+    c.set_v(5, 0x10);
+    // 2C0: LD V2, 0x07
+    c.set_v(2, 0x07);
+    // 2C2: RET
+    c.run_opcode(0x2C2, 0x00EE)?;
+    Ok(7)
+}
+
+fn block_334(c: &mut Chip8) -> Result<usize> {
+    // 334: DRW V0, V1, 4
+    c.run_opcode(0x334, 0xD014)?;
+    if c.pc() != 0x336 {
+        return Ok(1);
+    }
+    // 336: LD V6, 0x35
+    c.set_v(6, 0x35);
+    c.set_pc(0x338);
+    Ok(2)
+}
+
+fn block_338(c: &mut Chip8) -> Result<usize> {
+    // 338: ADD V6, 0xFF
+    c.set_v(6, c.v(6).wrapping_add(0xFF));
+    // 33A: SE V6, 0x00
+    c.run_opcode(0x33A, 0x3600)?;
+    Ok(2)
+}
+
+fn block_33c(c: &mut Chip8) -> Result<usize> {
+    // 33C: JP 0x338
+    c.set_pc(0x338);
+    Ok(1)
+}
+
+fn block_33e(c: &mut Chip8) -> Result<usize> {
+    // 33E: RET
+    c.run_opcode(0x33E, 0x00EE)?;
+    Ok(1)
+}
+
+fn block_340(c: &mut Chip8) -> Result<usize> {
+    // 340: LD I, 0x2B4
+    c.set_i(0x2B4);
+    // 342: LD VC, V1
+    c.set_v(12, c.v(1));
+    // 344: SE VC, 0x1E
+    c.run_opcode(0x344, 0x3C1E)?;
+    Ok(3)
+}
+
+fn block_346(c: &mut Chip8) -> Result<usize> {
+    // 346: ADD VC, 0x01
+    c.set_v(12, c.v(12).wrapping_add(0x01));
+    c.set_pc(0x348);
+    Ok(1)
+}
+
+fn block_348(c: &mut Chip8) -> Result<usize> {
+    // 348: SE VC, 0x1E
+    c.run_opcode(0x348, 0x3C1E)?;
+    Ok(1)
+}
+
+fn block_34a(c: &mut Chip8) -> Result<usize> {
+    // 34A: ADD VC, 0x01
+    c.set_v(12, c.v(12).wrapping_add(0x01));
+    c.set_pc(0x34C);
+    Ok(1)
+}
+
+fn block_34c(c: &mut Chip8) -> Result<usize> {
+    // 34C: SE VC, 0x1E
+    c.run_opcode(0x34C, 0x3C1E)?;
+    Ok(1)
+}
+
+fn block_34e(c: &mut Chip8) -> Result<usize> {
+    // 34E: ADD VC, 0x01
+    c.set_v(12, c.v(12).wrapping_add(0x01));
+    c.set_pc(0x350);
+    Ok(1)
+}
+
+fn block_350(c: &mut Chip8) -> Result<usize> {
+    // 350: CALL 0x35E
+    c.run_opcode(0x350, 0x235E)?;
+    Ok(1)
+}
+
+fn block_352(c: &mut Chip8) -> Result<usize> {
+    // 352: SNE VB, 0x0A
+    c.run_opcode(0x352, 0x4B0A)?;
+    Ok(1)
+}
+
+fn block_354(c: &mut Chip8) -> Result<usize> {
+    // 354: CALL 0x372
+    c.run_opcode(0x354, 0x2372)?;
+    Ok(1)
+}
+
+fn block_356(c: &mut Chip8) -> Result<usize> {
+    // 356: SNE V1, VC
+    c.run_opcode(0x356, 0x91C0)?;
+    Ok(1)
+}
+
+fn block_358(c: &mut Chip8) -> Result<usize> {
+    // 358: RET
+    c.run_opcode(0x358, 0x00EE)?;
+    Ok(1)
+}
+
+fn block_35a(c: &mut Chip8) -> Result<usize> {
+    // 35A: ADD V1, 0x01
+    c.set_v(1, c.v(1).wrapping_add(0x01));
+    // 35C: JP 0x350
+    c.set_pc(0x350);
+    Ok(2)
+}
+
+fn block_35e(c: &mut Chip8) -> Result<usize> {
+    // 35E: LD V0, 0x1B
+    c.set_v(0, 0x1B);
+    // 360: LD VB, 0x00
+    c.set_v(11, 0x00);
+    c.set_pc(0x362);
+    Ok(2)
+}
+
+fn block_362(c: &mut Chip8) -> Result<usize> {
+    // 362: DRW V0, V1, 1
+    c.run_opcode(0x362, 0xD011)?;
+    if c.pc() != 0x364 {
+        return Ok(1);
+    }
+    // 364: SE VF, 0x00
+    c.run_opcode(0x364, 0x3F00)?;
+    Ok(2)
+}
+
+fn block_366(c: &mut Chip8) -> Result<usize> {
+    // 366: ADD VB, 0x01
+    c.set_v(11, c.v(11).wrapping_add(0x01));
+    c.set_pc(0x368);
+    Ok(1)
+}
+
+fn block_368(c: &mut Chip8) -> Result<usize> {
+    // 368: DRW V0, V1, 1
+    c.run_opcode(0x368, 0xD011)?;
+    if c.pc() != 0x36A {
+        return Ok(1);
+    }
+    // 36A: ADD V0, 0x01
+    c.set_v(0, c.v(0).wrapping_add(0x01));
+    // 36C: SE V0, 0x25
+    c.run_opcode(0x36C, 0x3025)?;
+    Ok(3)
+}
+
+fn block_36e(c: &mut Chip8) -> Result<usize> {
+    // 36E: JP 0x362
+    c.set_pc(0x362);
+    Ok(1)
+}
+
+fn block_370(c: &mut Chip8) -> Result<usize> {
+    // 370: RET
+    c.run_opcode(0x370, 0x00EE)?;
+    Ok(1)
+}
+
+fn block_372(c: &mut Chip8) -> Result<usize> {
+    // 372: LD V0, 0x1B
+    c.set_v(0, 0x1B);
+    c.set_pc(0x374);
+    Ok(1)
+}
+
+fn block_374(c: &mut Chip8) -> Result<usize> {
+    // 374: DRW V0, V1, 1
+    c.run_opcode(0x374, 0xD011)?;
+    if c.pc() != 0x376 {
+        return Ok(1);
+    }
+    // 376: ADD V0, 0x01
+    c.set_v(0, c.v(0).wrapping_add(0x01));
+    // 378: SE V0, 0x25
+    c.run_opcode(0x378, 0x3025)?;
+    Ok(3)
+}
+
+fn block_37a(c: &mut Chip8) -> Result<usize> {
+    // 37A: JP 0x374
+    c.set_pc(0x374);
+    Ok(1)
+}
+
+fn block_37c(c: &mut Chip8) -> Result<usize> {
+    // 37C: LD VE, V1
+    c.set_v(14, c.v(1));
+    // 37E: LD VD, VE
+    c.set_v(13, c.v(14));
+    // 380: ADD VE, 0xFF
+    c.set_v(14, c.v(14).wrapping_add(0xFF));
+    c.set_pc(0x382);
+    Ok(3)
+}
+
+fn block_382(c: &mut Chip8) -> Result<usize> {
+    // 382: LD V0, 0x1B
+    c.set_v(0, 0x1B);
+    // 384: LD VB, 0x00
+    c.set_v(11, 0x00);
+    c.set_pc(0x386);
+    Ok(2)
+}
+
+fn block_386(c: &mut Chip8) -> Result<usize> {
+    // 386: DRW V0, VE, 1
+    c.run_opcode(0x386, 0xD0E1)?;
+    if c.pc() != 0x388 {
+        return Ok(1);
+    }
+    // 388: SE VF, 0x00
+    c.run_opcode(0x388, 0x3F00)?;
+    Ok(2)
+}
+
+fn block_38a(c: &mut Chip8) -> Result<usize> {
+    // 38A: JP 0x390
+    c.set_pc(0x390);
+    Ok(1)
+}
+
+fn block_38c(c: &mut Chip8) -> Result<usize> {
+    // 38C: DRW V0, VE, 1
+    c.run_opcode(0x38C, 0xD0E1)?;
+    if c.pc() != 0x38E {
+        return Ok(1);
+    }
+    // 38E: JP 0x394
+    c.set_pc(0x394);
+    Ok(2)
+}
+
+fn block_390(c: &mut Chip8) -> Result<usize> {
+    // 390: DRW V0, VD, 1
+    c.run_opcode(0x390, 0xD0D1)?;
+    if c.pc() != 0x392 {
+        return Ok(1);
+    }
+    // 392: ADD VB, 0x01
+    c.set_v(11, c.v(11).wrapping_add(0x01));
+    c.set_pc(0x394);
+    Ok(2)
+}
+
+fn block_394(c: &mut Chip8) -> Result<usize> {
+    // 394: ADD V0, 0x01
+    c.set_v(0, c.v(0).wrapping_add(0x01));
+    // 396: SE V0, 0x25
+    c.run_opcode(0x396, 0x3025)?;
+    Ok(2)
+}
+
+fn block_398(c: &mut Chip8) -> Result<usize> {
+    // 398: JP 0x386
+    c.set_pc(0x386);
+    Ok(1)
+}
+
+fn block_39a(c: &mut Chip8) -> Result<usize> {
+    // 39A: SNE VB, 0x00
+    c.run_opcode(0x39A, 0x4B00)?;
+    Ok(1)
+}
+
+fn block_39c(c: &mut Chip8) -> Result<usize> {
+    // 39C: JP 0x3A6
+    c.set_pc(0x3A6);
+    Ok(1)
+}
+
+fn block_39e(c: &mut Chip8) -> Result<usize> {
+    // 39E: ADD VD, 0xFF
+    c.set_v(13, c.v(13).wrapping_add(0xFF));
+    // 3A0: ADD VE, 0xFF
+    c.set_v(14, c.v(14).wrapping_add(0xFF));
+    // 3A2: SE VD, 0x01
+    c.run_opcode(0x3A2, 0x3D01)?;
+    Ok(3)
+}
+
+fn block_3a4(c: &mut Chip8) -> Result<usize> {
+    // 3A4: JP 0x382
+    c.set_pc(0x382);
+    Ok(1)
+}
+
+fn block_3a6(c: &mut Chip8) -> Result<usize> {
+    // 3A6: CALL 0x3C0
+    c.run_opcode(0x3A6, 0x23C0)?;
+    Ok(1)
+}
+
+fn block_3a8(c: &mut Chip8) -> Result<usize> {
+    // 3A8: SE VF, 0x01
+    c.run_opcode(0x3A8, 0x3F01)?;
+    Ok(1)
+}
+
+fn block_3aa(c: &mut Chip8) -> Result<usize> {
+    // 3AA: CALL 0x3C0
+    c.run_opcode(0x3AA, 0x23C0)?;
+    Ok(1)
+}
+
+fn block_3ac(c: &mut Chip8) -> Result<usize> {
+    // 3AC: ADD VA, 0x01
+    c.set_v(10, c.v(10).wrapping_add(0x01));
+    // 3AE: CALL 0x3C0
+    c.run_opcode(0x3AE, 0x23C0)?;
+    Ok(2)
+}
+
+fn block_3b0(c: &mut Chip8) -> Result<usize> {
+    // 3B0: LD V0, VA
+    c.set_v(0, c.v(10));
+    // 3B2: LD VD, 0x07
+    c.set_v(13, 0x07);
+    // 3B4: AND V0, VD
+    c.run_opcode(0x3B4, 0x80D2)?;
+    if c.pc() != 0x3B6 {
+        return Ok(3);
+    }
+    // 3B6: SNE V0, 0x04
+    c.run_opcode(0x3B6, 0x4004)?;
+    Ok(4)
+}
+
+fn block_3b8(c: &mut Chip8) -> Result<usize> {
+    // 3B8: ADD V5, 0xFE
+    c.set_v(5, c.v(5).wrapping_add(0xFE));
+    c.set_pc(0x3BA);
+    Ok(1)
+}
+
+fn block_3ba(c: &mut Chip8) -> Result<usize> {
+    // 3BA: SNE V5, 0x02
+    c.run_opcode(0x3BA, 0x4502)?;
+    Ok(1)
+}
+
+fn block_3bc(c: &mut Chip8) -> Result<usize> {
+    // 3BC: LD V5, 0x04
+    c.set_v(5, 0x04);
+    c.set_pc(0x3BE);
+    Ok(1)
+}
+
+fn block_3be(c: &mut Chip8) -> Result<usize> {
+    // 3BE: RET
+    c.run_opcode(0x3BE, 0x00EE)?;
+    Ok(1)
+}
+
+fn block_3c0(c: &mut Chip8) -> Result<usize> {
+    // 3C0: LD I, 0x700
+    c.set_i(0x700);
+    // 3C2: LD [I], V2
+    c.run_opcode(0x3C2, 0xF255)?;
+    if c.pc() != 0x3C4 {
+        return Ok(2);
+    }
+    // 3C4: LD I, 0x804
+    c.set_i(0x804);
+    // 3C6: LD B, VA
+    c.run_opcode(0x3C6, 0xFA33)?;
+    if c.pc() != 0x3C8 {
+        return Ok(4);
+    }
+    // 3C8: LD V2, [I]
+    c.run_opcode(0x3C8, 0xF265)?;
+    if c.pc() != 0x3CA {
+        return Ok(5);
+    }
+    // 3CA: LD F, V0
+    c.run_opcode(0x3CA, 0xF029)?;
+    if c.pc() != 0x3CC {
+        return Ok(6);
+    }
+    // 3CC: LD VD, 0x32
+    c.set_v(13, 0x32);
+    // 3CE: LD VE, 0x00
+    c.set_v(14, 0x00);
+    // 3D0: DRW VD, VE, 5
+    c.run_opcode(0x3D0, 0xDDE5)?;
+    if c.pc() != 0x3D2 {
+        return Ok(9);
+    }
+    // 3D2: ADD VD, 0x05
+    c.set_v(13, c.v(13).wrapping_add(0x05));
+    // 3D4: LD F, V1
+    c.run_opcode(0x3D4, 0xF129)?;
+    if c.pc() != 0x3D6 {
+        return Ok(11);
+    }
+    // 3D6: DRW VD, VE, 5
+    c.run_opcode(0x3D6, 0xDDE5)?;
+    if c.pc() != 0x3D8 {
+        return Ok(12);
+    }
+    // 3D8: ADD VD, 0x05
+    c.set_v(13, c.v(13).wrapping_add(0x05));
+    // 3DA: LD F, V2
+    c.run_opcode(0x3DA, 0xF229)?;
+    if c.pc() != 0x3DC {
+        return Ok(14);
+    }
+    // 3DC: DRW VD, VE, 5
+    c.run_opcode(0x3DC, 0xDDE5)?;
+    if c.pc() != 0x3DE {
+        return Ok(15);
+    }
+    // 3DE: LD I, 0x700
+    c.set_i(0x700);
+    // 3E0: LD V2, [I]
+    c.run_opcode(0x3E0, 0xF265)?;
+    if c.pc() != 0x3E2 {
+        return Ok(17);
+    }
+    // 3E2: LD I, 0x2B4
+    c.set_i(0x2B4);
+    // 3E4: RET
+    c.run_opcode(0x3E4, 0x00EE)?;
+    Ok(19)
+}
+
+fn block_3e6(c: &mut Chip8) -> Result<usize> {
+    // 3E6: LD VA, 0x00
+    c.set_v(10, 0x00);
+    // 3E8: LD V0, 0x19
+    c.set_v(0, 0x19);
+    // 3EA: RET
+    c.run_opcode(0x3EA, 0x00EE)?;
+    Ok(3)
+}
@@ -0,0 +1,63 @@
+// What the whole-ROM tests share: the bundled ROMs, machines to run them on and the keys pressed each frame.
+// Not every test uses all of it
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::PathBuf;
+
+use chip8::Chip8;
+
+pub const FRAMES: usize = 600;
+pub const SEED: u64 = 0x5EED;
+// How many frames each set of held keys lasts
+pub const KEY_HOLD: usize = 8;
+
+// Keys held on a frame, as a bit per key. A fixed LCG so every run presses the same keys, with most
+// frames having nothing or a single key held as a player would
+pub fn script(frame: usize) -> u16 {
+    let mut seed = (frame / KEY_HOLD) as u32;
+    seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+    let roll = (seed >> 16) & 0xFF;
+    match roll % 4 {
+        0 => 0,
+        1 | 2 => 1 << (roll >> 4),
+        _ => (seed >> 8) as u16,
+    }
+}
+
+pub fn press(chip8: &mut Chip8, keys: u16) {
+    for key in 0..16 {
+        chip8.keypress(key, keys & 1 << key != 0);
+    }
+}
+
+fn roms_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../roms")
+}
+
+// Every ROM in roms/ with its file name, in name order
+pub fn roms() -> Vec<(String, Vec<u8>)> {
+    let dir = roms_dir();
+    let mut roms: Vec<PathBuf> = fs::read_dir(&dir)
+        .unwrap_or_else(|err| panic!("couldn't list {}: {}", dir.display(), err))
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.is_file())
+        .collect();
+    roms.sort();
+    let name = |path: &PathBuf| path.file_name().unwrap().to_string_lossy().into_owned();
+    roms.iter().map(|path| (name(path), fs::read(path).unwrap())).collect()
+}
+
+// The ROM in roms/ called name
+pub fn rom(name: &str) -> Vec<u8> {
+    let path = roms_dir().join(name);
+    fs::read(&path).unwrap_or_else(|err| panic!("couldn't read {}: {}", path.display(), err))
+}
+
+// A machine with rom loaded and CXNN's generator seeded with SEED
+pub fn machine(rom: &[u8]) -> Chip8 {
+    let mut chip8 = Chip8::init();
+    chip8.seed_rng(SEED);
+    chip8.load(rom).unwrap();
+    chip8
+}
@@ -0,0 +1,88 @@
+// The determinism contract Movie documents: every ROM in roms/, run twice from the same seed with the same
+// scripted keys, goes through the same states frame for frame, and a movie recorded from one run passes
+// verify_replay() on a fresh machine while any change to it is caught
+#![cfg(feature = "alloc")]
+
+mod common;
+
+use chip8::{Movie, ReplayError};
+use common::{machine, roms, script, FRAMES, SEED};
+
+// The script recorded from power on, up to FRAMES frames or the first fault
+fn record(rom: &[u8], seed: u64) -> Movie {
+    let mut chip8 = machine(rom);
+    let mut movie = Movie::new(chip8.rom_hash().unwrap(), seed);
+    assert!(movie.start(&mut chip8));
+    for frame in 0..FRAMES {
+        if movie.record_frame(&mut chip8, script(frame)).is_err() {
+            break;
+        }
+    }
+    movie
+}
+
+#[test]
+fn same_seed_and_keys_give_same_states() {
+    for (name, rom) in roms() {
+        let first = record(&rom, SEED);
+        let second = record(&rom, SEED);
+        assert_eq!(first, second, "{}", name);
+        // The decode cache is only a shortcut, the states it goes through are the same
+        let mut cached = machine(&rom);
+        cached.enable_decode_cache(true);
+        assert_eq!(cached.verify_replay(&first), Ok(first.len()), "{} with the decode cache", name);
+    }
+}
+
+#[test]
+fn recorded_movies_verify() {
+    for (name, rom) in roms() {
+        let movie = record(&rom, SEED);
+        assert_eq!(machine(&rom).verify_replay(&movie), Ok(movie.len()), "{}", name);
+        let reparsed = Movie::parse(&movie.to_string()).unwrap();
+        assert_eq!(reparsed, movie, "{} after saving", name);
+    }
+}
+
+#[test]
+fn changed_digest_is_a_desync() {
+    let (_, rom) = &roms()[0];
+    let movie = record(rom, SEED);
+    // A frame whose state hasn't come up before, so the digest changed is first seen there
+    let frame = (100..movie.len()).find(|&frame| (0..frame).all(|earlier| movie.digest(earlier) != movie.digest(frame))).unwrap();
+    let expected = movie.digest(frame).unwrap();
+    let text = movie.to_string().replace(&format!("{:016x}", expected), &format!("{:016x}", !expected));
+    let tampered = Movie::parse(&text).unwrap();
+    let desync = ReplayError::Desync { frame, expected: !expected, actual: expected };
+    assert_eq!(machine(rom).verify_replay(&tampered), Err(desync));
+}
+
+#[test]
+fn changed_seed_is_a_desync() {
+    for (name, rom) in roms() {
+        let movie = record(&rom, SEED);
+        let text = movie.to_string().replace(&format!("seed {:x}", SEED), &format!("seed {:x}", SEED + 1));
+        let reseeded = Movie::parse(&text).unwrap();
+        // The digest takes in CXNN's generator, so even a ROM that never rolls a number notices straight away
+        let replay = machine(&rom).verify_replay(&reseeded);
+        assert!(matches!(replay, Err(ReplayError::Desync { frame: 0, .. })), "{}: {:?}", name, replay);
+    }
+}
+
+#[test]
+fn other_rom_is_refused() {
+    let roms = roms();
+    let movie = record(&roms[0].1, SEED);
+    assert_eq!(machine(&roms[1].1).verify_replay(&movie), Err(ReplayError::WrongRom));
+}
+
+#[test]
+fn splicing_drops_digests_after_the_cut() {
+    let (_, rom) = &roms()[0];
+    let mut movie = record(rom, SEED);
+    let segment = movie.segment(0..10);
+    movie.splice(200..210, &segment);
+    assert!(movie.digest(199).is_some());
+    assert!((200..movie.len()).all(|frame| movie.digest(frame).is_none()));
+    assert_eq!(machine(rom).verify_replay(&movie), Ok(200));
+}
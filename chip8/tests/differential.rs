@@ -0,0 +1,152 @@
+// Runs every ROM in roms/ through the core and the reference interpreter in tests/reference side by side,
+// with the same scripted key presses, and compares the whole machine after every instruction. The first
+// difference fails the test with the instruction that caused it
+#![cfg(feature = "alloc")]
+
+mod common;
+mod reference;
+
+use std::fmt;
+
+use chip8::{Chip8, Instruction, PcPolicy, Variant, START_ADDRESS};
+use proptest::prelude::*;
+use common::{roms, script, FRAMES};
+use reference::{Reference, Step};
+
+fn check<T: PartialEq + fmt::Debug>(diffs: &mut Vec<String>, what: &str, core: T, reference: T) {
+    if core != reference {
+        diffs.push(format!("{}: core {:X?} reference {:X?}", what, core, reference));
+    }
+}
+
+// Every difference between the two machines, empty when they agree. Unpacking the display is slow, so it's
+// only compared when asked
+fn compare(core: &Chip8, reference: &Reference, display: bool) -> Vec<String> {
+    let mut diffs = Vec::new();
+    check(&mut diffs, "PC", core.pc(), reference.pc);
+    check(&mut diffs, "I", core.i(), reference.i);
+    let v: [u8; 16] = std::array::from_fn(|x| core.v(x));
+    check(&mut diffs, "V", v, reference.v);
+    check(&mut diffs, "stack", core.stack(), reference.stack.as_slice());
+    check(&mut diffs, "DT", core.delay_timer(), reference.delay);
+    check(&mut diffs, "ST", core.sound_timer(), reference.sound);
+    check(&mut diffs, "hires", core.is_hires(), reference.hires);
+    check(&mut diffs, "halted", core.is_halted(), reference.halted);
+    if display && core.get_display() != reference.visible_display() {
+        diffs.push("display differs".to_string());
+    }
+    if core.ram() != reference.ram.as_slice() {
+        let addr = (0..reference::RAM_SIZE).find(|addr| core.ram()[*addr] != reference.ram[*addr]).unwrap();
+        diffs.push(format!("RAM at {:#05X}: core {:02X} reference {:02X}", addr, core.ram()[addr], reference.ram[addr]));
+    }
+    diffs
+}
+
+// Run both interpreters in lockstep for up to frames frames, or until both fault or halt
+fn run_program(name: &str, rom: &[u8], variant: Variant, frames: usize) -> Result<(), String> {
+    let mut core = Chip8::init();
+    core.set_variant(variant);
+    // The reference faults when the PC leaves program memory
+    core.set_pc_policy(PcPolicy::Fault);
+    core.load(rom).map_err(|err| format!("{}: {}", name, err))?;
+    let mut reference = Reference::new(core.ram(), START_ADDRESS, variant == Variant::SuperChip);
+    let mut count = 0;
+
+    for frame in 0..frames {
+        let keys = script(frame);
+        for key in 0..16 {
+            let pressed = keys & 1 << key != 0;
+            core.keypress(key, pressed);
+            reference.keys[key] = pressed;
+        }
+        for _ in 0..core.ticks_per_frame() {
+            let pc = core.pc();
+            let opcode = core.ram().get(pc as usize..pc as usize + 2).map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]));
+            let index = count;
+            count += 1;
+            let context = || {
+                let (opcode, text) = match opcode {
+                    Some(opcode) => (format!("{:04X}", opcode), Instruction::decode(opcode).map_or("?".to_string(), |i| i.to_string())),
+                    None => ("????".to_string(), "?".to_string()),
+                };
+                format!("{} frame {} instruction {} at {:#05X} ({} {})", name, frame, index, pc, opcode, text)
+            };
+
+            let result = core.clock();
+            match reference.step() {
+                Step::Ok => (),
+                Step::Random { x, mask } => {
+                    if core.v(x) & !mask != 0 {
+                        return Err(format!("{}: random V{:X}={:02X} not masked by {:02X}", context(), x, core.v(x), mask));
+                    }
+                    reference.v[x] = core.v(x);
+                },
+                Step::Fault(reason) => {
+                    return match result {
+                        // Both gave up on the program, which is as far as it can be followed
+                        Err(_) => Ok(()),
+                        Ok(_) => Err(format!("{}: reference faulted ({}) but the core didn't", context(), reason)),
+                    };
+                },
+            }
+            if let Err(err) = result {
+                return Err(format!("{}: core faulted ({}) but the reference didn't", context(), err));
+            }
+            let diffs = compare(&core, &reference, opcode.is_some_and(draws));
+            if !diffs.is_empty() {
+                return Err(format!("{}:\n  {}", context(), diffs.join("\n  ")));
+            }
+            if reference.halted {
+                return Ok(());
+            }
+        }
+        core.clock_timers();
+        reference.tick_timers();
+        let diffs = compare(&core, &reference, true);
+        if !diffs.is_empty() {
+            return Err(format!("{} end of frame {}:\n  {}", name, frame, diffs.join("\n  ")));
+        }
+    }
+    Ok(())
+}
+
+// Whether an opcode changes the display, i.e. DXYN, 00E0, the scrolls and the resolution switches
+fn draws(opcode: u16) -> bool {
+    opcode & 0xF000 == 0xD000 || opcode & 0xFFF0 == 0x00C0 || matches!(opcode, 0x00E0 | 0x00FB..=0x00FF)
+}
+
+#[test]
+fn core_matches_reference() {
+    let roms = roms();
+    assert!(!roms.is_empty(), "no ROMs found");
+    let failures: Vec<String> = roms.iter().filter_map(|(name, rom)| run_program(name, rom, Variant::Chip8, FRAMES).err()).collect();
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+// Opcodes weighted towards drawing, scrolling and switching resolution, with enough register loads and
+// jumps around them to put sprites all over the screen, edges included
+fn schip_opcode() -> impl Strategy<Value = u16> {
+    let x = || 0..16u16;
+    prop_oneof![
+        3 => (x(), x(), 0..16u16).prop_map(|(x, y, n)| 0xD000 | x << 8 | y << 4 | n),
+        3 => (x(), any::<u8>()).prop_map(|(x, nn)| 0x6000 | x << 8 | nn as u16),
+        1 => (0..0x40u16).prop_map(|addr| 0xA000 | (addr * 5)),
+        1 => (0..16u16).prop_map(|n| 0x00C0 | n),
+        1 => prop::sample::select(vec![0x00E0u16, 0x00FB, 0x00FC, 0x00FE, 0x00FF]),
+        1 => x().prop_map(|x| 0xF029 | x << 8),
+        1 => x().prop_map(|x| 0xF030 | x << 8),
+        1 => any::<u16>(),
+    ]
+}
+
+proptest! {
+    // Random SCHIP programs, which the bundled ROMs don't cover, looping back to the start when they run out
+    #[test]
+    fn random_schip_programs_match_reference(opcodes in prop::collection::vec(schip_opcode(), 1..128)) {
+        let mut rom: Vec<u8> = opcodes.iter().flat_map(|opcode| opcode.to_be_bytes()).collect();
+        rom.extend_from_slice(&(0x1000 | START_ADDRESS).to_be_bytes());
+        if let Err(diff) = run_program("random", &rom, Variant::SuperChip, 20) {
+            prop_assert!(false, "{}", diff);
+        }
+    }
+}
@@ -0,0 +1,122 @@
+// Breakpoint conditions as the debugger and the DAP server's evaluate take them: the precedence table, the
+// logical operators, reads off the end of the registers and RAM, and where parse errors point
+#![cfg(feature = "alloc")]
+
+use chip8::{Chip8, Condition, ConditionError};
+
+fn machine() -> Chip8 {
+    let mut chip8 = Chip8::init();
+    chip8.load(&[0xA2, 0x2A, 0x60, 0x0C]).unwrap();
+    for x in 0..16 {
+        chip8.set_v(x, x as u8 * 3);
+    }
+    chip8.set_i(0x22A);
+    chip8
+}
+
+fn eval(source: &str) -> i64 {
+    Condition::parse(source).unwrap_or_else(|err| panic!("{}: {}", source, err)).eval(&machine())
+}
+
+fn error(source: &str) -> ConditionError {
+    Condition::parse(source).expect_err(source)
+}
+
+#[test]
+fn precedence() {
+    let cases = [
+        ("1 + 2 == 3", 1),
+        ("2 < 3 == 1", 1),
+        // Comparisons bind tighter than the bitwise operators, as in C
+        ("1 == 1 & 0", 0),
+        ("1 | 2 ^ 3 & 1", 3),
+        ("1 || 0 && 0", 1),
+        ("10 - 3 - 2", 5),
+        ("-(1 + 2) + 5", 2),
+        ("!0 + 1", 2),
+        ("!(1 == 2) && -1", 1),
+    ];
+    for (source, expected) in cases {
+        assert_eq!(eval(source), expected, "{}", source);
+    }
+}
+
+#[test]
+fn logical_operators() {
+    let cases = [
+        // Anything nonzero is true, and the answer is always 0 or 1
+        ("5 && 7", 1),
+        ("0 || 9", 1),
+        ("0 && 5", 0),
+        ("0 || 0", 0),
+        // The left side decides where it can, whatever the right reads
+        ("1 || ram[0x10000]", 1),
+        ("0 && v[100] == 0", 0),
+    ];
+    for (source, expected) in cases {
+        assert_eq!(eval(source), expected, "{}", source);
+    }
+}
+
+#[test]
+fn values() {
+    let cases = [
+        ("pc", 0x200),
+        ("PC == 0x200", 1),
+        ("i", 0x22A),
+        ("sp", 0),
+        ("v3", 9),
+        ("VF", 45),
+        ("v[1 + 2]", 9),
+        ("v[v1]", 9),
+        ("ram[0x200]", 0xA2),
+        ("ram[pc + 3]", 0x0C),
+        // Out of range reads give 0
+        ("v[16]", 0),
+        ("v[-1]", 0),
+        ("ram[0x1000]", 0),
+        ("ram[-1]", 0),
+    ];
+    for (source, expected) in cases {
+        assert_eq!(eval(source), expected, "{}", source);
+    }
+}
+
+#[test]
+fn error_positions() {
+    let cases = [
+        ("pc = 1", 3, "'=' should be '=='"),
+        ("pc ==", 5, "unexpected end of condition"),
+        ("(pc == 1", 8, "expected ')'"),
+        ("v[1", 3, "expected ']'"),
+        ("ram 1", 4, "expected '['"),
+        ("foo > 1", 0, "unknown name 'foo'"),
+        ("1 2", 2, "unexpected number 2"),
+        ("pc == )", 6, "unexpected ')'"),
+        ("0x1G", 0, "bad number '0x1G'"),
+        ("pc @ 1", 3, "unexpected '@'"),
+    ];
+    for (source, position, message) in cases {
+        assert_eq!(error(source), ConditionError { position, message: message.to_string() }, "{}", source);
+    }
+    assert_eq!(error("foo").to_string(), "unknown name 'foo' at column 1");
+}
+
+#[test]
+fn source_is_kept() {
+    let condition = Condition::parse("pc == 0x32A && v[3] > 10").unwrap();
+    assert_eq!(condition.source(), "pc == 0x32A && v[3] > 10");
+    assert_eq!(condition.to_string(), condition.source());
+}
+
+#[test]
+fn nesting_is_limited() {
+    // Deep enough to overflow the stack if parsing recursed all the way
+    for source in ["(".repeat(200_000), "!".repeat(200_000), "v[".repeat(200_000), "1 + ".repeat(200_000) + "1"] {
+        assert_eq!(error(&source).message, "condition nested too deeply", "{}", &source[..8]);
+    }
+    // Realistic conditions are nowhere near the limit
+    assert_eq!(eval(&format!("{}1{}", "(".repeat(32), ")".repeat(32))), 1);
+    assert_eq!(eval(&["1"; 32].join(" + ")), 32);
+    assert_eq!(eval(&format!("{}1", "-".repeat(32))), 1);
+}
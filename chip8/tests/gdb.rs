@@ -0,0 +1,55 @@
+// GdbServer over a real TCP connection: packets are acked or refused by their checksum, and ones it can't take
+// get an error reply instead of stopping the stub
+#![cfg(feature = "std")]
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+
+use chip8::{Chip8, Debugger, GdbServer};
+
+// A stub serving one client on a free port, and the client's end
+fn connect() -> (TcpStream, thread::JoinHandle<()>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut chip8 = Chip8::init();
+        chip8.load(&[0x12, 0x00]).unwrap();
+        GdbServer::new(Debugger::new(chip8)).serve(stream).unwrap();
+    });
+    (TcpStream::connect(addr).unwrap(), server)
+}
+
+// payload framed with its checksum
+fn packet(payload: &[u8]) -> Vec<u8> {
+    let sum = payload.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+    let mut packet = vec![b'$'];
+    packet.extend_from_slice(payload);
+    packet.extend_from_slice(format!("#{:02x}", sum).as_bytes());
+    packet
+}
+
+// Send raw and read back exactly len bytes
+fn exchange(client: &mut TcpStream, raw: &[u8], len: usize) -> String {
+    client.write_all(raw).unwrap();
+    let mut reply = vec![0; len];
+    client.read_exact(&mut reply).unwrap();
+    String::from_utf8(reply).unwrap()
+}
+
+#[test]
+fn bad_packets_dont_stop_the_stub() {
+    let (mut client, server) = connect();
+    assert_eq!(exchange(&mut client, &packet(b"?"), 8), "+$S05#b8");
+    // A wrong checksum is refused for the client to send again
+    assert_eq!(exchange(&mut client, b"$?#00", 1), "-");
+    // Not UTF-8, not ASCII and too long are all errors
+    assert_eq!(exchange(&mut client, &packet(b"\xff"), 8), "+$E01#a6");
+    assert_eq!(exchange(&mut client, &packet("é".as_bytes()), 8), "+$E01#a6");
+    assert_eq!(exchange(&mut client, &packet(&vec![b'm'; 0x2000]), 8), "+$E01#a6");
+    // And it still answers afterwards
+    assert_eq!(exchange(&mut client, &packet(b"?"), 8), "+$S05#b8");
+    assert_eq!(exchange(&mut client, &packet(b"k"), 1), "+");
+    server.join().unwrap();
+}
@@ -0,0 +1,21 @@
+// GifRecorder refuses a scale whose frames won't fit a GIF's 16-bit dimensions rather than writing wrapped ones
+#![cfg(feature = "gif")]
+
+use std::io;
+
+use chip8::GifRecorder;
+
+#[test]
+fn scales_too_large_for_a_gif_are_refused() {
+    // 511 times the hi-res screen's 128 pixels is the widest a GIF can be
+    let mut recorder = GifRecorder::new(511);
+    recorder.start(io::sink()).unwrap();
+    recorder.stop().unwrap();
+
+    for scale in [512, usize::MAX] {
+        let mut recorder = GifRecorder::new(scale);
+        let err = recorder.start(io::sink()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(!recorder.is_recording());
+    }
+}
@@ -0,0 +1,146 @@
+// Property tests over the instruction set: each case sets up a random machine, runs one opcode through
+// clock() and checks what must hold whatever the registers were
+#![cfg(feature = "alloc")]
+
+use chip8::{Chip8, Chip8Error, Instruction, PcPolicy, UnknownOpcodePolicy, Variant, START_ADDRESS};
+use proptest::prelude::*;
+
+// Registers, I, timers and keys to start an opcode from
+#[derive(Clone, Debug)]
+struct Machine {
+    v: [u8; 16],
+    i: u16,
+    delay: u8,
+    sound: u8,
+    // Bit k set when key k is held
+    keys: u16,
+}
+
+fn machine() -> impl Strategy<Value = Machine> {
+    (prop::array::uniform16(any::<u8>()), 0..0x1000u16, any::<u8>(), any::<u8>(), any::<u16>())
+        .prop_map(|(v, i, delay, sound, keys)| Machine { v, i, delay, sound, keys })
+}
+
+// Any 16 bits half the time, otherwise a top nibble and register with one of the low bytes the 0, E and F
+// instructions use, so those get exercised as often as the rest
+fn opcode() -> impl Strategy<Value = u16> {
+    let low_bytes = vec![
+        0xC4, 0xE0, 0xEE, 0xFB, 0xFC, 0xFD, 0xFE, 0xFF, 0x9E, 0xA1, 0x07, 0x0A, 0x15, 0x18, 0x1E, 0x29, 0x30,
+        0x33, 0x55, 0x65,
+    ];
+    prop_oneof![
+        any::<u16>(),
+        (prop::sample::select(vec![0x0u16, 0xE, 0xF]), 0..16u16, prop::sample::select(low_bytes))
+            .prop_map(|(op, x, low)| op << 12 | if op == 0 { 0 } else { x << 8 } | low),
+    ]
+}
+
+// A Chip8 set up as machine with program loaded at the start address
+fn boot(machine: &Machine, program: &[u8]) -> Chip8 {
+    let mut chip8 = Chip8::init();
+    chip8.load(program).unwrap();
+    for (x, value) in machine.v.iter().enumerate() {
+        chip8.set_v(x, *value);
+    }
+    chip8.set_i(machine.i);
+    chip8.set_delay_timer(machine.delay);
+    chip8.set_sound_timer(machine.sound);
+    for key in 0..16 {
+        chip8.keypress(key, machine.keys & 1 << key != 0);
+    }
+    chip8
+}
+
+fn run_opcode(machine: &Machine, opcode: u16) -> (Chip8, chip8::Result<chip8::State>) {
+    let mut chip8 = boot(machine, &opcode.to_be_bytes());
+    let result = chip8.clock();
+    (chip8, result)
+}
+
+proptest! {
+    #[test]
+    fn encode_inverts_decode(opcode in any::<u16>()) {
+        if let Some(instruction) = Instruction::decode(opcode) {
+            prop_assert_eq!(Instruction::decode(instruction.encode()), Some(instruction));
+        }
+    }
+
+    #[test]
+    fn add_carries_into_vf(machine in machine(), x in 0..15usize, y in 0..15usize) {
+        let (vx, vy) = (machine.v[x], machine.v[y]);
+        let (chip8, result) = run_opcode(&machine, 0x8004 | (x as u16) << 8 | (y as u16) << 4);
+        prop_assert!(result.is_ok());
+        prop_assert_eq!(chip8.v(x), vx.wrapping_add(vy));
+        prop_assert_eq!(chip8.v(0xF), (vx as u16 + vy as u16 > 0xFF) as u8);
+    }
+
+    #[test]
+    fn sub_sets_vf_when_there_is_no_borrow(machine in machine(), x in 0..15usize, y in 0..15usize) {
+        let (vx, vy) = (machine.v[x], machine.v[y]);
+        let (chip8, result) = run_opcode(&machine, 0x8005 | (x as u16) << 8 | (y as u16) << 4);
+        prop_assert!(result.is_ok());
+        prop_assert_eq!(chip8.v(x), vx.wrapping_sub(vy));
+        prop_assert_eq!(chip8.v(0xF), (vx >= vy) as u8);
+
+        let (chip8, result) = run_opcode(&machine, 0x8007 | (x as u16) << 8 | (y as u16) << 4);
+        prop_assert!(result.is_ok());
+        prop_assert_eq!(chip8.v(x), vy.wrapping_sub(vx));
+        prop_assert_eq!(chip8.v(0xF), (vy >= vx) as u8);
+    }
+
+    // After any opcode the PC has moved on by 2, skipped by 4, jumped where the instruction says, or been
+    // left where it was by FX0A waiting or a fault
+    #[test]
+    fn pc_advances_or_jumps(machine in machine(), opcode in opcode()) {
+        let (chip8, result) = run_opcode(&machine, opcode);
+        let start = START_ADDRESS;
+        if result.is_err() {
+            prop_assert_eq!(chip8.pc(), start);
+            return Ok(());
+        }
+        let pc = chip8.pc();
+        match Instruction::decode(opcode) {
+            Some(Instruction::Jmp(nnn)) | Some(Instruction::Call(nnn)) => prop_assert_eq!(pc, nnn),
+            Some(Instruction::JmpV0(nnn)) => prop_assert_eq!(pc, machine.v[0] as u16 + nnn),
+            Some(Instruction::WaitKey(_)) if machine.keys == 0 => prop_assert_eq!(pc, start),
+            Some(
+                Instruction::SkipEqImm(..)
+                | Instruction::SkipNeImm(..)
+                | Instruction::SkipEqReg(..)
+                | Instruction::SkipNeReg(..)
+                | Instruction::SkipKey(_)
+                | Instruction::SkipNotKey(_),
+            ) => prop_assert!(pc == start + 2 || pc == start + 4, "PC {:#05X}", pc),
+            Some(_) => prop_assert_eq!(pc, start + 2),
+            None => prop_assert!(false, "unknown opcode {:04X} didn't fault", opcode),
+        }
+    }
+
+    // The dispatch tables and decode() have to agree about which opcodes exist
+    #[test]
+    fn only_undecodable_opcodes_are_unknown(machine in machine(), opcode in opcode()) {
+        let (_, result) = run_opcode(&machine, opcode);
+        let unknown = matches!(result, Err(Chip8Error::UnknownOpcode { .. }));
+        prop_assert_eq!(unknown, Instruction::decode(opcode).is_none(), "opcode {:04X}", opcode);
+    }
+
+    // Whatever a program does it should only ever fault, never panic
+    #[test]
+    fn random_programs_never_panic(
+        machine in machine(),
+        opcodes in prop::collection::vec(opcode(), 1..256),
+        superchip in any::<bool>(),
+    ) {
+        let program: Vec<u8> = opcodes.iter().flat_map(|opcode| opcode.to_be_bytes()).collect();
+        let mut chip8 = boot(&machine, &program);
+        chip8.set_variant(if superchip { Variant::SuperChip } else { Variant::Chip8 });
+        chip8.set_pc_policy(PcPolicy::Wrap);
+        chip8.set_unknown_opcode_policy(UnknownOpcodePolicy::Skip);
+        for _ in 0..2000 {
+            if chip8.clock().is_err() {
+                // Faults leave the PC on the instruction, so step past it to keep exploring
+                chip8.set_pc(chip8.pc().wrapping_add(2));
+            }
+        }
+    }
+}
@@ -0,0 +1,151 @@
+// compile_octo() against bytes worked out by hand: the blocks it lowers to skips and jumps, the label table
+// and forward references it patches, and the errors it gives for programs it can't compile
+#![cfg(feature = "alloc")]
+
+use chip8::{compile_octo, OctoError};
+
+fn rom(source: &str) -> Vec<u8> {
+    compile_octo(source).unwrap_or_else(|err| panic!("{}", err)).rom
+}
+
+fn error(source: &str) -> OctoError {
+    compile_octo(source).expect_err("compiled")
+}
+
+#[test]
+fn loop_while_again() {
+    let source = "
+: main
+  v0 := 0
+  loop
+    v0 += 1
+    while v0 != 10
+  again
+  exit
+";
+    #[rustfmt::skip]
+    let expected = [
+        0x12, 0x02, // 200: jump main
+        0x60, 0x00, // 202: v0 := 0
+        0x70, 0x01, // 204: loop, v0 += 1
+        0x40, 0x0A, // 206: while v0 != 10 skips the jump out
+        0x12, 0x0C, // 208: patched by again to go past the loop
+        0x12, 0x04, // 20A: again
+        0x00, 0xFD, // 20C: exit
+    ];
+    assert_eq!(rom(source), expected);
+}
+
+#[test]
+fn if_then_and_begin_else_end() {
+    let source = "
+: main
+  if v1 == 2 then v2 := 3
+  if v1 key begin
+    v3 := 4
+  else
+    v3 := 5
+  end
+: done
+  jump done
+";
+    #[rustfmt::skip]
+    let expected = [
+        0x12, 0x02, // 200: jump main
+        0x41, 0x02, // 202: then skips the next statement when the test fails
+        0x62, 0x03, // 204: v2 := 3
+        0xE1, 0x9E, // 206: begin skips the jump over its block when the test holds
+        0x12, 0x0E, // 208: to else
+        0x63, 0x04, // 20A: v3 := 4
+        0x12, 0x10, // 20C: else jumps to end
+        0x63, 0x05, // 20E: v3 := 5
+        0x12, 0x10, // 210: done
+    ];
+    assert_eq!(rom(source), expected);
+}
+
+#[test]
+fn comparisons_go_through_vf() {
+    let expected = [0x12, 0x02, 0x6F, 0x05, 0x8F, 0x17, 0x4F, 0x00, 0x62, 0x00];
+    assert_eq!(rom(": main\n  if v1 < 5 then v2 := 0"), expected);
+}
+
+#[test]
+fn labels_and_forward_references() {
+    let source = "
+: main
+  i := sprite
+  :unpack 0xA sprite
+  draw
+  jump main
+: draw
+  :const X 8
+  :calc Y { X * 2 + 1 }
+  v0 := Y
+  ;
+: sprite
+  0x3C 0x42
+";
+    let program = compile_octo(source).unwrap();
+    #[rustfmt::skip]
+    let expected = [
+        0x12, 0x02,             // 200: jump main
+        0xA2, 0x10,             // 202: i := sprite, patched once sprite is defined
+        0x60, 0xA2, 0x61, 0x10, // 204: :unpack, the nibble above the address
+        0x22, 0x0C,             // 208: draw
+        0x12, 0x02,             // 20A: jump main
+        0x60, 0x18,             // 20C: :calc works right to left, so Y is 8 * (2 + 1)
+        0x00, 0xEE,             // 20E: ;
+        0x3C, 0x42,             // 210: sprite
+    ];
+    assert_eq!(program.rom, expected);
+    let labels: Vec<(&str, u16)> = program.labels.iter().map(|(name, addr)| (name.as_str(), *addr)).collect();
+    assert_eq!(labels, [("main", 0x202), ("draw", 0x20C), ("sprite", 0x210)]);
+}
+
+#[test]
+fn unbalanced_blocks() {
+    let cases = [
+        (": main\n  again", 2, "again without loop"),
+        (": main\n  loop\n  v0 += 1", 2, "loop without again"),
+        (": main\n  v0 += 1\n  while v0 == 1", 3, "while outside a loop"),
+        (": main\n  else", 2, "else without if ... begin"),
+        (": main\n  if v0 == 1 begin\n  return", 2, "begin without end"),
+        (": main\n  loop\n  end", 3, "end without if ... begin"),
+        // Errors inside a macro are reported where it's used
+        (":macro bad { again }\n: main\n  bad", 3, "again without loop"),
+    ];
+    for (source, line, message) in cases {
+        assert_eq!(error(source), OctoError { line, message: message.to_string() }, "{:?}", source);
+    }
+}
+
+#[test]
+fn unknown_names() {
+    let cases = [
+        (": main\n  jump nowhere", 2, "undefined name 'nowhere'"),
+        // A name on its own is a call
+        (": main\n\n  frobnicate", 3, "undefined name 'frobnicate'"),
+        (": main\n  v0 := LIMIT", 2, "undefined name 'LIMIT'"),
+        (": start\n  return", 1, "there's no main label to start from"),
+        (": main\n: main", 2, "'main' is already defined"),
+        (": main\n  :stringmode", 2, ":stringmode isn't supported"),
+    ];
+    for (source, line, message) in cases {
+        assert_eq!(error(source), OctoError { line, message: message.to_string() }, "{:?}", source);
+    }
+}
+
+#[test]
+fn runaway_programs_are_errors() {
+    let cases = [
+        (": main\n:calc a { 1 << 63 }\n:calc b { a / -1 }", 3, "division overflows in :calc"),
+        (": main\n:calc a { 1 << 63 }\n:calc b { a % -1 }", 3, "division overflows in :calc"),
+        (":macro m { m }\n: main\n  m", 3, "too many macro expansions, is a macro expanding itself?"),
+    ];
+    for (source, line, message) in cases {
+        assert_eq!(error(source), OctoError { line, message: message.to_string() }, "{:?}", source);
+    }
+    // Negating the smallest value wraps as the other operators do
+    assert!(compile_octo(": main\n:calc a { 1 << 63 }\n:calc b { - a }").is_ok());
+}
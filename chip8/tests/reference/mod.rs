@@ -0,0 +1,318 @@
+// A deliberately plain CHIP-8/SCHIP interpreter to check the real one against. It is written straight from
+// the opcode table with no sharing with the core, and aims to be obviously right rather than fast. Where
+// platforms disagree it follows the quirks the core implements:
+//   - 8XY6 and 8XYE shift VX in place and ignore VY
+//   - FX55 and FX65 leave I alone
+//   - BNNN jumps to NNN + V0
+//   - sprites wrap around the screen edges
+//   - EX9E and EXA1 only look at the low nibble of VX
+// CXNN can't be predicted, so the harness hands over whatever the core rolled
+
+pub const RAM_SIZE: usize = 4096;
+const FONT_ADDRESS: u16 = 0;
+const LARGE_FONT_ADDRESS: u16 = 80;
+
+pub struct Reference {
+    pub ram: Vec<u8>,
+    pub v: [u8; 16],
+    pub i: u16,
+    pub pc: u16,
+    pub stack: Vec<u16>,
+    pub delay: u8,
+    pub sound: u8,
+    pub keys: [bool; 16],
+    pub hires: bool,
+    // Always 128x64, lo-res uses the top left 64x32 packed row by row like the core
+    pub display: Vec<bool>,
+    pub halted: bool,
+    pub superchip: bool,
+    start: u16,
+}
+
+// What the reference made of an instruction
+pub enum Step {
+    Ok,
+    // The instruction was CXNN, the harness has to supply VX
+    Random { x: usize, mask: u8 },
+    // Out of bounds, bad stack use or an unknown opcode, the core should have faulted too
+    Fault(String),
+}
+
+impl Reference {
+    // Start from the same RAM as the core, fonts and ROM included
+    pub fn new(ram: &[u8], start: u16, superchip: bool) -> Self {
+        Reference {
+            ram: ram.to_vec(),
+            v: [0; 16],
+            i: 0,
+            pc: start,
+            stack: Vec::new(),
+            delay: 0,
+            sound: 0,
+            keys: [false; 16],
+            hires: false,
+            display: vec![false; 128 * 64],
+            halted: false,
+            superchip,
+            start,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        if self.hires { 128 } else { 64 }
+    }
+
+    pub fn height(&self) -> usize {
+        if self.hires { 64 } else { 32 }
+    }
+
+    pub fn visible_display(&self) -> &[bool] {
+        &self.display[..self.width() * self.height()]
+    }
+
+    pub fn tick_timers(&mut self) {
+        self.delay = self.delay.saturating_sub(1);
+        self.sound = self.sound.saturating_sub(1);
+    }
+
+    fn read(&self, addr: usize) -> Result<u8, String> {
+        self.ram.get(addr).copied().ok_or_else(|| format!("read of {:#X}", addr))
+    }
+
+    fn write(&mut self, addr: usize, value: u8) -> Result<(), String> {
+        match self.ram.get_mut(addr) {
+            Some(byte) => {
+                *byte = value;
+                Ok(())
+            },
+            None => Err(format!("write to {:#X}", addr)),
+        }
+    }
+
+    pub fn step(&mut self) -> Step {
+        if self.halted {
+            return Step::Ok;
+        }
+        let pc = self.pc;
+        match self.execute() {
+            Ok(step) => step,
+            Err(fault) => {
+                self.pc = pc;
+                Step::Fault(fault)
+            },
+        }
+    }
+
+    fn execute(&mut self) -> Result<Step, String> {
+        if self.pc < self.start || self.pc as usize >= RAM_SIZE - 1 {
+            return Err(format!("PC {:#X} outside the program", self.pc));
+        }
+        let opcode = (self.read(self.pc as usize)? as u16) << 8 | self.read(self.pc as usize + 1)? as u16;
+        self.pc += 2;
+
+        let x = ((opcode >> 8) & 0xF) as usize;
+        let y = ((opcode >> 4) & 0xF) as usize;
+        let n = (opcode & 0xF) as u8;
+        let nn = (opcode & 0xFF) as u8;
+        let nnn = opcode & 0xFFF;
+
+        match opcode >> 12 {
+            0x0 => match opcode {
+                0x0000 => {},
+                0x00E0 => self.clear(),
+                0x00EE => match self.stack.pop() {
+                    Some(addr) => self.pc = addr,
+                    None => return Err("return with an empty stack".to_string()),
+                },
+                0x00FB => self.scroll_horizontally(4, true),
+                0x00FC => self.scroll_horizontally(4, false),
+                0x00FD => self.halted = true,
+                0x00FE => {
+                    self.hires = false;
+                    self.clear();
+                },
+                0x00FF => {
+                    self.hires = true;
+                    self.clear();
+                },
+                _ if opcode & 0xFFF0 == 0x00C0 => self.scroll_down(n as usize),
+                _ => return Err(format!("unknown opcode {:04X}", opcode)),
+            },
+            0x1 => self.pc = nnn,
+            0x2 => {
+                if self.stack.len() == 16 {
+                    return Err("call with a full stack".to_string());
+                }
+                self.stack.push(self.pc);
+                self.pc = nnn;
+            },
+            0x3 => {
+                if self.v[x] == nn {
+                    self.pc += 2;
+                }
+            },
+            0x4 => {
+                if self.v[x] != nn {
+                    self.pc += 2;
+                }
+            },
+            0x5 => {
+                if self.v[x] == self.v[y] {
+                    self.pc += 2;
+                }
+            },
+            0x6 => self.v[x] = nn,
+            0x7 => self.v[x] = self.v[x].wrapping_add(nn),
+            0x8 => {
+                let (vx, vy) = (self.v[x], self.v[y]);
+                match n {
+                    0x0 => self.v[x] = vy,
+                    0x1 => self.v[x] = vx | vy,
+                    0x2 => self.v[x] = vx & vy,
+                    0x3 => self.v[x] = vx ^ vy,
+                    0x4 => {
+                        let sum = vx as u16 + vy as u16;
+                        self.v[x] = sum as u8;
+                        self.v[0xF] = if sum > 255 { 1 } else { 0 };
+                    },
+                    0x5 => {
+                        self.v[x] = vx.wrapping_sub(vy);
+                        self.v[0xF] = if vx >= vy { 1 } else { 0 };
+                    },
+                    0x6 => {
+                        self.v[x] = vx >> 1;
+                        self.v[0xF] = vx & 1;
+                    },
+                    0x7 => {
+                        self.v[x] = vy.wrapping_sub(vx);
+                        self.v[0xF] = if vy >= vx { 1 } else { 0 };
+                    },
+                    0xE => {
+                        self.v[x] = vx << 1;
+                        self.v[0xF] = vx >> 7;
+                    },
+                    _ => return Err(format!("unknown opcode {:04X}", opcode)),
+                }
+            },
+            0x9 if n == 0 => {
+                if self.v[x] != self.v[y] {
+                    self.pc += 2;
+                }
+            },
+            0xA => self.i = nnn,
+            0xB => self.pc = nnn + self.v[0] as u16,
+            0xC => return Ok(Step::Random { x, mask: nn }),
+            0xD => self.draw(x, y, n)?,
+            0xE if nn == 0x9E => {
+                if self.keys[(self.v[x] & 0xF) as usize] {
+                    self.pc += 2;
+                }
+            },
+            0xE if nn == 0xA1 => {
+                if !self.keys[(self.v[x] & 0xF) as usize] {
+                    self.pc += 2;
+                }
+            },
+            0xF => match nn {
+                0x07 => self.v[x] = self.delay,
+                0x0A => match self.keys.iter().position(|pressed| *pressed) {
+                    Some(key) => self.v[x] = key as u8,
+                    None => self.pc -= 2,
+                },
+                0x15 => self.delay = self.v[x],
+                0x18 => self.sound = self.v[x],
+                0x1E => self.i = self.i.wrapping_add(self.v[x] as u16),
+                0x29 => self.i = FONT_ADDRESS + self.v[x] as u16 * 5,
+                0x30 => self.i = LARGE_FONT_ADDRESS + (self.v[x] & 0xF) as u16 * 10,
+                0x33 => {
+                    let i = self.i as usize;
+                    if i + 3 > RAM_SIZE {
+                        return Err(format!("BCD at {:#X}", i));
+                    }
+                    let vx = self.v[x];
+                    self.write(i, vx / 100)?;
+                    self.write(i + 1, vx / 10 % 10)?;
+                    self.write(i + 2, vx % 10)?;
+                },
+                0x55 => {
+                    let i = self.i as usize;
+                    if i + x + 1 > RAM_SIZE {
+                        return Err(format!("store at {:#X}", i));
+                    }
+                    for reg in 0..=x {
+                        self.write(i + reg, self.v[reg])?;
+                    }
+                },
+                0x65 => {
+                    let i = self.i as usize;
+                    if i + x + 1 > RAM_SIZE {
+                        return Err(format!("load from {:#X}", i));
+                    }
+                    for reg in 0..=x {
+                        self.v[reg] = self.read(i + reg)?;
+                    }
+                },
+                _ => return Err(format!("unknown opcode {:04X}", opcode)),
+            },
+            _ => return Err(format!("unknown opcode {:04X}", opcode)),
+        }
+        Ok(Step::Ok)
+    }
+
+    fn clear(&mut self) {
+        self.display = vec![false; 128 * 64];
+    }
+
+    fn draw(&mut self, x: usize, y: usize, n: u8) -> Result<(), String> {
+        let (x0, y0) = (self.v[x] as usize, self.v[y] as usize);
+        let big = n == 0 && (self.hires || self.superchip);
+        let (columns, rows) = if big { (16, 16) } else { (8, n as usize) };
+        let bytes_per_row = columns / 8;
+
+        // Fetch the sprite first so a bad I faults without drawing anything
+        let mut sprite = Vec::new();
+        for byte in 0..rows * bytes_per_row {
+            sprite.push(self.read(self.i as usize + byte)?);
+        }
+
+        let (width, height) = (self.width(), self.height());
+        let mut collision = false;
+        for row in 0..rows {
+            for column in 0..columns {
+                let byte = sprite[row * bytes_per_row + column / 8];
+                if byte & (0x80 >> (column % 8)) == 0 {
+                    continue;
+                }
+                let px = (x0 + column) % width;
+                let py = (y0 + row) % height;
+                let idx = py * width + px;
+                if self.display[idx] {
+                    collision = true;
+                }
+                self.display[idx] = !self.display[idx];
+            }
+        }
+        self.v[0xF] = if collision { 1 } else { 0 };
+        Ok(())
+    }
+
+    fn scroll_down(&mut self, n: usize) {
+        let (width, height) = (self.width(), self.height());
+        for py in (0..height).rev() {
+            for px in 0..width {
+                self.display[py * width + px] = py >= n && self.display[(py - n) * width + px];
+            }
+        }
+    }
+
+    fn scroll_horizontally(&mut self, n: usize, right: bool) {
+        let (width, height) = (self.width(), self.height());
+        for py in 0..height {
+            let row: Vec<bool> = self.display[py * width..(py + 1) * width].to_vec();
+            for px in 0..width {
+                let from = if right { px.checked_sub(n) } else { Some(px + n).filter(|from| *from < width) };
+                self.display[py * width + px] = from.is_some_and(|from| row[from]);
+            }
+        }
+    }
+}
@@ -0,0 +1,109 @@
+// Savestates through save_state(), save_state_compressed() and load_state(): every ROM in roms/ round trips
+// compressed and not, a ROM laid out to hit the edges of the run length encoding packs to the bytes the
+// format says it should, and cut short or damaged states are refused with the machine left as it was
+#![cfg(feature = "alloc")]
+
+mod common;
+
+use chip8::SavestateError;
+use common::{machine, roms};
+
+const FRAMES: usize = 300;
+// Between the cases in the edges ROM, a run so each case starts and ends packing afresh
+const MARK: [u8; 4] = [0xEE; 4];
+
+// Cases for the run length encoding, each with the bytes it should pack to
+fn edges() -> Vec<(Vec<u8>, Vec<u8>)> {
+    let literals = |len: u8| (1..=len).collect::<Vec<u8>>();
+    vec![
+        // The shortest run worth a control byte
+        (vec![0x11; 3], vec![128, 0x11]),
+        // One shorter is left as literals
+        (vec![0x22; 2], vec![1, 0x22, 0x22]),
+        // The longest run a control byte covers
+        (vec![0x33; 130], vec![255, 0x33]),
+        // One longer leaves a literal over
+        (vec![0x44; 131], vec![255, 0x44, 0, 0x44]),
+        // The most literals a control byte covers
+        (literals(128), [vec![127], literals(128)].concat()),
+        // One more starts another
+        (literals(129), [vec![127], literals(128), vec![0, 129]].concat()),
+    ]
+}
+
+#[test]
+fn states_round_trip() {
+    for (name, rom) in roms() {
+        let mut chip8 = machine(&rom);
+        for _ in 0..FRAMES {
+            if chip8.run_frame().is_err() {
+                break;
+            }
+        }
+        let (plain, packed) = (chip8.save_state(), chip8.save_state_compressed());
+        assert!(packed.len() < plain.len(), "{}: {} bytes compressed against {}", name, packed.len(), plain.len());
+        for bytes in [&plain, &packed] {
+            let mut restored = machine(&rom);
+            restored.load_state(bytes).unwrap_or_else(|err| panic!("{}: {}", name, err));
+            assert_eq!(restored.snapshot(), chip8.snapshot(), "{}", name);
+        }
+        assert_eq!(chip8.snapshot().compress().decompress(), chip8.snapshot(), "{}", name);
+    }
+}
+
+#[test]
+fn packing_edges() {
+    let cases = edges();
+    let mut rom = MARK.to_vec();
+    let mut expected = vec![MARK.len() as u8 + 125, MARK[0]];
+    for (bytes, packed) in &cases {
+        rom.extend_from_slice(bytes);
+        rom.extend_from_slice(&MARK);
+        expected.extend_from_slice(packed);
+        expected.extend_from_slice(&[MARK.len() as u8 + 125, MARK[0]]);
+    }
+    let chip8 = machine(&rom);
+    let state = chip8.save_state_compressed();
+    let found = state.windows(expected.len()).any(|window| window == expected);
+    assert!(found, "the ROM didn't pack to {:02X?}", expected);
+    let mut restored = machine(&rom);
+    restored.load_state(&state).unwrap();
+    assert_eq!(restored.snapshot(), chip8.snapshot());
+}
+
+#[test]
+fn truncated_states_are_refused() {
+    let (_, rom) = &roms()[0];
+    let chip8 = machine(rom);
+    for state in [chip8.save_state(), chip8.save_state_compressed()] {
+        for len in 0..state.len() {
+            let mut restored = machine(rom);
+            restored.run_frame().unwrap();
+            let before = restored.snapshot();
+            let result = restored.load_state(&state[..len]);
+            assert!(matches!(result, Err(SavestateError::Corrupt)), "cut to {} bytes: {:?}", len, result);
+            assert_eq!(restored.snapshot(), before);
+        }
+    }
+}
+
+#[test]
+fn damaged_states_are_refused() {
+    let (_, rom) = &roms()[0];
+    let chip8 = machine(rom);
+    for state in [chip8.save_state(), chip8.save_state_compressed()] {
+        for idx in 0..state.len() {
+            let mut damaged = state.clone();
+            damaged[idx] ^= 1;
+            let mut restored = machine(rom);
+            restored.run_frame().unwrap();
+            let before = restored.snapshot();
+            // Whether it lands on the version, the ROM hash or the state, any change is noticed
+            assert!(restored.load_state(&damaged).is_err(), "byte {} changed", idx);
+            assert_eq!(restored.snapshot(), before);
+        }
+        let mut longer = state.clone();
+        longer.push(0);
+        assert!(matches!(machine(rom).load_state(&longer), Err(SavestateError::Corrupt)));
+    }
+}
@@ -0,0 +1,142 @@
+// Boots each ROM from Timendus' CHIP-8 test suite, runs it for a fixed number of frames and checks the
+// screen it ends up on against the hash recorded in timendus.hashes. Off by default as the ROMs aren't
+// vendored, and skipped with a message when the feature is on but there's no checkout, to run it:
+//
+//   git clone https://github.com/Timendus/chip8-test-suite roms/chip8-test-suite
+//   cargo test -p chip8 --features timendus --test timendus
+//
+// CHIP8_TEST_SUITE points it at a checkout somewhere else. After a deliberate change to what a ROM shows,
+// look over the screens it prints and rerun with CHIP8_BLESS=1 to record the new hashes
+#![cfg(feature = "timendus")]
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use chip8::{Chip8, Variant};
+
+const HASHES: &str = "tests/timendus.hashes";
+
+struct Case {
+    rom: &'static str,
+    variant: Variant,
+    // Written to 0x1FF, which the quirks test reads to skip its menu asking which platform to test
+    platform: Option<u8>,
+    frames: usize,
+}
+
+const CASES: &[Case] = &[
+    Case { rom: "1-chip8-logo.ch8", variant: Variant::Chip8, platform: None, frames: 60 },
+    Case { rom: "2-ibm-logo.ch8", variant: Variant::Chip8, platform: None, frames: 60 },
+    Case { rom: "3-corax+.ch8", variant: Variant::Chip8, platform: None, frames: 120 },
+    Case { rom: "4-flags.ch8", variant: Variant::Chip8, platform: None, frames: 180 },
+    Case { rom: "5-quirks.ch8", variant: Variant::Chip8, platform: Some(1), frames: 600 },
+    Case { rom: "5-quirks.ch8", variant: Variant::SuperChip, platform: Some(2), frames: 600 },
+];
+
+impl Case {
+    // Key for the hashes file, the same ROM can be run as more than one platform
+    fn name(&self) -> String {
+        match self.variant {
+            Variant::Chip8 => self.rom.to_string(),
+            Variant::SuperChip => format!("{}/superchip", self.rom),
+        }
+    }
+}
+
+fn suite_dir() -> PathBuf {
+    match env::var_os("CHIP8_TEST_SUITE") {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../roms/chip8-test-suite"),
+    }
+}
+
+// Lines of "name hash", blank lines and # comments ignored
+fn read_hashes() -> BTreeMap<String, u64> {
+    let text = fs::read_to_string(HASHES).unwrap_or_default();
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let (name, hash) = line.split_once(' ')?;
+            Some((name.to_string(), u64::from_str_radix(hash.trim(), 16).ok()?))
+        })
+        .collect()
+}
+
+fn write_hashes(hashes: &BTreeMap<String, u64>) {
+    let mut text = String::from("# Display hashes for tests/timendus.rs, regenerate with CHIP8_BLESS=1\n");
+    for (name, hash) in hashes {
+        text.push_str(&format!("{} {:016x}\n", name, hash));
+    }
+    fs::write(HASHES, text).expect("couldn't write the hashes file");
+}
+
+fn run(case: &Case, rom: &[u8]) -> Chip8 {
+    let mut chip8 = Chip8::init();
+    chip8.set_variant(case.variant);
+    chip8.load(rom).unwrap();
+    if let Some(platform) = case.platform {
+        chip8.write_ram(0x1FF, &[platform]).unwrap();
+    }
+    for frame in 0..case.frames {
+        if let Err(err) = chip8.run_frame() {
+            panic!("{} faulted on frame {}: {}", case.name(), frame, err);
+        }
+    }
+    chip8
+}
+
+fn screen(chip8: &Chip8) -> String {
+    chip8
+        .get_display()
+        .chunks(chip8.width())
+        .map(|row| row.iter().map(|pixel| if *pixel { '#' } else { '.' }).collect::<String>() + "\n")
+        .collect()
+}
+
+#[test]
+fn timendus_suite() {
+    let dir = suite_dir();
+    let bless = env::var_os("CHIP8_BLESS").is_some();
+    let mut hashes = read_hashes();
+    let mut failures = Vec::new();
+    if !dir.join("bin").is_dir() {
+        eprintln!("skipping the Timendus suite, there's no checkout at {}", dir.display());
+        return;
+    }
+
+    for case in CASES {
+        let path = dir.join("bin").join(case.rom);
+        let rom = fs::read(&path).unwrap_or_else(|err| panic!("couldn't read {}: {}", path.display(), err));
+        let chip8 = run(case, &rom);
+        let hash = chip8.display_hash();
+        let name = case.name();
+        if bless {
+            hashes.insert(name, hash);
+            continue;
+        }
+        match hashes.get(&name) {
+            Some(expected) if *expected == hash => (),
+            Some(expected) => failures.push(format!(
+                "{}: display hash {:016x}, expected {:016x}\n{}",
+                name,
+                hash,
+                expected,
+                screen(&chip8)
+            )),
+            None => failures.push(format!(
+                "{}: no hash recorded, got {:016x}, check the screen and rerun with CHIP8_BLESS=1\n{}",
+                name,
+                hash,
+                screen(&chip8)
+            )),
+        }
+    }
+
+    if bless {
+        write_hashes(&hashes);
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
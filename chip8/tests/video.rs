@@ -0,0 +1,72 @@
+// VideoRecorder: the sizes it patches in at the end match what it wrote, and a scale whose frames won't fit
+// an AVI's header fields is refused rather than written with wrapped sizes
+#![cfg(feature = "std")]
+
+use std::io::{self, Cursor, Seek, SeekFrom, Write};
+use std::sync::{Arc, Mutex};
+
+use chip8::{Chip8, VideoRecorder, DEFAULT_SAMPLE_RATE};
+
+// A writer whose bytes can still be read after the recorder has taken it
+#[derive(Clone, Default)]
+struct Shared(Arc<Mutex<Cursor<Vec<u8>>>>);
+
+impl Write for Shared {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for Shared {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.0.lock().unwrap().seek(pos)
+    }
+}
+
+fn u32_at(bytes: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes(bytes[pos..pos + 4].try_into().unwrap())
+}
+
+#[test]
+fn sizes_match_what_was_written() {
+    let mut chip8 = Chip8::init();
+    chip8.load(&[0x00, 0xE0, 0x12, 0x02]).unwrap();
+    let mut recorder = VideoRecorder::new(1, DEFAULT_SAMPLE_RATE);
+    let out = Shared::default();
+    recorder.start(out.clone()).unwrap();
+    for _ in 0..3 {
+        chip8.run_frame().unwrap();
+        recorder.capture(&chip8).unwrap();
+    }
+    recorder.stop().unwrap();
+
+    let bytes = out.0.lock().unwrap().get_ref().clone();
+    assert_eq!(&bytes[0..4], b"RIFF");
+    assert_eq!(u32_at(&bytes, 4) as usize, bytes.len() - 8);
+    // Frames, then the video stream's width and height
+    assert_eq!(u32_at(&bytes, 48), 3);
+    assert_eq!((u32_at(&bytes, 64), u32_at(&bytes, 68)), (128, 64));
+    // The index is the last chunk, three frames of video and audio
+    let idx1 = bytes.len() - 8 - 6 * 16;
+    assert_eq!(&bytes[idx1..idx1 + 4], b"idx1");
+    assert_eq!(u32_at(&bytes, idx1 + 4), 6 * 16);
+}
+
+#[test]
+fn scales_too_large_for_an_avi_are_refused() {
+    // 418 times the hi-res screen is the largest frame whose size fits a chunk header
+    let mut recorder = VideoRecorder::new(418, DEFAULT_SAMPLE_RATE);
+    recorder.start(Shared::default()).unwrap();
+    recorder.stop().unwrap();
+
+    for scale in [419, 512, usize::MAX] {
+        let mut recorder = VideoRecorder::new(scale, DEFAULT_SAMPLE_RATE);
+        let err = recorder.start(Shared::default()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(!recorder.is_recording());
+    }
+}
@@ -0,0 +1,315 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::process;
+
+use chip8::{
+    compile_octo, Analysis, ByteKind, Cartridge, Chip8, Config, GifRecorder, Instruction, State, TextStyle,
+    Variant, VideoRecorder, WavRecorder, DEFAULT_SAMPLE_RATE,
+};
+
+// Ten seconds
+const DEFAULT_FRAMES: usize = 600;
+const DEFAULT_SCALE: usize = 4;
+// Read for the quirks, speed and palette, as the desktop frontend does
+const CONFIG_PATH: &str = "chip8.toml";
+// Bytes of data to a DB line in listings
+const DATA_PER_LINE: usize = 8;
+
+const USAGE: &str = "\
+usage: chip8 <command> [options]
+  run <rom> [--frames N]                               run headless and print the display
+  disasm <rom>                                         disassemble, telling code from data
+  info <rom>                                           show the hash, what the ROM database knows and the analysis
+  test <rom-dir> [--frames N]                          run every ROM in a directory and report any that fault
+  record <rom> <out> [--frames N] [--scale N]          record a .gif, .avi or .wav
+  screenshot <rom> <out.png> [--frames N] [--scale N]  save a PNG of the display after running
+ROMs can also be Octo source (.8o) or cartridges (.gif). They run for 600 frames, ten seconds, unless halted
+first, and chip8.toml's quirks, speed and palette apply as in the desktop frontend. Running a ROM also takes:
+  --keys K@F,...   keys to press, each hex key held for frame F or frames F-G, counting from 1, e.g. 5@120,5@180
+  --out <path>     where record and screenshot save, in place of <out>";
+
+// Arguments after the command, with the flags taken out
+struct Options {
+    args: Vec<String>,
+    frames: usize,
+    scale: usize,
+    keys: Vec<KeyPress>,
+    out: Option<String>,
+}
+
+// A keypad key held down for some frames of run_frames()
+struct KeyPress {
+    key: usize,
+    frames: RangeInclusive<usize>,
+}
+
+impl KeyPress {
+    // "5@120" or "5@120-180"
+    fn parse(text: &str) -> Option<Self> {
+        let (key, frames) = text.split_once('@')?;
+        let key = usize::from_str_radix(key, 16).ok().filter(|key| *key < 0x10)?;
+        let (first, last) = frames.split_once('-').unwrap_or((frames, frames));
+        Some(Self { key, frames: first.parse().ok()?..=last.parse().ok()? })
+    }
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Self {
+        let mut options =
+            Self { args: Vec::new(), frames: DEFAULT_FRAMES, scale: DEFAULT_SCALE, keys: Vec::new(), out: None };
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().unwrap_or_else(|| exit(USAGE));
+            let number = |value: String| value.parse().unwrap_or_else(|_| exit(USAGE));
+            match arg.as_str() {
+                "--frames" => options.frames = number(value()),
+                "--scale" => options.scale = number(value()).max(1),
+                "--keys" => {
+                    let keys = value();
+                    for press in keys.split(',') {
+                        let press = KeyPress::parse(press.trim());
+                        options.keys.push(press.unwrap_or_else(|| exit(&format!("bad --keys '{}'", keys))));
+                    }
+                },
+                "--out" => options.out = Some(value()),
+                _ if arg.starts_with("--") => exit(USAGE),
+                _ => options.args.push(arg),
+            }
+        }
+        options
+    }
+}
+
+// The emulator's tools from a terminal, for using it without writing Rust
+fn main() {
+    let mut args = env::args().skip(1);
+    let command = args.next().unwrap_or_else(|| exit(USAGE));
+    let options = Options::parse(args);
+    let config = load_config();
+    // --out stands in for the last argument
+    let mut args = options.args.clone();
+    args.extend(options.out.clone());
+    let result = match (command.as_str(), args.as_slice()) {
+        ("run", [rom]) => run(&config, rom, &options),
+        ("disasm", [rom]) => disasm(&config, rom),
+        ("info", [rom]) => info(&config, rom),
+        ("test", [dir]) => test(&config, dir, &options),
+        ("record", [rom, out]) => record(&config, rom, out, &options),
+        ("screenshot", [rom, out]) => screenshot(&config, rom, out, &options),
+        ("help" | "-h" | "--help", _) => {
+            println!("{}", USAGE);
+            Ok(())
+        },
+        _ => exit(USAGE),
+    };
+    if let Err(message) = result {
+        exit(&message);
+    }
+}
+
+fn run(config: &Config, path: &str, options: &Options) -> Result<(), String> {
+    let mut chip8 = machine(config, path)?;
+    let frames = run_frames(&mut chip8, options, |_| Ok(()))?;
+    println!("{}", chip8.render_text(TextStyle::Ascii));
+    println!("{} frames, display hash {:016x}", frames, chip8.display_hash());
+    Ok(())
+}
+
+fn disasm(config: &Config, path: &str) -> Result<(), String> {
+    let chip8 = machine(config, path)?;
+    let analysis = Analysis::of(&chip8);
+    let ram = chip8.ram();
+    for (range, kind) in analysis.regions() {
+        let (mut addr, end) = (range.start as usize, range.end as usize);
+        while addr < end {
+            if let Some(name) = chip8.symbols().name(addr as u16) {
+                println!("{}:", name);
+            }
+            // A lone byte left at the end of some code is shown as data
+            if kind == ByteKind::Code && addr + 1 < end {
+                let opcode = u16::from_be_bytes([ram[addr], ram[addr + 1]]);
+                let text = match Instruction::decode(opcode) {
+                    Some(instruction) => instruction.with_symbols(chip8.symbols()).to_string(),
+                    None => String::from("??"),
+                };
+                println!("{:03X}: {:04X}  {}", addr, opcode, text);
+                addr += 2;
+            } else {
+                let len = DATA_PER_LINE.min(end - addr);
+                let db: Vec<String> = ram[addr..addr + len].iter().map(|byte| format!("0x{:02X}", byte)).collect();
+                let note = if kind == ByteKind::Unreachable { "  ; unreachable" } else { "" };
+                println!("{:03X}:       DB {}{}", addr, db.join(", "), note);
+                addr += len;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn info(config: &Config, path: &str) -> Result<(), String> {
+    let chip8 = machine(config, path)?;
+    let analysis = Analysis::of(&chip8);
+    let regions = analysis.regions();
+    let bytes = |of: ByteKind| -> usize {
+        regions.iter().filter(|(_, kind)| *kind == of).map(|(range, _)| range.len()).sum()
+    };
+    let (code, data, unreachable) = (bytes(ByteKind::Code), bytes(ByteKind::Data), bytes(ByteKind::Unreachable));
+    println!("size         {} bytes", code + data + unreachable);
+    if let Some(hash) = chip8.rom_hash() {
+        println!("sha1         {}", hash);
+    }
+    match chip8.rom_info() {
+        Some(info) => {
+            println!("title        {}", info.title);
+            if let Some(author) = info.author {
+                println!("author       {}", author);
+            }
+            if let Some(year) = info.year {
+                println!("year         {}", year);
+            }
+            let variant = match info.variant {
+                Variant::Chip8 => "CHIP-8",
+                Variant::SuperChip => "SUPER-CHIP",
+            };
+            println!("platform     {}", variant);
+            let keys: Vec<String> =
+                (0..16).filter(|key| info.keys & 1 << key != 0).map(|key| format!("{:X}", key)).collect();
+            println!("keys         {}", if keys.is_empty() { String::from("any") } else { keys.join(" ") });
+            if !info.controls.is_empty() {
+                println!("controls     {}", info.hints());
+            }
+        },
+        None => println!("title        not in the ROM database"),
+    }
+    println!("code         {} bytes", code);
+    println!("data         {} bytes", data);
+    println!("unreachable  {} bytes", unreachable);
+    for issue in analysis.issues() {
+        println!("issue        {}", issue);
+    }
+    Ok(())
+}
+
+// Every file in dir in name order, noting which fault or don't load. Fails if any did
+fn test(config: &Config, dir: &str, options: &Options) -> Result<(), String> {
+    let entries = fs::read_dir(dir).map_err(|err| format!("{}: {}", dir, err))?;
+    let mut paths: Vec<_> = entries.flatten().map(|entry| entry.path()).filter(|path| path.is_file()).collect();
+    paths.sort();
+    let name = |path: &Path| path.file_name().map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+    let width = paths.iter().map(|path| name(path).len()).max().unwrap_or_default();
+    let mut failed = 0;
+    for path in &paths {
+        let name = name(path);
+        let result = path
+            .to_str()
+            .ok_or_else(|| String::from("not a UTF-8 path"))
+            .and_then(|rom| machine(config, rom))
+            .and_then(|mut chip8| Ok((run_frames(&mut chip8, options, |_| Ok(()))?, chip8.display_hash())));
+        match result {
+            Ok((frames, hash)) => {
+                println!("ok    {:<width$}  {} frames, display hash {:016x}", name, frames, hash, width = width)
+            },
+            Err(err) => {
+                println!("FAIL  {:<width$}  {}", name, err, width = width);
+                failed += 1;
+            },
+        }
+    }
+    match failed {
+        0 => Ok(()),
+        _ => Err(format!("{} of {} failed", failed, paths.len())),
+    }
+}
+
+fn record(config: &Config, path: &str, out: &str, options: &Options) -> Result<(), String> {
+    let mut chip8 = machine(config, path)?;
+    let error = |err: io::Error| format!("{}: {}", out, err);
+    let extension = Path::new(out).extension().and_then(|extension| extension.to_str()).unwrap_or_default();
+    match extension.to_ascii_lowercase().as_str() {
+        "gif" => {
+            let mut recorder = GifRecorder::new(options.scale);
+            recorder.start_file(out).map_err(error)?;
+            run_frames(&mut chip8, options, |chip8| recorder.capture(chip8))?;
+            recorder.stop().map_err(error)
+        },
+        "avi" => {
+            let mut recorder = VideoRecorder::new(options.scale, DEFAULT_SAMPLE_RATE);
+            recorder.start_file(out).map_err(error)?;
+            run_frames(&mut chip8, options, |chip8| recorder.capture(chip8))?;
+            recorder.stop().map_err(error)
+        },
+        "wav" => {
+            let mut recorder = WavRecorder::new(DEFAULT_SAMPLE_RATE);
+            recorder.start_file(out).map_err(error)?;
+            run_frames(&mut chip8, options, |chip8| recorder.capture(chip8))?;
+            recorder.stop().map_err(error)
+        },
+        _ => Err(format!("{}: can only record to .gif, .avi or .wav", out)),
+    }
+}
+
+fn screenshot(config: &Config, path: &str, out: &str, options: &Options) -> Result<(), String> {
+    let mut chip8 = machine(config, path)?;
+    run_frames(&mut chip8, options, |_| Ok(()))?;
+    chip8.screenshot_png(out, config.palette, options.scale).map_err(|err| format!("{}: {}", out, err))
+}
+
+// A machine set up from the config with the ROM at path loaded. Octo cartridges and source are built on the
+// way in, anything else is a ROM
+fn machine(config: &Config, path: &str) -> Result<Chip8, String> {
+    let mut chip8 = Chip8::init();
+    // Before loading, so a cartridge's own settings win
+    config.apply(&mut chip8);
+    if path.ends_with(".gif") {
+        let cartridge = Cartridge::open(path).and_then(|cartridge| cartridge.load(&mut chip8));
+        cartridge.map_err(|err| format!("{}: {}", path, err))?;
+    } else if path.ends_with(".8o") {
+        let source = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+        let program = compile_octo(&source).map_err(|err| format!("{}: {}", path, err))?;
+        chip8.load(&program.rom).map_err(|err| format!("{}: {}", path, err))?;
+        chip8.set_symbols(program.symbols());
+    } else {
+        chip8.load_file(path).map_err(|err| err.to_string())?;
+    }
+    Ok(chip8)
+}
+
+// Run up to options.frames frames with options.keys pressed, calling each after every one, stopping early if
+// the ROM halts. Returns the frames run
+fn run_frames(
+    chip8: &mut Chip8,
+    options: &Options,
+    mut each: impl FnMut(&Chip8) -> io::Result<()>,
+) -> Result<usize, String> {
+    let frames = options.frames;
+    for frame in 1..=frames {
+        for key in 0..0x10 {
+            chip8.keypress(key, options.keys.iter().any(|press| press.key == key && press.frames.contains(&frame)));
+        }
+        let state = chip8.run_frame().map_err(|err| format!("fault in frame {}: {}", frame, err))?;
+        each(chip8).map_err(|err| err.to_string())?;
+        if state == State::Halted {
+            return Ok(frame);
+        }
+    }
+    Ok(frames)
+}
+
+// CONFIG_PATH's settings, or the defaults
+fn load_config() -> Config {
+    match fs::read_to_string(CONFIG_PATH).map(|text| Config::parse(&text)) {
+        Ok(Ok(config)) => config,
+        Ok(Err(err)) => {
+            eprintln!("ignoring {}, {}", CONFIG_PATH, err);
+            Config::default()
+        },
+        Err(_) => Config::default(),
+    }
+}
+
+fn exit(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1)
+}
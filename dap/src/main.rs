@@ -0,0 +1,50 @@
+mod protocol;
+mod session;
+
+use std::io::{self, BufReader};
+use std::sync::mpsc::{self, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use session::Session;
+
+const FRAME_DURATION: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+// Debug Adapter Protocol server for CHIP-8 ROMs, speaking to the editor over stdin and stdout.
+// Point a VS Code debug configuration's adapter executable at this binary and launch with
+// { "program": "path/to/rom", "stopOnEntry": true }
+fn main() -> io::Result<()> {
+    // Requests are read on their own thread so a running program can still be paused
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut input = BufReader::new(io::stdin());
+        while let Ok(Some(message)) = protocol::read_message(&mut input) {
+            if sender.send(message).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut session = Session::new(io::stdout());
+    while !session.is_done() {
+        if !session.is_running() {
+            match receiver.recv() {
+                Ok(message) => session.handle(message)?,
+                Err(_) => break,
+            }
+            continue;
+        }
+        match receiver.try_recv() {
+            Ok(message) => session.handle(message)?,
+            Err(TryRecvError::Empty) => {
+                let started = Instant::now();
+                session.run_frame()?;
+                if let Some(remaining) = FRAME_DURATION.checked_sub(started.elapsed()) {
+                    thread::sleep(remaining);
+                }
+            },
+            Err(TryRecvError::Disconnected) => break,
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use std::io::{self, BufRead, ErrorKind, Write};
+
+use serde_json::{json, Value};
+
+// Far more than any request needs, writeMemory's base64 of all of RAM included
+const MAX_MESSAGE_LEN: usize = 4 << 20;
+
+// Read one Content-Length framed message, None once the client closes its end
+pub fn read_message(input: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut len = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            // Headers end with a blank line, stray ones before them are skipped
+            if len.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some(value) = line.strip_prefix("Content-Length:") {
+            len = value.trim().parse::<usize>().ok();
+        }
+    }
+    let len = len.unwrap_or(0);
+    // Refused before allocating anything for it
+    if len > MAX_MESSAGE_LEN {
+        return Err(io::Error::new(ErrorKind::InvalidData, format!("message of {} bytes is too long", len)));
+    }
+    let mut body = vec![0; len];
+    input.read_exact(&mut body)?;
+    serde_json::from_slice(&body).map(Some).map_err(|err| io::Error::new(ErrorKind::InvalidData, err))
+}
+
+// Numbers and frames outgoing responses and events
+pub struct Output<W: Write> {
+    writer: W,
+    seq: u64,
+}
+
+impl<W: Write> Output<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer, seq: 1 }
+    }
+
+    pub fn response(&mut self, request: &Value, body: Value) -> io::Result<()> {
+        let message = json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": true,
+            "body": body,
+        });
+        self.send(message)
+    }
+
+    pub fn error(&mut self, request: &Value, message: &str) -> io::Result<()> {
+        let message = json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": false,
+            "message": message,
+        });
+        self.send(message)
+    }
+
+    pub fn event(&mut self, event: &str, body: Value) -> io::Result<()> {
+        self.send(json!({ "type": "event", "event": event, "body": body }))
+    }
+
+    fn send(&mut self, mut message: Value) -> io::Result<()> {
+        message["seq"] = json!(self.seq);
+        self.seq += 1;
+        let body = message.to_string();
+        write!(self.writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+        self.writer.flush()
+    }
+}
@@ -0,0 +1,497 @@
+use std::fs;
+use std::io::{self, Write};
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chip8::{compile_octo, Analysis, Breakpoint, BreakpointId, ByteKind, Chip8, Condition, Debugger, Instruction, State, Stop, Symbols};
+use serde_json::{json, Value};
+
+use crate::protocol::Output;
+
+// There's only ever the one CPU
+const THREAD_ID: u64 = 1;
+const REGISTERS_REF: u64 = 1;
+const STACK_REF: u64 = 2;
+// The most instructions one disassemble request gets, far more than a client shows at once
+const MAX_DISASSEMBLY: i64 = 0x10000;
+
+type Reply = Result<Value, String>;
+
+pub struct Session<W: Write> {
+    out: Output<W>,
+    debugger: Option<Debugger>,
+    // Of the launched ROM, so the disassembly can show data as data
+    analysis: Option<Analysis>,
+    stop_on_entry: bool,
+    running: bool,
+    done: bool,
+    instruction_breakpoints: Vec<BreakpointId>,
+    function_breakpoints: Vec<BreakpointId>,
+    // Events that have to follow the response to the request that caused them
+    events: Vec<(&'static str, Value)>,
+}
+
+impl<W: Write> Session<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            out: Output::new(writer),
+            debugger: None,
+            analysis: None,
+            stop_on_entry: false,
+            running: false,
+            done: false,
+            instruction_breakpoints: Vec::new(),
+            function_breakpoints: Vec::new(),
+            events: Vec::new(),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    pub fn handle(&mut self, request: Value) -> io::Result<()> {
+        if request["type"] != "request" {
+            return Ok(());
+        }
+        let command = request["command"].as_str().unwrap_or_default().to_string();
+        let args = &request["arguments"];
+        let reply = match command.as_str() {
+            "initialize" => self.initialize(),
+            "launch" => self.launch(args),
+            "configurationDone" => self.configuration_done(),
+            // ROMs have no source to set line breakpoints in, the disassembly view uses instruction breakpoints
+            "setBreakpoints" => Ok(unverified_breakpoints(args, "Set breakpoints in the disassembly view")),
+            "setExceptionBreakpoints" => Ok(json!({ "breakpoints": [] })),
+            "setInstructionBreakpoints" => self.set_instruction_breakpoints(args),
+            "setFunctionBreakpoints" => self.set_function_breakpoints(args),
+            "threads" => Ok(json!({ "threads": [{ "id": THREAD_ID, "name": "CHIP-8" }] })),
+            "stackTrace" => self.stack_trace(),
+            "scopes" => Ok(scopes()),
+            "variables" => self.variables(args),
+            "setVariable" => self.set_variable(args),
+            "evaluate" => self.evaluate(args),
+            "readMemory" => self.read_memory(args),
+            "writeMemory" => self.write_memory(args),
+            "disassemble" => self.disassemble(args),
+            "continue" => self.resume(),
+            "pause" => self.pause(),
+            "next" => self.step_with(|debugger| debugger.step_over()),
+            "stepIn" => self.step_with(|debugger| debugger.step().map(step_stop)),
+            "stepOut" => self.step_with(|debugger| debugger.step_out()),
+            "stepBack" => self.step_back(),
+            "disconnect" | "terminate" => {
+                self.done = true;
+                Ok(json!({}))
+            },
+            _ => Err(format!("Unsupported request '{}'", command)),
+        };
+        match reply {
+            Ok(body) => self.out.response(&request, body)?,
+            Err(message) => self.out.error(&request, &message)?,
+        }
+        for (event, body) in std::mem::take(&mut self.events) {
+            self.out.event(event, body)?;
+        }
+        Ok(())
+    }
+
+    // Run one frame of a continued program, reporting why it stopped if it did
+    pub fn run_frame(&mut self) -> io::Result<()> {
+        let result = match self.debugger.as_mut() {
+            Some(debugger) => debugger.run_frame(),
+            None => return Ok(()),
+        };
+        if result != Ok(Stop::FrameComplete) {
+            self.report(result, "step");
+        }
+        for (event, body) in std::mem::take(&mut self.events) {
+            self.out.event(event, body)?;
+        }
+        Ok(())
+    }
+
+    fn debugger(&self) -> Result<&Debugger, String> {
+        self.debugger.as_ref().ok_or_else(|| String::from("No ROM has been launched"))
+    }
+
+    fn debugger_mut(&mut self) -> Result<&mut Debugger, String> {
+        self.debugger.as_mut().ok_or_else(|| String::from("No ROM has been launched"))
+    }
+
+    fn initialize(&mut self) -> Reply {
+        self.events.push(("initialized", json!({})));
+        Ok(json!({
+            "supportsConfigurationDoneRequest": true,
+            "supportsFunctionBreakpoints": true,
+            "supportsConditionalBreakpoints": true,
+            "supportsInstructionBreakpoints": true,
+            "supportsDisassembleRequest": true,
+            "supportsReadMemoryRequest": true,
+            "supportsWriteMemoryRequest": true,
+            "supportsSetVariable": true,
+            "supportsStepBack": true,
+            "supportsTerminateRequest": true,
+        }))
+    }
+
+    fn launch(&mut self, args: &Value) -> Reply {
+        let path = args["program"].as_str().ok_or("Launch needs a 'program' ROM path")?;
+        let mut chip8 = Chip8::init();
+        // Octo source is built here, with its labels as symbols
+        if path.ends_with(".8o") {
+            let source = fs::read_to_string(path).map_err(|err| format!("Couldn't read {}: {}", path, err))?;
+            let program = compile_octo(&source).map_err(|err| format!("{}: {}", path, err))?;
+            chip8.load(&program.rom).map_err(|err| format!("{}: {}", path, err))?;
+            chip8.set_symbols(program.symbols());
+        } else {
+            chip8.load_file(path).map_err(|err| err.to_string())?;
+        }
+        // Or they can come from a symbol file
+        if let Some(path) = args["symbols"].as_str() {
+            let text = fs::read_to_string(path).map_err(|err| format!("Couldn't read {}: {}", path, err))?;
+            chip8.set_symbols(Symbols::parse(&text).map_err(|err| format!("{}: {}", path, err))?);
+        }
+        self.analysis = Some(Analysis::of(&chip8));
+        self.debugger = Some(Debugger::new(chip8));
+        self.stop_on_entry = args["stopOnEntry"].as_bool().unwrap_or(false);
+        Ok(json!({}))
+    }
+
+    // Breakpoints are in, so the program can start
+    fn configuration_done(&mut self) -> Reply {
+        self.debugger()?;
+        if self.stop_on_entry {
+            self.stopped("entry", json!({}));
+        } else {
+            self.running = true;
+        }
+        Ok(json!({}))
+    }
+
+    fn set_instruction_breakpoints(&mut self, args: &Value) -> Reply {
+        let requested = args["breakpoints"].as_array().cloned().unwrap_or_default();
+        let debugger = self.debugger.as_mut().ok_or("No ROM has been launched")?;
+        for id in self.instruction_breakpoints.drain(..) {
+            debugger.remove_breakpoint(id);
+        }
+        let mut breakpoints = Vec::new();
+        for bp in requested {
+            let addr = bp["instructionReference"]
+                .as_str()
+                .and_then(parse_address)
+                .map(|addr| addr + bp["offset"].as_i64().unwrap_or(0));
+            let reply = match addr.and_then(|addr| u16::try_from(addr).ok()) {
+                Some(addr) => add_breakpoint(debugger, Breakpoint::Address(addr), bp["condition"].as_str()),
+                None => Err(String::from("Not a valid address")),
+            };
+            breakpoints.push(breakpoint_reply(reply, &mut self.instruction_breakpoints));
+        }
+        Ok(json!({ "breakpoints": breakpoints }))
+    }
+
+    // Function breakpoints are a label, an address such as 0x2F2 or a condition like "v3 == 10"
+    fn set_function_breakpoints(&mut self, args: &Value) -> Reply {
+        let requested = args["breakpoints"].as_array().cloned().unwrap_or_default();
+        let debugger = self.debugger.as_mut().ok_or("No ROM has been launched")?;
+        for id in self.function_breakpoints.drain(..) {
+            debugger.remove_breakpoint(id);
+        }
+        let mut breakpoints = Vec::new();
+        for bp in requested {
+            let name = bp["name"].as_str().unwrap_or_default();
+            let addr = debugger.chip8().symbols().address(name).map(i64::from).or_else(|| parse_address(name));
+            let breakpoint = match addr.and_then(|addr| u16::try_from(addr).ok()) {
+                Some(addr) => Ok(Breakpoint::Address(addr)),
+                None => Condition::parse(name).map(Breakpoint::Condition).map_err(|err| err.to_string()),
+            };
+            let reply = breakpoint.and_then(|breakpoint| add_breakpoint(debugger, breakpoint, bp["condition"].as_str()));
+            breakpoints.push(breakpoint_reply(reply, &mut self.function_breakpoints));
+        }
+        Ok(json!({ "breakpoints": breakpoints }))
+    }
+
+    fn stack_trace(&self) -> Reply {
+        let chip8 = self.debugger()?.chip8();
+        let calls = chip8.call_stack();
+        // Innermost first: the current PC, then each call site back out to the top level
+        let function = |depth: usize| match depth {
+            0 => String::from("main"),
+            _ => match chip8.symbols().name(calls[depth - 1].target) {
+                Some(name) => name.to_string(),
+                None => format!("sub_{:03X}", calls[depth - 1].target),
+            },
+        };
+        let mut frames = vec![frame(0, function(calls.len()), chip8.pc())];
+        for depth in (0..calls.len()).rev() {
+            frames.push(frame(frames.len(), function(depth), calls[depth].call_site));
+        }
+        Ok(json!({ "stackFrames": frames, "totalFrames": frames.len() }))
+    }
+
+    fn variables(&self, args: &Value) -> Reply {
+        let chip8 = self.debugger()?.chip8();
+        let variables: Vec<Value> = match args["variablesReference"].as_u64() {
+            Some(REGISTERS_REF) => registers(chip8)
+                .into_iter()
+                .map(|(name, value, width)| {
+                    let mut variable = variable(&name, value, width);
+                    if name == "I" || name == "PC" {
+                        variable["memoryReference"] = json!(format!("{:#05X}", value));
+                    }
+                    variable
+                })
+                .collect(),
+            Some(STACK_REF) => chip8
+                .stack()
+                .iter()
+                .enumerate()
+                .map(|(idx, addr)| variable(&format!("[{}]", idx), *addr, 3))
+                .collect(),
+            _ => Vec::new(),
+        };
+        Ok(json!({ "variables": variables }))
+    }
+
+    // The new value can be any expression, e.g. "v3 + 1"
+    fn set_variable(&mut self, args: &Value) -> Reply {
+        let name = args["name"].as_str().unwrap_or_default().to_ascii_uppercase();
+        let expression = args["value"].as_str().unwrap_or_default();
+        let condition = Condition::parse(expression).map_err(|err| err.to_string())?;
+        let chip8 = self.debugger_mut()?.chip8_mut();
+        let value = condition.eval(chip8);
+        match name.as_str() {
+            "I" => chip8.set_i(value as u16),
+            "PC" => chip8.set_pc(value as u16),
+            "DT" => chip8.set_delay_timer(value as u8),
+            "ST" => chip8.set_sound_timer(value as u8),
+            _ => match name.strip_prefix('V').and_then(|reg| usize::from_str_radix(reg, 16).ok()) {
+                Some(x) if x < 0x10 => chip8.set_v(x, value as u8),
+                _ => return Err(format!("{} can't be changed", name)),
+            },
+        }
+        let (_, value, width) = registers(chip8).into_iter().find(|(reg, _, _)| *reg == name).unwrap_or_default();
+        Ok(json!({ "value": format_value(value, width) }))
+    }
+
+    // Watches and the debug console take the same expressions as conditional breakpoints
+    fn evaluate(&self, args: &Value) -> Reply {
+        let expression = args["expression"].as_str().unwrap_or_default();
+        let condition = Condition::parse(expression).map_err(|err| err.to_string())?;
+        let value = condition.eval(self.debugger()?.chip8());
+        Ok(json!({ "result": format!("{} ({:#X})", value, value), "variablesReference": 0 }))
+    }
+
+    fn read_memory(&self, args: &Value) -> Reply {
+        let ram = self.debugger()?.chip8().ram();
+        let addr = memory_address(args)?;
+        let count = i64::try_from(args["count"].as_u64().unwrap_or(0)).map_err(|_| "Count is out of range")?;
+        let start = addr.clamp(0, ram.len() as i64);
+        let end = addr.saturating_add(count).clamp(start, ram.len() as i64);
+        Ok(json!({
+            "address": format!("{:#05X}", start),
+            // readMemory and writeMemory carry their bytes as standard padded base64
+            "data": BASE64.encode(&ram[start as usize..end as usize]),
+            "unreadableBytes": count - (end - start),
+        }))
+    }
+
+    fn write_memory(&mut self, args: &Value) -> Reply {
+        let addr = memory_address(args)?;
+        let data = BASE64.decode(args["data"].as_str().unwrap_or_default()).map_err(|_| "Data isn't valid base64")?;
+        let addr = u16::try_from(addr).map_err(|_| String::from("Address is out of range"))?;
+        self.debugger_mut()?.chip8_mut().write_ram(addr, &data).map_err(|err| err.to_string())?;
+        Ok(json!({ "bytesWritten": data.len() }))
+    }
+
+    fn disassemble(&self, args: &Value) -> Reply {
+        let chip8 = self.debugger()?.chip8();
+        let ram = chip8.ram();
+        let count = args["instructionCount"].as_u64().unwrap_or(0);
+        let count = i64::try_from(count).ok().filter(|count| *count <= MAX_DISASSEMBLY).ok_or("Too many instructions")?;
+        let addr = memory_address(args)?;
+        let offset = args["instructionOffset"].as_i64().unwrap_or(0);
+        // Checked as far as the end, so the addresses of the instructions in between can't overflow either
+        let start = offset
+            .checked_mul(2)
+            .and_then(|offset| addr.checked_add(offset))
+            .filter(|start| start.checked_add(count * 2).is_some())
+            .ok_or("Address is out of range")?;
+        let instructions: Vec<Value> = (0..count)
+            .map(|idx| {
+                let addr = start + idx * 2;
+                if addr < 0 || addr + 1 >= ram.len() as i64 {
+                    return json!({ "address": format!("{:#X}", addr), "instruction": "??", "presentationHint": "invalid" });
+                }
+                let opcode = u16::from_be_bytes([ram[addr as usize], ram[addr as usize + 1]]);
+                let is_data = |addr: i64| {
+                    let kind = self.analysis.as_ref().and_then(|analysis| analysis.kind(addr as u16));
+                    kind.is_some_and(|kind| kind != ByteKind::Code)
+                };
+                // Slots stay two bytes apart, so data is shown a pair at a time
+                let text = match Instruction::decode(opcode) {
+                    _ if addr != chip8.pc() as i64 && is_data(addr) && is_data(addr + 1) => {
+                        format!("DB {:#04X}, {:#04X}", opcode >> 8, opcode & 0xFF)
+                    },
+                    Some(instruction) => instruction.with_symbols(chip8.symbols()).to_string(),
+                    None => format!("DW {:#06X}", opcode),
+                };
+                let mut instruction = json!({
+                    "address": format!("{:#05X}", addr),
+                    "instructionBytes": format!("{:02X} {:02X}", opcode >> 8, opcode & 0xFF),
+                    "instruction": text,
+                });
+                if let Some(name) = chip8.symbols().name(addr as u16) {
+                    instruction["symbol"] = json!(name);
+                }
+                instruction
+            })
+            .collect();
+        Ok(json!({ "instructions": instructions }))
+    }
+
+    fn resume(&mut self) -> Reply {
+        self.debugger()?;
+        self.running = true;
+        Ok(json!({ "allThreadsContinued": true }))
+    }
+
+    fn pause(&mut self) -> Reply {
+        self.debugger()?;
+        if self.running {
+            self.running = false;
+            self.stopped("pause", json!({}));
+        }
+        Ok(json!({}))
+    }
+
+    fn step_with(&mut self, step: impl FnOnce(&mut Debugger) -> chip8::Result<Stop>) -> Reply {
+        let result = step(self.debugger_mut()?);
+        self.report(result, "step");
+        Ok(json!({}))
+    }
+
+    fn step_back(&mut self) -> Reply {
+        if !self.debugger_mut()?.step_back() {
+            return Err(String::from("No more history to step back through"));
+        }
+        self.stopped("step", json!({}));
+        Ok(json!({}))
+    }
+
+    // Queue the events describing how a run or step ended
+    fn report(&mut self, result: chip8::Result<Stop>, reason: &'static str) {
+        self.running = false;
+        match result {
+            Ok(Stop::Breakpoint(id)) => self.stopped("breakpoint", json!({ "hitBreakpointIds": [id.0] })),
+            Ok(Stop::Halted) => {
+                self.events.push(("exited", json!({ "exitCode": 0 })));
+                self.events.push(("terminated", json!({})));
+            },
+            Ok(_) => self.stopped(reason, json!({})),
+            Err(err) => self.stopped("exception", json!({ "description": err.to_string(), "text": err.to_string() })),
+        }
+    }
+
+    fn stopped(&mut self, reason: &str, mut body: Value) {
+        body["reason"] = json!(reason);
+        body["threadId"] = json!(THREAD_ID);
+        body["allThreadsStopped"] = json!(true);
+        self.events.push(("stopped", body));
+    }
+}
+
+fn step_stop(state: State) -> Stop {
+    match state {
+        State::Halted => Stop::Halted,
+        State::Running => Stop::StepComplete,
+    }
+}
+
+// A breakpoint's own condition narrows its address or condition down further
+fn add_breakpoint(debugger: &mut Debugger, breakpoint: Breakpoint, condition: Option<&str>) -> Result<BreakpointId, String> {
+    let breakpoint = match (condition, breakpoint) {
+        (None, breakpoint) | (Some(""), breakpoint) => breakpoint,
+        (Some(condition), Breakpoint::Address(addr)) => {
+            Breakpoint::Condition(parse_condition(&format!("pc == {} && ({})", addr, condition))?)
+        },
+        (Some(condition), Breakpoint::Condition(outer)) => {
+            Breakpoint::Condition(parse_condition(&format!("({}) && ({})", outer, condition))?)
+        },
+    };
+    Ok(debugger.add_breakpoint(breakpoint))
+}
+
+fn parse_condition(source: &str) -> Result<Condition, String> {
+    Condition::parse(source).map_err(|err| err.to_string())
+}
+
+fn breakpoint_reply(result: Result<BreakpointId, String>, ids: &mut Vec<BreakpointId>) -> Value {
+    match result {
+        Ok(id) => {
+            ids.push(id);
+            json!({ "id": id.0, "verified": true })
+        },
+        Err(message) => json!({ "verified": false, "message": message }),
+    }
+}
+
+fn unverified_breakpoints(args: &Value, message: &str) -> Value {
+    let count = args["breakpoints"].as_array().map_or(0, |bps| bps.len());
+    let breakpoints: Vec<Value> = (0..count).map(|_| json!({ "verified": false, "message": message })).collect();
+    json!({ "breakpoints": breakpoints })
+}
+
+fn frame(id: usize, name: String, pc: u16) -> Value {
+    json!({
+        "id": id,
+        "name": name,
+        "line": 0,
+        "column": 0,
+        "instructionPointerReference": format!("{:#05X}", pc),
+    })
+}
+
+fn scopes() -> Value {
+    json!({ "scopes": [
+        { "name": "Registers", "variablesReference": REGISTERS_REF, "expensive": false },
+        { "name": "Stack", "variablesReference": STACK_REF, "expensive": false },
+    ]})
+}
+
+// Name, value and hex digits to show for every register
+fn registers(chip8: &Chip8) -> Vec<(String, u16, usize)> {
+    let mut registers: Vec<_> = (0..0x10).map(|x| (format!("V{:X}", x), chip8.v(x) as u16, 2)).collect();
+    registers.push((String::from("I"), chip8.i(), 3));
+    registers.push((String::from("PC"), chip8.pc(), 3));
+    registers.push((String::from("SP"), chip8.stack().len() as u16, 1));
+    registers.push((String::from("DT"), chip8.delay_timer() as u16, 2));
+    registers.push((String::from("ST"), chip8.sound_timer() as u16, 2));
+    registers
+}
+
+fn variable(name: &str, value: u16, width: usize) -> Value {
+    json!({ "name": name, "value": format_value(value, width), "variablesReference": 0 })
+}
+
+fn format_value(value: u16, width: usize) -> String {
+    format!("0x{:0width$X}", value, width = width)
+}
+
+// Memory references are the "0x2F2" addresses handed out in frames and variables, decimal works too
+fn parse_address(text: &str) -> Option<i64> {
+    let text = text.trim();
+    match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => i64::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
+
+fn memory_address(args: &Value) -> Result<i64, String> {
+    let addr = args["memoryReference"].as_str().and_then(parse_address).ok_or("Not a valid memory reference")?;
+    addr.checked_add(args["offset"].as_i64().unwrap_or(0)).ok_or_else(|| String::from("Address is out of range"))
+}
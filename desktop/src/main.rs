@@ -1,22 +1,87 @@
+use chip8::Action;
+use chip8::Cartridge;
+use chip8::CartridgeError;
 use chip8::Chip8;
+use chip8::Config;
+use chip8::CoreDump;
+use chip8::GifRecorder;
+use chip8::Keymap;
+use chip8::Palette;
+use chip8::RomInfo;
+use chip8::SaveSlots;
+use chip8::SpeedControl;
+use chip8::State;
+use chip8::VideoRecorder;
+use chip8::Viewport;
+use chip8::DEFAULT_SAMPLE_RATE;
 use chip8::SCREEN_WIDTH;
 use chip8::SCREEN_HEIGHT;
 
-use std::fs::File;
-use std::io::Read;
 use std::env;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 
+use sdl2::audio::{AudioQueue, AudioSpecDesired};
+use sdl2::controller::Button;
 use sdl2::event::Event;
 use sdl2::keyboard::Keycode;
 use sdl2::pixels::Color;
 use sdl2::rect::Rect;
-use sdl2::render::Canvas;
-use sdl2::video::Window;
+use sdl2::render::{BlendMode, Canvas};
+use sdl2::video::{FullscreenType, Window};
 
+const WINDOW_TITLE: &str = "Rust Chip-8 Emulator";
 const SCALE: u32 = 15;
 const WINDOW_WIDTH: u32 = (SCREEN_WIDTH as u32) * SCALE;
 const WINDOW_HEIGHT: u32 = (SCREEN_HEIGHT as u32) * SCALE;
-const TICKS_PER_FRAME: usize = 10;
+// Each hi-res pixel becomes a GIF_SCALE x GIF_SCALE square in recordings
+const GIF_SCALE: usize = 2;
+const SCREENSHOT_SCALE: usize = 10;
+const VIDEO_SCALE: usize = 4;
+// Instructions kept to show in the core dump saved on a fault
+const CORE_DUMP_INSTRUCTIONS: usize = 64;
+// Read for the settings if it's there, see Config for the format
+const CONFIG_PATH: &str = "chip8.toml";
+// Read for the keypad layout if it's there and the config has no [keymap], see Keymap for the format, unless
+// the config's keymaps directory has one for the ROM named after its hash. Gamepad buttons are "Pad " and
+// SDL's name for the button, e.g. "Pad dpup"
+const KEYMAP_PATH: &str = "keymap.txt";
+// Pad buttons in the order the game's keys are put on them, after the directions go on the d-pad
+const PAD_BUTTONS: [Button; 8] = [
+    Button::A,
+    Button::B,
+    Button::X,
+    Button::Y,
+    Button::LeftShoulder,
+    Button::RightShoulder,
+    Button::Back,
+    Button::Start,
+];
+// Keys nearly every game steers with, and what a game the ROM database doesn't know gets on the pad
+const DPAD_KEYS: [(usize, Button); 4] =
+    [(0x2, Button::DPadUp), (0x8, Button::DPadDown), (0x4, Button::DPadLeft), (0x6, Button::DPadRight)];
+const DEFAULT_PAD_KEYS: u16 = 1 << 0x2 | 1 << 0x4 | 1 << 0x5 | 1 << 0x6 | 1 << 0x8;
+// Speeds while Tab or ` is held
+const FAST_FORWARD_SPEED: f32 = 4.0;
+const SLOW_MOTION_SPEED: f32 = 0.25;
+// Most of the beep queued ahead of the speakers, in 60ths of a second
+const AUDIO_FRAMES_QUEUED: usize = 3;
+// How dark the effects Effects toggles draw, out of 255, with a scanline every this many window rows and a
+// vignette this many window pixels deep
+const SCANLINE_ALPHA: u8 = 90;
+const SCANLINE_SPACING: u32 = 3;
+const GRID_ALPHA: u8 = 60;
+const VIGNETTE_ALPHA: f32 = 160.0;
+const VIGNETTE_DEPTH: u32 = 64;
+
+// A CRT's look drawn over the pixels, each toggled while running: F3 for scanlines, F4 for a grid between the
+// pixels and F6 for a vignette darkening the edges
+#[derive(Clone, Copy, Default)]
+struct Effects {
+    scanlines: bool,
+    grid: bool,
+    vignette: bool,
+}
 
 fn main() {
     let args: Vec<_> = env::args().collect();
@@ -28,8 +93,9 @@ fn main() {
     let sdl_context = sdl2::init().unwrap();
     let video_subsystem = sdl_context.video().unwrap();
     let window = video_subsystem
-        .window("Rust Chip-8 Emulator", WINDOW_WIDTH, WINDOW_HEIGHT)
+        .window(WINDOW_TITLE, WINDOW_WIDTH, WINDOW_HEIGHT)
         .position_centered()
+        .resizable()
         .opengl()
         .build()
         .unwrap();
@@ -39,14 +105,43 @@ fn main() {
     canvas.present();
 
     let mut event_pump = sdl_context.event_pump().unwrap();
+    // Opened as they're plugged in, and those already plugged in are announced at the start
+    let controller_subsystem = sdl_context.game_controller().unwrap();
+    let mut controllers = Vec::new();
 
-    let mut chip8 = Chip8::init();
+    // Run silently without an audio device
+    let spec = AudioSpecDesired { freq: Some(DEFAULT_SAMPLE_RATE as i32), channels: Some(1), samples: None };
+    let audio = sdl_context.audio().and_then(|audio| audio.open_queue::<f32, _>(None, &spec));
+    let audio = match audio {
+        Ok(queue) => {
+            queue.resume();
+            Some(queue)
+        },
+        Err(err) => {
+            println!("No sound: {}", err);
+            None
+        },
+    };
 
-    let mut rom = File::open(&args[1]).expect("Unable to open file");
-    let mut buffer = Vec::new();
+    let config = load_config();
+    // Dropping a file on the window switches to it, and F2 reloads whichever was opened last from disk
+    let mut rom_path = args[1].clone();
+    let mut chip8 = Chip8::init();
+    chip8.set_recent_instructions(CORE_DUMP_INSTRUCTIONS);
+    // Taps quicker than a frame still count
+    chip8.enable_key_queue(true);
+    let Some(mut keymap) = open_game(&mut chip8, &rom_path, &config, &mut canvas) else {
+        return;
+    };
 
-    rom.read_to_end(&mut buffer).unwrap();
-    chip8.load(&buffer);
+    // F9 starts and stops recording a GIF, F10 a video with sound and F12 saves a screenshot
+    let mut recorder = GifRecorder::new(GIF_SCALE);
+    let mut video = VideoRecorder::new(VIDEO_SCALE, DEFAULT_SAMPLE_RATE);
+    // P pauses and resumes, Tab fast-forwards and ` slows down while held
+    let mut speed = SpeedControl::new();
+    // F5 saves and F8 loads, one slot per ROM
+    let slots = SaveSlots::new(&config.paths.saves);
+    let mut effects = Effects::default();
 
     'gameloop: loop {
         for evt in event_pump.poll_iter() {
@@ -54,68 +149,373 @@ fn main() {
                 Event::Quit{..} | Event::KeyDown{keycode: Some(Keycode::Escape), ..}=> {
                     break 'gameloop;
                 },
-                Event::KeyDown{keycode: Some(key), ..} => {
-                    if let Some(k) = key2btn(key) {
-                        chip8.keypress(k, true);
+                Event::DropFile{filename, ..} => {
+                    if let Some(game_keymap) = open_game(&mut chip8, &filename, &config, &mut canvas) {
+                        keymap = game_keymap;
+                        rom_path = filename;
                     }
                 },
-                Event::KeyUp{keycode: Some(key), ..} => {
-                    if let Some(k) = key2btn(key) {
-                        chip8.keypress(k, false);
+                Event::KeyDown{keycode: Some(Keycode::F2), repeat: false, ..} => {
+                    if let Some(game_keymap) = open_game(&mut chip8, &rom_path, &config, &mut canvas) {
+                        keymap = game_keymap;
+                    }
+                },
+                Event::KeyDown{keycode: Some(Keycode::F9), repeat: false, ..} => {
+                    toggle_recording(&mut recorder, &config);
+                },
+                Event::KeyDown{keycode: Some(Keycode::F10), repeat: false, ..} => {
+                    toggle_video(&mut video, &config);
+                },
+                Event::KeyDown{keycode: Some(Keycode::F12), repeat: false, ..} => {
+                    let path = capture_path(&config, "png");
+                    match chip8.screenshot_png(&path, config.palette, SCREENSHOT_SCALE) {
+                        Ok(()) => println!("Screenshot saved to {}", path.display()),
+                        Err(err) => println!("Couldn't save screenshot: {}", err),
                     }
                 },
+                Event::KeyDown{keycode: Some(Keycode::F3), repeat: false, ..} => effects.scanlines = !effects.scanlines,
+                Event::KeyDown{keycode: Some(Keycode::F4), repeat: false, ..} => effects.grid = !effects.grid,
+                Event::KeyDown{keycode: Some(Keycode::F6), repeat: false, ..} => effects.vignette = !effects.vignette,
+                Event::KeyDown{keycode: Some(Keycode::F11), repeat: false, ..} => toggle_fullscreen(&mut canvas),
+                Event::KeyDown{keycode: Some(Keycode::F5), repeat: false, ..} => {
+                    match slots.save_slot(&chip8, 0) {
+                        Ok(()) => println!("State saved to {}", slots.path(&chip8, 0).display()),
+                        Err(err) => println!("Couldn't save state: {}", err),
+                    }
+                },
+                Event::KeyDown{keycode: Some(Keycode::F8), repeat: false, ..} => {
+                    match slots.load_slot(&mut chip8, 0) {
+                        Ok(()) => println!("State loaded"),
+                        Err(err) => println!("Couldn't load state: {}", err),
+                    }
+                },
+                Event::KeyDown{keycode: Some(Keycode::P), repeat: false, ..} => {
+                    if chip8.is_paused() { chip8.resume() } else { chip8.pause() }
+                },
+                Event::KeyDown{keycode: Some(Keycode::Tab), ..} => speed.set_speed(FAST_FORWARD_SPEED),
+                Event::KeyDown{keycode: Some(Keycode::Backquote), ..} => speed.set_speed(SLOW_MOTION_SPEED),
+                Event::KeyUp{keycode: Some(Keycode::Tab | Keycode::Backquote), ..} => speed.set_speed(1.0),
+                Event::KeyDown{keycode: Some(key), ..} => {
+                    keymap.apply(&key.name(), true, &mut chip8);
+                },
+                Event::KeyUp{keycode: Some(key), ..} => {
+                    keymap.apply(&key.name(), false, &mut chip8);
+                },
+                Event::ControllerDeviceAdded{which, ..} => match controller_subsystem.open(which) {
+                    Ok(controller) => {
+                        println!("Gamepad connected: {}", controller.name());
+                        controllers.push(controller);
+                    },
+                    Err(err) => println!("Couldn't open gamepad: {}", err),
+                },
+                Event::ControllerDeviceRemoved{which, ..} => {
+                    controllers.retain(|controller| controller.instance_id() != which);
+                },
+                Event::ControllerButtonDown{button, ..} => {
+                    keymap.apply(&pad_name(button), true, &mut chip8);
+                },
+                Event::ControllerButtonUp{button, ..} => {
+                    keymap.apply(&pad_name(button), false, &mut chip8);
+                },
                 _ => ()
             }
         }
 
-        for _ in 0..TICKS_PER_FRAME {
-            chip8.clock();
+        match speed.run(&mut chip8) {
+            // Close the window once the ROM exits via 00FD
+            Ok(State::Halted) => break 'gameloop,
+            Ok(State::Running) => (),
+            Err(err) => {
+                println!("Emulator fault: {}", err);
+                let path = capture_path(&config, "core");
+                match CoreDump::new(&chip8, &err).save(&path) {
+                    Ok(()) => {
+                        println!("Core dump saved to {}, load it with the monitor's core command", path.display())
+                    },
+                    Err(err) => println!("Couldn't save core dump: {}", err),
+                }
+                break 'gameloop;
+            },
+        }
+        if let Err(err) = recorder.capture(&chip8) {
+            println!("Recording failed: {}", err);
+            let _ = recorder.stop();
+        }
+        if let Err(err) = video.capture(&chip8) {
+            println!("Video recording failed: {}", err);
+            let _ = video.stop();
         }
-        chip8.clock_timers();
-        draw_screen(&chip8, &mut canvas);
+        if let Some(queue) = &audio {
+            play_audio(&mut chip8, queue);
+        }
+        draw_screen(&chip8, &mut canvas, config.palette, effects);
+    }
+}
+
+// Queue a frame's worth of the beep, unless plenty is queued already
+fn play_audio(chip8: &mut Chip8, queue: &AudioQueue<f32>) {
+    let sample_rate = queue.spec().freq as u32;
+    let mut frame = vec![0.0; (sample_rate / 60) as usize];
+    let queued = queue.size() as usize / std::mem::size_of::<f32>();
+    if queued < frame.len() * AUDIO_FRAMES_QUEUED {
+        chip8.fill_audio(&mut frame, sample_rate);
+        queue.queue(&frame);
+    }
+}
+
+// Start the game at path on chip8, set up from the config, titling the window for it, and give its keymap. On
+// failure says why and gives None, and whatever was running carries on
+fn open_game(chip8: &mut Chip8, path: &str, config: &Config, canvas: &mut Canvas<Window>) -> Option<Keymap> {
+    if let Err(err) = load(chip8, path, config) {
+        println!("Unable to load {}", err);
+        return None;
+    }
+    // Known ROMs get their title in the window
+    let title = match chip8.rom_info() {
+        Some(info) => format!("{} - {}", WINDOW_TITLE, info.title),
+        None => String::from(WINDOW_TITLE),
+    };
+    let _ = canvas.window_mut().set_title(&title);
+    let keymap = load_keymap(chip8, config);
+    if let Some(info) = chip8.rom_info().filter(|info| !info.controls.is_empty()) {
+        println!("Controls: {}", control_hints(&keymap, info));
     }
+    Some(keymap)
 }
 
-fn draw_screen(emu: &Chip8, canvas: &mut Canvas<Window>) {
-    // Clear canvas as black
+// Octo cartridges and source are built on the way in, anything else is a ROM. Files are read before chip8 is
+// touched, so one that can't be leaves the game running
+fn load(chip8: &mut Chip8, path: &str, config: &Config) -> Result<(), String> {
+    let error = |err: &dyn std::fmt::Display| format!("{}: {}", path, err);
+    if path.ends_with(".gif") {
+        let cartridge = Cartridge::open(path).map_err(|err: CartridgeError| error(&err))?;
+        // Before loading, so the cartridge's own settings win
+        config.apply(chip8);
+        cartridge.load(chip8).map_err(|err| error(&err))
+    } else {
+        let rom = if path.ends_with(".8o") {
+            let source = std::fs::read_to_string(path).map_err(|err| error(&err))?;
+            chip8::compile_octo(&source).map_err(|err| error(&err))?.rom
+        } else {
+            std::fs::read(path).map_err(|err| error(&err))?
+        };
+        config.apply(chip8);
+        chip8.load_rom(&rom).map_err(|err| error(&err))
+    }
+}
+
+fn toggle_recording(recorder: &mut GifRecorder, config: &Config) {
+    if recorder.is_recording() {
+        match recorder.stop() {
+            Ok(()) => println!("Recording saved"),
+            Err(err) => println!("Recording failed: {}", err),
+        }
+        return;
+    }
+    let path = capture_path(config, "gif");
+    match recorder.start_file(&path) {
+        Ok(()) => println!("Recording to {}", path.display()),
+        Err(err) => println!("Couldn't start recording: {}", err),
+    }
+}
+
+fn toggle_video(video: &mut VideoRecorder, config: &Config) {
+    if video.is_recording() {
+        match video.stop() {
+            Ok(()) => println!("Video saved"),
+            Err(err) => println!("Video recording failed: {}", err),
+        }
+        return;
+    }
+    let path = capture_path(config, "avi");
+    match video.start_file(&path) {
+        Ok(()) => println!("Recording video to {}", path.display()),
+        Err(err) => println!("Couldn't start video recording: {}", err),
+    }
+}
+
+// Seconds since the epoch, to give saved files unique names
+fn timestamp() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs())
+}
+
+// A new file in the config's captures directory, which is made if need be
+fn capture_path(config: &Config, extension: &str) -> PathBuf {
+    let _ = std::fs::create_dir_all(&config.paths.captures);
+    config.paths.captures.join(format!("chip8-{}.{}", timestamp(), extension))
+}
+
+// CONFIG_PATH's settings, or the defaults
+fn load_config() -> Config {
+    match std::fs::read_to_string(CONFIG_PATH).map(|text| Config::parse(&text)) {
+        Ok(Ok(config)) => config,
+        Ok(Err(err)) => {
+            println!("Ignoring {}, {}", CONFIG_PATH, err);
+            Config::default()
+        },
+        Err(_) => Config::default(),
+    }
+}
+
+// The display as large as it fits in whole pixels, with black borders around it in a window of another shape
+fn draw_screen(emu: &Chip8, canvas: &mut Canvas<Window>, palette: Palette, effects: Effects) {
     canvas.set_draw_color(Color::RGB(0, 0, 0));
     canvas.clear();
+    let (window_width, window_height) = canvas.output_size().unwrap();
+    let width = emu.width();
+    // Hi-res mode has twice the pixels, so they come out half the size
+    let viewport = Viewport::fit(window_width, window_height, width, emu.height());
+    let [r, g, b] = palette.off;
+    canvas.set_draw_color(Color::RGB(r, g, b));
+    canvas.fill_rect(viewport_rect(viewport, 0)).unwrap();
 
-    let screen_buf = emu.get_display();
-    // Now set draw color to white, iterate through each point and see if it should be drawn
-    canvas.set_draw_color(Color::RGB(255, 255, 255));
-    for (i, pixel) in screen_buf.iter().enumerate() {
+    // Now set draw color to the foreground, iterate through each point and see if it should be drawn
+    let [r, g, b] = palette.on;
+    canvas.set_draw_color(Color::RGB(r, g, b));
+    for (i, pixel) in emu.get_display().iter().enumerate() {
         if *pixel {
-            // Convert our 1D array's index into a 2D (x,y) position
-            let x = (i % SCREEN_WIDTH) as u32;
-            let y = (i / SCREEN_WIDTH) as u32;
-
-            // Draw a rectangle at (x,y), scaled up by our SCALE value
-            let rect = Rect::new((x * SCALE) as i32, (y * SCALE) as i32, SCALE, SCALE);
-            canvas.fill_rect(rect).unwrap();
+            let (x, y) = viewport.pixel(i % width, i / width);
+            canvas.fill_rect(Rect::new(x as i32, y as i32, viewport.scale, viewport.scale)).unwrap();
         }
     }
+    draw_effects(canvas, effects, viewport);
     canvas.present();
 }
 
-fn key2btn(key: Keycode) -> Option<usize> {
-    match key {
-        Keycode::Num1 =>    Some(0x1),
-        Keycode::Num2 =>    Some(0x2),
-        Keycode::Num3 =>    Some(0x3),
-        Keycode::Num4 =>    Some(0xC),
-        Keycode::Q =>       Some(0x4),
-        Keycode::W =>       Some(0x5),
-        Keycode::E =>       Some(0x6),
-        Keycode::R =>       Some(0xD),
-        Keycode::A =>       Some(0x7),
-        Keycode::S =>       Some(0x8),
-        Keycode::D =>       Some(0x9),
-        Keycode::F =>       Some(0xE),
-        Keycode::Z =>       Some(0xA),
-        Keycode::X =>       Some(0x0),
-        Keycode::C =>       Some(0xB),
-        Keycode::V =>       Some(0xF),
-        _ =>                None,
-    }
-}
\ No newline at end of file
+// Black at the effects' alphas blended over the display
+fn draw_effects(canvas: &mut Canvas<Window>, effects: Effects, viewport: Viewport) {
+    let Viewport { x, y, scale, width, height } = viewport;
+    let (x, y) = (x as i32, y as i32);
+    canvas.set_blend_mode(BlendMode::Blend);
+    if effects.scanlines {
+        canvas.set_draw_color(Color::RGBA(0, 0, 0, SCANLINE_ALPHA));
+        let lines = (0..height).step_by(SCANLINE_SPACING as usize);
+        canvas.fill_rects(&lines.map(|row| Rect::new(x, y + row as i32, width, 1)).collect::<Vec<_>>()).unwrap();
+    }
+    if effects.grid {
+        canvas.set_draw_color(Color::RGBA(0, 0, 0, GRID_ALPHA));
+        let columns = (0..width).step_by(scale as usize).map(|column| Rect::new(x + column as i32, y, 1, height));
+        let rows = (0..height).step_by(scale as usize).map(|row| Rect::new(x, y + row as i32, width, 1));
+        canvas.fill_rects(&columns.chain(rows).collect::<Vec<_>>()).unwrap();
+    }
+    if effects.vignette {
+        // An outline a pixel in from the last, fading from the edge inwards
+        for inset in 0..VIGNETTE_DEPTH.min(width.min(height) / 2) {
+            let fade = 1.0 - inset as f32 / VIGNETTE_DEPTH as f32;
+            canvas.set_draw_color(Color::RGBA(0, 0, 0, (VIGNETTE_ALPHA * fade * fade) as u8));
+            canvas.draw_rect(viewport_rect(viewport, inset)).unwrap();
+        }
+    }
+    canvas.set_blend_mode(BlendMode::None);
+}
+
+// The viewport less inset pixels all round
+fn viewport_rect(viewport: Viewport, inset: u32) -> Rect {
+    let (x, y) = ((viewport.x + inset) as i32, (viewport.y + inset) as i32);
+    Rect::new(x, y, viewport.width - 2 * inset, viewport.height - 2 * inset)
+}
+
+// Between the window and the whole screen at the desktop's resolution
+fn toggle_fullscreen(canvas: &mut Canvas<Window>) {
+    let window = canvas.window_mut();
+    let fullscreen = match window.fullscreen_state() {
+        FullscreenType::Off => FullscreenType::Desktop,
+        _ => FullscreenType::Off,
+    };
+    if let Err(err) = window.set_fullscreen(fullscreen) {
+        println!("Couldn't change fullscreen: {}", err);
+    }
+}
+
+// The ROM's own keymap, or the config's, or KEYMAP_PATH's, or QWERTY. Unless it says where the pad's buttons
+// go, they're given the keys the ROM database says the game plays with
+fn load_keymap(chip8: &Chip8, config: &Config) -> Keymap {
+    let own = chip8.rom_hash().map(|hash| config.paths.keymaps.join(format!("{}.txt", hash)));
+    let mut keymap = match (own.filter(|path| path.exists()), &config.keymap) {
+        (None, Some(keymap)) => keymap.clone(),
+        (own, _) => read_keymap(&own.unwrap_or_else(|| PathBuf::from(KEYMAP_PATH))),
+    };
+    if !keymap.iter().any(|(host, _)| is_pad(host)) {
+        match chip8.rom_info() {
+            Some(info) if !info.controls.is_empty() => bind_pad_controls(&mut keymap, info),
+            Some(info) if info.keys != 0 => bind_pad(&mut keymap, info.keys),
+            _ => bind_pad(&mut keymap, DEFAULT_PAD_KEYS),
+        }
+    }
+    keymap
+}
+
+// QWERTY if path isn't there or doesn't parse
+fn read_keymap(path: &Path) -> Keymap {
+    match std::fs::read_to_string(path).map(|text| Keymap::parse(&text)) {
+        Ok(Ok(keymap)) => keymap,
+        Ok(Err(err)) => {
+            println!("Ignoring {}, {}", path.display(), err);
+            Keymap::qwerty()
+        },
+        Err(_) => Keymap::qwerty(),
+    }
+}
+
+// Put the directions among keys on the d-pad and the rest on the buttons, as many as fit
+fn bind_pad(keymap: &mut Keymap, keys: u16) {
+    let mut buttons = PAD_BUTTONS.into_iter();
+    for key in (0..16).filter(|key| keys & 1 << key != 0) {
+        let button = match DPAD_KEYS.iter().find(|(dpad_key, _)| *dpad_key == key) {
+            Some((_, button)) => Some(*button),
+            None => buttons.next(),
+        };
+        if let Some(button) = button {
+            keymap.bind(&pad_name(button), key);
+        }
+    }
+}
+
+// Put the game's moves on the d-pad and its other controls on the buttons in the order it lists them, then
+// any keys it plays with that aren't among its controls
+fn bind_pad_controls(keymap: &mut Keymap, info: &RomInfo) {
+    let mut buttons = PAD_BUTTONS.into_iter();
+    let mut bound = 0u16;
+    for (key, action) in info.controls {
+        let button = match action {
+            Action::Up => Some(Button::DPadUp),
+            Action::Down => Some(Button::DPadDown),
+            Action::Left => Some(Button::DPadLeft),
+            Action::Right => Some(Button::DPadRight),
+            _ => buttons.next(),
+        };
+        if let Some(button) = button {
+            keymap.bind(&pad_name(button), *key);
+            bound |= 1 << key;
+        }
+    }
+    for key in (0..16).filter(|key| info.keys & !bound & 1 << key != 0) {
+        if let Some(button) = buttons.next() {
+            keymap.bind(&pad_name(button), key);
+        }
+    }
+}
+
+// The game's controls with the keyboard keys that press them, e.g. "Q left, E right, W fire"
+fn control_hints(keymap: &Keymap, info: &RomInfo) -> String {
+    let hints: Vec<_> = info
+        .controls
+        .iter()
+        .map(|(key, action)| {
+            let host = keymap.iter().find(|(host, bound)| bound == key && !is_pad(host)).map(|(host, _)| host);
+            match host {
+                Some(host) => format!("{} {}", host, action),
+                None => format!("keypad {:X} {}", key, action),
+            }
+        })
+        .collect();
+    hints.join(", ")
+}
+
+fn is_pad(host: &str) -> bool {
+    host.to_ascii_lowercase().starts_with("pad ")
+}
+
+fn pad_name(button: Button) -> String {
+    format!("Pad {}", button.string())
+}
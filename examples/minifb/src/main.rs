@@ -0,0 +1,96 @@
+use std::env;
+use std::process;
+
+use chip8::{Chip8, Keymap, Palette, State, Viewport, HIRES_SCREEN_HEIGHT, HIRES_SCREEN_WIDTH};
+use minifb::{Key, KeyRepeat, Scale, ScaleMode, Window, WindowOptions};
+
+// The display's frame rate, which the timers count down at and run_frame() is paced for
+const FPS: usize = 60;
+
+// About the least a playable frontend takes, for seeing how the core fits into one: load a ROM, make a frame
+// of the keys pressed, run_frame() sixty times a second and draw get_display(). There's no sound, which
+// minifb doesn't do, but fill_audio() gives the beep's samples for any audio library that does. P pauses
+// and Escape quits
+fn main() {
+    let Some(path) = env::args().nth(1) else {
+        exit("usage: minifb-example <rom>");
+    };
+    let mut chip8 = Chip8::init();
+    chip8.load_file(&path).unwrap_or_else(|err| exit(&err.to_string()));
+    // Taps quicker than a frame still count, and FX0A takes a key once it's let go, as the original did
+    chip8.enable_key_queue(true);
+
+    // Room for hi-res, which lo-res games are doubled up to fill
+    let (width, height) = (HIRES_SCREEN_WIDTH, HIRES_SCREEN_HEIGHT);
+    let options = WindowOptions {
+        resize: true,
+        scale: Scale::X8,
+        scale_mode: ScaleMode::AspectRatioStretch,
+        ..Default::default()
+    };
+    let mut window = Window::new("CHIP-8", width, height, options).unwrap_or_else(|err| exit(&err.to_string()));
+    // update_with_buffer() waits out the rest of each frame, which times the emulation too
+    window.set_target_fps(FPS);
+    let mut buffer = vec![0u32; width * height];
+    let palette = Palette::default();
+    // 1234, QWER, ASDF and ZXCV, which minifb names the same but for the digits
+    let keymap = Keymap::qwerty();
+
+    while window.is_open() && !window.is_key_down(Key::Escape) {
+        if window.is_key_pressed(Key::P, KeyRepeat::No) {
+            if chip8.is_paused() {
+                chip8.resume();
+            } else {
+                chip8.pause();
+            }
+        }
+        // Setting every key every frame is fine, keypress() ignores those that haven't changed
+        let mut down = [false; 16];
+        for host in window.get_keys() {
+            if let Some(key) = keymap.key(&key_name(host)) {
+                down[key] = true;
+            }
+        }
+        for (key, pressed) in down.into_iter().enumerate() {
+            chip8.keypress(key, pressed);
+        }
+        match chip8.run_frame() {
+            Ok(State::Running) => (),
+            // The ROM exited with 00FD
+            Ok(State::Halted) => break,
+            Err(err) => exit(&format!("fault: {}", err)),
+        }
+        draw(&chip8, palette, &mut buffer, width, height);
+        window.update_with_buffer(&buffer, width, height).unwrap_or_else(|err| exit(&err.to_string()));
+    }
+}
+
+// The name a keymap knows host by, which is minifb's but for the digits, which are "1" rather than "Key1"
+fn key_name(host: Key) -> String {
+    let name = format!("{:?}", host);
+    match name.strip_prefix("Key") {
+        Some(digit) if digit.len() == 1 => digit.to_string(),
+        _ => name,
+    }
+}
+
+// The display into buffer, as 0RGB, centred in whole pixels
+fn draw(chip8: &Chip8, palette: Palette, buffer: &mut [u32], width: usize, height: usize) {
+    let rgb = |[r, g, b]: [u8; 3]| u32::from_be_bytes([0, r, g, b]);
+    let (off, on) = (rgb(palette.off), rgb(palette.on));
+    let display_width = chip8.width();
+    let viewport = Viewport::fit(width as u32, height as u32, display_width, chip8.height());
+    buffer.fill(off);
+    for (i, _) in chip8.get_display().iter().enumerate().filter(|(_, pixel)| **pixel) {
+        let (x, y) = viewport.pixel(i % display_width, i / display_width);
+        let (x, y, scale) = (x as usize, y as usize, viewport.scale as usize);
+        for row in buffer[y * width..].chunks_mut(width).take(scale) {
+            row[x..x + scale].fill(on);
+        }
+    }
+}
+
+fn exit(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1)
+}
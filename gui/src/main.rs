@@ -0,0 +1,497 @@
+use std::env;
+use std::fs;
+
+use chip8::{
+    compile_octo, Breakpoint, BreakpointId, Chip8, Config, Debugger, Glyph, Instruction, Keymap, Palette, State, Stop,
+    Symbols, Viewport, HEX_ROW_LEN, MAX_SPRITE_ROWS,
+};
+use eframe::egui::{self, Color32, RichText, Sense, Vec2};
+
+// Read for the quirks, speed, palette and keymap, as the desktop frontend does
+const CONFIG_PATH: &str = "chip8.toml";
+// Instructions listed either side of the PC
+const DISASM_CONTEXT: usize = 12;
+const MEM_ROWS: usize = 16;
+// Points to a pixel of the sprite preview
+const SPRITE_SCALE: f32 = 8.0;
+const GLYPH_SCALE: f32 = 3.0;
+// Frames run in one repaint at most, so a stall doesn't turn into a burst of catching up
+const MAX_FRAMES_PER_UPDATE: f32 = 4.0;
+
+// A debugger window for watching a ROM run: the screen, registers, the disassembly around the PC, memory,
+// the stack and breakpoints, all redrawn as it goes
+fn main() -> eframe::Result<()> {
+    let mut gui = Gui::new(load_config());
+    if let Some(path) = env::args().nth(1) {
+        gui.path = path.clone();
+        gui.load(&path);
+    }
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default().with_inner_size([1100.0, 720.0]),
+        ..Default::default()
+    };
+    eframe::run_native("CHIP-8 debugger", options, Box::new(|_| Box::new(gui)))
+}
+
+struct Gui {
+    debugger: Option<Debugger>,
+    rom: Option<Vec<u8>>,
+    // Given to each machine started
+    symbols: Symbols,
+    config: Config,
+    keymap: Keymap,
+    running: bool,
+    // Which font the character map shows
+    large_font: bool,
+    // Frames owed to the real time passed, run as whole ones
+    owed: f32,
+    // Why it last stopped, or what went wrong
+    status: String,
+    // What's typed into the ROM, memory and breakpoint fields
+    path: String,
+    mem_addr: String,
+    breakpoint: String,
+}
+
+impl Gui {
+    fn new(config: Config) -> Self {
+        Self {
+            debugger: None,
+            rom: None,
+            symbols: Symbols::new(),
+            keymap: config.keymap.clone().unwrap_or_else(Keymap::qwerty),
+            config,
+            running: false,
+            large_font: false,
+            owed: 0.0,
+            status: String::new(),
+            path: String::new(),
+            mem_addr: String::new(),
+            breakpoint: String::new(),
+        }
+    }
+
+    // A ROM, or Octo source (.8o) built with its labels
+    fn load(&mut self, path: &str) {
+        let rom = if path.ends_with(".8o") {
+            let program = fs::read_to_string(path)
+                .map_err(|err| err.to_string())
+                .and_then(|source| compile_octo(&source).map_err(|err| err.to_string()));
+            match program {
+                Ok(program) => {
+                    self.symbols = program.symbols();
+                    program.rom
+                },
+                Err(err) => {
+                    self.status = format!("couldn't build {}: {}", path, err);
+                    return;
+                },
+            }
+        } else {
+            match fs::read(path) {
+                // The last program's labels don't apply to this one
+                Ok(rom) => {
+                    self.symbols = Symbols::new();
+                    rom
+                },
+                Err(err) => {
+                    self.status = format!("couldn't read {}: {}", path, err);
+                    return;
+                },
+            }
+        };
+        self.start(rom);
+    }
+
+    fn start(&mut self, rom: Vec<u8>) {
+        let mut chip8 = Chip8::init();
+        self.config.apply(&mut chip8);
+        if let Err(err) = chip8.load(&rom) {
+            self.status = err.to_string();
+            return;
+        }
+        chip8.set_symbols(self.symbols.clone());
+        self.debugger = Some(Debugger::new(chip8));
+        self.rom = Some(rom);
+        self.running = false;
+        self.status = String::from("loaded, press Run");
+    }
+
+    // Stop running on anything but a frame or step coming to its end, saying why
+    fn report(&mut self, stop: chip8::Result<Stop>) {
+        self.status = match stop {
+            Ok(Stop::FrameComplete | Stop::StepComplete) => {
+                self.status.clear();
+                return;
+            },
+            Ok(Stop::Breakpoint(id)) => format!("breakpoint {}", id.0),
+            Ok(Stop::Halted) => String::from("halted"),
+            Ok(Stop::StepLimit) => String::from("gave up waiting for the subroutine to return"),
+            Err(err) => format!("fault: {}", err),
+        };
+        self.running = false;
+    }
+
+    // Run up to frames frames, stopping early on whatever report() stops on
+    fn run_frames(&mut self, frames: usize) {
+        for _ in 0..frames {
+            let Some(debugger) = &mut self.debugger else {
+                return;
+            };
+            let stop = debugger.run_frame();
+            self.report(stop);
+            if !self.running {
+                return;
+            }
+        }
+    }
+
+    // Keypad keys from the keyboard, unless a text field has it
+    fn keys(&mut self, ctx: &egui::Context) {
+        let Some(debugger) = &mut self.debugger else {
+            return;
+        };
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+        let keymap = &self.keymap;
+        ctx.input(|input| {
+            for event in &input.events {
+                if let egui::Event::Key { key, pressed, repeat: false, .. } = event {
+                    keymap.apply(key.name(), *pressed, debugger.chip8_mut());
+                }
+            }
+        });
+    }
+
+    // A label or a hex address
+    fn parse_addr(&self, text: &str) -> Option<u16> {
+        let text = text.trim();
+        self.symbols.address(text).or_else(|| {
+            let digits = text.trim_start_matches("0x").trim_start_matches("0X");
+            u16::from_str_radix(digits, 16).ok()
+        })
+    }
+
+    fn controls(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("ROM");
+            let field = ui.add(egui::TextEdit::singleline(&mut self.path).desired_width(240.0));
+            let entered = field.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter));
+            if ui.button("Load").clicked() || entered {
+                let path = self.path.clone();
+                self.load(&path);
+            }
+            ui.separator();
+            let loaded = self.debugger.is_some();
+            if ui.add_enabled(loaded, egui::Button::new(if self.running { "Pause" } else { "Run" })).clicked() {
+                self.running = !self.running;
+                self.status.clear();
+            }
+            ui.add_enabled_ui(loaded && !self.running, |ui| {
+                let Some(debugger) = &mut self.debugger else {
+                    return;
+                };
+                let [step, over, out, back] = ["Step", "Over", "Out", "Back"].map(|name| ui.button(name).clicked());
+                let stop = if step {
+                    Some(debugger.step().map(|state| match state {
+                        State::Halted => Stop::Halted,
+                        State::Running => Stop::StepComplete,
+                    }))
+                } else if over {
+                    Some(debugger.step_over())
+                } else if out {
+                    Some(debugger.step_out())
+                } else {
+                    if back && !debugger.step_back() {
+                        self.status = String::from("no more history");
+                    }
+                    None
+                };
+                if let Some(stop) = stop {
+                    self.report(stop);
+                }
+            });
+            if ui.add_enabled(self.rom.is_some(), egui::Button::new("Reset")).clicked() {
+                let rom = self.rom.clone().expect("a ROM to reset");
+                self.start(rom);
+            }
+            ui.separator();
+            ui.label(&self.status);
+        });
+    }
+
+    fn screen(&self, ui: &mut egui::Ui) {
+        let Some(debugger) = &self.debugger else {
+            ui.label("Load a ROM to start, from the field above or by passing its path");
+            return;
+        };
+        let chip8 = debugger.chip8();
+        let (width, height) = (chip8.width(), chip8.height());
+        // Whole pixels as large as fit, so none come out wider than the rest
+        let available = ui.available_size();
+        let viewport = Viewport::fit(available.x as u32, available.y as u32, width, height);
+        let display = chip8.get_display();
+        draw_pixels(ui, self.config.palette, (width, height), viewport.scale as f32, |x, y| display[y * width + x]);
+    }
+
+    // The font's glyphs as they are in RAM, the one I points at picked out
+    fn font(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.heading("Font");
+            ui.checkbox(&mut self.large_font, "large");
+        });
+        let Some(debugger) = &self.debugger else {
+            return;
+        };
+        let chip8 = debugger.chip8();
+        let glyphs: Vec<Glyph> =
+            if self.large_font { chip8.large_font_glyphs().collect() } else { chip8.font_glyphs().collect() };
+        ui.horizontal_wrapped(|ui| {
+            for glyph in glyphs {
+                ui.vertical(|ui| {
+                    let rows = glyph.rows();
+                    draw_pixels(ui, self.config.palette, (8, rows.len()), GLYPH_SCALE, |x, y| rows[y][x]);
+                    let label = RichText::new(format!("{:X} {:03X}", glyph.digit, glyph.addr)).monospace();
+                    ui.label(if glyph.addr == chip8.i() { label.strong() } else { label });
+                });
+            }
+        });
+    }
+
+    // What a DXYN would draw from I, as tall as the one at the PC or as tall as they come
+    fn sprite(&self, ui: &mut egui::Ui) {
+        ui.heading("Sprite at I");
+        let Some(debugger) = &self.debugger else {
+            return;
+        };
+        let height = match debugger.current_instruction() {
+            Some(Instruction::Draw(_, _, n)) if n > 0 => n as usize,
+            _ => MAX_SPRITE_ROWS,
+        };
+        let rows = debugger.chip8().sprite_preview(height);
+        draw_pixels(ui, self.config.palette, (8, height), SPRITE_SCALE, |x, y| rows[y][x]);
+    }
+
+    fn registers(&self, ui: &mut egui::Ui) {
+        ui.heading("Registers");
+        let Some(debugger) = &self.debugger else {
+            return;
+        };
+        let chip8 = debugger.chip8();
+        egui::Grid::new("registers").show(ui, |ui| {
+            for row in 0..4 {
+                for x in row * 4..row * 4 + 4 {
+                    ui.monospace(format!("V{:X} {:02X}", x, chip8.v(x)));
+                }
+                ui.end_row();
+            }
+            ui.monospace(format!("I  {:03X}", chip8.i()));
+            ui.monospace(format!("PC {:03X}", chip8.pc()));
+            ui.monospace(format!("DT {:02X}", chip8.delay_timer()));
+            ui.monospace(format!("ST {:02X}", chip8.sound_timer()));
+            ui.end_row();
+        });
+    }
+
+    // Innermost call first, by the label returned to where there is one
+    fn stack(&self, ui: &mut egui::Ui) {
+        ui.heading("Stack");
+        let Some(debugger) = &self.debugger else {
+            return;
+        };
+        let chip8 = debugger.chip8();
+        if chip8.stack().is_empty() {
+            ui.label("empty");
+        }
+        for addr in chip8.stack().iter().rev() {
+            match chip8.symbols().name(*addr) {
+                Some(name) => ui.monospace(format!("{:03X} {}", addr, name)),
+                None => ui.monospace(format!("{:03X}", addr)),
+            };
+        }
+    }
+
+    // Clicking a line sets or clears a breakpoint on it
+    fn disassembly(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Disassembly");
+        let Some(debugger) = &mut self.debugger else {
+            return;
+        };
+        let chip8 = debugger.chip8();
+        let (pc, ram) = (chip8.pc() as usize, chip8.ram());
+        let mut clicked = None;
+        let addrs = (pc.saturating_sub(DISASM_CONTEXT * 2)..ram.len() - 1).step_by(2).take(DISASM_CONTEXT * 2 + 1);
+        for addr in addrs {
+            if let Some(name) = chip8.symbols().name(addr as u16) {
+                ui.monospace(format!("{}:", name));
+            }
+            let opcode = u16::from_be_bytes([ram[addr], ram[addr + 1]]);
+            let text = match Instruction::decode(opcode) {
+                Some(instruction) => instruction.with_symbols(chip8.symbols()).to_string(),
+                None => String::from("??"),
+            };
+            let marker = match (addr == pc, address_breakpoint(debugger, addr as u16).is_some()) {
+                (true, _) => '>',
+                (false, true) => '*',
+                (false, false) => ' ',
+            };
+            let mut line = RichText::new(format!("{}{:03X}: {:04X}  {}", marker, addr, opcode, text)).monospace();
+            if addr == pc {
+                line = line.strong();
+            }
+            if ui.add(egui::Label::new(line).sense(Sense::click())).clicked() {
+                clicked = Some(addr as u16);
+            }
+        }
+        if let Some(addr) = clicked {
+            match address_breakpoint(debugger, addr) {
+                Some(id) => {
+                    debugger.remove_breakpoint(id);
+                },
+                None => {
+                    debugger.add_breakpoint(Breakpoint::Address(addr));
+                },
+            }
+        }
+    }
+
+    // From the address typed in, or following I when there isn't one
+    fn memory(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.heading("Memory");
+            ui.add(egui::TextEdit::singleline(&mut self.mem_addr).hint_text("I").desired_width(80.0));
+        });
+        let Some(debugger) = &self.debugger else {
+            return;
+        };
+        let chip8 = debugger.chip8();
+        let start = usize::from(match self.mem_addr.trim() {
+            "" => chip8.i(),
+            text => match self.parse_addr(text) {
+                Some(addr) => addr,
+                None => {
+                    ui.label(format!("bad address '{}', expected a label or hex", text));
+                    return;
+                },
+            },
+        });
+        let start = start / HEX_ROW_LEN * HEX_ROW_LEN;
+        for row in chip8.hexdump(start..start + MEM_ROWS * HEX_ROW_LEN) {
+            ui.monospace(row.to_string());
+        }
+    }
+
+    // An address or label, or a condition like `v3 == 10`, as the monitor's `break` takes
+    fn breakpoints(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Breakpoints");
+        let mut add = false;
+        ui.horizontal(|ui| {
+            let field = ui.add(egui::TextEdit::singleline(&mut self.breakpoint).desired_width(140.0));
+            let entered = field.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter));
+            add = ui.button("Add").clicked() || entered;
+        });
+        let text = self.breakpoint.trim().to_string();
+        let addr = self.parse_addr(&text);
+        let Some(debugger) = &mut self.debugger else {
+            return;
+        };
+        if add && !text.is_empty() {
+            // A lone label or hex number is an address, anything else a condition
+            let added = match addr {
+                Some(addr) if !text.contains(' ') => Ok(debugger.add_breakpoint(Breakpoint::Address(addr))),
+                _ => debugger.add_condition(&text).map_err(|err| err.to_string()),
+            };
+            match added {
+                Ok(_) => self.breakpoint.clear(),
+                Err(err) => self.status = err,
+            }
+        }
+        let mut remove = None;
+        for (id, breakpoint) in debugger.breakpoints() {
+            ui.horizontal(|ui| {
+                if ui.small_button("x").clicked() {
+                    remove = Some(id);
+                }
+                match breakpoint {
+                    Breakpoint::Address(addr) => match self.symbols.name(*addr) {
+                        Some(name) => ui.monospace(format!("{:>3}: {:03X} {}", id.0, addr, name)),
+                        None => ui.monospace(format!("{:>3}: {:03X}", id.0, addr)),
+                    },
+                    Breakpoint::Condition(condition) => ui.monospace(format!("{:>3}: {}", id.0, condition)),
+                };
+            });
+        }
+        if let Some(id) = remove {
+            debugger.remove_breakpoint(id);
+        }
+    }
+}
+
+impl eframe::App for Gui {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.keys(ctx);
+        if self.running {
+            // Frames at 60 a second whatever the repaint rate
+            self.owed = (self.owed + ctx.input(|input| input.stable_dt) * 60.0).min(MAX_FRAMES_PER_UPDATE);
+            let frames = self.owed as usize;
+            self.owed -= frames as f32;
+            self.run_frames(frames);
+            ctx.request_repaint();
+        }
+        egui::TopBottomPanel::top("controls").show(ctx, |ui| self.controls(ui));
+        egui::SidePanel::left("disassembly").show(ctx, |ui| self.disassembly(ui));
+        egui::SidePanel::right("state").show(ctx, |ui| {
+            self.registers(ui);
+            ui.separator();
+            self.stack(ui);
+            ui.separator();
+            self.sprite(ui);
+            ui.separator();
+            self.font(ui);
+            ui.separator();
+            self.breakpoints(ui);
+        });
+        egui::TopBottomPanel::bottom("memory").show(ctx, |ui| self.memory(ui));
+        egui::CentralPanel::default().show(ctx, |ui| self.screen(ui));
+    }
+}
+
+fn address_breakpoint(debugger: &Debugger, addr: u16) -> Option<BreakpointId> {
+    let mut breakpoints = debugger.breakpoints();
+    breakpoints.find(|(_, breakpoint)| matches!(breakpoint, Breakpoint::Address(at) if *at == addr)).map(|(id, _)| id)
+}
+
+// A grid of width by height pixels, scale points square, lit where lit(x, y) says
+fn draw_pixels(
+    ui: &mut egui::Ui,
+    palette: Palette,
+    (width, height): (usize, usize),
+    scale: f32,
+    lit: impl Fn(usize, usize) -> bool,
+) {
+    let (rect, _) = ui.allocate_exact_size(Vec2::new(width as f32, height as f32) * scale, Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 0.0, colour(palette.off));
+    let on = colour(palette.on);
+    for (x, y) in (0..height).flat_map(|y| (0..width).map(move |x| (x, y))).filter(|(x, y)| lit(*x, *y)) {
+        let min = rect.min + Vec2::new(x as f32, y as f32) * scale;
+        painter.rect_filled(egui::Rect::from_min_size(min, Vec2::splat(scale)), 0.0, on);
+    }
+}
+
+fn colour([r, g, b]: [u8; 3]) -> Color32 {
+    Color32::from_rgb(r, g, b)
+}
+
+// CONFIG_PATH's settings, or the defaults
+fn load_config() -> Config {
+    match fs::read_to_string(CONFIG_PATH).map(|text| Config::parse(&text)) {
+        Ok(Ok(config)) => config,
+        Ok(Err(err)) => {
+            eprintln!("ignoring {}, {}", CONFIG_PATH, err);
+            Config::default()
+        },
+        Err(_) => Config::default(),
+    }
+}
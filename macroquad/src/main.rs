@@ -0,0 +1,143 @@
+use std::env;
+use std::time::Duration;
+
+use chip8::{Chip8, Keymap, SoundEdge, State, Viewport};
+use macroquad::audio::{load_sound_from_bytes, play_sound, stop_sound, PlaySoundParams};
+use macroquad::miniquad;
+use macroquad::prelude::*;
+
+// Loaded when no ROM is named, which in a browser it can't be, fetched from alongside the page
+const DEFAULT_ROM: &str = "rom.ch8";
+const SCALE: i32 = 15;
+// A second of the beep, looped while the sound timer runs
+const BEEP_SAMPLE_RATE: u32 = 44100;
+const OFF: Color = BLACK;
+const ON: Color = WHITE;
+const BORDER: Color = Color::new(0.1, 0.1, 0.1, 1.0);
+
+fn window_conf() -> Conf {
+    Conf {
+        window_title: String::from("CHIP-8"),
+        window_width: SCALE * chip8::SCREEN_WIDTH as i32,
+        window_height: SCALE * chip8::SCREEN_HEIGHT as i32,
+        window_resizable: true,
+        ..Default::default()
+    }
+}
+
+// A frontend that builds the same natively and for the web, with no SDL to install:
+//     cargo run --release -- roms/PONG
+//     cargo build --release --target wasm32-unknown-unknown
+// index.html runs the .wasm from target/wasm32-unknown-unknown/release in a browser, playing the rom.ch8
+// served beside them. P pauses and Escape quits
+#[macroquad::main(window_conf)]
+async fn main() {
+    let path = env::args().nth(1).unwrap_or_else(|| String::from(DEFAULT_ROM));
+    let rom = match load_file(&path).await {
+        Ok(rom) => rom,
+        Err(err) => return show_message(&format!("couldn't load {}: {}", path, err)).await,
+    };
+    // The core without std can't get entropy from the OS for CXNN, so the clock stands in
+    let mut chip8 = Chip8::init();
+    chip8.seed_rng(miniquad::date::now().to_bits());
+    // Taps quicker than a frame still count
+    chip8.enable_key_queue(true);
+    if let Err(err) = chip8.load(&rom) {
+        return show_message(&format!("couldn't load {}: {}", path, err)).await;
+    }
+    // Silent if the platform's audio won't take it
+    let beep = load_sound_from_bytes(&beep_wav(&chip8)).await.ok();
+    let keymap = Keymap::qwerty();
+
+    while !is_key_pressed(KeyCode::Escape) {
+        if is_key_pressed(KeyCode::P) {
+            if chip8.is_paused() {
+                chip8.resume();
+            } else {
+                chip8.pause();
+            }
+        }
+        // Every keypad key with a host key down for it, and keypress() ignores keys that haven't changed
+        let mut down = [false; 16];
+        for host in get_keys_down() {
+            if let Some(key) = keymap.key(&key_name(host)) {
+                down[key] = true;
+            }
+        }
+        for (key, pressed) in down.into_iter().enumerate() {
+            chip8.keypress(key, pressed);
+        }
+        // The time since the last draw, so it runs at the same speed whatever the refresh rate
+        match chip8.advance(Duration::from_secs_f32(get_frame_time())) {
+            Ok(State::Running) => (),
+            Ok(State::Halted) => return show_message("the ROM has exited").await,
+            Err(err) => return show_message(&format!("fault: {}", err)).await,
+        }
+        match (&beep, chip8.sound_edge()) {
+            (Some(beep), Some(SoundEdge::Started)) => play_sound(beep, PlaySoundParams { looped: true, volume: 1.0 }),
+            (Some(beep), Some(SoundEdge::Stopped)) => stop_sound(beep),
+            _ => (),
+        }
+        draw(&chip8);
+        next_frame().await;
+    }
+}
+
+// The name a keymap knows host by, which is macroquad's but for the digits, which are "1" rather than "Key1"
+fn key_name(host: KeyCode) -> String {
+    let name = format!("{:?}", host);
+    match name.strip_prefix("Key") {
+        Some(digit) if digit.len() == 1 => digit.to_string(),
+        _ => name,
+    }
+}
+
+// The display as large as fits in whole pixels, centred
+fn draw(chip8: &Chip8) {
+    clear_background(BORDER);
+    let width = chip8.width();
+    let viewport = Viewport::fit(screen_width() as u32, screen_height() as u32, width, chip8.height());
+    let scale = viewport.scale as f32;
+    draw_rectangle(viewport.x as f32, viewport.y as f32, viewport.width as f32, viewport.height as f32, OFF);
+    for (i, _) in chip8.get_display().iter().enumerate().filter(|(_, pixel)| **pixel) {
+        let (x, y) = viewport.pixel(i % width, i / width);
+        draw_rectangle(x as f32, y as f32, scale, scale, ON);
+    }
+}
+
+// The machine's beep as a 16-bit mono WAV, from a scratch machine with its sound timer running
+fn beep_wav(chip8: &Chip8) -> Vec<u8> {
+    let mut scratch = Chip8::init();
+    scratch.set_buzzer(chip8.buzzer());
+    scratch.set_sound_timer(u8::MAX);
+    let mut samples = vec![0.0; BEEP_SAMPLE_RATE as usize];
+    scratch.fill_audio(&mut samples, BEEP_SAMPLE_RATE);
+    let data_len = samples.len() as u32 * 2;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend(b"RIFF");
+    wav.extend((36 + data_len).to_le_bytes());
+    wav.extend(b"WAVEfmt ");
+    // PCM, one channel, two bytes a sample
+    wav.extend(16u32.to_le_bytes());
+    wav.extend(1u16.to_le_bytes());
+    wav.extend(1u16.to_le_bytes());
+    wav.extend(BEEP_SAMPLE_RATE.to_le_bytes());
+    wav.extend((BEEP_SAMPLE_RATE * 2).to_le_bytes());
+    wav.extend(2u16.to_le_bytes());
+    wav.extend(16u16.to_le_bytes());
+    wav.extend(b"data");
+    wav.extend(data_len.to_le_bytes());
+    for sample in samples {
+        wav.extend(((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16).to_le_bytes());
+    }
+    wav
+}
+
+// Shown until Escape, when nothing can run
+async fn show_message(message: &str) {
+    while !is_key_pressed(KeyCode::Escape) {
+        clear_background(BORDER);
+        draw_text(message, 20.0, 40.0, 30.0, ON);
+        next_frame().await;
+    }
+}
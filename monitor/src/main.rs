@@ -0,0 +1,502 @@
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, Write};
+
+use chip8::{compile_octo, transpile_rust, Analysis, Breakpoint, ByteKind, BreakpointId, Chip8, Config, CoreDump, Debugger, Glyph, Instruction, Register, RegisterChange, State, Stop, Symbols, TextStyle, MAX_SPRITE_ROWS};
+
+// How long `continue` runs without hitting anything before handing the prompt back, one emulated minute
+const CONTINUE_FRAME_LIMIT: usize = 60 * 60;
+const DEFAULT_MEM_LEN: usize = 0x40;
+const DEFAULT_DISASM_COUNT: usize = 10;
+// Read for the quirks and speed machines start with, as the desktop frontend does
+const CONFIG_PATH: &str = "chip8.toml";
+
+const HELP: &str = "\
+Numbers are hex unless noted, e.g. `mem 200 20` dumps 0x20 bytes from 0x200. Addresses can also be labels
+  load <path>              load a ROM, or build Octo source (.8o) with its labels, and reset the machine
+  symbols [path]           load labels from a symbol file, or list them
+  core <path>              load a core dump saved on a fault, into the same ROM loaded first
+  reset                    restart the loaded ROM
+  step [count]             run count instructions (decimal, default 1)
+  next                     step over a subroutine call
+  out                      run until the current subroutine returns
+  back                     undo the last instruction
+  continue                 run until a breakpoint, halt or fault
+  regs                     show registers, timers and the stack
+  mem <addr> [len]         hex dump memory
+  disasm [addr] [count]    disassemble, from the PC by default (count is decimal), data shown as bytes
+  analyze                  show which parts of the ROM are code, data and unreachable, and anything odd
+  dot <path>               save the ROM's control flow graph for Graphviz
+  transpile <path>         save the ROM translated to Rust, an experiment in running it compiled
+  break <addr|condition>   break at an address or label, or when a condition holds, e.g. `break v3 == 10`
+  delete <id>              remove a breakpoint (id is decimal)
+  breaks                   list breakpoints
+  watch <reg>              print whenever an instruction changes a register, e.g. `watch v5` or `watch dt`
+  unwatch <reg>            stop watching a register
+  key <key> [up]           hold a keypad key down, or let it go
+  screen [style]           print the display, as ascii, half-block or braille
+  sprite [height]          show what a DXYN would draw from I, as tall as the one at the PC or 15 (decimal)
+  font [large]             show the font's glyphs as they are in memory, marking the one I points at
+  quit                     exit the monitor";
+
+// Interactive machine monitor for poking at ROMs from a terminal
+fn main() {
+    println!("CHIP-8 monitor, type `help` for commands");
+    let config = match fs::read_to_string(CONFIG_PATH).map(|text| Config::parse(&text)) {
+        Ok(Ok(config)) => config,
+        Ok(Err(err)) => {
+            println!("ignoring {}, {}", CONFIG_PATH, err);
+            Config::default()
+        },
+        Err(_) => Config::default(),
+    };
+    let mut monitor =
+        Monitor { debugger: None, rom: None, symbols: Symbols::new(), analysis: None, watches: Vec::new(), config };
+    if let Some(path) = env::args().nth(1) {
+        monitor.load(&path);
+    }
+
+    let stdin = io::stdin();
+    loop {
+        print!("> ");
+        let _ = io::stdout().flush();
+        let mut line = String::new();
+        match stdin.lock().read_line(&mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => (),
+        }
+        let mut words = line.split_whitespace();
+        let command = match words.next() {
+            Some(command) => command,
+            None => continue,
+        };
+        let args: Vec<&str> = words.collect();
+        if matches!(command, "quit" | "q" | "exit") {
+            break;
+        }
+        if let Err(message) = monitor.run(command, &args) {
+            println!("{}", message);
+        }
+    }
+}
+
+struct Monitor {
+    debugger: Option<Debugger>,
+    rom: Option<Vec<u8>>,
+    // Given to each machine started
+    symbols: Symbols,
+    // Of the ROM as loaded, so disasm can tell code from data
+    analysis: Option<Analysis>,
+    // Watched on each machine started
+    watches: Vec<Register>,
+    config: Config,
+}
+
+impl Monitor {
+    fn run(&mut self, command: &str, args: &[&str]) -> Result<(), String> {
+        match command {
+            "help" | "h" | "?" => println!("{}", HELP),
+            "load" | "l" => {
+                let path = args.first().ok_or("usage: load <path>")?;
+                self.load(path);
+            },
+            "symbols" => match args.first() {
+                Some(path) => {
+                    let text = fs::read_to_string(path).map_err(|err| format!("couldn't read {}: {}", path, err))?;
+                    self.symbols = Symbols::parse(&text).map_err(|err| format!("{}: {}", path, err))?;
+                    println!("{} symbols", self.symbols.len());
+                    if let Some(debugger) = &mut self.debugger {
+                        debugger.chip8_mut().set_symbols(self.symbols.clone());
+                    }
+                },
+                None => print!("{}", self.symbols),
+            },
+            "core" => {
+                let path = args.first().ok_or("usage: core <path>")?;
+                let dump = CoreDump::open(path).map_err(|err| format!("{}: {}", path, err))?;
+                dump.restore(self.debugger_mut()?.chip8_mut()).map_err(|err| format!("{}: {}", path, err))?;
+                println!("fault: {}", dump.error);
+                let chip8 = self.debugger()?.chip8();
+                for (addr, opcode) in &dump.recent {
+                    let text = match Instruction::decode(*opcode) {
+                        Some(instruction) => instruction.with_symbols(chip8.symbols()).to_string(),
+                        None => String::from("??"),
+                    };
+                    println!(" {:03X}: {:04X}  {}", addr, opcode, text);
+                }
+                self.show_next()?;
+            },
+            "reset" => {
+                let rom = self.rom.clone().ok_or("no ROM loaded")?;
+                self.start(rom);
+                self.show_next()?;
+            },
+            "step" | "s" => {
+                let count = match args.first() {
+                    Some(count) => count.parse().map_err(|_| format!("bad count '{}'", count))?,
+                    None => 1,
+                };
+                let before = self.debugger()?.chip8().snapshot();
+                for _ in 0..count {
+                    let state = self.debugger_mut()?.step().map_err(|err| err.to_string())?;
+                    if state == State::Halted {
+                        println!("halted");
+                        break;
+                    }
+                }
+                // The PC is shown with the next instruction anyway
+                let mut diff = before.diff(&self.debugger()?.chip8().snapshot());
+                diff.registers.retain(|(register, _, _)| *register != Register::Pc);
+                if !diff.is_empty() {
+                    println!("changed: {}", diff);
+                }
+                self.show_next()?;
+            },
+            "next" | "n" => {
+                let stop = self.debugger_mut()?.step_over();
+                self.report(stop)?;
+            },
+            "out" | "o" => {
+                let stop = self.debugger_mut()?.step_out();
+                self.report(stop)?;
+            },
+            "back" | "b" => {
+                if !self.debugger_mut()?.step_back() {
+                    return Err(String::from("no more history"));
+                }
+                self.show_next()?;
+            },
+            "continue" | "c" => {
+                let debugger = self.debugger_mut()?;
+                let mut stop = Ok(Stop::FrameComplete);
+                for _ in 0..CONTINUE_FRAME_LIMIT {
+                    stop = debugger.run_frame();
+                    if stop != Ok(Stop::FrameComplete) {
+                        break;
+                    }
+                }
+                if stop == Ok(Stop::FrameComplete) {
+                    println!("still running after {} frames", CONTINUE_FRAME_LIMIT);
+                    return self.show_next();
+                }
+                self.report(stop)?;
+            },
+            "regs" | "r" => self.regs()?,
+            "mem" | "m" => {
+                let addr = self.parse_addr(args.first().ok_or("usage: mem <addr> [len]")?)?;
+                let len = args.get(1).map_or(Ok(DEFAULT_MEM_LEN), |len| parse_hex(len))?;
+                self.mem(addr, len)?;
+            },
+            "disasm" | "d" => {
+                let addr = match args.first() {
+                    Some(addr) => self.parse_addr(addr)?,
+                    None => self.debugger()?.chip8().pc() as usize,
+                };
+                let count = match args.get(1) {
+                    Some(count) => count.parse().map_err(|_| format!("bad count '{}'", count))?,
+                    None => DEFAULT_DISASM_COUNT,
+                };
+                self.disasm(addr, count)?;
+            },
+            "analyze" => self.analyze()?,
+            "dot" => {
+                let path = args.first().ok_or("usage: dot <path>")?;
+                self.debugger()?;
+                let analysis = self.analysis.as_ref().expect("analysed on load");
+                fs::write(path, analysis.to_dot(&self.symbols)).map_err(|err| format!("couldn't write {}: {}", path, err))?;
+            },
+            "transpile" => {
+                let path = args.first().ok_or("usage: transpile <path>")?;
+                self.debugger()?;
+                let analysis = self.analysis.as_ref().expect("analysed on load");
+                fs::write(path, transpile_rust(analysis)).map_err(|err| format!("couldn't write {}: {}", path, err))?;
+            },
+            "break" | "bp" => {
+                if args.is_empty() {
+                    return Err(String::from("usage: break <addr|condition>"));
+                }
+                let text = args.join(" ");
+                let addr = self.parse_addr(&text);
+                let debugger = self.debugger_mut()?;
+                // A lone label or hex number is an address, anything else a condition
+                let id = match addr {
+                    Ok(addr) if args.len() == 1 => debugger.add_breakpoint(Breakpoint::Address(addr as u16)),
+                    _ => debugger.add_condition(&text).map_err(|err| err.to_string())?,
+                };
+                println!("breakpoint {}", id.0);
+            },
+            "delete" => {
+                let id = args.first().and_then(|id| id.parse().ok()).ok_or("usage: delete <id>")?;
+                if !self.debugger_mut()?.remove_breakpoint(BreakpointId(id)) {
+                    return Err(format!("no breakpoint {}", id));
+                }
+            },
+            "breaks" => {
+                for (id, breakpoint) in self.debugger()?.breakpoints() {
+                    match breakpoint {
+                        Breakpoint::Address(addr) => match self.symbols.name(*addr) {
+                            Some(name) => println!("{:>3}: {:03X} {}", id.0, addr, name),
+                            None => println!("{:>3}: {:03X}", id.0, addr),
+                        },
+                        Breakpoint::Condition(condition) => println!("{:>3}: {}", id.0, condition),
+                    }
+                }
+            },
+            "watch" => {
+                let name = args.first().ok_or("usage: watch <reg>")?;
+                let register = Register::from_name(name).ok_or(format!("bad register '{}'", name))?;
+                if !self.watches.contains(&register) {
+                    self.debugger_mut()?.chip8_mut().watch_register(register, print_change);
+                    self.watches.push(register);
+                }
+            },
+            "unwatch" => {
+                let name = args.first().ok_or("usage: unwatch <reg>")?;
+                let register = Register::from_name(name).ok_or(format!("bad register '{}'", name))?;
+                if !self.debugger_mut()?.chip8_mut().unwatch_register(register) {
+                    return Err(format!("{} isn't watched", register));
+                }
+                self.watches.retain(|watched| *watched != register);
+            },
+            "key" | "k" => {
+                let key = args.first().ok_or("usage: key <key> [up]")?;
+                let key = parse_hex(key).ok().filter(|key| *key < 0x10).ok_or(format!("bad key '{}'", key))?;
+                let pressed = args.get(1) != Some(&"up");
+                self.debugger_mut()?.chip8_mut().keypress(key, pressed);
+            },
+            "screen" => {
+                let style = match args.first() {
+                    Some(name) => TextStyle::named(name).ok_or(format!("bad style '{}'", name))?,
+                    None => TextStyle::Ascii,
+                };
+                self.screen(style)?;
+            },
+            "sprite" => {
+                let height = match args.first() {
+                    Some(height) => height.parse().map_err(|_| format!("bad height '{}'", height))?,
+                    None => sprite_height(self.debugger()?),
+                };
+                self.sprite(height)?;
+            },
+            "font" => self.font(args.first() == Some(&"large"))?,
+            _ => return Err(format!("unknown command '{}', try `help`", command)),
+        }
+        Ok(())
+    }
+
+    fn debugger(&self) -> Result<&Debugger, String> {
+        self.debugger.as_ref().ok_or_else(|| String::from("no ROM loaded"))
+    }
+
+    fn debugger_mut(&mut self) -> Result<&mut Debugger, String> {
+        self.debugger.as_mut().ok_or_else(|| String::from("no ROM loaded"))
+    }
+
+    fn load(&mut self, path: &str) {
+        let rom = if path.ends_with(".8o") {
+            let program = fs::read_to_string(path)
+                .map_err(|err| err.to_string())
+                .and_then(|source| compile_octo(&source).map_err(|err| err.to_string()));
+            match program {
+                Ok(program) => {
+                    self.symbols = program.symbols();
+                    program.rom
+                },
+                Err(err) => return println!("couldn't build {}: {}", path, err),
+            }
+        } else {
+            match fs::read(path) {
+                // The last program's labels don't apply to this one
+                Ok(rom) => {
+                    self.symbols = Symbols::new();
+                    rom
+                },
+                Err(err) => return println!("couldn't read {}: {}", path, err),
+            }
+        };
+        println!("loaded {} ({} bytes)", path, rom.len());
+        self.start(rom);
+        let _ = self.show_next();
+    }
+
+    fn start(&mut self, rom: Vec<u8>) {
+        let mut chip8 = Chip8::init();
+        self.config.apply(&mut chip8);
+        // Oversized ROMs are cut down to what fits rather than refused, so their start can still be poked at
+        if let Err(err) = chip8.load(&rom) {
+            let space = chip8.ram().len() - chip8.start_address() as usize;
+            println!("{}, truncated to {} bytes", err, space);
+            chip8.load(&rom[..space]).expect("truncated ROM fits");
+        }
+        chip8.set_symbols(self.symbols.clone());
+        for register in &self.watches {
+            chip8.watch_register(*register, print_change);
+        }
+        self.analysis = Some(Analysis::of(&chip8));
+        self.debugger = Some(Debugger::new(chip8));
+        self.rom = Some(rom);
+    }
+
+    // A label or a hex address
+    fn parse_addr(&self, text: &str) -> Result<usize, String> {
+        match self.symbols.address(text) {
+            Some(addr) => Ok(addr as usize),
+            None => parse_hex(text),
+        }
+    }
+
+    fn report(&self, stop: chip8::Result<Stop>) -> Result<(), String> {
+        match stop.map_err(|err| err.to_string())? {
+            Stop::Breakpoint(id) => println!("breakpoint {}", id.0),
+            Stop::Halted => println!("halted"),
+            Stop::StepLimit => println!("gave up waiting for the subroutine to return"),
+            Stop::FrameComplete | Stop::StepComplete => (),
+        }
+        self.show_next()
+    }
+
+    // Print the instruction about to run
+    fn show_next(&self) -> Result<(), String> {
+        let pc = self.debugger()?.chip8().pc() as usize;
+        self.disasm(pc, 1)
+    }
+
+    fn regs(&self) -> Result<(), String> {
+        let chip8 = self.debugger()?.chip8();
+        for row in 0..2 {
+            let regs: Vec<String> = (row * 8..row * 8 + 8).map(|x| format!("V{:X}={:02X}", x, chip8.v(x))).collect();
+            println!("{}", regs.join(" "));
+        }
+        println!(" I={:03X} PC={:03X} DT={:02X} ST={:02X}", chip8.i(), chip8.pc(), chip8.delay_timer(), chip8.sound_timer());
+        let stack: Vec<String> = chip8.stack().iter().map(|addr| format!("{:03X}", addr)).collect();
+        println!(" stack: [{}]", stack.join(" "));
+        Ok(())
+    }
+
+    fn mem(&self, addr: usize, len: usize) -> Result<(), String> {
+        for row in self.debugger()?.chip8().hexdump(addr..addr + len) {
+            println!("{}", row);
+        }
+        Ok(())
+    }
+
+    fn disasm(&self, addr: usize, count: usize) -> Result<(), String> {
+        let chip8 = self.debugger()?.chip8();
+        let ram = chip8.ram();
+        let kind = |addr: usize| self.analysis.as_ref().and_then(|analysis| analysis.kind(addr as u16));
+        let mut addr = addr;
+        for _ in 0..count {
+            if addr + 1 >= ram.len() {
+                break;
+            }
+            if let Some(name) = chip8.symbols().name(addr as u16) {
+                println!("{}:", name);
+            }
+            let marker = if addr == chip8.pc() as usize { '>' } else { ' ' };
+            // Data is shown a pair of bytes at a time, or one where code follows it
+            if marker == ' ' && matches!(kind(addr), Some(ByteKind::Data | ByteKind::Unreachable)) {
+                let len = if kind(addr + 1) == kind(addr) { 2 } else { 1 };
+                let bytes = &ram[addr..addr + len];
+                let hex: String = bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+                let db: Vec<String> = bytes.iter().map(|byte| format!("0x{:02X}", byte)).collect();
+                println!(" {:03X}: {:<4}  DB {}", addr, hex, db.join(", "));
+                addr += len;
+                continue;
+            }
+            let opcode = u16::from_be_bytes([ram[addr], ram[addr + 1]]);
+            let text = match Instruction::decode(opcode) {
+                Some(instruction) => instruction.with_symbols(chip8.symbols()).to_string(),
+                None => String::from("??"),
+            };
+            println!("{}{:03X}: {:04X}  {}", marker, addr, opcode, text);
+            addr += 2;
+        }
+        Ok(())
+    }
+
+    fn analyze(&self) -> Result<(), String> {
+        self.debugger()?;
+        let analysis = self.analysis.as_ref().expect("analysed on load");
+        for (range, kind) in analysis.regions() {
+            let kind = match kind {
+                ByteKind::Code => "code",
+                ByteKind::Data => "data",
+                ByteKind::Unreachable => "unreachable",
+            };
+            println!("{:03X}-{:03X}  {}", range.start, range.end - 1, kind);
+        }
+        for issue in analysis.issues() {
+            println!("{}", issue);
+        }
+        Ok(())
+    }
+
+    fn screen(&self, style: TextStyle) -> Result<(), String> {
+        let chip8 = self.debugger()?.chip8();
+        println!("{}", chip8.render_text(style));
+        println!("hash {:016x}", chip8.display_hash());
+        Ok(())
+    }
+
+    // Eight glyphs across, each under its digit and address
+    fn font(&self, large: bool) -> Result<(), String> {
+        let chip8 = self.debugger()?.chip8();
+        let glyphs: Vec<Glyph> = if large { chip8.large_font_glyphs().collect() } else { chip8.font_glyphs().collect() };
+        for line in glyphs.chunks(8) {
+            let headings: Vec<String> = line
+                .iter()
+                .map(|glyph| {
+                    let marker = if glyph.addr == chip8.i() { '<' } else { ' ' };
+                    format!("{:X} {:03X}{}", glyph.digit, glyph.addr, marker)
+                })
+                .collect();
+            println!("{}", headings.join("   ").trim_end());
+            for row in 0..line[0].rows().len() {
+                let rows: Vec<String> = line
+                    .iter()
+                    .map(|glyph| glyph.rows()[row].iter().map(|pixel| if *pixel { '#' } else { '.' }).collect())
+                    .collect();
+                println!("{}", rows.join(" "));
+            }
+        }
+        Ok(())
+    }
+
+    fn sprite(&self, height: usize) -> Result<(), String> {
+        let chip8 = self.debugger()?.chip8();
+        println!("I={:03X}", chip8.i());
+        for row in chip8.sprite_preview(height).iter().take(height) {
+            let line: String = row.iter().map(|pixel| if *pixel { '#' } else { '.' }).collect();
+            println!("{}", line);
+        }
+        Ok(())
+    }
+}
+
+// The N of the DXYN at the PC, or as tall as they come when it's something else
+fn sprite_height(debugger: &Debugger) -> usize {
+    match debugger.current_instruction() {
+        Some(Instruction::Draw(_, _, n)) if n > 0 => n as usize,
+        _ => MAX_SPRITE_ROWS,
+    }
+}
+
+fn parse_hex(text: &str) -> Result<usize, String> {
+    let digits = text.trim_start_matches("0x").trim_start_matches("0X");
+    usize::from_str_radix(digits, 16).map_err(|_| format!("bad hex number '{}'", text))
+}
+
+// A watched register changing, as it happens
+fn print_change(chip8: &mut Chip8, change: RegisterChange) {
+    let at = match chip8.symbols().name(change.addr) {
+        Some(name) => format!("{:03X} {}", change.addr, name),
+        None => format!("{:03X}", change.addr),
+    };
+    match change.register {
+        Register::I | Register::Pc => {
+            println!("{} {:#05X} -> {:#05X} at {}", change.register, change.before, change.after, at)
+        },
+        Register::Sp => println!("{} {} -> {} at {}", change.register, change.before, change.after, at),
+        _ => println!("{} {:02X} -> {:02X} at {}", change.register, change.before, change.after, at),
+    }
+}
@@ -0,0 +1,294 @@
+use std::fs;
+use std::sync::mpsc::Sender;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chip8::{Chip8, Palette, State};
+use serde_json::{json, Value};
+
+// Largest PNG scale a subscriber can ask for
+const MAX_SCALE: usize = 16;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Format {
+    // One bit per pixel, rows top to bottom with the leftmost pixel in each byte's high bit
+    Raw,
+    // Black and white PNG, each pixel scaled up to a square
+    Png { scale: usize },
+}
+
+// Somewhere to send replies and frames, one per connection
+#[derive(Clone)]
+pub struct Client {
+    pub id: usize,
+    pub sender: Sender<String>,
+}
+
+// A client being streamed frames
+struct Subscriber {
+    client: Client,
+    format: Format,
+}
+
+// The machine being controlled, with the commands clients can send it. Every command is a JSON object
+// with a "cmd" field and gets exactly one reply, {"ok": true, ...} or {"ok": false, "error": "..."}.
+// Any "id" in the command is echoed back so scripts can match replies up.
+//
+// After {"cmd": "subscribe", "format": "raw"} or {"cmd": "subscribe", "format": "png", "scale": 8} the client
+// is also sent the current frame and then every frame that changes the display, as
+// {"event": "frame", "frame": 120, "format": "png", "width": 64, "height": 32, "data": "<base64>"}
+pub struct Emulator {
+    chip8: Chip8,
+    rom: Option<Vec<u8>>,
+    paused: bool,
+    frame: u64,
+    // Why the machine stopped by itself, if it did
+    fault: Option<String>,
+    subscribers: Vec<Subscriber>,
+    // Display rows the subscribers were last sent, None to send whatever's there next time. A resolution
+    // change shows up as a different number of rows
+    published: Option<Vec<u128>>,
+}
+
+impl Emulator {
+    pub fn new(paused: bool) -> Self {
+        Self {
+            chip8: Chip8::init(),
+            rom: None,
+            paused,
+            frame: 0,
+            fault: None,
+            subscribers: Vec::new(),
+            published: None,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        !self.paused && self.rom.is_some()
+    }
+
+    pub fn load(&mut self, rom: Vec<u8>) -> Result<(), String> {
+        let mut chip8 = Chip8::init();
+        chip8.load(&rom).map_err(|err| err.to_string())?;
+        self.chip8 = chip8;
+        self.rom = Some(rom);
+        self.frame = 0;
+        self.fault = None;
+        Ok(())
+    }
+
+    // One frame of real time, pausing on a halt or a fault
+    pub fn run_frame(&mut self) {
+        let result = self.chip8.run_frame();
+        self.end_frame(result);
+    }
+
+    fn end_frame<T>(&mut self, result: chip8::Result<T>) {
+        match result {
+            Ok(_) if self.chip8.is_halted() => {
+                self.paused = true;
+                self.fault = Some(String::from("halted"));
+            },
+            Ok(_) => (),
+            Err(err) => {
+                self.paused = true;
+                self.fault = Some(err.to_string());
+            },
+        }
+        self.frame += 1;
+    }
+
+    // Stream the display to subscribers if it's changed since they last saw it
+    pub fn publish(&mut self) {
+        if self.subscribers.is_empty() || self.published.as_deref() == Some(self.chip8.display_rows()) {
+            return;
+        }
+        let chip8 = &self.chip8;
+        let frame = self.frame;
+        self.subscribers.retain(|subscriber| {
+            let (format, data) = match subscriber.format {
+                Format::Raw => ("raw", encode_raw(chip8)),
+                Format::Png { scale } => ("png", encode_png(chip8, scale)),
+            };
+            let message = json!({
+                "event": "frame",
+                "frame": frame,
+                "format": format,
+                "width": chip8.width(),
+                "height": chip8.height(),
+                "data": BASE64.encode(data),
+            });
+            // A client that's gone away has dropped its end of the channel
+            subscriber.client.sender.send(message.to_string()).is_ok()
+        });
+        self.published = Some(chip8.display_rows().to_vec());
+    }
+
+    // client receives the reply and, once subscribed, the frame stream
+    pub fn handle(&mut self, request: &Value, client: &Client) -> Value {
+        let mut reply = match self.command(request, client) {
+            Ok(mut body) => {
+                body["ok"] = json!(true);
+                body
+            },
+            Err(message) => json!({ "ok": false, "error": message }),
+        };
+        if !request["id"].is_null() {
+            reply["id"] = request["id"].clone();
+        }
+        reply
+    }
+
+    fn command(&mut self, request: &Value, client: &Client) -> Result<Value, String> {
+        let cmd = request["cmd"].as_str().ok_or("missing \"cmd\"")?;
+        match cmd {
+            // {"cmd": "load", "path": "roms/PONG"} or {"cmd": "load", "bytes": [106, 2, ...]}
+            "load" => {
+                let rom = match (request["path"].as_str(), request["bytes"].as_array()) {
+                    (Some(path), _) => fs::read(path).map_err(|err| format!("couldn't read {}: {}", path, err))?,
+                    (None, Some(bytes)) => bytes
+                        .iter()
+                        .map(|byte| byte.as_u64().and_then(|byte| u8::try_from(byte).ok()))
+                        .collect::<Option<Vec<u8>>>()
+                        .ok_or("\"bytes\" must be numbers from 0 to 255")?,
+                    (None, None) => return Err(String::from("load needs \"path\" or \"bytes\"")),
+                };
+                self.load(rom)?;
+                Ok(json!({}))
+            },
+            "reset" => {
+                let rom = self.rom.clone().ok_or("no ROM loaded")?;
+                self.load(rom)?;
+                Ok(json!({}))
+            },
+            // {"cmd": "key", "key": 5, "pressed": true}
+            "key" => {
+                let key = request["key"].as_u64().filter(|key| *key < 0x10).ok_or("\"key\" must be 0 to 15")?;
+                let pressed = request["pressed"].as_bool().unwrap_or(true);
+                self.chip8.keypress(key as usize, pressed);
+                Ok(json!({}))
+            },
+            "pause" => {
+                self.paused = true;
+                Ok(json!({}))
+            },
+            "resume" => {
+                self.paused = false;
+                self.fault = None;
+                Ok(json!({}))
+            },
+            // Single instructions while paused, {"cmd": "step", "count": 10}
+            "step" => {
+                self.rom.as_ref().ok_or("no ROM loaded")?;
+                for _ in 0..request["count"].as_u64().unwrap_or(1) {
+                    match self.chip8.clock() {
+                        Ok(State::Running) => (),
+                        Ok(State::Halted) => break,
+                        Err(err) => return Err(err.to_string()),
+                    }
+                }
+                Ok(self.state())
+            },
+            // Whole frames as fast as possible, which keeps tests deterministic, {"cmd": "run", "frames": 60}
+            "run" => {
+                self.rom.as_ref().ok_or("no ROM loaded")?;
+                for _ in 0..request["frames"].as_u64().unwrap_or(1) {
+                    self.run_frame();
+                    if self.fault.is_some() {
+                        break;
+                    }
+                }
+                Ok(self.state())
+            },
+            // One frame holding exactly the keys listed, for tool-assisted runs, {"cmd": "advance", "keys": [4, 6]}.
+            // The reply's display_hash is the frame's
+            "advance" => {
+                self.rom.as_ref().ok_or("no ROM loaded")?;
+                let mut keys = [false; 0x10];
+                for key in request["keys"].as_array().map(Vec::as_slice).unwrap_or_default() {
+                    let key = key.as_u64().filter(|key| *key < 0x10).ok_or("\"keys\" must be numbers from 0 to 15")?;
+                    keys[key as usize] = true;
+                }
+                let result = self.chip8.advance_frame_with_input(keys);
+                self.end_frame(result);
+                Ok(self.state())
+            },
+            "subscribe" => {
+                let format = match request["format"].as_str().unwrap_or("raw") {
+                    "raw" => Format::Raw,
+                    "png" => {
+                        let scale = request["scale"].as_u64().unwrap_or(1) as usize;
+                        if !(1..=MAX_SCALE).contains(&scale) {
+                            return Err(format!("\"scale\" must be 1 to {}", MAX_SCALE));
+                        }
+                        Format::Png { scale }
+                    },
+                    format => return Err(format!("unknown format '{}', use \"raw\" or \"png\"", format)),
+                };
+                self.subscribers.retain(|subscriber| subscriber.client.id != client.id);
+                self.subscribers.push(Subscriber { client: client.clone(), format });
+                // Make sure the new subscriber gets a first frame
+                self.published = None;
+                Ok(json!({}))
+            },
+            "unsubscribe" => {
+                self.subscribers.retain(|subscriber| subscriber.client.id != client.id);
+                Ok(json!({}))
+            },
+            "state" => Ok(self.state()),
+            "frame" => Ok(self.frame_rows()),
+            _ => Err(format!("unknown command '{}'", cmd)),
+        }
+    }
+
+    fn state(&self) -> Value {
+        let chip8 = &self.chip8;
+        json!({
+            "frame": self.frame,
+            "paused": self.paused,
+            "fault": self.fault,
+            "pc": chip8.pc(),
+            "i": chip8.i(),
+            "v": (0..0x10).map(|x| chip8.v(x)).collect::<Vec<u8>>(),
+            "dt": chip8.delay_timer(),
+            "st": chip8.sound_timer(),
+            "stack": chip8.stack(),
+            // Hex, as the full 64 bits don't survive being a JavaScript number
+            "display_hash": format!("{:016x}", chip8.display_hash()),
+        })
+    }
+
+    // The display as one hex string per row, each digit four pixels with the leftmost in the high bit
+    fn frame_rows(&self) -> Value {
+        let chip8 = &self.chip8;
+        let rows: Vec<String> = chip8
+            .get_display()
+            .chunks(chip8.width())
+            .map(|row| {
+                row.chunks(4)
+                    .map(|pixels| {
+                        let nibble = pixels.iter().fold(0, |nibble, pixel| (nibble << 1) | *pixel as u32);
+                        char::from_digit(nibble, 16).unwrap_or('0').to_ascii_uppercase()
+                    })
+                    .collect()
+            })
+            .collect();
+        json!({ "frame": self.frame, "width": chip8.width(), "height": chip8.height(), "rows": rows })
+    }
+}
+
+fn encode_raw(chip8: &Chip8) -> Vec<u8> {
+    let bytes_per_row = chip8.width() / 8;
+    let mut bytes = Vec::with_capacity(bytes_per_row * chip8.height());
+    for row in chip8.display_rows() {
+        bytes.extend_from_slice(&row.to_be_bytes()[..bytes_per_row]);
+    }
+    bytes
+}
+
+fn encode_png(chip8: &Chip8, scale: usize) -> Vec<u8> {
+    let mut png = Vec::new();
+    // Writing into a Vec can't fail
+    let _ = chip8.write_png(&mut png, Palette::default(), scale);
+    png
+}
@@ -0,0 +1,130 @@
+mod emulator;
+mod transport;
+
+use std::env;
+use std::fs;
+use std::net::{TcpListener, TcpStream};
+use std::process;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use emulator::{Client, Emulator};
+use serde_json::{json, Value};
+use transport::{Incoming, LineTransport, Transport, WebSocketTransport};
+
+const FRAME_DURATION: Duration = Duration::from_nanos(1_000_000_000 / 60);
+const DEFAULT_ADDRESS: &str = "127.0.0.1:7700";
+
+const USAGE: &str = "usage: chip8-remote [--listen ADDR] [--websocket] [--paused] [ROM]";
+
+// A command from one of the clients
+struct Request {
+    message: Value,
+    client: Client,
+}
+
+// Runs an emulator in real time and lets other processes drive it with JSON commands,
+// one per line over TCP or one per message over WebSocket. Clients can also subscribe to a stream of
+// frames, raw or PNG, for thin clients and visual testing. See emulator.rs for the commands
+fn main() {
+    let mut address = String::from(DEFAULT_ADDRESS);
+    let mut websocket = false;
+    let mut paused = false;
+    let mut rom = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--listen" => address = args.next().unwrap_or_else(|| exit(USAGE)),
+            "--websocket" => websocket = true,
+            "--paused" => paused = true,
+            "-h" | "--help" => exit(USAGE),
+            _ if arg.starts_with('-') => exit(USAGE),
+            _ => rom = Some(arg),
+        }
+    }
+
+    let mut emulator = Emulator::new(paused);
+    if let Some(path) = rom {
+        let rom = fs::read(&path).unwrap_or_else(|err| exit(&format!("couldn't read {}: {}", path, err)));
+        emulator.load(rom).unwrap_or_else(|err| exit(&err));
+    }
+
+    let listener = TcpListener::bind(&address).unwrap_or_else(|err| exit(&format!("couldn't listen on {}: {}", address, err)));
+    eprintln!("listening on {}{}", if websocket { "ws://" } else { "" }, address);
+
+    let (requests, incoming) = mpsc::channel();
+    thread::spawn(move || {
+        for (id, stream) in listener.incoming().flatten().enumerate() {
+            let requests = requests.clone();
+            thread::spawn(move || serve(stream, id, websocket, requests));
+        }
+    });
+
+    // The emulator lives on this thread, handling commands in between frames
+    let mut next_frame = Instant::now() + FRAME_DURATION;
+    loop {
+        match incoming.recv_timeout(next_frame.saturating_duration_since(Instant::now())) {
+            Ok(request) => {
+                let reply = emulator.handle(&request.message, &request.client);
+                let _ = request.client.sender.send(reply.to_string());
+                emulator.publish();
+            },
+            Err(RecvTimeoutError::Timeout) => {
+                if emulator.is_running() {
+                    emulator.run_frame();
+                    emulator.publish();
+                }
+                next_frame += FRAME_DURATION;
+                // Don't try to catch up after falling far behind, e.g. when the machine was suspended
+                if next_frame < Instant::now() {
+                    next_frame = Instant::now() + FRAME_DURATION;
+                }
+            },
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+}
+
+fn serve(stream: TcpStream, id: usize, websocket: bool, requests: Sender<Request>) {
+    let result = if websocket {
+        WebSocketTransport::accept(stream).map(|transport| serve_transport(transport, id, requests))
+    } else {
+        LineTransport::new(stream).map(|transport| serve_transport(transport, id, requests))
+    };
+    if let Err(err) = result {
+        eprintln!("client connection failed: {}", err);
+    }
+}
+
+// Pass commands on to the emulator and send back whatever it has for this client, replies and streamed frames
+fn serve_transport(mut transport: impl Transport, id: usize, requests: Sender<Request>) {
+    let (sender, outgoing) = mpsc::channel();
+    let client = Client { id, sender };
+    loop {
+        match transport.receive() {
+            Ok(Incoming::Message(text)) => match serde_json::from_str(&text) {
+                Ok(message) => {
+                    if requests.send(Request { message, client: client.clone() }).is_err() {
+                        break;
+                    }
+                },
+                Err(err) => {
+                    let _ = client.sender.send(json!({ "ok": false, "error": format!("bad JSON: {}", err) }).to_string());
+                },
+            },
+            Ok(Incoming::Idle) => (),
+            Ok(Incoming::Closed) | Err(_) => break,
+        }
+        for message in outgoing.try_iter() {
+            if transport.send(&message).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+fn exit(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1)
+}
@@ -0,0 +1,108 @@
+use std::io::{self, BufRead, BufReader, ErrorKind, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+use tungstenite::{Message, WebSocket};
+
+// How long receive() waits before giving the connection a chance to send streamed frames
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+pub enum Incoming {
+    Message(String),
+    // Nothing arrived within POLL_INTERVAL
+    Idle,
+    Closed,
+}
+
+// One JSON message at a time in each direction, however the client is connected
+pub trait Transport {
+    fn receive(&mut self) -> io::Result<Incoming>;
+    fn send(&mut self, message: &str) -> io::Result<()>;
+}
+
+fn is_timeout(err: &io::Error) -> bool {
+    matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut)
+}
+
+// Plain TCP with one message per line, easy to drive from netcat or a test script
+pub struct LineTransport {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    // A line that's only partly arrived
+    line: Vec<u8>,
+}
+
+impl LineTransport {
+    pub fn new(stream: TcpStream) -> io::Result<Self> {
+        stream.set_read_timeout(Some(POLL_INTERVAL))?;
+        Ok(Self { reader: BufReader::new(stream.try_clone()?), writer: stream, line: Vec::new() })
+    }
+}
+
+impl Transport for LineTransport {
+    fn receive(&mut self) -> io::Result<Incoming> {
+        loop {
+            // Bytes read before a timeout stay in self.line for next time
+            match self.reader.read_until(b'\n', &mut self.line) {
+                Ok(0) => return Ok(Incoming::Closed),
+                Ok(_) => {
+                    let line = String::from_utf8_lossy(&self.line).trim().to_string();
+                    self.line.clear();
+                    if !line.is_empty() {
+                        return Ok(Incoming::Message(line));
+                    }
+                },
+                Err(err) if is_timeout(&err) => return Ok(Incoming::Idle),
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    fn send(&mut self, message: &str) -> io::Result<()> {
+        writeln!(self.writer, "{}", message)?;
+        self.writer.flush()
+    }
+}
+
+// One message per WebSocket text frame, for browsers
+pub struct WebSocketTransport {
+    socket: WebSocket<TcpStream>,
+}
+
+impl WebSocketTransport {
+    pub fn accept(stream: TcpStream) -> io::Result<Self> {
+        let socket = tungstenite::accept(stream).map_err(|err| io::Error::other(err.to_string()))?;
+        socket.get_ref().set_read_timeout(Some(POLL_INTERVAL))?;
+        Ok(Self { socket })
+    }
+}
+
+impl Transport for WebSocketTransport {
+    fn receive(&mut self) -> io::Result<Incoming> {
+        loop {
+            match self.socket.read() {
+                Ok(Message::Text(text)) => return Ok(Incoming::Message(text)),
+                Ok(Message::Binary(bytes)) => {
+                    return Ok(Incoming::Message(String::from_utf8_lossy(&bytes).into_owned()))
+                },
+                Ok(Message::Close(_)) => return Ok(Incoming::Closed),
+                // Pings are answered by tungstenite itself
+                Ok(_) => (),
+                Err(tungstenite::Error::ConnectionClosed) | Err(tungstenite::Error::AlreadyClosed) => {
+                    return Ok(Incoming::Closed)
+                },
+                // tungstenite keeps any partial frame, so reading again later is fine
+                Err(tungstenite::Error::Io(err)) if is_timeout(&err) => return Ok(Incoming::Idle),
+                Err(tungstenite::Error::Io(err)) => return Err(err),
+                Err(err) => return Err(io::Error::other(err.to_string())),
+            }
+        }
+    }
+
+    fn send(&mut self, message: &str) -> io::Result<()> {
+        self.socket.send(Message::Text(message.to_string())).map_err(|err| match err {
+            tungstenite::Error::Io(err) => err,
+            err => io::Error::other(err.to_string()),
+        })
+    }
+}
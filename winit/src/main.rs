@@ -0,0 +1,200 @@
+use std::env;
+use std::fs;
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chip8::{Chip8, Config, Keymap, State, SCREEN_HEIGHT, SCREEN_WIDTH};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use pixels::{Pixels, SurfaceTexture};
+use winit::dpi::{LogicalSize, PhysicalSize};
+use winit::event::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent};
+use winit::event_loop::EventLoop;
+use winit::window::{Fullscreen, Window, WindowBuilder};
+
+// Read for the quirks, speed, palette and keymap, as the desktop frontend does
+const CONFIG_PATH: &str = "chip8.toml";
+// In logical pixels, which high-DPI screens make more of
+const SCALE: f64 = 12.0;
+const FRAME: Duration = Duration::from_nanos(1_000_000_000 / 60);
+// Frames run at once at most, past which a stall is given up on instead of caught up with
+const MAX_FRAMES_OWED: u32 = 4;
+
+// A desktop frontend on winit and pixels, drawing through the GPU with render_rgba():
+//     cargo run --release --manifest-path winit/Cargo.toml -- roms/PONG
+// pixels scales the display to the window in whole pixels, letterboxed, at the screen's own resolution. The
+// event loop sleeps until the next frame is due instead of spinning, and runs frames by the clock, so a slow
+// redraw or a 144Hz screen doesn't change the speed. P pauses, F11 toggles fullscreen and Escape quits
+fn main() {
+    let Some(path) = env::args().nth(1) else {
+        exit("usage: chip8-winit <rom>");
+    };
+    let config = load_config();
+    let keymap = config.keymap.clone().unwrap_or_else(Keymap::qwerty);
+    let mut chip8 = Chip8::init();
+    config.apply(&mut chip8);
+    chip8.load_file(&path).unwrap_or_else(|err| exit(&err.to_string()));
+    // Taps quicker than a frame still count
+    chip8.enable_key_queue(true);
+
+    let event_loop = EventLoop::new();
+    let size = LogicalSize::new(SCALE * SCREEN_WIDTH as f64, SCALE * SCREEN_HEIGHT as f64);
+    let window = WindowBuilder::new()
+        .with_title("CHIP-8")
+        .with_inner_size(size)
+        .with_min_inner_size(LogicalSize::new(SCREEN_WIDTH as f64, SCREEN_HEIGHT as f64))
+        .build(&event_loop)
+        .unwrap_or_else(|err| exit(&err.to_string()));
+    let (mut width, mut height) = (chip8.width() as u32, chip8.height() as u32);
+    let mut pixels = new_pixels(&window, width, height);
+    let palette = config.palette;
+    let mut buffer = Vec::new();
+
+    // Kept alive for as long as the loop runs, or silent without an audio device
+    let beeping = Arc::new(AtomicBool::new(false));
+    let _stream = start_audio(&chip8, beeping.clone());
+
+    let mut next_frame = Instant::now();
+    let mut p_held = false;
+    event_loop.run(move |event, _, control_flow| match event {
+        Event::WindowEvent { event, .. } => match event {
+            WindowEvent::CloseRequested => control_flow.set_exit(),
+            WindowEvent::Resized(size) => resize(&mut pixels, &window, size),
+            // Moved to a screen of another scale, where the same logical size is more or fewer pixels
+            WindowEvent::ScaleFactorChanged { new_inner_size, .. } => resize(&mut pixels, &window, *new_inner_size),
+            WindowEvent::KeyboardInput {
+                input: KeyboardInput { virtual_keycode: Some(host), state, .. },
+                ..
+            } => {
+                let pressed = state == ElementState::Pressed;
+                match host {
+                    VirtualKeyCode::Escape => control_flow.set_exit(),
+                    // Held keys repeat, which shouldn't flip the pause back and forth
+                    VirtualKeyCode::P if pressed && !p_held => {
+                        if chip8.is_paused() {
+                            chip8.resume();
+                        } else {
+                            chip8.pause();
+                        }
+                    }
+                    VirtualKeyCode::F11 if pressed => {
+                        let fullscreen = window.fullscreen().is_none().then_some(Fullscreen::Borderless(None));
+                        window.set_fullscreen(fullscreen);
+                    }
+                    _ => {
+                        keymap.apply(&key_name(host), pressed, &mut chip8);
+                    }
+                }
+                if host == VirtualKeyCode::P {
+                    p_held = pressed;
+                }
+            }
+            _ => (),
+        },
+        Event::MainEventsCleared => {
+            let now = Instant::now();
+            let mut ran = 0;
+            while next_frame <= now && ran < MAX_FRAMES_OWED {
+                match chip8.run_frame() {
+                    Ok(State::Running) => (),
+                    // The ROM exited with 00FD
+                    Ok(State::Halted) => {
+                        control_flow.set_exit();
+                        return;
+                    }
+                    Err(err) => exit(&format!("fault: {}", err)),
+                }
+                next_frame += FRAME;
+                ran += 1;
+            }
+            if next_frame <= now {
+                next_frame = now + FRAME;
+            }
+            beeping.store(chip8.is_beeping(), Ordering::Relaxed);
+            if ran > 0 {
+                window.request_redraw();
+            }
+            control_flow.set_wait_until(next_frame);
+        }
+        Event::RedrawRequested(_) => {
+            // Switching between lo-res and hi-res changes the size of the buffer pixels scales up
+            let size = (chip8.width() as u32, chip8.height() as u32);
+            if size != (width, height) {
+                (width, height) = size;
+                if let Err(err) = pixels.resize_buffer(width, height) {
+                    exit(&err.to_string());
+                }
+            }
+            chip8.render_rgba(palette, &mut buffer);
+            pixels.frame_mut().copy_from_slice(&buffer);
+            if let Err(err) = pixels.render() {
+                exit(&err.to_string());
+            }
+        }
+        _ => (),
+    });
+}
+
+// The name a keymap knows host by, which is winit's but for the digits, which are "1" rather than "Key1"
+fn key_name(host: VirtualKeyCode) -> String {
+    let name = format!("{:?}", host);
+    match name.strip_prefix("Key") {
+        Some(digit) if digit.len() == 1 => digit.to_string(),
+        _ => name,
+    }
+}
+
+// CONFIG_PATH's settings, or the defaults
+fn load_config() -> Config {
+    match fs::read_to_string(CONFIG_PATH).map(|text| Config::parse(&text)) {
+        Ok(Ok(config)) => config,
+        Ok(Err(err)) => {
+            eprintln!("ignoring {}, {}", CONFIG_PATH, err);
+            Config::default()
+        }
+        Err(_) => Config::default(),
+    }
+}
+
+// pixels the size of the display, drawn to the whole window in physical pixels
+fn new_pixels(window: &Window, width: u32, height: u32) -> Pixels {
+    let size = window.inner_size();
+    let surface = SurfaceTexture::new(size.width, size.height, window);
+    Pixels::new(width, height, surface).unwrap_or_else(|err| exit(&err.to_string()))
+}
+
+fn resize(pixels: &mut Pixels, window: &Window, size: PhysicalSize<u32>) {
+    // Minimised windows are 0 by 0, which the surface can't be
+    if let Err(err) = pixels.resize_surface(size.width.max(1), size.height.max(1)) {
+        exit(&err.to_string());
+    }
+    window.request_redraw();
+}
+
+// Play the machine's beep while beeping is set, from a scratch machine in the audio thread whose sound timer is
+// kept running for as long. None without a device that takes f32 samples
+fn start_audio(chip8: &Chip8, beeping: Arc<AtomicBool>) -> Option<cpal::Stream> {
+    let device = cpal::default_host().default_output_device()?;
+    let config = device.default_output_config().ok()?.config();
+    let (sample_rate, channels) = (config.sample_rate.0, config.channels as usize);
+    let mut scratch = Chip8::init();
+    scratch.set_buzzer(chip8.buzzer());
+    let mut samples = Vec::new();
+    let callback = move |out: &mut [f32], _: &cpal::OutputCallbackInfo| {
+        scratch.set_sound_timer(if beeping.load(Ordering::Relaxed) { u8::MAX } else { 0 });
+        samples.resize(out.len() / channels, 0.0);
+        scratch.fill_audio(&mut samples, sample_rate);
+        for (frame, sample) in out.chunks_mut(channels).zip(&samples) {
+            frame.fill(*sample);
+        }
+    };
+    let stream = device.build_output_stream(&config, callback, |err| eprintln!("audio: {}", err), None).ok()?;
+    stream.play().ok()?;
+    Some(stream)
+}
+
+fn exit(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1)
+}